@@ -1,5 +1,5 @@
 use criterion::{criterion_group, criterion_main, Criterion};
-use fips203::traits::{Decaps, Encaps, KeyGen};
+use fips203::traits::{Decaps, Encaps, KeyGen, SerDes};
 use fips203::{ml_kem_1024, ml_kem_512, ml_kem_768};
 use rand_core::{CryptoRng, RngCore};
 
@@ -63,5 +63,90 @@ pub fn criterion_benchmark(c: &mut Criterion) {
     c.bench_function("ml_kem_1024 Decaps", |b| b.iter(|| dk_1024.try_decaps(&ct_1024)));
 }
 
-criterion_group!(benches, criterion_benchmark);
+
+// Beyond the three core operations: deserialization (with validation), serialization, seeded key
+// generation, expanded keys, the caller-owned workspace and (with `rayon`) the batch operations
+macro_rules! bench_parameter_set {
+    ($c:ident, $rng:ident, $ml_kem:ident, $name:literal) => {{
+        let (ek, dk) = $ml_kem::KG::try_keygen_with_rng(&mut $rng).unwrap();
+        let (_, ct) = ek.try_encaps_with_rng(&mut $rng).unwrap();
+        let (ek_bytes, dk_bytes, ct_bytes) =
+            (ek.clone().into_bytes(), dk.clone().into_bytes(), ct.clone().into_bytes());
+        let ek_expanded = ek.try_precompute().unwrap();
+        let dk_expanded = dk.try_precompute().unwrap();
+        let mut ws = $ml_kem::Workspace::default();
+
+        $c.bench_function(concat!($name, " EncapsKey try_from_bytes"), |b| {
+            b.iter(|| $ml_kem::EncapsKey::try_from_bytes(ek_bytes))
+        });
+        $c.bench_function(concat!($name, " DecapsKey try_from_bytes"), |b| {
+            b.iter(|| $ml_kem::DecapsKey::try_from_bytes(dk_bytes))
+        });
+        $c.bench_function(concat!($name, " CipherText try_from_bytes"), |b| {
+            b.iter(|| $ml_kem::CipherText::try_from_bytes(ct_bytes))
+        });
+        $c.bench_function(concat!($name, " EncapsKey into_bytes"), |b| {
+            b.iter(|| ek.clone().into_bytes())
+        });
+        $c.bench_function(concat!($name, " DecapsKey into_bytes"), |b| {
+            b.iter(|| dk.clone().into_bytes())
+        });
+
+        $c.bench_function(concat!($name, " KeyGen from seed"), |b| {
+            b.iter(|| $ml_kem::KG::keygen_from_seed([1u8; 32], [2u8; 32]))
+        });
+
+        $c.bench_function(concat!($name, " Encaps precompute"), |b| {
+            b.iter(|| ek.try_precompute())
+        });
+        $c.bench_function(concat!($name, " Encaps expanded"), |b| {
+            b.iter(|| ek_expanded.try_encaps_with_rng(&mut $rng))
+        });
+        $c.bench_function(concat!($name, " Decaps precompute"), |b| {
+            b.iter(|| dk.try_precompute())
+        });
+        $c.bench_function(concat!($name, " Decaps expanded"), |b| {
+            b.iter(|| dk_expanded.try_decaps(&ct))
+        });
+
+        $c.bench_function(concat!($name, " Encaps workspace"), |b| {
+            b.iter(|| ek.try_encaps_in_workspace_with_rng(&mut $rng, &mut ws))
+        });
+        $c.bench_function(concat!($name, " Decaps workspace"), |b| {
+            b.iter(|| dk.try_decaps_in_workspace(&ct, &mut ws))
+        });
+
+        #[cfg(feature = "rayon")]
+        {
+            let keys = $ml_kem::KG::try_keygen_par(BATCH).unwrap();
+            let eks: Vec<_> = keys.iter().map(|(ek, _dk)| ek.clone()).collect();
+            let encapsulated = $ml_kem::EncapsKey::try_encaps_par(&eks).unwrap();
+            let pairs: Vec<_> =
+                keys.iter().zip(&encapsulated).map(|((_ek, dk), (_ssk, ct))| (dk, ct)).collect();
+
+            $c.bench_function(concat!($name, " KeyGen par x64"), |b| {
+                b.iter(|| $ml_kem::KG::try_keygen_par(BATCH))
+            });
+            $c.bench_function(concat!($name, " Encaps par x64"), |b| {
+                b.iter(|| $ml_kem::EncapsKey::try_encaps_par(&eks))
+            });
+            $c.bench_function(concat!($name, " Decaps par x64"), |b| {
+                b.iter(|| $ml_kem::DecapsKey::try_decaps_par(&pairs))
+            });
+        }
+    }};
+}
+
+#[cfg(feature = "rayon")]
+const BATCH: usize = 64;
+
+
+pub fn extended_benchmark(c: &mut Criterion) {
+    let mut bench_rng = TestRng { value: 0 };
+    bench_parameter_set!(c, bench_rng, ml_kem_512, "ml_kem_512 ");
+    bench_parameter_set!(c, bench_rng, ml_kem_768, "ml_kem_768 ");
+    bench_parameter_set!(c, bench_rng, ml_kem_1024, "ml_kem_1024");
+}
+
+criterion_group!(benches, criterion_benchmark, extended_benchmark);
 criterion_main!(benches);
@@ -0,0 +1,102 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use fips203::traits::{Decaps, Encaps, KeyGen};
+#[cfg(feature = "ml-kem")]
+use fips203::traits::SerDes;
+use fips203::{ml_kem_1024, ml_kem_512, ml_kem_768};
+#[cfg(feature = "ml-kem")]
+use ml_kem::kem::{Decapsulate, Encapsulate};
+#[cfg(feature = "ml-kem")]
+use ml_kem::{KemCore, MlKem1024, MlKem512, MlKem768};
+use rand_core::{CryptoRng, RngCore};
+
+
+// Test RNG returning the same byte pattern on every request, so that each implementation drawing
+// `d`, `z` and `m` from it (in whatever order) operates on identical inputs
+struct FixedRng {
+    value: u8,
+}
+
+impl RngCore for FixedRng {
+    fn next_u32(&mut self) -> u32 { rand_core::impls::next_u32_via_fill(self) }
+
+    fn next_u64(&mut self) -> u64 { rand_core::impls::next_u64_via_fill(self) }
+
+    fn fill_bytes(&mut self, out: &mut [u8]) { out.iter_mut().for_each(|b| *b = self.value); }
+
+    fn try_fill_bytes(&mut self, out: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(out);
+        Ok(())
+    }
+}
+
+impl CryptoRng for FixedRng {}
+
+
+// For each operation, a benchmark group runs the implementations side by side so that criterion
+// reports their relative throughput. The `ml-kem` crate is first checked to produce the identical
+// ciphertext and shared secret. Note that `pqcrypto-kyber` implements round 3 Kyber (not FIPS 203)
+// and draws its own randomness, so only its throughput is comparable.
+macro_rules! compare_parameter_set {
+    ($c:ident, $name:literal, $fips203:ident, $ml_kem:ident, $kyber:ident) => {{
+        let mut rng = FixedRng { value: 0x5a };
+        let (ek, dk) = $fips203::KG::try_keygen_with_rng(&mut rng).unwrap();
+        let (_, ct) = ek.try_encaps_with_rng(&mut rng).unwrap();
+
+        #[cfg(feature = "ml-kem")]
+        let (other_dk, other_ek) = $ml_kem::generate(&mut rng);
+        #[cfg(feature = "ml-kem")]
+        let other_ct = {
+            let (ssk, ct) = ek.try_encaps_with_rng(&mut rng).unwrap();
+            let (other_ct, other_ssk) = other_ek.encapsulate(&mut rng).unwrap();
+            assert_eq!(other_ct.as_slice(), &ct.clone().into_bytes()[..], "ciphertexts differ");
+            assert_eq!(other_ssk.as_slice(), &ssk.into_bytes()[..], "shared secrets differ");
+            other_ct
+        };
+
+        #[cfg(feature = "pqcrypto-kyber")]
+        let (kyber_pk, kyber_sk) = pqcrypto_kyber::$kyber::keypair();
+        #[cfg(feature = "pqcrypto-kyber")]
+        let (_, kyber_ct) = pqcrypto_kyber::$kyber::encapsulate(&kyber_pk);
+
+        let mut group = $c.benchmark_group(concat!($name, " KeyGen"));
+        group.throughput(Throughput::Elements(1));
+        group.bench_function("fips203", |b| b.iter(|| $fips203::KG::try_keygen_with_rng(&mut rng)));
+        #[cfg(feature = "ml-kem")]
+        group.bench_function("ml-kem", |b| b.iter(|| $ml_kem::generate(&mut rng)));
+        #[cfg(feature = "pqcrypto-kyber")]
+        group.bench_function("pqcrypto-kyber", |b| b.iter(pqcrypto_kyber::$kyber::keypair));
+        group.finish();
+
+        let mut group = $c.benchmark_group(concat!($name, " Encaps"));
+        group.throughput(Throughput::Elements(1));
+        group.bench_function("fips203", |b| b.iter(|| ek.try_encaps_with_rng(&mut rng)));
+        #[cfg(feature = "ml-kem")]
+        group.bench_function("ml-kem", |b| b.iter(|| other_ek.encapsulate(&mut rng)));
+        #[cfg(feature = "pqcrypto-kyber")]
+        group.bench_function("pqcrypto-kyber", |b| {
+            b.iter(|| pqcrypto_kyber::$kyber::encapsulate(&kyber_pk))
+        });
+        group.finish();
+
+        let mut group = $c.benchmark_group(concat!($name, " Decaps"));
+        group.throughput(Throughput::Elements(1));
+        group.bench_function("fips203", |b| b.iter(|| dk.try_decaps(&ct)));
+        #[cfg(feature = "ml-kem")]
+        group.bench_function("ml-kem", |b| b.iter(|| other_dk.decapsulate(&other_ct)));
+        #[cfg(feature = "pqcrypto-kyber")]
+        group.bench_function("pqcrypto-kyber", |b| {
+            b.iter(|| pqcrypto_kyber::$kyber::decapsulate(&kyber_ct, &kyber_sk))
+        });
+        group.finish();
+    }};
+}
+
+
+pub fn compare_benchmark(c: &mut Criterion) {
+    compare_parameter_set!(c, "ML-KEM-512 ", ml_kem_512, MlKem512, kyber512);
+    compare_parameter_set!(c, "ML-KEM-768 ", ml_kem_768, MlKem768, kyber768);
+    compare_parameter_set!(c, "ML-KEM-1024", ml_kem_1024, MlKem1024, kyber1024);
+}
+
+criterion_group!(benches, compare_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,2 @@
+//! Side-by-side benchmarks of `fips203` against other ML-KEM (and Kyber) implementations; see
+//! `benches/compare.rs` and the README.
@@ -0,0 +1,174 @@
+#![no_std]
+#![no_main]
+
+use core::sync::atomic::{compiler_fence, AtomicU32, Ordering};
+use cortex_m::peripheral::{syst::SystClkSource, SYST};
+use cortex_m_rt::exception;
+use fips203::ml_kem_512;
+use fips203::traits::{Decaps, Encaps, KeyGen, SerDes};
+use panic_rtt_target as _;
+use rand_core::{CryptoRng, RngCore};
+use rp_pico::entry;
+use rp_pico::hal::{clocks::init_clocks_and_plls, pac, Watchdog};
+use rtt_target::{rprintln, rtt_init_print};
+use subtle::{ConditionallySelectable, ConstantTimeEq};
+
+
+// Test RNG to regurgitate incremented values when 'asked' except rho every i mod 4 == 0 (seed d)
+#[derive(Clone)]
+struct TestRng {
+    rho: u32,
+    value: u32,
+}
+
+impl RngCore for TestRng {
+    fn next_u32(&mut self) -> u32 { unimplemented!() }
+
+    fn next_u64(&mut self) -> u64 { unimplemented!() }
+
+    fn fill_bytes(&mut self, _out: &mut [u8]) { unimplemented!() }
+
+    fn try_fill_bytes(&mut self, out: &mut [u8]) -> Result<(), rand_core::Error> {
+        out.iter_mut().for_each(|b| *b = 0);
+        let supply_rho = (self.value & 0x03).ct_eq(&0);
+        let target = u32::conditional_select(&self.value, &self.rho, supply_rho);
+        out[0..4].copy_from_slice(&target.to_be_bytes());
+        self.value = self.value.wrapping_add(1);
+        Ok(())
+    }
+}
+
+impl CryptoRng for TestRng {}
+
+
+// Expected cycle count for one operation, which must be constant for each rho
+struct Expected {
+    op: &'static str,
+    cycles: u32,
+}
+
+impl Expected {
+    const fn new(op: &'static str) -> Self { Self { op, cycles: 0 } }
+
+    fn check(&mut self, i: u32, count: u32) {
+        // capture the cycle count
+        if (i % 1000) == 2 {
+            self.cycles = count
+        };
+        // make sure it is constant
+        if ((i % 1000) > 2) & (count != self.cycles) {
+            panic!("Non constant-time {}!! iteration:{} cycles:{}", self.op, i, count)
+        };
+    }
+}
+
+
+// The Cortex-M0+ has no DWT cycle counter, so cycles are counted by the (24-bit) SysTick, which
+// counts down from `RELOAD` at the core clock and interrupts on each wrap to extend the count
+const RELOAD: u32 = 0x00FF_FFFF;
+static WRAPS: AtomicU32 = AtomicU32::new(0);
+
+#[exception]
+fn SysTick() {
+    // Only written here (ARMv6-M has no atomic read-modify-write)
+    WRAPS.store(WRAPS.load(Ordering::Relaxed).wrapping_add(1), Ordering::Relaxed);
+}
+
+// Cycles since the SysTick was last restarted, re-reading if it wrapped in between
+fn cycles() -> u32 {
+    loop {
+        let wraps = WRAPS.load(Ordering::Relaxed);
+        let current = SYST::get_current();
+        if wraps == WRAPS.load(Ordering::Relaxed) {
+            return wraps.wrapping_mul(RELOAD + 1).wrapping_add(RELOAD - current);
+        }
+    }
+}
+
+
+// Runs a single operation with the SysTick restarted beforehand, returning its cycle count. The
+// restart ensures the wrap interrupt(s) occur at the same point(s) within each run of the
+// operation, so that their own cycles do not vary the count.
+fn measure<T>(syst: &mut SYST, op: impl FnOnce() -> T) -> (T, u32) {
+    ///////////////////// Start measurement period
+    syst.clear_current();
+    while SYST::get_current() == 0 {} // the cleared counter reloads on the next tick
+    WRAPS.store(0, Ordering::Relaxed);
+    compiler_fence(Ordering::SeqCst);
+    let start = cycles();
+    compiler_fence(Ordering::SeqCst);
+
+    let result = op();
+
+    compiler_fence(Ordering::SeqCst);
+    let finish = cycles();
+    compiler_fence(Ordering::SeqCst);
+    ///////////////////// Finish measurement period
+
+    (result, finish.wrapping_sub(start))
+}
+
+
+#[entry]
+fn main() -> ! {
+    rtt_init_print!();
+    let mut pac = pac::Peripherals::take().unwrap();
+    let core = pac::CorePeripherals::take().unwrap();
+    let mut watchdog = Watchdog::new(pac.WATCHDOG);
+    let _clocks = init_clocks_and_plls(
+        rp_pico::XOSC_CRYSTAL_FREQ,
+        pac.XOSC,
+        pac.CLOCKS,
+        pac.PLL_SYS,
+        pac.PLL_USB,
+        &mut pac.RESETS,
+        &mut watchdog,
+    )
+    .ok()
+    .unwrap();
+
+    let mut syst = core.SYST;
+    syst.set_clock_source(SystClkSource::Core);
+    syst.set_reload(RELOAD);
+    syst.clear_current();
+    syst.enable_interrupt();
+    syst.enable_counter();
+
+    let mut rng = TestRng { rho: 999, value: 4 }; // arbitrary choice (value must be mult of 4)
+    let mut spare_draw = [0u8; 32];
+    let mut expected_keygen = Expected::new("keygen");
+    let mut expected_encaps = Expected::new("encaps");
+    let mut expected_decaps = Expected::new("decaps");
+    let mut i = 0u32;
+
+    loop {
+        i += 1;
+
+        // Each operation is measured (and tracked) separately, as with `ct_cm4`
+        let ((ek, dk), keygen_count) =
+            measure(&mut syst, || ml_kem_512::KG::try_keygen_with_rng(&mut rng).unwrap());
+        let ((ssk1, ct), encaps_count) =
+            measure(&mut syst, || ek.try_encaps_with_rng(&mut rng).unwrap());
+        let (ssk2, decaps_count) = measure(&mut syst, || dk.try_decaps(&ct).unwrap());
+        assert_eq!(ssk1.into_bytes(), ssk2.into_bytes());
+
+        rng.try_fill_bytes(&mut spare_draw).unwrap(); // ease our lives; multiple of 4
+
+        // each rho should have a fixed cycle count per operation
+        if (i % 1000) == 0 {
+            rng.rho += 1
+        };
+        expected_keygen.check(i, keygen_count);
+        expected_encaps.check(i, encaps_count);
+        expected_decaps.check(i, decaps_count);
+        if i % 100 == 0 {
+            rprintln!(
+                "Iteration {} cycle counts: keygen {} encaps {} decaps {}",
+                i,
+                keygen_count,
+                encaps_count,
+                decaps_count
+            )
+        };
+    }
+}
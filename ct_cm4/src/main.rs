@@ -1,17 +1,32 @@
 #![no_std]
 #![no_main]
 
+#[cfg(not(any(feature = "ml-kem-512", feature = "ml-kem-768", feature = "ml-kem-1024")))]
+compile_error!("at least one parameter set feature must be enabled");
+
+use core::sync::atomic::{AtomicU32, Ordering};
 use cortex_m::asm;
-use cortex_m_rt::entry;
+use cortex_m::peripheral::{syst::SystClkSource, DCB, SYST};
+use cortex_m_rt::{entry, exception};
+#[cfg(feature = "ml-kem-1024")]
+use fips203::ml_kem_1024;
+#[cfg(feature = "ml-kem-512")]
 use fips203::ml_kem_512;
-use fips203::traits::{Decaps, Encaps, KeyGen, SerDes};
+#[cfg(feature = "ml-kem-768")]
+use fips203::ml_kem_768;
+use fips203::traits::{Decaps, Encaps, Kem, KeyGen};
 use microbit::{
     board::Board,
-    hal::{pac::DWT, prelude::OutputPin},
+    gpio::DisplayPins,
+    hal::{
+        gpio::{Level, Output, Pin, PushPull},
+        pac::DWT,
+        prelude::OutputPin,
+    },
 };
 use panic_rtt_target as _;
 use rand_core::{CryptoRng, RngCore};
-use rtt_target::{rprintln, rtt_init_print};
+use rtt_target::{rprintln, rtt_init, set_print_channel, DownChannel};
 use subtle::{ConditionallySelectable, ConstantTimeEq};
 
 
@@ -42,60 +57,495 @@ impl RngCore for TestRng {
 impl CryptoRng for TestRng {}
 
 
-#[entry]
-fn main() -> ! {
-    let mut board = Board::take().unwrap();
-    board.DCB.enable_trace();
-    board.DWT.enable_cycle_counter();
-    board.display_pins.col1.set_low().unwrap();
-    rtt_init_print!();
+// Expected cycle count for one operation, which must be constant for each rho
+struct Expected {
+    op: &'static str,
+    cycles: u32,
+}
+
+impl Expected {
+    const fn new(op: &'static str) -> Self { Self { op, cycles: 0 } }
+
+    fn check(&mut self, i: u32, count: u32) {
+        // capture the cycle count
+        if (i % 1000) == 2 {
+            self.cycles = count
+        };
+        // make sure it is constant
+        if ((i % 1000) > 2) & (count != self.cycles) {
+            panic!("Non constant-time {}!! iteration:{} cycles:{}", self.op, i, count)
+        };
+    }
+}
+
+
+// Boundaries of the image and RAM regions, from the cortex-m-rt linker script
+extern "C" {
+    static __vector_table: u32; // the start of flash
+    static __sidata: u32; // the load address (in flash) of the initialized statics
+    static __sdata: u32; // the start of the statics in RAM
+    static __edata: u32;
+    static __sheap: u32; // the end of the statics
+    static _stack_start: u32; // the (initial) top of the stack
+}
+
+// Returns the flash (code, constants and initializers) and static RAM consumption of the image,
+// and the stack available below the initial stack pointer
+fn memory() -> (usize, usize, usize) {
+    let addr = |sym: &u32| sym as *const u32 as usize;
+    unsafe {
+        let data = addr(&__edata) - addr(&__sdata);
+        let flash = addr(&__sidata) + data - addr(&__vector_table);
+        let statics = addr(&__sheap) - addr(&__sdata);
+        let stack = addr(&_stack_start) - addr(&__sheap);
+        (flash, statics, stack)
+    }
+}
+
+
+// Returns the (high-water) stack usage of `op`, by painting the unused stack below the current
+// frame (down to the end of the statics) and then finding the lowest overwritten word
+fn stack_usage<T>(op: impl FnOnce() -> T) -> (T, usize) {
+    const PAINT: u32 = 0xA5A5_5A5A;
+    let bottom = unsafe { core::ptr::addr_of!(__sheap) } as usize;
+    let top = cortex_m::register::msp::read() as usize - 64; // leave room for this frame
+    for addr in (bottom..top).step_by(4) {
+        unsafe { core::ptr::write_volatile(addr as *mut u32, PAINT) };
+    }
+    let result = op();
+    let mut addr = bottom;
+    while (addr < top) && unsafe { core::ptr::read_volatile(addr as *const u32) } == PAINT {
+        addr += 4;
+    }
+    (result, top - addr)
+}
+
+
+// Reports the stack usage of each operation once, for comparison with the published bounds
+// (e.g., `ml_kem_1024::MAX_STACK_DECAP`), which are measured on the host
+fn report_stack<P: Kem>(rng: &mut TestRng) {
+    let ((ek, dk), keygen_stack) = stack_usage(|| P::try_keygen_with_rng(&mut *rng).unwrap());
+    let ((ssk1, ct), encaps_stack) = stack_usage(|| ek.try_encaps_with_rng(&mut *rng).unwrap());
+    let (ssk2, decaps_stack) = stack_usage(|| dk.try_decaps(&ct).unwrap());
+    assert!(ssk1 == ssk2);
+    rprintln!(
+        "Stack usage (bytes): keygen {} encaps {} decaps {}",
+        keygen_stack,
+        encaps_stack,
+        decaps_stack
+    );
+}
+
+
+// GPIO output (edge connector pin 0) that is high for the duration of each triggering operation,
+// so that an oscilloscope or ChipWhisperer capture can be aligned to it
+type Trigger = Pin<Output<PushPull>>;
+
+
+// The SysTick (24-bit) counts down from `RELOAD` at the core clock and interrupts on each wrap to
+// extend the count, as in `ct_cm0`
+const RELOAD: u32 = 0x00FF_FFFF;
+static WRAPS: AtomicU32 = AtomicU32::new(0);
+
+#[exception]
+fn SysTick() {
+    // Only written here (and kept as load/store to match ARMv6-M, which has no atomic RMW)
+    WRAPS.store(WRAPS.load(Ordering::Relaxed).wrapping_add(1), Ordering::Relaxed);
+}
+
+
+// The source of cycle counts: the DWT cycle counter where present, or otherwise the SysTick. The
+// Cortex-M0/M0+/M23 (ARMv6-M and ARMv8-M Baseline) have no DWT cycle counter, so the SysTick path
+// can be selected here with the `systick` feature to validate it against the DWT on the same part.
+enum Clock {
+    Dwt,
+    SysTick(SYST),
+}
+
+impl Clock {
+    fn new(dcb: &mut DCB, dwt: &mut DWT, mut syst: SYST) -> Self {
+        dcb.enable_trace();
+        if !cfg!(feature = "systick") && dwt.has_cycle_counter() {
+            dwt.enable_cycle_counter();
+            return Clock::Dwt;
+        }
+        syst.set_clock_source(SystClkSource::Core);
+        syst.set_reload(RELOAD);
+        syst.clear_current();
+        syst.enable_interrupt();
+        syst.enable_counter();
+        Clock::SysTick(syst)
+    }
+
+    const fn name(&self) -> &'static str {
+        match self {
+            Clock::Dwt => "DWT cycle counter",
+            Clock::SysTick(_) => "SysTick",
+        }
+    }
+
+    // Restarts the SysTick so that its wrap interrupt(s) occur at the same point(s) within each run
+    // of an operation, and so that their own cycles do not vary the count
+    fn restart(&mut self) {
+        if let Clock::SysTick(syst) = self {
+            syst.clear_current();
+            while SYST::get_current() == 0 {} // the cleared counter reloads on the next tick
+            WRAPS.store(0, Ordering::Relaxed);
+        }
+    }
+
+    // Cycles since the last restart (SysTick) or an arbitrary point (DWT), re-reading the SysTick
+    // if it wrapped in between
+    fn cycles(&self) -> u32 {
+        match self {
+            Clock::Dwt => DWT::cycle_count(),
+            Clock::SysTick(_) => loop {
+                let wraps = WRAPS.load(Ordering::Relaxed);
+                let current = SYST::get_current();
+                if wraps == WRAPS.load(Ordering::Relaxed) {
+                    break wraps.wrapping_mul(RELOAD + 1).wrapping_add(RELOAD - current);
+                }
+            },
+        }
+    }
+}
+
+
+// The clock for measurements and the trigger for captures
+struct Bench {
+    clock: Clock,
+    trigger: Trigger,
+}
+
+
+// Runs a single operation between serializing cycle counter reads, returning its cycle count. When
+// `armed`, the trigger is raised immediately before and lowered immediately after the measurement
+// period, so that the trigger edges do not contribute to the cycle count.
+fn measure<T>(bench: &mut Bench, armed: bool, op: impl FnOnce() -> T) -> (T, u32) {
+    bench.clock.restart();
+    if armed {
+        bench.trigger.set_high().unwrap();
+    }
+
+    ///////////////////// Start measurement period
+    asm::isb();
+    let start = bench.clock.cycles();
+    asm::isb();
+
+    let result = op();
+
+    asm::isb();
+    let finish = bench.clock.cycles();
+    asm::isb();
+    ///////////////////// Finish measurement period
 
+    if armed {
+        bench.trigger.set_low().unwrap();
+    }
+    (result, finish.wrapping_sub(start))
+}
+
+
+const HELP: &str = "Commands: any of <512|768|1024|sweep> <keygen|encaps|decaps|all> \
+    <iterations> (0 runs forever) trigger=<keygen|encaps|decaps|all|off> stack, e.g., '768 decaps \
+    5000 trigger=decaps', then newline";
+
+
+// Iterations per parameter set for a sweep, covering two rho values
+const SWEEP_ITERATIONS: u32 = 2000;
+
+
+// The parameter set (or a sweep over each one built) and operation(s) to measure, how many
+// iterations (0 runs forever), and the operation(s) that toggle the trigger, plus whether to
+// first report the stack usage
+#[derive(Clone, Copy, Debug)]
+struct Config {
+    param_set: u16,
+    sweep: bool,
+    op: Op,
+    iterations: u32,
+    trigger: Option<Op>,
+    stack: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Op {
+    KeyGen,
+    Encaps,
+    Decaps,
+    All,
+}
+
+impl Config {
+    // Applies each whitespace-separated token of a command to a copy of the current config
+    fn update(self, command: &str) -> Result<Self, &'static str> {
+        let mut config = Config { stack: false, ..self };
+        for token in command.split_ascii_whitespace() {
+            match token {
+                "512" | "768" | "1024" => {
+                    config.param_set = token.parse().unwrap();
+                    config.sweep = false;
+                    if !built(config.param_set) {
+                        return Err("parameter set not built");
+                    }
+                }
+                "sweep" => config.sweep = true,
+                "keygen" => config.op = Op::KeyGen,
+                "encaps" => config.op = Op::Encaps,
+                "decaps" => config.op = Op::Decaps,
+                "all" => config.op = Op::All,
+                "trigger=keygen" => config.trigger = Some(Op::KeyGen),
+                "trigger=encaps" => config.trigger = Some(Op::Encaps),
+                "trigger=decaps" => config.trigger = Some(Op::Decaps),
+                "trigger=all" => config.trigger = Some(Op::All),
+                "trigger=off" => config.trigger = None,
+                "stack" => config.stack = true,
+                "help" => return Err("help"),
+                _ => config.iterations = token.parse().map_err(|_| "unrecognized token")?,
+            }
+        }
+        Ok(config)
+    }
+
+    // Whether the trigger is toggled around `op`
+    fn triggers(&self, op: Op) -> bool {
+        match self.trigger {
+            Some(Op::All) => true,
+            Some(trigger) => trigger == op,
+            None => false,
+        }
+    }
+}
+
+
+// Whether `param_set` is included in this build (see the crate features)
+fn built(param_set: u16) -> bool {
+    match param_set {
+        512 => cfg!(feature = "ml-kem-512"),
+        768 => cfg!(feature = "ml-kem-768"),
+        1024 => cfg!(feature = "ml-kem-1024"),
+        _ => false,
+    }
+}
+
+
+// Assembles command lines from the RTT down channel, without blocking
+struct Commands {
+    channel: DownChannel,
+    line: [u8; 64],
+    len: usize,
+}
+
+impl Commands {
+    // Returns the updated config once a complete command line has arrived
+    fn poll(&mut self, config: Config) -> Option<Config> {
+        let mut buf = [0u8; 16];
+        let count = self.channel.read(&mut buf);
+        for &byte in &buf[..count] {
+            if (byte != b'\n') & (byte != b'\r') {
+                if self.len < self.line.len() {
+                    self.line[self.len] = byte;
+                    self.len += 1;
+                }
+                continue;
+            }
+            if self.len == 0 {
+                continue;
+            }
+            let result = core::str::from_utf8(&self.line[..self.len])
+                .map_err(|_| "invalid UTF-8")
+                .and_then(|line| config.update(line));
+            self.len = 0;
+            match result {
+                Ok(new_config) => return Some(new_config),
+                Err(msg) => rprintln!("{}: {}", msg, HELP),
+            }
+        }
+        None
+    }
+}
+
+
+// Runs the configured measurements for parameter set `P` until the iterations are complete
+// (returning `None`) or a new command arrives (which is returned). The set is skipped when the
+// available stack is below its published bound `max_stack`.
+fn run<P: Kem>(
+    config: Config, max_stack: usize, commands: &mut Commands, leds: &mut DisplayPins,
+    bench: &mut Bench,
+) -> Option<Config> {
+    rprintln!("Running ML-KEM-{} {:?}", config.param_set, config);
+    let (_, _, stack) = memory();
+    if stack < max_stack {
+        rprintln!("Skipping: {} bytes of stack available, {} required", stack, max_stack);
+        return None;
+    }
+    if config.stack {
+        report_stack::<P>(&mut TestRng { rho: 999, value: 4 });
+    }
     let mut rng = TestRng { rho: 999, value: 4 }; // arbitrary choice (value must be mult of 4)
     let mut spare_draw = [0u8; 32];
-    let mut expected_cycles = 0;
+    let mut expected_keygen = Expected::new("keygen");
+    let mut expected_encaps = Expected::new("encaps");
+    let mut expected_decaps = Expected::new("decaps");
     let mut i = 0u32;
 
-    loop {
+    while (config.iterations == 0) | (i < config.iterations) {
+        if let Some(new_config) = commands.poll(config) {
+            return Some(new_config);
+        }
         if (i % 100) == 0 {
-            board.display_pins.row1.set_high().unwrap();
+            leds.row1.set_high().unwrap();
         };
         if (i % 100) == 50 {
-            board.display_pins.row1.set_low().unwrap();
+            leds.row1.set_low().unwrap();
         };
         i += 1;
 
-        ///////////////////// Start measurement period
-        asm::isb();
-        let start = DWT::cycle_count();
-        asm::isb();
-
-        let (ek, dk) = ml_kem_512::KG::try_keygen_with_rng(&mut rng).unwrap();
-        let (ssk1, ct) = ek.try_encaps_with_rng(&mut rng).unwrap();
-        let ssk2 = dk.try_decaps(&ct).unwrap();
-        assert_eq!(ssk1.into_bytes(), ssk2.into_bytes());
-
-        asm::isb();
-        let finish = DWT::cycle_count();
-        asm::isb();
-        ///////////////////// Finish measurement period
+        // Each operation is measured (and tracked) separately, so that a timing difference in one
+        // is not masked by (or attributed to) another
+        let ((ek, dk), keygen_count) = measure(bench, config.triggers(Op::KeyGen), || {
+            P::try_keygen_with_rng(&mut rng).unwrap()
+        });
+        let ((ssk1, ct), encaps_count) = measure(bench, config.triggers(Op::Encaps), || {
+            ek.try_encaps_with_rng(&mut rng).unwrap()
+        });
+        let (ssk2, decaps_count) =
+            measure(bench, config.triggers(Op::Decaps), || dk.try_decaps(&ct).unwrap());
+        assert!(ssk1 == ssk2);
 
         let _ = rng.try_fill_bytes(&mut spare_draw).unwrap(); // ease our lives; multiple of 4
-        let count = finish - start;
 
-        // each rho should have a fixed cycle count
+        // each rho should have a fixed cycle count per operation
         if (i % 1000) == 0 {
             rng.rho += 1
         };
-        // capture the cycle count
-        if (i % 1000) == 2 {
-            expected_cycles = count
-        };
-        // make sure it is constant
-        if ((i % 1000) > 2) & (count != expected_cycles) {
-            panic!("Non constant-time operation!! iteration:{} cycles:{}", i, count)
+        let counts = [
+            (Op::KeyGen, &mut expected_keygen, keygen_count),
+            (Op::Encaps, &mut expected_encaps, encaps_count),
+            (Op::Decaps, &mut expected_decaps, decaps_count),
+        ];
+        for (op, expected, count) in counts {
+            if (config.op == op) | (config.op == Op::All) {
+                expected.check(i, count);
+                if i % 100 == 0 {
+                    rprintln!("Iteration {} {} cycle count: {}", i, expected.op, count)
+                };
+            }
+        }
+    }
+
+    rprintln!(
+        "Completed {} iterations of ML-KEM-{} in constant time; cycle counts (final rho): \
+        keygen {} encaps {} decaps {}",
+        i,
+        config.param_set,
+        expected_keygen.cycles,
+        expected_encaps.cycles,
+        expected_decaps.cycles
+    );
+    None
+}
+
+
+// Runs the configured measurements for the selected parameter set, with its published stack bound
+fn run_set(
+    config: Config, commands: &mut Commands, leds: &mut DisplayPins, bench: &mut Bench,
+) -> Option<Config> {
+    match config.param_set {
+        #[cfg(feature = "ml-kem-512")]
+        512 => {
+            use ml_kem_512::{MAX_STACK_DECAP, MAX_STACK_ENCAP, MAX_STACK_KEYGEN};
+            let max_stack = MAX_STACK_KEYGEN.max(MAX_STACK_ENCAP).max(MAX_STACK_DECAP);
+            run::<ml_kem_512::KG>(config, max_stack, commands, leds, bench)
+        }
+        #[cfg(feature = "ml-kem-768")]
+        768 => {
+            use ml_kem_768::{MAX_STACK_DECAP, MAX_STACK_ENCAP, MAX_STACK_KEYGEN};
+            let max_stack = MAX_STACK_KEYGEN.max(MAX_STACK_ENCAP).max(MAX_STACK_DECAP);
+            run::<ml_kem_768::KG>(config, max_stack, commands, leds, bench)
+        }
+        #[cfg(feature = "ml-kem-1024")]
+        1024 => {
+            use ml_kem_1024::{MAX_STACK_DECAP, MAX_STACK_ENCAP, MAX_STACK_KEYGEN};
+            let max_stack = MAX_STACK_KEYGEN.max(MAX_STACK_ENCAP).max(MAX_STACK_DECAP);
+            run::<ml_kem_1024::KG>(config, max_stack, commands, leds, bench)
+        }
+        _ => unreachable!("parameter sets are checked by Config::update()"),
+    }
+}
+
+
+// Runs the configured measurements (with stack reports) for each parameter set built, in turn,
+// for `SWEEP_ITERATIONS` unless otherwise specified
+fn sweep(
+    config: Config, commands: &mut Commands, leds: &mut DisplayPins, bench: &mut Bench,
+) -> Option<Config> {
+    let iterations = if config.iterations == 0 {
+        SWEEP_ITERATIONS
+    } else {
+        config.iterations
+    };
+    for param_set in [512, 768, 1024] {
+        let set_config = Config { param_set, stack: true, iterations, ..config };
+        if built(param_set) {
+            if let Some(new_config) = run_set(set_config, commands, leds, bench) {
+                return Some(new_config);
+            }
+        }
+    }
+    None
+}
+
+
+#[entry]
+fn main() -> ! {
+    let mut board = Board::take().unwrap();
+    let clock = Clock::new(&mut board.DCB, &mut board.DWT, board.SYST);
+    board.display_pins.col1.set_low().unwrap();
+    let channels = rtt_init! {
+        up: { 0: { size: 1024, name: "Terminal" } }
+        down: { 0: { size: 64, name: "Terminal" } }
+    };
+    set_print_channel(channels.up.0);
+    let mut commands = Commands { channel: channels.down.0, line: [0u8; 64], len: 0 };
+    let trigger = board.edge.e00.into_push_pull_output(Level::Low).degrade();
+    let mut bench = Bench { clock, trigger };
+    rprintln!("{}", HELP);
+    rprintln!("Timing by {}", bench.clock.name());
+    let (flash, statics, stack) = memory();
+    rprintln!(
+        "Memory (bytes): flash {} static RAM {} stack available {}",
+        flash,
+        statics,
+        stack
+    );
+
+    // Starts with a sweep over each parameter set built, then waits for a command
+    let mut config = Config {
+        param_set: [512, 768, 1024].into_iter().find(|&param_set| built(param_set)).unwrap(),
+        sweep: true,
+        op: Op::All,
+        iterations: 0,
+        trigger: None,
+        stack: false,
+    };
+    loop {
+        let (leds, bench) = (&mut board.display_pins, &mut bench);
+        let next = if config.sweep {
+            sweep(config, &mut commands, leds, bench)
+        } else {
+            run_set(config, &mut commands, leds, bench)
         };
-        if i % 100 == 0 {
-            rprintln!("Iteration {} cycle count: {}", i, count)
+        config = match next {
+            Some(new_config) => new_config,
+            None => {
+                rprintln!("Waiting for a command");
+                loop {
+                    if let Some(new_config) = commands.poll(config) {
+                        break new_config;
+                    }
+                }
+            }
         };
     }
 }
@@ -0,0 +1,102 @@
+use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
+use fips203::traits::{Decaps, Encaps, KeyGen, SerDes};
+use secret::{classify, declassify};
+
+mod secret;
+
+// Each target below runs an operation with its secret inputs classified, so that the taint
+// tracking tool (Valgrind's memcheck, or MemorySanitizer) reports any branch or memory index that
+// depends upon them. Targets are selected with `--filter <name>` (a substring match); with no
+// filter, all run. Results are declassified before they are checked, as the harness's own checks
+// are not of interest.
+
+// Each target runs over this many distinct inputs
+const ROUNDS: u8 = 4;
+
+// The secret portions of an ML-KEM-512 dk, i.e., dk_pke (s_hat) at the start and z at the end; the
+// ek and H(ek) between them are public
+const DK_PKE_LEN: usize = 384 * 2;
+const Z_START: usize = ml_kem_512::DK_LEN - 32;
+
+
+fn classify_dk(dk_bytes: &[u8; ml_kem_512::DK_LEN]) {
+    classify(&dk_bytes[..DK_PKE_LEN]);
+    classify(&dk_bytes[Z_START..]);
+}
+
+
+// Key generation with secret d and z. Note that rho = G(d || k) is public (sent within ek), so the
+// rejection sampling of A_hat from it is expected to be reported; see `ctgrind.supp`.
+fn keygen() {
+    for i in 0..ROUNDS {
+        let (d, z) = ([i; 32], [i ^ 0xFF; 32]);
+        classify(&d);
+        classify(&z);
+        let (ek, _dk) = ml_kem_512::KG::keygen_from_seed(d, z);
+        declassify(&ek.into_bytes());
+    }
+}
+
+
+// Encapsulation to a public ek with a secret m, from which the secret r derives
+fn encaps() {
+    let (ek, _dk) = ml_kem_512::KG::keygen_from_seed([0x11; 32], [0x22; 32]);
+    for i in 0..ROUNDS {
+        let m = [i; 32];
+        classify(&m);
+        let (ssk, ct) = ek.encaps_from_seed(&m);
+        declassify(&ct.into_bytes());
+        declassify(&ssk.into_bytes());
+    }
+}
+
+
+// Deserialization (and validation) of a secret dk, then decapsulation of a public ciphertext that
+// is valid or, with `corrupt`, has a single bit flipped so as to be implicitly rejected
+fn decaps_with(corrupt: bool) {
+    for i in 0..ROUNDS {
+        let (ek, dk) = ml_kem_512::KG::keygen_from_seed([0x11; 32], [i; 32]);
+        let (ssk1, ct) = ek.encaps_from_seed(&[i ^ 0xFF; 32]);
+        let mut ct_bytes = ct.into_bytes();
+        ct_bytes[usize::from(i) * 7] ^= u8::from(corrupt) << (i % 8);
+        let ct = ml_kem_512::CipherText::try_from_bytes(ct_bytes).unwrap();
+        let dk_bytes = dk.into_bytes();
+        classify_dk(&dk_bytes);
+        let dk = ml_kem_512::DecapsKey::try_from_bytes(dk_bytes).unwrap();
+        let ssk2 = dk.try_decaps(&ct).unwrap();
+        let (ssk1, ssk2) = (ssk1.into_bytes(), ssk2.into_bytes());
+        declassify(&ssk2);
+        assert_eq!(ssk1 == ssk2, !corrupt);
+    }
+}
+
+fn decaps() { decaps_with(false) }
+
+fn decaps_rejection() { decaps_with(true) }
+
+
+const TARGETS: [(&str, fn()); 4] = [
+    ("keygen", keygen),
+    ("encaps", encaps),
+    ("decaps", decaps),
+    ("decaps_rejection", decaps_rejection),
+];
+
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let filter = match (args.next().as_deref(), args.next()) {
+        (None, _) => None,
+        (Some("--filter"), Some(filter)) => Some(filter),
+        _ => {
+            eprintln!("usage: fips203-ctgrind [--filter <substring>]");
+            std::process::exit(2);
+        }
+    };
+    for (name, target) in TARGETS {
+        if filter.as_ref().map_or(true, |filter| name.contains(filter.as_str())) {
+            println!("running {name}");
+            target();
+        }
+    }
+}
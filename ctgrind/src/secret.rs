@@ -0,0 +1,66 @@
+// Marks memory as secret (classify) or public (declassify) for the taint tracking tool. Under
+// Valgrind's memcheck, secret bytes are marked undefined, so that any branch or memory index that
+// depends upon them is reported as a use of uninitialised memory (as with Adam Langley's ctgrind).
+// The client requests are the magic instruction sequences of `valgrind.h`, which are no-ops when
+// not running under Valgrind. With the `msan` feature, MemorySanitizer's poisoning is used instead.
+
+#[cfg(not(feature = "msan"))]
+const MAKE_MEM_UNDEFINED: u64 = 0x4d43_0001; // VG_USERREQ_TOOL_BASE('M', 'C') + 1
+#[cfg(not(feature = "msan"))]
+const MAKE_MEM_DEFINED: u64 = 0x4d43_0002; // VG_USERREQ_TOOL_BASE('M', 'C') + 2
+
+
+/// Marks `bytes` as secret
+pub fn classify(bytes: &[u8]) {
+    #[cfg(not(feature = "msan"))]
+    client_request(MAKE_MEM_UNDEFINED, bytes);
+    #[cfg(feature = "msan")]
+    unsafe {
+        __msan_poison(bytes.as_ptr(), bytes.len());
+    }
+}
+
+/// Marks `bytes` as public, e.g., a ciphertext or an encapsulation key (before it is used)
+pub fn declassify(bytes: &[u8]) {
+    #[cfg(not(feature = "msan"))]
+    client_request(MAKE_MEM_DEFINED, bytes);
+    #[cfg(feature = "msan")]
+    unsafe {
+        __msan_unpoison(bytes.as_ptr(), bytes.len());
+    }
+}
+
+
+#[cfg(feature = "msan")]
+extern "C" {
+    fn __msan_poison(addr: *const u8, size: usize);
+    fn __msan_unpoison(addr: *const u8, size: usize);
+}
+
+
+// The arguments are the request followed by up to five parameters, and the result (ignored here)
+// is the default of zero when not running under Valgrind
+#[cfg(not(feature = "msan"))]
+fn client_request(request: u64, bytes: &[u8]) {
+    let args: [u64; 6] = [request, bytes.as_ptr() as u64, bytes.len() as u64, 0, 0, 0];
+    let mut _result: u64 = 0;
+    #[cfg(target_arch = "x86_64")]
+    unsafe {
+        // The rotations of rdi total 128 bits, so leave it unchanged, as does the exchange of rbx
+        core::arch::asm!(
+            "rol rdi, 3", "rol rdi, 13", "rol rdi, 61", "rol rdi, 51", "xchg rbx, rbx",
+            inout("rdx") _result, in("rax") args.as_ptr(),
+        );
+    }
+    #[cfg(target_arch = "aarch64")]
+    unsafe {
+        // Likewise, the rotations of x12 total 128 bits, and the orr of x10 is a no-op
+        core::arch::asm!(
+            "ror x12, x12, #3", "ror x12, x12, #13", "ror x12, x12, #51", "ror x12, x12, #61",
+            "orr x10, x10, x10",
+            inout("x3") _result, in("x4") args.as_ptr(),
+        );
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    compile_error!("the Valgrind client requests are implemented for x86_64 and aarch64 only");
+}
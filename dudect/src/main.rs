@@ -1,7 +1,21 @@
-use dudect_bencher::{ctbench_main, BenchRng, Class, CtRunner};
 use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
-use fips203::traits::{Decaps, Encaps, KeyGen};
-use rand_core::{CryptoRng, RngCore};
+use fips203::traits::{Decaps, Encaps, KeyGen, SerDes};
+use rand_core::{CryptoRng, RngCore, SeedableRng};
+use runner::{BenchRng, Class, CtRunner, Summary};
+use std::fmt::Write;
+
+mod runner;
+
+// Each target below is a separate bench, selected on the command line with `--filter <name>` (a
+// substring match), e.g., `cargo run --release -- --filter encaps_m`. With no filter, all run.
+// See `USAGE` below for the remaining options.
+
+const ITERATIONS_INNER: usize = 5;
+const ITERATIONS_OUTER: usize = 2_usize.pow(23); // 8_388_608; the default, see `--iterations`
+const THRESHOLD: f64 = 5.0; // the default, see `--threshold`
+
+// Inputs for the right class are drawn from a pool of this many, generated before timing starts
+const POOL: usize = 1024;
 
 
 // Simplistic RNG to regurgitate set value
@@ -29,10 +43,138 @@ impl CryptoRng for TestRng {}
 pub struct AlignedBytes<const BYTE_LEN: usize>(pub(crate) [u8; BYTE_LEN]);
 
 
-fn full_flow(runner: &mut CtRunner, mut _rng: &mut BenchRng) {
-    const ITERATIONS_INNER: usize = 5;
-    const ITERATIONS_OUTER: usize = 2_usize.pow(23); // 8_388_608;
+// Interleaves left and right, with the left class given copies of `left` and the right class
+// given successive entries of `right`. The copies are as many as the entries, so that both classes
+// touch the same amount of memory (and suffer the same cache misses).
+fn run<T: Clone>(runner: &mut CtRunner, left: &T, right: &[T], op: impl Fn(&T)) {
+    let left = vec![left.clone(); right.len()];
+    for i in 0..runner.iterations() {
+        let (class, input) = if i % 2 == 0 {
+            (Class::Left, &left[(i / 2) % left.len()])
+        } else {
+            (Class::Right, &right[(i / 2) % right.len()])
+        };
+        runner.run_one(class, || {
+            for _ in 0..ITERATIONS_INNER {
+                op(input);
+            }
+        })
+    }
+}
+
+
+fn random_32(rng: &mut BenchRng) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    rng.fill_bytes(&mut bytes);
+    bytes
+}
+
+
+// Key generation from a fixed vs random `z`. Note that d drives rho, which is not constant time
+// (the rejection sampling of A_hat) and is sent in the clear within ek, so d is held fixed; see
+// steps 1 & 19 of k_pke_key_gen.
+fn keygen_seeded(runner: &mut CtRunner, rng: &mut BenchRng) {
+    let d = AlignedBytes::<32>([0u8; 32]);
+    let z_left = AlignedBytes::<32>([0x55u8; 32]);
+    let z_right: Vec<[u8; 32]> = (0..POOL).map(|_| random_32(rng)).collect();
+    run(runner, &z_left.0, &z_right, |z| {
+        let _keys = ml_kem_512::KG::keygen_from_seed(d.0, *z);
+    });
+}
+
+
+// Encapsulation to a fixed ek with a fixed vs random `m`, which derives the secret r
+fn encaps_m(runner: &mut CtRunner, rng: &mut BenchRng) {
+    let (ek, _dk) = ml_kem_512::KG::keygen_from_seed([0x11u8; 32], [0x22u8; 32]);
+    let m_left = AlignedBytes::<32>([0x55u8; 32]);
+    let m_right: Vec<[u8; 32]> = (0..POOL).map(|_| random_32(rng)).collect();
+    run(runner, &m_left.0, &m_right, |m| {
+        let _ssk_ct = ek.encaps_from_seed(m);
+    });
+}
+
+
+// Decapsulation of a (valid) ciphertext under a fixed vs random dk. Each dk shares the seed d,
+// and so rho and A_hat, with the fixed one (see keygen_seeded above), but has its own z.
+fn decaps_dk(runner: &mut CtRunner, rng: &mut BenchRng) {
+    let pair = |rng: &mut BenchRng, z: [u8; 32]| {
+        let (ek, dk) = ml_kem_512::KG::keygen_from_seed([0x11u8; 32], z);
+        let (_ssk, ct) = ek.encaps_from_seed(&random_32(rng));
+        (dk, ct)
+    };
+    let left = pair(rng, [0x55u8; 32]);
+    let right: Vec<_> = (0..POOL)
+        .map(|_| {
+            let z = random_32(rng);
+            pair(rng, z)
+        })
+        .collect();
+    run(runner, &left, &right, |(dk, ct)| {
+        let _ssk = dk.try_decaps(ct).unwrap();
+    });
+}
+
+
+// Deserialization (and validation) of a fixed vs random dk, as above
+fn dk_deserialization(runner: &mut CtRunner, rng: &mut BenchRng) {
+    let d = [0x11u8; 32];
+    let dk_bytes = |z: [u8; 32]| ml_kem_512::KG::keygen_from_seed(d, z).1.into_bytes();
+    let left = dk_bytes([0x55u8; 32]);
+    let right: Vec<_> = (0..POOL).map(|_| dk_bytes(random_32(rng))).collect();
+    run(runner, &left, &right, |dk| {
+        let _dk = ml_kem_512::DecapsKey::try_from_bytes(*dk).unwrap();
+    });
+}
+
+
+// Decapsulates valid vs corrupted ciphertexts under a fixed dk, so the two classes differ only in
+// whether the re-encryption matches, i.e., in the `ct_ne()`/`conditional_assign()` implicit
+// rejection at steps 9-11 of decaps_internal. Both classes draw from equally sized pools of
+// ciphertexts, each corrupted one being a valid one with a single bit flipped.
+fn decaps_rejection(runner: &mut CtRunner, rng: &mut BenchRng) {
+    let (ek, dk) = ml_kem_512::KG::keygen_from_seed([0x11u8; 32], [0x22u8; 32]);
+
+    let mut valid = Vec::with_capacity(POOL);
+    let mut corrupted = Vec::with_capacity(POOL);
+    for _ in 0..POOL {
+        let (_ssk, ct) = ek.encaps_from_seed(&random_32(rng));
+        let mut ct_bytes = ct.clone().into_bytes();
+        let bit = rng.next_u32() as usize % (8 * ct_bytes.len());
+        ct_bytes[bit / 8] ^= 1 << (bit % 8);
+        corrupted.push(ml_kem_512::CipherText::try_from_bytes(ct_bytes).unwrap());
+        valid.push(ct);
+    }
+
+    for i in 0..runner.iterations() {
+        // Interleave left and right
+        let (class, ct) = if i % 2 == 0 {
+            (Class::Left, &valid[(i / 2) % POOL])
+        } else {
+            (Class::Right, &corrupted[(i / 2) % POOL])
+        };
+        runner.run_one(class, || {
+            for _ in 0..ITERATIONS_INNER {
+                let _ssk = dk.try_decaps(ct).unwrap();
+            }
+        })
+    }
+}
 
+
+// Decapsulates valid ciphertexts under a fixed dk, of a fixed vs random message m, so the two
+// classes differ only in the secret message recovered by decryption, i.e., in the
+// `ByteEncode_1(Compress_1(w))` message extraction (and the re-encryption that follows).
+fn decaps_message(runner: &mut CtRunner, rng: &mut BenchRng) {
+    let (ek, dk) = ml_kem_512::KG::keygen_from_seed([0x11u8; 32], [0x22u8; 32]);
+    let left = ek.encaps_from_seed(&[0x55u8; 32]).1;
+    let right: Vec<_> = (0..POOL).map(|_| ek.encaps_from_seed(&random_32(rng)).1).collect();
+    run(runner, &left, &right, |ct| {
+        let _ssk = dk.try_decaps(ct).unwrap();
+    });
+}
+
+
+fn full_flow(runner: &mut CtRunner, mut _rng: &mut BenchRng) {
     let z_left = AlignedBytes::<32>([0x55u8; 32]);
     let z_right = AlignedBytes::<32>([0xaau8; 32]);
 
@@ -40,11 +182,11 @@ fn full_flow(runner: &mut CtRunner, mut _rng: &mut BenchRng) {
     // See step 1 & 19 of k_pke_key_gen
     let d = AlignedBytes::<32>([0u8; 32]);
 
-    let mut classes = vec![Class::Right; ITERATIONS_OUTER];
-    let mut z_refs = vec![&z_right.0; ITERATIONS_OUTER];
+    let mut classes = vec![Class::Right; runner.iterations()];
+    let mut z_refs = vec![&z_right.0; runner.iterations()];
 
     // Interleave left and right
-    for i in (0..(ITERATIONS_OUTER)).step_by(2) {
+    for i in (0..(runner.iterations())).step_by(2) {
         classes[i] = Class::Left;
         z_refs[i] = &z_left.0;
     }
@@ -62,4 +204,118 @@ fn full_flow(runner: &mut CtRunner, mut _rng: &mut BenchRng) {
     }
 }
 
-ctbench_main!(full_flow);
+type Target = fn(&mut CtRunner, &mut BenchRng);
+
+const TARGETS: [(&str, Target); 7] = [
+    ("keygen_seeded", keygen_seeded),
+    ("encaps_m", encaps_m),
+    ("decaps_dk", decaps_dk),
+    ("dk_deserialization", dk_deserialization),
+    ("decaps_rejection", decaps_rejection),
+    ("decaps_message", decaps_message),
+    ("full_flow", full_flow),
+];
+
+const USAGE: &str = "usage: fips203-dudect [--filter <substring>] [--iterations <n>] \
+                     [--threshold <t>] [--seed <u64>] [--json <path>]";
+
+
+struct Options {
+    filter: Option<String>,
+    iterations: usize,
+    threshold: f64,
+    seed: u64,
+    json: Option<String>,
+}
+
+impl Options {
+    fn parse(mut args: impl Iterator<Item = String>) -> Result<Self, String> {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map_or(0, |time| time.as_nanos() as u64);
+        let mut options = Options {
+            filter: None,
+            iterations: ITERATIONS_OUTER,
+            threshold: THRESHOLD,
+            seed,
+            json: None,
+        };
+        while let Some(flag) = args.next() {
+            let value = args.next().ok_or(format!("missing value for {flag}"))?;
+            let invalid = || format!("invalid value for {flag}: {value}");
+            match flag.as_str() {
+                "--filter" => options.filter = Some(value),
+                "--iterations" => options.iterations = value.parse().map_err(|_| invalid())?,
+                "--threshold" => options.threshold = value.parse().map_err(|_| invalid())?,
+                "--seed" => options.seed = value.parse().map_err(|_| invalid())?,
+                "--json" => options.json = Some(value),
+                _ => return Err(format!("unknown option {flag}")),
+            }
+        }
+        Ok(options)
+    }
+}
+
+
+// The report, with the configuration so that runs can be compared
+fn to_json(options: &Options, summaries: &[Summary]) -> String {
+    let mut json = String::new();
+    let pass = summaries.iter().all(|summary| summary.passes(options.threshold));
+    let _ = write!(
+        json,
+        "{{\n  \"config\": {{\"param_set\": \"ml-kem-512\", \"iterations\": {}, \"inner\": {}, \
+         \"threshold\": {}, \"seed\": {}}},\n  \"pass\": {pass},\n  \"targets\": [",
+        options.iterations, ITERATIONS_INNER, options.threshold, options.seed
+    );
+    for (i, summary) in summaries.iter().enumerate() {
+        let _ = write!(
+            json,
+            "{}\n    {{\"name\": \"{}\", \"samples\": {}, \"max_t\": {}, \"max_tau\": {}, \"pass\": {}}}",
+            if i == 0 { "" } else { "," },
+            summary.name,
+            summary.samples,
+            summary.max_t,
+            summary.max_tau,
+            summary.passes(options.threshold)
+        );
+    }
+    json.push_str("\n  ]\n}\n");
+    json
+}
+
+
+// Exits with 1 when any target exceeds the threshold, and 2 upon a usage or output error
+fn main() {
+    let options = Options::parse(std::env::args().skip(1)).unwrap_or_else(|err| {
+        eprintln!("{err}\n{USAGE}");
+        std::process::exit(2);
+    });
+    let selected = TARGETS.iter().filter(|(name, _)| {
+        options.filter.as_ref().map_or(true, |filter| name.contains(filter.as_str()))
+    });
+    println!("running with seed {:#x}", options.seed);
+    let mut summaries = Vec::new();
+    for (name, target) in selected {
+        let mut runner = CtRunner::new(options.iterations);
+        target(&mut runner, &mut BenchRng::seed_from_u64(options.seed));
+        let summary = runner.summary(name);
+        println!(
+            "bench {name} ... : n == {:+.3}M, max t = {:+.5}, max tau = {:+.5}, (5/tau)^2 = {:.0}{}",
+            summary.samples as f64 / 1e6,
+            summary.max_t,
+            summary.max_tau,
+            (5.0 / summary.max_tau).powi(2),
+            if summary.passes(options.threshold) { "" } else { "  <-- FAIL" }
+        );
+        summaries.push(summary);
+    }
+    if let Some(path) = &options.json {
+        if let Err(err) = std::fs::write(path, to_json(&options, &summaries)) {
+            eprintln!("failed to write {path}: {err}");
+            std::process::exit(2);
+        }
+    }
+    if !summaries.iter().all(|summary| summary.passes(options.threshold)) {
+        std::process::exit(1);
+    }
+}
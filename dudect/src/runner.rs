@@ -0,0 +1,160 @@
+// A minimal dudect-style runner (after Reparaz, Balasch and Verbauwhede, "Dude, is my code constant
+// time?"), in the manner of the `dudect-bencher` crate but with the results available for a JSON
+// report and a pass/fail threshold. Each measurement is a wall-clock duration of one closure,
+// tagged with its input class. Welch's t-test is run over all measurements, and again over those
+// below each of a series of percentiles (cropping), as long tails can mask a difference. The
+// percentiles are fixed from an initial batch of measurements, which is otherwise discarded.
+
+use std::time::Instant;
+
+/// The RNG handed to each target for generating its inputs
+pub type BenchRng = rand_chacha::ChaCha8Rng;
+
+/// The input class of a measurement, i.e., fixed (left) or random (right)
+#[derive(Clone, Copy)]
+pub enum Class {
+    Left,
+    Right,
+}
+
+const WARMUP: usize = 10_000;
+const PERCENTILES: usize = 100;
+
+// A cropped test only counts towards the maximum once it has at least this many measurements
+const MIN_SAMPLES: f64 = 10_000.0;
+
+
+// Online mean and variance via Welford's method
+#[derive(Clone, Copy, Default)]
+struct Moments {
+    n: f64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    fn push(&mut self, x: f64) {
+        self.n += 1.0;
+        let delta = x - self.mean;
+        self.mean += delta / self.n;
+        self.m2 += delta * (x - self.mean);
+    }
+
+    fn variance(&self) -> f64 {
+        if self.n < 2.0 {
+            0.0
+        } else {
+            self.m2 / (self.n - 1.0)
+        }
+    }
+}
+
+
+// Welch's t-test between the left and right classes
+#[derive(Clone, Copy, Default)]
+struct TTest([Moments; 2]);
+
+impl TTest {
+    fn push(&mut self, class: Class, x: f64) { self.0[class as usize].push(x) }
+
+    fn samples(&self) -> f64 { self.0[0].n + self.0[1].n }
+
+    fn t(&self) -> f64 {
+        let [l, r] = self.0;
+        let se = (l.variance() / l.n + r.variance() / r.n).sqrt();
+        if se > 0.0 {
+            (l.mean - r.mean) / se
+        } else {
+            0.0
+        }
+    }
+}
+
+
+/// The outcome of one target
+pub struct Summary {
+    pub name: &'static str,
+    pub samples: usize,
+    pub max_t: f64,
+    pub max_tau: f64,
+}
+
+impl Summary {
+    /// Whether `max_t` is within the threshold (in magnitude)
+    pub fn passes(&self, threshold: f64) -> bool { self.max_t.abs() <= threshold }
+}
+
+
+/// Times closures by class, as `dudect_bencher::CtRunner`
+pub struct CtRunner {
+    iterations: usize,
+    warmup: Vec<u64>,
+    cutoffs: Vec<u64>,
+    tests: Vec<TTest>, // tests[0] is uncropped, tests[1 + k] is cropped at cutoffs[k]
+}
+
+impl CtRunner {
+    pub fn new(iterations: usize) -> Self {
+        Self {
+            iterations,
+            warmup: Vec::with_capacity(WARMUP),
+            cutoffs: Vec::new(),
+            tests: Vec::new(),
+        }
+    }
+
+    /// The number of measurements each target should make (i.e., calls to `run_one()`)
+    pub fn iterations(&self) -> usize { self.iterations }
+
+    /// Times a single call of `f` as a measurement of `class`
+    pub fn run_one<T>(&mut self, class: Class, f: impl Fn() -> T) {
+        let start = Instant::now();
+        let result = std::hint::black_box(f());
+        let elapsed = u64::try_from(start.elapsed().as_nanos()).unwrap_or(u64::MAX);
+        drop(result);
+        self.record(class, elapsed);
+    }
+
+    fn record(&mut self, class: Class, elapsed: u64) {
+        if self.cutoffs.is_empty() {
+            self.warmup.push(elapsed);
+            if self.warmup.len() == WARMUP {
+                // As dudect, percentiles 1 - 0.5^(10·(k + 1) / 100), i.e., concentrated near the top
+                self.warmup.sort_unstable();
+                self.cutoffs = (0..PERCENTILES)
+                    .map(|k| {
+                        let p = 1.0 - 0.5f64.powf(10.0 * (k + 1) as f64 / PERCENTILES as f64);
+                        self.warmup[(p * WARMUP as f64) as usize]
+                    })
+                    .collect();
+                self.tests = vec![TTest::default(); 1 + PERCENTILES];
+            }
+            return;
+        }
+        let x = elapsed as f64;
+        self.tests[0].push(class, x);
+        for (test, &cutoff) in self.tests[1..].iter_mut().zip(&self.cutoffs) {
+            if elapsed < cutoff {
+                test.push(class, x);
+            }
+        }
+    }
+
+    /// The largest t (in magnitude) across the uncropped and cropped tests
+    pub fn summary(&self, name: &'static str) -> Summary {
+        let max_t = self
+            .tests
+            .iter()
+            .enumerate()
+            .filter(|(i, test)| *i == 0 || test.samples() >= MIN_SAMPLES)
+            .map(|(_, test)| test.t())
+            .fold(0.0, |max: f64, t| if t.abs() > max.abs() { t } else { max });
+        let samples = self.tests.first().map_or(0.0, TTest::samples);
+        let max_tau = if samples > 0.0 {
+            max_t / samples.sqrt()
+        } else {
+            0.0
+        };
+        Summary { name, samples: samples as usize, max_t, max_tau }
+    }
+}
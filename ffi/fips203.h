@@ -10,8 +10,19 @@
   between calls, and should be completely reentrant.
 
   These functions return 0 (ML_KEM_OK) on success, or a more specific
-  non-zero octet on error.
+  non-zero octet on error. On error, every non-NULL output buffer is
+  zeroed rather than left stale or partially written.
+
+  A failure of the randomness (the OS RNG or the registered callback)
+  is reported as ML_KEM_ENTROPY_ERROR. An encapsulation key with a
+  coefficient not reduced modulo q is reported as
+  ML_KEM_ENCAPS_KEY_ERROR, and a decapsulation key whose embedded
+  encapsulation key is malformed, or whose embedded hash of it does
+  not match, as ML_KEM_DECAPS_KEY_ERROR or
+  ML_KEM_DECAPS_KEY_HASH_ERROR respectively. ml_kem_strerror()
+  describes any code.
 */
+#include <stddef.h>
 #include <stdint.h>
 
 typedef uint8_t ml_kem_err;
@@ -23,6 +34,16 @@ const ml_kem_err ML_KEM_DESERIALIZATION_ERROR = 3;
 const ml_kem_err ML_KEM_KEYGEN_ERROR = 4;
 const ml_kem_err ML_KEM_ENCAPSULATION_ERROR = 5;
 const ml_kem_err ML_KEM_DECAPSULATION_ERROR = 6;
+const ml_kem_err ML_KEM_ENTROPY_ERROR = 7;
+const ml_kem_err ML_KEM_ENCAPS_KEY_ERROR = 8;
+const ml_kem_err ML_KEM_DECAPS_KEY_ERROR = 9;
+const ml_kem_err ML_KEM_DECAPS_KEY_HASH_ERROR = 10;
+
+/*
+  Entropy callback: fills len bytes at out, returning 0 on success
+  (or non-zero on failure). ctx is as passed at registration.
+*/
+typedef int (*ml_kem_entropy_callback)(void *ctx, uint8_t *out, size_t len);
 
 
 typedef struct ml_kem_shared_secret {
@@ -33,6 +54,10 @@ typedef struct ml_kem_seed {
   uint8_t data[64];
 } ml_kem_seed;
 
+typedef struct ml_kem_encaps_seed {
+  uint8_t data[32];
+} ml_kem_encaps_seed;
+
 
 typedef struct ml_kem_512_encaps_key {
   uint8_t data[800];
@@ -75,8 +100,31 @@ extern "C" {
 #endif
 
 
+/*
+  Returns a static, NUL-terminated description of err (which must
+  not be freed), or "unknown error" for an unrecognized code.
+*/
+const char *ml_kem_strerror(ml_kem_err err);
+
+/*
+  Registers the source of randomness for keygen, encaps and
+  ml_kem_populate_seed(). Passing NULL reverts to the OS RNG; the
+  bare-metal (no_std) build has no OS RNG, so requires a callback.
+  Register once at startup, before any other call.
+*/
+ml_kem_err ml_kem_set_entropy_callback(ml_kem_entropy_callback callback,
+                                       void *ctx);
+
 ml_kem_err ml_kem_populate_seed(ml_kem_seed *seed_out);
 
+/*
+  The *_keygen_from_seed() functions take the seed d (the first 32
+  octets) and z (the last 32 octets), and the *_encaps_from_seed()
+  functions take the message m. They are deterministic, for
+  known-answer tests, reproducible tests and seed-based key storage;
+  FIPS 203 requires that d, z and m otherwise come from an approved
+  random bit generator, as used by *_keygen() and *_encaps().
+*/
 ml_kem_err ml_kem_512_keygen(ml_kem_512_encaps_key *encaps_out,
                              ml_kem_512_decaps_key *decaps_out);
 
@@ -88,6 +136,11 @@ ml_kem_err ml_kem_512_encaps(const ml_kem_512_encaps_key *encaps,
                              ml_kem_512_ciphertext *ciphertext_out,
                              ml_kem_shared_secret *shared_secret_out);
 
+ml_kem_err ml_kem_512_encaps_from_seed(const ml_kem_512_encaps_key *encaps,
+                                       const ml_kem_encaps_seed *m,
+                                       ml_kem_512_ciphertext *ciphertext_out,
+                                       ml_kem_shared_secret *shared_secret_out);
+
 ml_kem_err ml_kem_512_decaps(const ml_kem_512_decaps_key *decaps,
                              const ml_kem_512_ciphertext *ciphertext,
                              ml_kem_shared_secret *shared_secret_out);
@@ -103,6 +156,11 @@ ml_kem_err ml_kem_768_encaps(const ml_kem_768_encaps_key *encaps,
                              ml_kem_768_ciphertext *ciphertext_out,
                              ml_kem_shared_secret *shared_secret_out);
 
+ml_kem_err ml_kem_768_encaps_from_seed(const ml_kem_768_encaps_key *encaps,
+                                       const ml_kem_encaps_seed *m,
+                                       ml_kem_768_ciphertext *ciphertext_out,
+                                       ml_kem_shared_secret *shared_secret_out);
+
 ml_kem_err ml_kem_768_decaps(const ml_kem_768_decaps_key *decaps,
                              const ml_kem_768_ciphertext *ciphertext,
                              ml_kem_shared_secret *shared_secret_out);
@@ -118,6 +176,11 @@ ml_kem_err ml_kem_1024_encaps(const ml_kem_1024_encaps_key *encaps,
                               ml_kem_1024_ciphertext *ciphertext_out,
                               ml_kem_shared_secret *shared_secret_out);
 
+ml_kem_err ml_kem_1024_encaps_from_seed(const ml_kem_1024_encaps_key *encaps,
+                                        const ml_kem_encaps_seed *m,
+                                        ml_kem_1024_ciphertext *ciphertext_out,
+                                        ml_kem_shared_secret *shared_secret_out);
+
 ml_kem_err ml_kem_1024_decaps(const ml_kem_1024_decaps_key *decaps,
                               const ml_kem_1024_ciphertext *ciphertext,
                               ml_kem_shared_secret *shared_secret_out);
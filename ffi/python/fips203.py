@@ -35,6 +35,16 @@ seed2 = Seed(b'\x00'*ML_KEM_512.SEED_SIZE)  # This seed is clearly not a secret!
 ```
 
 
+Encapsulation can likewise be done deterministically, by passing the
+32-byte message m to `encaps`:
+
+```
+(ct1, ss1) = ek1.encaps(b'\x00'*32)  # Again, clearly not a secret!
+(ct2, ss2) = ek1.encaps(b'\x00'*32)
+assert(bytes(ct1) == bytes(ct2) and ss1 == ss2)
+```
+
+
 Encapsulation keys, decapsulation keys, seeds, and ciphertexts can all
 be serialized by accessing them as `bytes`, and deserialized by
 initializing them with the appropriate size bytes object.
@@ -126,6 +136,9 @@ class _SharedSecret(ctypes.Structure):
 class _Seed(ctypes.Structure):
     _fields_ = [('data', ctypes.c_uint8 * 64)]
 
+class _EncapsSeed(ctypes.Structure):
+    _fields_ = [('data', ctypes.c_uint8 * 32)]
+
 class Err(enum.IntEnum):
     OK = 0
     NULL_PTR_ERROR = 1
@@ -134,6 +147,10 @@ class Err(enum.IntEnum):
     KEYGEN_ERROR = 4
     ENCAPSULATION_ERROR = 5
     DECAPSULATION_ERROR = 6
+    ENTROPY_ERROR = 7
+    ENCAPS_KEY_ERROR = 8
+    DECAPS_KEY_ERROR = 9
+    DECAPS_KEY_HASH_ERROR = 10
 
 
 class Seed():
@@ -238,15 +255,33 @@ class EncapsulationKey():
         for i in range(len(data)):
             self._ek.data[i] = data[i]
 
-    def encaps(self) -> Tuple[Ciphertext, bytes]:
-        '''Produce a new Ciphertext and corresponding 32-byte shared secret.'''
+    def encaps(self, m: Optional[bytes] = None) -> Tuple[Ciphertext, bytes]:
+        '''Produce a new Ciphertext and corresponding 32-byte shared secret.
+
+        If a 32-byte message m is supplied, do a deterministic
+        encapsulation from it (e.g., for known-answer tests).
+        Otherwise, m is randomly generated.'''
         ct = Ciphertext(self._strength)
         ss = _SharedSecret()
-        ret = Err(self._ffi['encaps'](ctypes.byref(self._ek),
-                                      ctypes.byref(ct._ct),
-                                      ctypes.byref(ss)))
+        if m is None:
+            ret = Err(self._ffi['encaps'](ctypes.byref(self._ek),
+                                          ctypes.byref(ct._ct),
+                                          ctypes.byref(ss)))
+            name = 'encaps'
+        else:
+            seed = _EncapsSeed()
+            if len(m) != len(seed.data):
+                raise ValueError(f"Expected {len(seed.data)} bytes, "
+                                 f"got {len(m)}")
+            for i in range(len(m)):
+                seed.data[i] = m[i]
+            ret = Err(self._ffi['encaps_from_seed'](ctypes.byref(self._ek),
+                                                    ctypes.byref(seed),
+                                                    ctypes.byref(ct._ct),
+                                                    ctypes.byref(ss)))
+            name = 'encaps_from_seed'
         if ret is not Err.OK:
-            raise Exception(f"ml_kem_{self._strength}_encaps() "
+            raise Exception(f"ml_kem_{self._strength}_{name}() "
                             f"returned {ret} ({ret.name})")
         return (ct, bytes(ss.data))
 
@@ -357,6 +392,13 @@ class _ML_KEM():
                                       ctypes.POINTER(_SharedSecret)]
             ffi['encaps'].restype = ctypes.c_uint8
 
+            ffi['encaps_from_seed'] = cls.lib[f'ml_kem_{level}_encaps_from_seed']
+            ffi['encaps_from_seed'].argtypes = [ctypes.POINTER(_EncapsKey),
+                                                ctypes.POINTER(_EncapsSeed),
+                                                ctypes.POINTER(_Ciphertext),
+                                                ctypes.POINTER(_SharedSecret)]
+            ffi['encaps_from_seed'].restype = ctypes.c_uint8
+
             ffi['decaps'] = cls.lib[f'ml_kem_{level}_decaps']
             ffi['decaps'].argtypes = [ctypes.POINTER(_DecapsKey),
                                       ctypes.POINTER(_Ciphertext),
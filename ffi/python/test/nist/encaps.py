@@ -0,0 +1,107 @@
+#!/usr/bin/python3
+"""Tests for fips203 python module
+
+From the ffi/python/ directory, do:
+
+PYTHONPATH=. test/nist/encaps.py
+
+"""
+from __future__ import annotations
+
+import fips203
+import json
+import re
+from binascii import a2b_hex, b2a_hex
+
+from typing import Dict, Union, List, TypedDict
+
+with open(
+    "../../tests/nist_vectors/ML-KEM-encapDecap-FIPS203/internalProjection.json"
+) as f:
+    t = json.load(f)
+
+assert t["vsId"] == 42
+assert t["algorithm"] == "ML-KEM"
+assert t["mode"] == "encapDecap"
+assert t["revision"] == "FIPS203"
+
+
+class EncapsTestData(TypedDict):
+    tcId: int
+    deferred: bool
+    ek: str
+    dk: str
+    c: str
+    k: str
+    m: str
+
+
+class EncapsTest:
+    def __init__(self, data: EncapsTestData):
+        self.tcId = data["tcId"]
+        self.deferred = data["deferred"]
+        self.ek = a2b_hex(data["ek"])
+        self.dk = a2b_hex(data["dk"])
+        self.c = a2b_hex(data["c"])
+        self.k = a2b_hex(data["k"])
+        self.m = a2b_hex(data["m"])
+
+    def run(self, group: TestGroup) -> None:
+        ek = fips203.EncapsulationKey(self.ek)
+        (ct, k) = ek.encaps(self.m)
+        if bytes(ct) != self.c:
+            raise Exception(
+                f"""test {self.tcId} (group {group.tgId}, str: {group.strength}) c failed:
+                   got: {b2a_hex(bytes(ct))}
+                wanted: {b2a_hex(self.c)}"""
+            )
+        if k != self.k:
+            raise Exception(
+                f"""test {self.tcId} (group {group.tgId}, str: {group.strength}) k failed:
+                   got: {b2a_hex(k)}
+                wanted: {b2a_hex(self.k)}"""
+            )
+        dk = fips203.DecapsulationKey(self.dk)
+        if dk.decaps(ct) != self.k:
+            raise Exception(
+                f"test {self.tcId} (group {group.tgId}, str: {group.strength}) decaps failed"
+            )
+
+
+class TestGroupData(TypedDict):
+    tgId: int
+    testType: str
+    parameterSet: str
+    function: str
+    tests: List[EncapsTestData]
+
+
+class TestGroup:
+    param_matcher = re.compile("^ML-KEM-(?P<strength>512|768|1024)$")
+
+    def __init__(self, d: TestGroupData) -> None:
+        self.tgId: int = d["tgId"]
+        self.testType: str = d["testType"]
+        assert self.testType == "AFT"
+        self.parameterSet: str = d["parameterSet"]
+        m = self.param_matcher.match(self.parameterSet)
+        assert m
+        self.strength: int = int(m["strength"])
+        self.tests: List[EncapsTest] = []
+        for t in d["tests"]:
+            self.tests.append(EncapsTest(t))
+
+    def run(self) -> None:
+        for t in self.tests:
+            t.run(self)
+
+
+groups: List[TestGroup] = []
+for g in t["testGroups"]:
+    if g["function"] == "encapsulation":
+        groups.append(TestGroup(g))
+
+assert len(groups) == 3
+
+for g in groups:
+    g.run()
@@ -1,15 +1,35 @@
-use rand_core::{OsRng, RngCore};
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use core::ffi::{c_char, c_void};
+use core::sync::atomic::{AtomicPtr, Ordering};
+use fips203::traits::EntropySource;
+use zeroize::Zeroize;
+
+// Without `std` (and so without an operating system), there is no one else to provide this
+#[cfg(all(not(feature = "std"), not(test)))]
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    loop {}
+}
 
 #[repr(C)]
+#[derive(Zeroize)]
 pub struct ml_kem_shared_secret {
     data: [u8; fips203::SSK_LEN],
 }
 
 #[repr(C)]
+#[derive(Zeroize)]
 pub struct ml_kem_seed {
     data: [u8; 64],
 }
 
+#[repr(C)]
+#[derive(Zeroize)]
+pub struct ml_kem_encaps_seed {
+    data: [u8; 32],
+}
+
 pub const ML_KEM_OK: u8 = 0;
 pub const ML_KEM_NULL_PTR_ERROR: u8 = 1;
 pub const ML_KEM_SERIALIZATION_ERROR: u8 = 2;
@@ -17,27 +37,163 @@ pub const ML_KEM_DESERIALIZATION_ERROR: u8 = 3;
 pub const ML_KEM_KEYGEN_ERROR: u8 = 4;
 pub const ML_KEM_ENCAPSULATION_ERROR: u8 = 5;
 pub const ML_KEM_DECAPSULATION_ERROR: u8 = 6;
+pub const ML_KEM_ENTROPY_ERROR: u8 = 7;
+pub const ML_KEM_ENCAPS_KEY_ERROR: u8 = 8;
+pub const ML_KEM_DECAPS_KEY_ERROR: u8 = 9;
+pub const ML_KEM_DECAPS_KEY_HASH_ERROR: u8 = 10;
+
+/// Returns a static, NUL-terminated description of `err`, which the caller must not free.
+#[no_mangle]
+pub extern "C" fn ml_kem_strerror(err: u8) -> *const c_char {
+    let description: &'static [u8] = match err {
+        ML_KEM_OK => b"success\0",
+        ML_KEM_NULL_PTR_ERROR => b"null pointer argument\0",
+        ML_KEM_SERIALIZATION_ERROR => b"serialization failed\0",
+        ML_KEM_DESERIALIZATION_ERROR => b"deserialization failed\0",
+        ML_KEM_KEYGEN_ERROR => b"key generation failed\0",
+        ML_KEM_ENCAPSULATION_ERROR => b"encapsulation failed\0",
+        ML_KEM_DECAPSULATION_ERROR => b"decapsulation failed\0",
+        ML_KEM_ENTROPY_ERROR => b"random number generator or entropy callback failed\0",
+        ML_KEM_ENCAPS_KEY_ERROR => b"encapsulation key malformed (coefficient not reduced mod q)\0",
+        ML_KEM_DECAPS_KEY_ERROR => b"decapsulation key malformed (embedded encapsulation key)\0",
+        ML_KEM_DECAPS_KEY_HASH_ERROR => b"decapsulation key hash of encapsulation key mismatch\0",
+        _ => b"unknown error\0",
+    };
+    description.as_ptr().cast()
+}
+
+/// Fills `len` bytes at `out` with entropy, returning 0 on success (or non-zero on failure).
+#[allow(non_camel_case_types)] // as the C typedef
+pub type ml_kem_entropy_callback =
+    Option<unsafe extern "C" fn(ctx: *mut c_void, out: *mut u8, len: usize) -> i32>;
+
+// The registered callback (as a data pointer) and its context, or null for none
+static ENTROPY_CALLBACK: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+static ENTROPY_CONTEXT: AtomicPtr<c_void> = AtomicPtr::new(core::ptr::null_mut());
+
+/// Registers the source of randomness for keygen, encaps and seed population, which is then
+/// called with `ctx`. A null callback reverts to the OS RNG (or, without `std`, to failing
+/// with `ML_KEM_ENTROPY_ERROR`). Register it before any other call, not concurrently.
+#[no_mangle]
+pub extern "C" fn ml_kem_set_entropy_callback(
+    callback: ml_kem_entropy_callback, ctx: *mut c_void,
+) -> u8 {
+    ENTROPY_CONTEXT.store(ctx, Ordering::Release);
+    let callback = callback.map_or(core::ptr::null_mut(), |callback| callback as *mut ());
+    ENTROPY_CALLBACK.store(callback, Ordering::Release);
+    ML_KEM_OK
+}
+
+// The registered callback if any, else the OS RNG (with `std`). Any failure is noted, so that it
+// is reported as `ML_KEM_ENTROPY_ERROR` rather than as a failure of the operation drawing from it
+#[derive(Default)]
+struct Entropy {
+    failed: bool,
+}
+
+impl Entropy {
+    // The error code for an operation that failed with `err`, unless its randomness failed
+    fn error_or(&self, err: u8) -> u8 {
+        if self.failed {
+            ML_KEM_ENTROPY_ERROR
+        } else {
+            err
+        }
+    }
+
+    fn draw(out: &mut [u8]) -> Result<(), &'static str> {
+        let callback = ENTROPY_CALLBACK.load(Ordering::Acquire);
+        if callback.is_null() {
+            #[cfg(feature = "std")]
+            return rand_core::RngCore::try_fill_bytes(&mut rand_core::OsRng, out)
+                .map_err(|_| "OS random number generator failed");
+            #[cfg(not(feature = "std"))]
+            return Err("No entropy callback registered");
+        }
+        // SAFETY: non-null values are only ever stored by `ml_kem_set_entropy_callback()` above,
+        // from the callback function pointer, so this converts back to the original
+        let callback = unsafe {
+            core::mem::transmute::<
+                *mut (),
+                unsafe extern "C" fn(ctx: *mut c_void, out: *mut u8, len: usize) -> i32,
+            >(callback)
+        };
+        let ctx = ENTROPY_CONTEXT.load(Ordering::Acquire);
+        // SAFETY: the callback is documented to fill exactly `len` bytes at `out`
+        match unsafe { callback(ctx, out.as_mut_ptr(), out.len()) } {
+            0 => Ok(()),
+            _ => Err("Entropy callback failed"),
+        }
+    }
+}
+
+impl EntropySource for Entropy {
+    fn fill(&mut self, out: &mut [u8]) -> Result<(), &'static str> {
+        let result = Entropy::draw(out);
+        self.failed |= result.is_err();
+        result
+    }
+}
+
+// Distinguishes why a decapsulation key failed validation: either its embedded encapsulation key
+// (ahead of H(ek) and z in its final 64 bytes) is malformed, or else H(ek) does not match it
+fn decaps_key_error<E, const EK_LEN: usize>(
+    dk: &[u8], try_ek: fn([u8; EK_LEN]) -> Result<E, &'static str>,
+) -> u8 {
+    let mut ek = [0u8; EK_LEN];
+    for (out, byte) in ek.iter_mut().zip(dk.iter().skip(dk.len().saturating_sub(64 + EK_LEN))) {
+        *out = *byte;
+    }
+    match try_ek(ek) {
+        Ok(_) => ML_KEM_DECAPS_KEY_HASH_ERROR,
+        Err(_) => ML_KEM_DECAPS_KEY_ERROR,
+    }
+}
+
+// Splits the seed into `d` and `z`, without a (panicking) slice-to-array conversion
+fn split_seed(seed: &ml_kem_seed) -> ([u8; 32], [u8; 32]) {
+    let (mut d, mut z) = ([0u8; 32], [0u8; 32]);
+    for (out, byte) in d.iter_mut().chain(z.iter_mut()).zip(&seed.data) {
+        *out = *byte;
+    }
+    (d, z)
+}
+
+// Zeroes the (non-null) output up front, so that after any error return the caller observes zeros
+// rather than stale or partially written key or secret material; success then overwrites it
+fn wiped<T: Zeroize>(out: Option<&mut T>) -> Option<&mut T> {
+    out.map(|out| {
+        out.zeroize();
+        out
+    })
+}
 
 #[no_mangle]
 pub extern "C" fn ml_kem_populate_seed(seed_out: Option<&mut ml_kem_seed>) -> u8 {
     let Some(seed_out) = seed_out else {
         return ML_KEM_NULL_PTR_ERROR;
     };
-    OsRng.fill_bytes(&mut seed_out.data);
+    if Entropy::default().fill(&mut seed_out.data).is_err() {
+        seed_out.zeroize(); // the callback may have written part of it
+        return ML_KEM_ENTROPY_ERROR;
+    }
     ML_KEM_OK
 }
 
 // ML-KEM-512
 
 #[repr(C)]
+#[derive(Zeroize)]
 pub struct ml_kem_512_encaps_key {
     data: [u8; fips203::ml_kem_512::EK_LEN],
 }
 #[repr(C)]
+#[derive(Zeroize)]
 pub struct ml_kem_512_decaps_key {
     data: [u8; fips203::ml_kem_512::DK_LEN],
 }
 #[repr(C)]
+#[derive(Zeroize)]
 pub struct ml_kem_512_ciphertext {
     data: [u8; fips203::ml_kem_512::CT_LEN],
 }
@@ -48,11 +204,12 @@ pub extern "C" fn ml_kem_512_keygen(
 ) -> u8 {
     use fips203::traits::{KeyGen, SerDes};
 
-    let (Some(encaps_out), Some(decaps_out)) = (encaps_out, decaps_out) else {
+    let (Some(encaps_out), Some(decaps_out)) = (wiped(encaps_out), wiped(decaps_out)) else {
         return ML_KEM_NULL_PTR_ERROR;
     };
-    let Ok((ek, dk)) = fips203::ml_kem_512::KG::try_keygen() else {
-        return ML_KEM_KEYGEN_ERROR;
+    let mut entropy = Entropy::default();
+    let Ok((ek, dk)) = fips203::ml_kem_512::KG::try_keygen_with_entropy(&mut entropy) else {
+        return entropy.error_or(ML_KEM_KEYGEN_ERROR);
     };
 
     encaps_out.data = ek.into_bytes();
@@ -67,13 +224,13 @@ pub extern "C" fn ml_kem_512_keygen_from_seed(
 ) -> u8 {
     use fips203::traits::{KeyGen, SerDes};
 
-    let (Some(encaps_out), Some(decaps_out), Some(seed)) = (encaps_out, decaps_out, seed) else {
+    let (Some(encaps_out), Some(decaps_out), Some(seed)) =
+        (wiped(encaps_out), wiped(decaps_out), seed)
+    else {
         return ML_KEM_NULL_PTR_ERROR;
     };
-    let (ek, dk) = fips203::ml_kem_512::KG::keygen_from_seed(
-        seed.data[0..32].try_into().unwrap(),
-        seed.data[32..64].try_into().unwrap(),
-    );
+    let (d, z) = split_seed(seed);
+    let (ek, dk) = fips203::ml_kem_512::KG::keygen_from_seed(d, z);
 
     encaps_out.data = ek.into_bytes();
     decaps_out.data = dk.into_bytes();
@@ -88,15 +245,16 @@ pub extern "C" fn ml_kem_512_encaps(
     use fips203::traits::{Encaps, SerDes};
 
     let (Some(encaps), Some(ciphertext_out), Some(shared_secret_out)) =
-        (encaps, ciphertext_out, shared_secret_out)
+        (encaps, wiped(ciphertext_out), wiped(shared_secret_out))
     else {
         return ML_KEM_NULL_PTR_ERROR;
     };
     let Ok(ek) = fips203::ml_kem_512::EncapsKey::try_from_bytes(encaps.data) else {
-        return ML_KEM_DESERIALIZATION_ERROR;
+        return ML_KEM_ENCAPS_KEY_ERROR;
     };
-    let Ok((ssk, ct)) = ek.try_encaps() else {
-        return ML_KEM_ENCAPSULATION_ERROR;
+    let mut entropy = Entropy::default();
+    let Ok((ssk, ct)) = ek.try_encaps_with_entropy(&mut entropy) else {
+        return entropy.error_or(ML_KEM_ENCAPSULATION_ERROR);
     };
 
     shared_secret_out.data = ssk.into_bytes();
@@ -104,6 +262,29 @@ pub extern "C" fn ml_kem_512_encaps(
     ML_KEM_OK
 }
 
+#[no_mangle]
+pub extern "C" fn ml_kem_512_encaps_from_seed(
+    encaps: Option<&ml_kem_512_encaps_key>, m: Option<&ml_kem_encaps_seed>,
+    ciphertext_out: Option<&mut ml_kem_512_ciphertext>,
+    shared_secret_out: Option<&mut ml_kem_shared_secret>,
+) -> u8 {
+    use fips203::traits::{Encaps, SerDes};
+
+    let (Some(encaps), Some(m), Some(ciphertext_out), Some(shared_secret_out)) =
+        (encaps, m, wiped(ciphertext_out), wiped(shared_secret_out))
+    else {
+        return ML_KEM_NULL_PTR_ERROR;
+    };
+    let Ok(ek) = fips203::ml_kem_512::EncapsKey::try_from_bytes(encaps.data) else {
+        return ML_KEM_ENCAPS_KEY_ERROR;
+    };
+    let (ssk, ct) = ek.encaps_from_seed(&m.data);
+
+    shared_secret_out.data = ssk.into_bytes();
+    ciphertext_out.data = ct.into_bytes();
+    ML_KEM_OK
+}
+
 #[no_mangle]
 pub extern "C" fn ml_kem_512_decaps(
     decaps: Option<&ml_kem_512_decaps_key>, ciphertext: Option<&ml_kem_512_ciphertext>,
@@ -112,12 +293,12 @@ pub extern "C" fn ml_kem_512_decaps(
     use fips203::traits::{Decaps, SerDes};
 
     let (Some(decaps), Some(ciphertext), Some(shared_secret_out)) =
-        (decaps, ciphertext, shared_secret_out)
+        (decaps, ciphertext, wiped(shared_secret_out))
     else {
         return ML_KEM_NULL_PTR_ERROR;
     };
     let Ok(dk) = fips203::ml_kem_512::DecapsKey::try_from_bytes(decaps.data) else {
-        return ML_KEM_DESERIALIZATION_ERROR;
+        return decaps_key_error(&decaps.data, fips203::ml_kem_512::EncapsKey::try_from_bytes);
     };
     let Ok(ct) = fips203::ml_kem_512::CipherText::try_from_bytes(ciphertext.data) else {
         return ML_KEM_DESERIALIZATION_ERROR;
@@ -133,14 +314,17 @@ pub extern "C" fn ml_kem_512_decaps(
 // ML-KEM-768
 
 #[repr(C)]
+#[derive(Zeroize)]
 pub struct ml_kem_768_encaps_key {
     data: [u8; fips203::ml_kem_768::EK_LEN],
 }
 #[repr(C)]
+#[derive(Zeroize)]
 pub struct ml_kem_768_decaps_key {
     data: [u8; fips203::ml_kem_768::DK_LEN],
 }
 #[repr(C)]
+#[derive(Zeroize)]
 pub struct ml_kem_768_ciphertext {
     data: [u8; fips203::ml_kem_768::CT_LEN],
 }
@@ -151,11 +335,12 @@ pub extern "C" fn ml_kem_768_keygen(
 ) -> u8 {
     use fips203::traits::{KeyGen, SerDes};
 
-    let (Some(encaps_out), Some(decaps_out)) = (encaps_out, decaps_out) else {
+    let (Some(encaps_out), Some(decaps_out)) = (wiped(encaps_out), wiped(decaps_out)) else {
         return ML_KEM_NULL_PTR_ERROR;
     };
-    let Ok((ek, dk)) = fips203::ml_kem_768::KG::try_keygen() else {
-        return ML_KEM_KEYGEN_ERROR;
+    let mut entropy = Entropy::default();
+    let Ok((ek, dk)) = fips203::ml_kem_768::KG::try_keygen_with_entropy(&mut entropy) else {
+        return entropy.error_or(ML_KEM_KEYGEN_ERROR);
     };
 
     encaps_out.data = ek.into_bytes();
@@ -170,13 +355,13 @@ pub extern "C" fn ml_kem_768_keygen_from_seed(
 ) -> u8 {
     use fips203::traits::{KeyGen, SerDes};
 
-    let (Some(encaps_out), Some(decaps_out), Some(seed)) = (encaps_out, decaps_out, seed) else {
+    let (Some(encaps_out), Some(decaps_out), Some(seed)) =
+        (wiped(encaps_out), wiped(decaps_out), seed)
+    else {
         return ML_KEM_NULL_PTR_ERROR;
     };
-    let (ek, dk) = fips203::ml_kem_768::KG::keygen_from_seed(
-        seed.data[0..32].try_into().unwrap(),
-        seed.data[32..64].try_into().unwrap(),
-    );
+    let (d, z) = split_seed(seed);
+    let (ek, dk) = fips203::ml_kem_768::KG::keygen_from_seed(d, z);
 
     encaps_out.data = ek.into_bytes();
     decaps_out.data = dk.into_bytes();
@@ -191,16 +376,40 @@ pub extern "C" fn ml_kem_768_encaps(
     use fips203::traits::{Encaps, SerDes};
 
     let (Some(encaps), Some(ciphertext_out), Some(shared_secret_out)) =
-        (encaps, ciphertext_out, shared_secret_out)
+        (encaps, wiped(ciphertext_out), wiped(shared_secret_out))
     else {
         return ML_KEM_NULL_PTR_ERROR;
     };
     let Ok(ek) = fips203::ml_kem_768::EncapsKey::try_from_bytes(encaps.data) else {
-        return ML_KEM_DESERIALIZATION_ERROR;
+        return ML_KEM_ENCAPS_KEY_ERROR;
+    };
+    let mut entropy = Entropy::default();
+    let Ok((ssk, ct)) = ek.try_encaps_with_entropy(&mut entropy) else {
+        return entropy.error_or(ML_KEM_ENCAPSULATION_ERROR);
+    };
+
+    shared_secret_out.data = ssk.into_bytes();
+    ciphertext_out.data = ct.into_bytes();
+    ML_KEM_OK
+}
+
+#[no_mangle]
+pub extern "C" fn ml_kem_768_encaps_from_seed(
+    encaps: Option<&ml_kem_768_encaps_key>, m: Option<&ml_kem_encaps_seed>,
+    ciphertext_out: Option<&mut ml_kem_768_ciphertext>,
+    shared_secret_out: Option<&mut ml_kem_shared_secret>,
+) -> u8 {
+    use fips203::traits::{Encaps, SerDes};
+
+    let (Some(encaps), Some(m), Some(ciphertext_out), Some(shared_secret_out)) =
+        (encaps, m, wiped(ciphertext_out), wiped(shared_secret_out))
+    else {
+        return ML_KEM_NULL_PTR_ERROR;
     };
-    let Ok((ssk, ct)) = ek.try_encaps() else {
-        return ML_KEM_ENCAPSULATION_ERROR;
+    let Ok(ek) = fips203::ml_kem_768::EncapsKey::try_from_bytes(encaps.data) else {
+        return ML_KEM_ENCAPS_KEY_ERROR;
     };
+    let (ssk, ct) = ek.encaps_from_seed(&m.data);
 
     shared_secret_out.data = ssk.into_bytes();
     ciphertext_out.data = ct.into_bytes();
@@ -215,12 +424,12 @@ pub extern "C" fn ml_kem_768_decaps(
     use fips203::traits::{Decaps, SerDes};
 
     let (Some(decaps), Some(ciphertext), Some(shared_secret_out)) =
-        (decaps, ciphertext, shared_secret_out)
+        (decaps, ciphertext, wiped(shared_secret_out))
     else {
         return ML_KEM_NULL_PTR_ERROR;
     };
     let Ok(dk) = fips203::ml_kem_768::DecapsKey::try_from_bytes(decaps.data) else {
-        return ML_KEM_DESERIALIZATION_ERROR;
+        return decaps_key_error(&decaps.data, fips203::ml_kem_768::EncapsKey::try_from_bytes);
     };
     let Ok(ct) = fips203::ml_kem_768::CipherText::try_from_bytes(ciphertext.data) else {
         return ML_KEM_DESERIALIZATION_ERROR;
@@ -236,14 +445,17 @@ pub extern "C" fn ml_kem_768_decaps(
 // ML-KEM-1024
 
 #[repr(C)]
+#[derive(Zeroize)]
 pub struct ml_kem_1024_encaps_key {
     data: [u8; fips203::ml_kem_1024::EK_LEN],
 }
 #[repr(C)]
+#[derive(Zeroize)]
 pub struct ml_kem_1024_decaps_key {
     data: [u8; fips203::ml_kem_1024::DK_LEN],
 }
 #[repr(C)]
+#[derive(Zeroize)]
 pub struct ml_kem_1024_ciphertext {
     data: [u8; fips203::ml_kem_1024::CT_LEN],
 }
@@ -255,11 +467,12 @@ pub extern "C" fn ml_kem_1024_keygen(
 ) -> u8 {
     use fips203::traits::{KeyGen, SerDes};
 
-    let (Some(encaps_out), Some(decaps_out)) = (encaps_out, decaps_out) else {
+    let (Some(encaps_out), Some(decaps_out)) = (wiped(encaps_out), wiped(decaps_out)) else {
         return ML_KEM_NULL_PTR_ERROR;
     };
-    let Ok((ek, dk)) = fips203::ml_kem_1024::KG::try_keygen() else {
-        return ML_KEM_KEYGEN_ERROR;
+    let mut entropy = Entropy::default();
+    let Ok((ek, dk)) = fips203::ml_kem_1024::KG::try_keygen_with_entropy(&mut entropy) else {
+        return entropy.error_or(ML_KEM_KEYGEN_ERROR);
     };
 
     encaps_out.data = ek.into_bytes();
@@ -274,13 +487,13 @@ pub extern "C" fn ml_kem_1024_keygen_from_seed(
 ) -> u8 {
     use fips203::traits::{KeyGen, SerDes};
 
-    let (Some(encaps_out), Some(decaps_out), Some(seed)) = (encaps_out, decaps_out, seed) else {
+    let (Some(encaps_out), Some(decaps_out), Some(seed)) =
+        (wiped(encaps_out), wiped(decaps_out), seed)
+    else {
         return ML_KEM_NULL_PTR_ERROR;
     };
-    let (ek, dk) = fips203::ml_kem_1024::KG::keygen_from_seed(
-        seed.data[0..32].try_into().unwrap(),
-        seed.data[32..64].try_into().unwrap(),
-    );
+    let (d, z) = split_seed(seed);
+    let (ek, dk) = fips203::ml_kem_1024::KG::keygen_from_seed(d, z);
 
     encaps_out.data = ek.into_bytes();
     decaps_out.data = dk.into_bytes();
@@ -295,15 +508,16 @@ pub extern "C" fn ml_kem_1024_encaps(
     use fips203::traits::{Encaps, SerDes};
 
     let (Some(encaps), Some(ciphertext_out), Some(shared_secret_out)) =
-        (encaps, ciphertext_out, shared_secret_out)
+        (encaps, wiped(ciphertext_out), wiped(shared_secret_out))
     else {
         return ML_KEM_NULL_PTR_ERROR;
     };
     let Ok(ek) = fips203::ml_kem_1024::EncapsKey::try_from_bytes(encaps.data) else {
-        return ML_KEM_DESERIALIZATION_ERROR;
+        return ML_KEM_ENCAPS_KEY_ERROR;
     };
-    let Ok((ssk, ct)) = ek.try_encaps() else {
-        return ML_KEM_ENCAPSULATION_ERROR;
+    let mut entropy = Entropy::default();
+    let Ok((ssk, ct)) = ek.try_encaps_with_entropy(&mut entropy) else {
+        return entropy.error_or(ML_KEM_ENCAPSULATION_ERROR);
     };
 
     shared_secret_out.data = ssk.into_bytes();
@@ -311,6 +525,29 @@ pub extern "C" fn ml_kem_1024_encaps(
     ML_KEM_OK
 }
 
+#[no_mangle]
+pub extern "C" fn ml_kem_1024_encaps_from_seed(
+    encaps: Option<&ml_kem_1024_encaps_key>, m: Option<&ml_kem_encaps_seed>,
+    ciphertext_out: Option<&mut ml_kem_1024_ciphertext>,
+    shared_secret_out: Option<&mut ml_kem_shared_secret>,
+) -> u8 {
+    use fips203::traits::{Encaps, SerDes};
+
+    let (Some(encaps), Some(m), Some(ciphertext_out), Some(shared_secret_out)) =
+        (encaps, m, wiped(ciphertext_out), wiped(shared_secret_out))
+    else {
+        return ML_KEM_NULL_PTR_ERROR;
+    };
+    let Ok(ek) = fips203::ml_kem_1024::EncapsKey::try_from_bytes(encaps.data) else {
+        return ML_KEM_ENCAPS_KEY_ERROR;
+    };
+    let (ssk, ct) = ek.encaps_from_seed(&m.data);
+
+    shared_secret_out.data = ssk.into_bytes();
+    ciphertext_out.data = ct.into_bytes();
+    ML_KEM_OK
+}
+
 #[no_mangle]
 pub extern "C" fn ml_kem_1024_decaps(
     decaps: Option<&ml_kem_1024_decaps_key>, ciphertext: Option<&ml_kem_1024_ciphertext>,
@@ -319,12 +556,12 @@ pub extern "C" fn ml_kem_1024_decaps(
     use fips203::traits::{Decaps, SerDes};
 
     let (Some(decaps), Some(ciphertext), Some(shared_secret_out)) =
-        (decaps, ciphertext, shared_secret_out)
+        (decaps, ciphertext, wiped(shared_secret_out))
     else {
         return ML_KEM_NULL_PTR_ERROR;
     };
     let Ok(dk) = fips203::ml_kem_1024::DecapsKey::try_from_bytes(decaps.data) else {
-        return ML_KEM_DESERIALIZATION_ERROR;
+        return decaps_key_error(&decaps.data, fips203::ml_kem_1024::EncapsKey::try_from_bytes);
     };
     let Ok(ct) = fips203::ml_kem_1024::CipherText::try_from_bytes(ciphertext.data) else {
         return ML_KEM_DESERIALIZATION_ERROR;
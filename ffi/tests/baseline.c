@@ -2,12 +2,39 @@
 #include <string.h>
 #include <fips203.h>
 
+/* deterministic entropy: successive values of the counter at ctx */
+static int counting_entropy(void *ctx, uint8_t *out, size_t len) {
+  uint8_t *counter = ctx;
+  for (size_t n = 0; n < len; n++)
+    out[n] = (*counter)++;
+  return 0;
+}
+
+static int failing_entropy(void *ctx, uint8_t *out, size_t len) {
+  return 1;
+}
+
+/* writes part of the request before failing */
+static int partial_entropy(void *ctx, uint8_t *out, size_t len) {
+  memset (out, 0xaa, len / 2);
+  return 1;
+}
+
+static int is_zero(const void *buf, size_t len) {
+  const uint8_t *p = buf;
+  uint8_t acc = 0;
+  for (size_t n = 0; n < len; n++)
+    acc |= p[n];
+  return acc == 0;
+}
+
 int main(int argc, const char **argv) {
   MLKEM_encaps_key encaps;
   MLKEM_decaps_key decaps;
   MLKEM_encaps_key encaps_2;
   MLKEM_decaps_key decaps_2;
   MLKEM_ciphertext ct;
+  MLKEM_ciphertext ct_2;
   ml_kem_shared_secret ssk_a;
   ml_kem_shared_secret ssk_b;
   ml_kem_seed seed;
@@ -31,6 +58,28 @@ int main(int argc, const char **argv) {
     return 6;
   }
 
+  /* ensure that seed-based encapsulation is deterministic, and decapsulates */
+  ml_kem_encaps_seed m;
+  memset (&m, 0x42, sizeof(m));
+  if (MLKEM_encaps_from_seed (&encaps, &m, &ct, &ssk_a))
+    return 2;
+  if (MLKEM_encaps_from_seed (&encaps_2, &m, &ct_2, &ssk_b))
+    return 2;
+  if (memcmp(&ct, &ct_2, sizeof(ct)) || memcmp(&ssk_a, &ssk_b, sizeof(ssk_a))) {
+    fprintf (stderr, "ciphertexts or shared secrets encapsulated by seed did not match\n");
+    return 5;
+  }
+  if (MLKEM_decaps (&decaps, &ct, &ssk_b))
+    return 3;
+  if (memcmp(&ssk_a, &ssk_b, sizeof(ssk_a))) {
+    fprintf (stderr, "shared secret encapsulated by seed did not decapsulate\n");
+    return 6;
+  }
+  if (! MLKEM_encaps_from_seed (&encaps, NULL, &ct, &ssk_a)) {
+    fprintf (stderr, "encaps_from_seed should have failed with NULL m\n");
+    return 1;
+  }
+
   if (MLKEM_keygen (&encaps, &decaps))
       return 1;
 
@@ -117,18 +166,94 @@ int main(int argc, const char **argv) {
   for (int i = 0; i < sizeof(encaps_weird.data); i++)
     encaps_weird.data[i] = 0xff;
   err = MLKEM_encaps (&encaps_weird, &ct, &ssk_a);
-  if (err != ML_KEM_DESERIALIZATION_ERROR) {
-    fprintf (stderr, "encaps against an encaps_key of all 0xff octets should have failed with deserialization error, got %d\n", err);
+  if (err != ML_KEM_ENCAPS_KEY_ERROR) {
+    fprintf (stderr, "encaps against an encaps_key of all 0xff octets should have failed with encaps key error, got %d\n", err);
     return 1;
   }
 
   for (int i = 0; i < sizeof(decaps_weird.data); i++)
     decaps_weird.data[i] = 0xff;
   err = MLKEM_decaps (&decaps_weird, &ct, &ssk_a);
-  if (err != ML_KEM_DESERIALIZATION_ERROR) {
-    fprintf (stderr, "decaps against a tampered decaps_key should have failed with deserialization error, got %d\n", err);
+  if (err != ML_KEM_DECAPS_KEY_ERROR) {
+    fprintf (stderr, "decaps against a tampered decaps_key should have failed with decaps key error, got %d\n", err);
     return 1;
   } 
-  
+  if (! is_zero (&ssk_a, sizeof(ssk_a))) {
+    fprintf (stderr, "failed decaps should have zeroed shared_secret_out\n");
+    return 8;
+  }
+  /* a well-formed decaps_key with a wrong hash of its encaps_key */
+  decaps_weird = decaps;
+  decaps_weird.data[sizeof(decaps_weird.data) - 64] ^= 1;
+  err = MLKEM_decaps (&decaps_weird, &ct, &ssk_a);
+  if (err != ML_KEM_DECAPS_KEY_HASH_ERROR) {
+    fprintf (stderr, "decaps against a decaps_key with a wrong hash should have failed with decaps key hash error, got %d\n", err);
+    return 1;
+  }
+
+  /* every code is described, and unknown codes are too */
+  for (ml_kem_err e = ML_KEM_OK; e <= ML_KEM_DECAPS_KEY_HASH_ERROR; e++)
+    if (! ml_kem_strerror (e) || ! strcmp (ml_kem_strerror (e), "unknown error")) {
+      fprintf (stderr, "error code %d should have a description\n", e);
+      return 9;
+    }
+  if (strcmp (ml_kem_strerror (ML_KEM_ENTROPY_ERROR), "random number generator or entropy callback failed") ||
+      strcmp (ml_kem_strerror (0xff), "unknown error")) {
+    fprintf (stderr, "unexpected error descriptions\n");
+    return 9;
+  }
+
+  /* a registered entropy callback is used by keygen, encaps and seed population */
+  uint8_t counter = 0;
+  if (ml_kem_set_entropy_callback (counting_entropy, &counter) ||
+      MLKEM_keygen (&encaps, &decaps) || MLKEM_encaps (&encaps, &ct, &ssk_a))
+    return 1;
+  counter = 0;
+  if (MLKEM_keygen (&encaps_2, &decaps_2) || MLKEM_encaps (&encaps_2, &ct, &ssk_b))
+    return 1;
+  if (memcmp(&decaps, &decaps_2, sizeof(decaps)) || memcmp(&ssk_a, &ssk_b, sizeof(ssk_a))) {
+    fprintf (stderr, "keys or shared secrets from the same entropy did not match\n");
+    return 7;
+  }
+  if (ml_kem_set_entropy_callback (failing_entropy, NULL))
+    return 1;
+  err = MLKEM_keygen (&encaps, &decaps);
+  if (err != ML_KEM_ENTROPY_ERROR) {
+    fprintf (stderr, "keygen with failing entropy should have failed with entropy error, got %d\n", err);
+    return 1;
+  }
+  if (! is_zero (&encaps, sizeof(encaps)) || ! is_zero (&decaps, sizeof(decaps))) {
+    fprintf (stderr, "failed keygen should have zeroed encaps_out and decaps_out\n");
+    return 8;
+  }
+  err = MLKEM_encaps (&encaps_2, &ct, &ssk_b);
+  if (err != ML_KEM_ENTROPY_ERROR) {
+    fprintf (stderr, "encaps with failing entropy should have failed with entropy error, got %d\n", err);
+    return 1;
+  }
+  if (! is_zero (&ct, sizeof(ct)) || ! is_zero (&ssk_b, sizeof(ssk_b))) {
+    fprintf (stderr, "failed encaps should have zeroed ciphertext_out and shared_secret_out\n");
+    return 8;
+  }
+  err = ml_kem_populate_seed (&seed);
+  if (err != ML_KEM_ENTROPY_ERROR) {
+    fprintf (stderr, "populating a seed with failing entropy should have failed with entropy error, got %d\n", err);
+    return 1;
+  }
+  if (ml_kem_set_entropy_callback (partial_entropy, NULL))
+    return 1;
+  err = ml_kem_populate_seed (&seed);
+  if (err != ML_KEM_ENTROPY_ERROR || ! is_zero (&seed, sizeof(seed))) {
+    fprintf (stderr, "populating a seed with partial entropy should have failed and zeroed seed_out, got %d\n", err);
+    return 8;
+  }
+  /* a NULL output fails, but any non-NULL one is still zeroed */
+  if (! MLKEM_keygen (&encaps_2, NULL) || ! is_zero (&encaps_2, sizeof(encaps_2))) {
+    fprintf (stderr, "keygen with NULL decaps_out should have failed and zeroed encaps_out\n");
+    return 8;
+  }
+  if (ml_kem_set_entropy_callback (NULL, NULL) || MLKEM_keygen (&encaps, &decaps))
+    return 1;
+
   return 0;
 }
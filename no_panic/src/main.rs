@@ -0,0 +1,88 @@
+// Link-time check that no panic is reachable from keygen, encaps or decaps (in the manner of the
+// `panic-never` crate). This binary is `no_std` with a panic handler that calls a function that
+// does not exist. The handler (and so the missing symbol) is only linked when some panicking
+// branch survives optimization, so this builds (in release) only when none can occur in the
+// operations below. The harness itself is written to avoid any panicking branches.
+#![no_std]
+#![no_main]
+
+use fips203::traits::{Decaps, Encaps, KeyGen};
+use fips203::{ml_kem_1024, ml_kem_512, ml_kem_768};
+use rand_core::{CryptoRng, RngCore};
+
+
+// The C library provides the process entry (calling `main()` below) and `memcpy()` etc.
+#[link(name = "c")]
+extern "C" {}
+
+
+#[panic_handler]
+fn panic(_info: &core::panic::PanicInfo) -> ! {
+    extern "C" {
+        #[link_name = "\n\nERROR: a panic is reachable from fips203 keygen, encaps or decaps\n\n"]
+        fn panic_is_reachable() -> !;
+    }
+    unsafe { panic_is_reachable() }
+}
+
+
+// An infallible source of (non-random) bytes, which cannot panic either
+struct CountingRng(u8);
+
+impl RngCore for CountingRng {
+    fn next_u32(&mut self) -> u32 { u32::from(self.0) }
+
+    fn next_u64(&mut self) -> u64 { u64::from(self.0) }
+
+    fn fill_bytes(&mut self, out: &mut [u8]) {
+        for byte in out {
+            self.0 = self.0.wrapping_add(1);
+            *byte = self.0;
+        }
+    }
+
+    fn try_fill_bytes(&mut self, out: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(out);
+        Ok(())
+    }
+}
+
+impl CryptoRng for CountingRng {}
+
+
+// Runs keygen and encaps (both random and from seed) and decaps for one parameter set, returning
+// whether the shared secrets agree. The `Err` results are propagated rather than unwrapped.
+macro_rules! check {
+    ($ml_kem:ident, $rng:expr) => {{
+        let rng = $rng;
+        let (ek1, dk1) = $ml_kem::KG::try_keygen_with_rng(rng)?;
+        let (ek2, dk2) = $ml_kem::KG::keygen_from_seed([1u8; 32], [2u8; 32]);
+        let (ssk1, ct1) = ek1.try_encaps_with_rng(rng)?;
+        let (ssk2, ct2) = ek2.try_encaps_with_rng(rng)?;
+        let (ssk3, ct3) = ek2.encaps_from_seed(&[3u8; 32]);
+        Ok::<bool, &'static str>(
+            (dk1.try_decaps(&ct1)? == ssk1)
+                & (dk2.try_decaps(&ct2)? == ssk2)
+                & (dk2.try_decaps(&ct3)? == ssk3),
+        )
+    }};
+}
+
+
+fn run() -> Result<bool, &'static str> {
+    let mut rng = CountingRng(0);
+    let ok_512 = check!(ml_kem_512, &mut rng)?;
+    let ok_768 = check!(ml_kem_768, &mut rng)?;
+    let ok_1024 = check!(ml_kem_1024, &mut rng)?;
+    Ok(ok_512 & ok_768 & ok_1024)
+}
+
+
+// Returns 0 (success) when all shared secrets agree
+#[no_mangle]
+pub extern "C" fn main(_argc: i32, _argv: *const *const u8) -> i32 {
+    match run() {
+        Ok(true) => 0,
+        _ => 1,
+    }
+}
@@ -0,0 +1,44 @@
+// ASN.1 encodings of ML-KEM keys per the IETF LAMPS "Algorithm Identifiers for ML-KEM" draft.
+// These are not part of FIPS 203, and are injected into each parameter set via the macro.
+
+use pkcs8::der::asn1::{AnyRef, OctetStringRef};
+use pkcs8::der::{Decode, Reader, SliceReader, Tag, TagNumber, Tagged};
+
+
+/// The `ML-KEM-PrivateKey` CHOICE, carried in the `privateKey` field of a PKCS#8 `PrivateKeyInfo`:
+/// ```text
+/// ML-KEM-PrivateKey ::= CHOICE {
+///     seed [0] IMPLICIT OCTET STRING (SIZE (64)),
+///     expandedKey OCTET STRING,
+///     both SEQUENCE { seed OCTET STRING (SIZE (64)), expandedKey OCTET STRING } }
+/// ```
+pub(crate) enum PrivateKeyChoice<'a> {
+    Seed(&'a [u8; 64]),
+    ExpandedKey(&'a [u8]),
+    Both { seed: &'a [u8; 64], expanded_key: &'a [u8] },
+}
+
+
+// The seed is `d ‖ z`
+fn seed(bytes: &[u8]) -> pkcs8::Result<&[u8; 64]> {
+    <&[u8; 64]>::try_from(bytes).map_err(|_| pkcs8::Error::KeyMalformed)
+}
+
+
+/// Decodes the `ML-KEM-PrivateKey` CHOICE; the seed is checked for size only.
+pub(crate) fn decode_private_key(bytes: &[u8]) -> pkcs8::Result<PrivateKeyChoice<'_>> {
+    let any = AnyRef::from_der(bytes)?;
+    match any.tag() {
+        Tag::OctetString => Ok(PrivateKeyChoice::ExpandedKey(any.value())),
+        Tag::ContextSpecific { constructed: false, number: TagNumber::N0 } => {
+            Ok(PrivateKeyChoice::Seed(seed(any.value())?))
+        }
+        Tag::Sequence => {
+            let mut reader = SliceReader::new(any.value())?;
+            let seed = seed(OctetStringRef::decode(&mut reader)?.as_bytes())?;
+            let expanded_key = OctetStringRef::decode(&mut reader)?.as_bytes();
+            reader.finish(PrivateKeyChoice::Both { seed, expanded_key }).map_err(Into::into)
+        }
+        _ => Err(pkcs8::Error::KeyMalformed),
+    }
+}
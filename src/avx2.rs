@@ -0,0 +1,910 @@
+// x86_64 AVX2 backend (with the `avx2` feature) for the NTT, inverse NTT, NTT multiplication, CBD
+// and rejection sampling, plus a 4-way Keccak for the matrix and PRF outputs, similar to the
+// reference AVX2 Kyber code. Support is detected at runtime via CPUID, so each function returns
+// `None` (and the portable code is used) on processors without AVX2.
+//
+// The arithmetic operates on 16 lanes of signed 16-bit Montgomery multiplication, and every result
+// is normalized into [0, q) so that outputs are identical to the portable code. As with the
+// portable code, there are no secret-dependent branches or memory accesses. The unsafe code here
+// is limited to the (unaligned) loads and stores plus the intrinsics themselves, all of which
+// require the AVX2 support checked at runtime. The other modules allowing unsafe code are
+// avx512.rs, cortex_m4.rs, keccak.rs and locked.rs, each with its own scope noted at the top.
+#![allow(unsafe_code, unsafe_op_in_unsafe_fn)]
+
+use crate::types::Poly;
+use crate::Q;
+use core::arch::x86_64::{
+    __m256i, _mm256_add_epi16, _mm256_and_si256, _mm256_andnot_si256, _mm256_blend_epi16,
+    _mm256_blend_epi32, _mm256_broadcastsi128_si256, _mm256_castsi128_si256,
+    _mm256_castsi256_si128, _mm256_cmpgt_epi16, _mm256_extracti128_si256, _mm256_inserti128_si256,
+    _mm256_load_si256, _mm256_loadu_si256, _mm256_movemask_epi8, _mm256_mulhi_epi16,
+    _mm256_mullo_epi16, _mm256_or_si256, _mm256_packs_epi16, _mm256_packus_epi32,
+    _mm256_permute2x128_si256, _mm256_set1_epi16, _mm256_set1_epi64x, _mm256_setzero_si256,
+    _mm256_shuffle_epi8, _mm256_slli_epi64, _mm256_sllv_epi64, _mm256_srai_epi16,
+    _mm256_srli_epi16, _mm256_srli_epi32, _mm256_srli_epi64, _mm256_srlv_epi64, _mm256_store_si256,
+    _mm256_storeu_si256, _mm256_sub_epi16, _mm256_unpackhi_epi16, _mm256_unpackhi_epi64,
+    _mm256_unpacklo_epi16, _mm256_unpacklo_epi64, _mm256_xor_si256, _mm_loadu_si128,
+    _mm_shuffle_epi8, _mm_storeu_si128,
+};
+
+cpufeatures::new!(cpuid_avx2, "avx2");
+
+
+/// Returns whether AVX2 is available (and so will be used by the functions below).
+pub(crate) fn available() -> bool { cpuid_avx2::get() }
+
+
+/// Computes `NTT(f)` in place when AVX2 is available, returning whether it did. See `ntt::ntt_portable()`.
+pub(crate) fn ntt(array_f: &mut Poly) -> bool {
+    // SAFETY: AVX2 support was detected at runtime
+    cpuid_avx2::get().then(|| unsafe { ntt_avx2(array_f) }).is_some()
+}
+
+
+/// Computes `NTTinv(f_hat)` (plus any `addend`) in place when AVX2 is available, returning whether
+/// it did. See `ntt::ntt_inv_portable()`.
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+pub(crate) fn ntt_inv(f_hat: &mut Poly, addend: Option<&Poly>) -> bool {
+    // SAFETY: AVX2 support was detected at runtime
+    cpuid_avx2::get().then(|| unsafe { ntt_inv_avx2(f_hat, addend) }).is_some()
+}
+
+
+/// Computes `MultiplyNTTs(f_hat, g_hat)` when AVX2 is available. See `ntt::multiply_ntts_portable()`.
+pub(crate) fn multiply_ntts(f_hat: &Poly, g_hat: &Poly) -> Option<Poly> {
+    // SAFETY: AVX2 support was detected at runtime
+    cpuid_avx2::get().then(|| unsafe { multiply_ntts_avx2(f_hat, g_hat) })
+}
+
+
+/// Computes `SamplePolyCBD_η(B)` when AVX2 is available. See `sampling::sample_poly_cbd_portable()`.
+pub(crate) fn sample_poly_cbd(byte_array_b: &[u8]) -> Option<Poly> {
+    debug_assert!(byte_array_b.len() == 128 || byte_array_b.len() == 192, "Alg 8: eta not 2 or 3");
+    // SAFETY: AVX2 support was detected at runtime
+    cpuid_avx2::get().then(|| unsafe { sample_poly_cbd_avx2(byte_array_b) })
+}
+
+
+/// Parses one 168-byte XOF block into the candidates of `SampleNTT()` from `a_hat[j]` onwards,
+/// returning the updated `j`, when AVX2 is available. See `sampling::sample_ntt()`.
+pub(crate) fn sample_ntt_block(block: &[u8; 168], a_hat: &mut Poly, j: usize) -> Option<usize> {
+    // SAFETY: AVX2 support was detected at runtime
+    cpuid_avx2::get().then(|| unsafe { sample_ntt_block_avx2(block, a_hat, j) })
+}
+
+
+/// Samples `A_hat` with four SHAKE128 instances at a time when AVX2 is available (and no custom
+/// Keccak backend is configured). See `k_pke::gen_a_hat()`.
+pub(crate) fn gen_a_hat<const K: usize>(rho: &[u8; 32]) -> Option<[[Poly; K]; K]> {
+    let enabled = cfg!(not(feature = "custom-keccak")) && cpuid_avx2::get();
+    // SAFETY: AVX2 support was detected at runtime
+    enabled.then(|| unsafe { gen_a_hat_avx2(rho) })
+}
+
+
+/// Computes `PRF_η(s, b + i)` for `i ∈ [0, L)` with four SHAKE256 instances at a time when AVX2 is
+/// available (and no custom Keccak backend is configured). See `helpers::prf()`.
+pub(crate) fn prf_many<const ETA_64: usize, const L: usize>(
+    s: &[u8; 32], b: u8,
+) -> Option<[[u8; ETA_64]; L]> {
+    let enabled = cfg!(not(feature = "custom-keccak")) && cpuid_avx2::get();
+    // SAFETY: AVX2 support was detected at runtime
+    enabled.then(|| unsafe { prf_many_avx2(s, b) })
+}
+
+// ----------
+// Constants, with the zetas and gammas in Montgomery form `x·2^16 mod q` alongside `x·2^16·q^{-1}`
+// (as used by `fqmul()`), calculated at compile-time.
+
+/// `q^{-1} mod 2^16` as a signed value
+pub(crate) const QINV: i16 = -3327;
+
+/// The zetas of the NTT layers that pair whole vectors, indexed as in Algorithms 9 and 10
+static ZETAS: [[i16; 2]; 128] = gen_zetas();
+
+/// Per-lane zetas of the NTT layers with len 8, 4 and 2, after the shuffles in `split()`
+static ZETAS_8: [[[i16; 16]; 8]; 2] = gen_lane_zetas(8, false);
+static ZETAS_4: [[[i16; 16]; 8]; 2] = gen_lane_zetas(4, false);
+static ZETAS_2: [[[i16; 16]; 8]; 2] = gen_lane_zetas(2, false);
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+static ZETAS_INV_8: [[[i16; 16]; 8]; 2] = gen_lane_zetas(8, true);
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+static ZETAS_INV_4: [[[i16; 16]; 8]; 2] = gen_lane_zetas(4, true);
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+static ZETAS_INV_2: [[[i16; 16]; 8]; 2] = gen_lane_zetas(2, true);
+
+/// Per-lane gammas of `BaseCaseMultiply()`, after the (de-interleaving) pack in `multiply_ntts_avx2()`
+static GAMMAS: [[[i16; 16]; 8]; 2] = gen_gammas();
+
+/// CBD sampling loads each 16-bit lane with the (up to two) bytes containing its 2·η bits via a
+/// shuffle (where -1 produces zero), and then multiplies to move those bits to the top, as AVX2
+/// lacks per-lane 16-bit shifts. Each 128-bit lane processes 8 coefficients from 2·η bytes.
+static CBD2_SHUFFLE: [i8; 32] = [
+    0, -1, 0, -1, 1, -1, 1, -1, 2, -1, 2, -1, 3, -1, 3, -1, //
+    4, -1, 4, -1, 5, -1, 5, -1, 6, -1, 6, -1, 7, -1, 7, -1,
+];
+static CBD2_MUL: [i16; 16] = [
+    4096, 256, 4096, 256, 4096, 256, 4096, 256, 4096, 256, 4096, 256, 4096, 256, 4096, 256,
+];
+static CBD3_SHUFFLE: [i8; 32] = [
+    0, 1, 0, 1, 1, 2, 1, 2, 3, 4, 3, 4, 4, 5, 4, 5, //
+    6, 7, 6, 7, 7, 8, 7, 8, 9, 10, 9, 10, 10, 11, 10, 11,
+];
+static CBD3_MUL: [i16; 16] = [
+    1024, 16, 64, 1, 1024, 16, 64, 1, 1024, 16, 64, 1, 1024, 16, 64, 1,
+];
+
+/// `SampleNTT()` loads 24 bytes as two overlapping 16-byte halves (bytes 0..16 and 8..24), and
+/// each 16-bit lane then gets the two bytes containing its 12-bit candidate via this shuffle.
+static REJ_SHUFFLE: [i8; 32] = [
+    0, 1, 1, 2, 3, 4, 4, 5, 6, 7, 7, 8, 9, 10, 10, 11, //
+    4, 5, 5, 6, 7, 8, 8, 9, 10, 11, 11, 12, 13, 14, 14, 15,
+];
+
+/// For each 8-bit mask of accepted candidates, the shuffle that moves them (in order) to the front
+static REJ_COMPACT: [[i8; 16]; 256] = gen_compact();
+
+/// The Keccak-f[1600] round constants
+static KECCAK_RC: [u64; 24] = [
+    0x0000_0000_0000_0001,
+    0x0000_0000_0000_8082,
+    0x8000_0000_0000_808A,
+    0x8000_0000_8000_8000,
+    0x0000_0000_0000_808B,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8009,
+    0x0000_0000_0000_008A,
+    0x0000_0000_0000_0088,
+    0x0000_0000_8000_8009,
+    0x0000_0000_8000_000A,
+    0x0000_0000_8000_808B,
+    0x8000_0000_0000_008B,
+    0x8000_0000_0000_8089,
+    0x8000_0000_0000_8003,
+    0x8000_0000_0000_8002,
+    0x8000_0000_0000_0080,
+    0x0000_0000_0000_800A,
+    0x8000_0000_8000_000A,
+    0x8000_0000_8000_8081,
+    0x8000_0000_0000_8080,
+    0x0000_0000_8000_0001,
+    0x8000_0000_8000_8008,
+];
+
+/// The Keccak-f[1600] rotation offsets of ρ, indexed by `x + 5·y`
+static KECCAK_RHO: [u32; 25] = [
+    0, 1, 62, 28, 27, 36, 44, 6, 55, 20, 3, 10, 43, 25, 39, 41, 45, 15, 21, 8, 18, 2, 61, 56, 14,
+];
+
+/// `1441 ≡ 128^{-1}·2^{32} mod q` for the final step of the inverse NTT, which also reverses the
+/// `2^{-16}` factor of the NTT products (as per the portable `ntt_inv()`)
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+pub(crate) const F: [i16; 2] = [1441, 1441i16.wrapping_mul(QINV)];
+
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)] // const fns cannot use i16::try_from() etc...
+pub(crate) const fn mont(x: u32) -> [i16; 2] {
+    let r = ((x as u64 * 65536) % Q as u64) as i16;
+    [r, r.wrapping_mul(QINV)]
+}
+
+#[allow(clippy::cast_possible_truncation)] // const fns cannot use u32::from() etc...
+pub(crate) const fn zeta_pow(exp: u32) -> u32 {
+    let (mut result, mut i) = (1u32, 0);
+    while i < exp {
+        result = (result * crate::ZETA as u32) % Q as u32;
+        i += 1;
+    }
+    result
+}
+
+// ζ^{BitRev_7(i)}
+#[allow(clippy::cast_possible_truncation)] // const fns cannot use u8::try_from() etc...
+pub(crate) const fn zeta(i: usize) -> u32 { zeta_pow(((i as u8).reverse_bits() >> 1) as u32) }
+
+const fn gen_zetas() -> [[i16; 2]; 128] {
+    let mut result = [[0i16; 2]; 128];
+    let mut i = 0;
+    while i < 128 {
+        result[i] = mont(zeta(i));
+        i += 1;
+    }
+    result
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)] // const fns cannot use i8::try_from() etc...
+const fn gen_compact() -> [[i8; 16]; 256] {
+    let mut result = [[-1i8; 16]; 256];
+    let mut mask = 0;
+    while mask < 256 {
+        let (mut lane, mut n) = (0, 0);
+        while lane < 8 {
+            if (mask >> lane) & 1 == 1 {
+                result[mask][2 * n] = 2 * lane as i8;
+                result[mask][2 * n + 1] = 2 * lane as i8 + 1;
+                n += 1;
+            }
+            lane += 1;
+        }
+        mask += 1;
+    }
+    result
+}
+
+// The block of `len` butterflies (numbered across all 256 coefficients) that each lane belongs to
+// for the vector pair `p` after `split()`; see there for the shuffles.
+const fn lane_block(len: usize, p: usize, lane: usize) -> usize {
+    match len {
+        8 => 2 * p + lane / 8,
+        4 => 4 * p + [0, 2, 1, 3][lane / 4],
+        _ => 8 * p + lane / 4 + 4 * ((lane / 2) % 2),
+    }
+}
+
+const fn gen_lane_zetas(len: usize, inverse: bool) -> [[[i16; 16]; 8]; 2] {
+    let mut result = [[[0i16; 16]; 8]; 2];
+    let mut p = 0;
+    while p < 8 {
+        let mut lane = 0;
+        while lane < 16 {
+            let block = lane_block(len, p, lane);
+            // Algorithm 9 increments i from 128/len, while Algorithm 10 decrements i to 128/len
+            let i = if inverse {
+                256 / len - 1 - block
+            } else {
+                128 / len + block
+            };
+            let [z, zq] = mont(zeta(i));
+            result[0][p][lane] = z;
+            result[1][p][lane] = zq;
+            lane += 1;
+        }
+        p += 1;
+    }
+    result
+}
+
+const fn gen_gammas() -> [[[i16; 16]; 8]; 2] {
+    let mut result = [[[0i16; 16]; 8]; 2];
+    let mut chunk = 0;
+    while chunk < 8 {
+        let mut lane = 0;
+        while lane < 16 {
+            // `_mm256_packus_epi32()` orders each 128-bit lane as 4 pairs from each input
+            let i = 16 * chunk + 4 * (lane / 8) + lane % 4 + 8 * ((lane % 8) / 4);
+            // ζ^{2·BitRev_7(i) + 1}
+            #[allow(clippy::cast_possible_truncation)] // const fns cannot use u8::try_from() etc...
+            let [g, gq] = mont(zeta_pow(2 * (((i as u8).reverse_bits() >> 1) as u32) + 1));
+            result[0][chunk][lane] = g;
+            result[1][chunk][lane] = gq;
+            lane += 1;
+        }
+        chunk += 1;
+    }
+    result
+}
+
+
+// ----------
+// Vector arithmetic: `fqmul()` returns values in (-q, q), which `normalize()` maps into [0, q).
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn splat(x: i16) -> __m256i { _mm256_set1_epi16(x) }
+
+#[inline]
+#[target_feature(enable = "avx2")]
+#[allow(clippy::cast_possible_wrap)] // Q < 2^15
+unsafe fn q() -> __m256i { _mm256_set1_epi16(Q as i16) }
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn load(x: &[i16; 16]) -> __m256i {
+    // SAFETY: 32 bytes are readable, and unaligned loads are used
+    unsafe { _mm256_loadu_si256(x.as_ptr().cast()) }
+}
+
+// Montgomery multiplication `a·b·2^{-16}` for a constant `b` with precomputed `bq = b·q^{-1}`
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn fqmul(a: __m256i, b: __m256i, bq: __m256i) -> __m256i {
+    let hi = _mm256_mulhi_epi16(a, b);
+    let t = _mm256_mullo_epi16(a, bq);
+    _mm256_sub_epi16(hi, _mm256_mulhi_epi16(t, q()))
+}
+
+// Montgomery multiplication `a·b·2^{-16}` for variable `a` and `b`
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn fqmul_var(a: __m256i, b: __m256i) -> __m256i {
+    fqmul(a, b, _mm256_mullo_epi16(b, splat(QINV)))
+}
+
+// Maps (-q, q) into [0, q) by adding q to negative lanes (via a mask rather than a branch)
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn normalize(a: __m256i) -> __m256i {
+    _mm256_add_epi16(a, _mm256_and_si256(_mm256_srai_epi16(a, 15), q()))
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn add(a: __m256i, b: __m256i) -> __m256i {
+    normalize(_mm256_sub_epi16(_mm256_add_epi16(a, b), q()))
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn sub(a: __m256i, b: __m256i) -> __m256i { normalize(_mm256_sub_epi16(a, b)) }
+
+// Algorithm 9 steps 8-10
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn butterfly(a: __m256i, b: __m256i, z: __m256i, zq: __m256i) -> (__m256i, __m256i) {
+    let t = normalize(fqmul(b, z, zq));
+    (add(a, t), sub(a, t))
+}
+
+// Algorithm 10 steps 8-10
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn butterfly_inv(a: __m256i, b: __m256i, z: __m256i, zq: __m256i) -> (__m256i, __m256i) {
+    (add(a, b), normalize(fqmul(sub(b, a), z, zq)))
+}
+
+// Rearranges two adjacent vectors `(a, b)` such that the butterflies of `len` 8, 4 or 2 pair the
+// same lane of each output, i.e., the 128-bit halves, the 64-bit quarters, or the 32-bit eighths.
+// Each rearrangement is its own inverse, and `lane_block()` describes the resulting lane order.
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn split(len: usize, a: __m256i, b: __m256i) -> (__m256i, __m256i) {
+    match len {
+        8 => (_mm256_permute2x128_si256(a, b, 0x20), _mm256_permute2x128_si256(a, b, 0x31)),
+        4 => (_mm256_unpacklo_epi64(a, b), _mm256_unpackhi_epi64(a, b)),
+        _ => (
+            _mm256_blend_epi32(a, _mm256_slli_epi64(b, 32), 0xAA),
+            _mm256_blend_epi32(_mm256_srli_epi64(a, 32), b, 0xAA),
+        ),
+    }
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn load_poly(f: &Poly) -> [__m256i; 16] {
+    let mut v = [_mm256_setzero_si256(); 16];
+    for (i, vec) in v.iter_mut().enumerate() {
+        // SAFETY: `Z` is a transparent `u16` and `Poly` is 64-byte aligned, so each of the 16
+        // aligned loads reads 32 bytes within `f`
+        *vec = unsafe { _mm256_load_si256(f.as_ptr().add(16 * i).cast()) };
+    }
+    v
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn even(x: __m256i) -> __m256i { _mm256_blend_epi16(x, _mm256_setzero_si256(), 0xAA) }
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn odd(x: __m256i) -> __m256i { _mm256_srli_epi32(x, 16) }
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn store_poly_into(v: &[__m256i; 16], f: &mut Poly) {
+    for (i, vec) in v.iter().enumerate() {
+        // SAFETY: `Z` is a transparent `u16` and `Poly` is 64-byte aligned, so each of the 16
+        // aligned stores writes 32 bytes within `f`
+        unsafe { _mm256_store_si256(f.as_mut_ptr().add(16 * i).cast(), *vec) };
+    }
+}
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn store_poly(v: &[__m256i; 16]) -> Poly {
+    let mut f = Poly::default();
+    store_poly_into(v, &mut f);
+    f
+}
+
+
+// ----------
+// The backend functions themselves.
+
+#[target_feature(enable = "avx2")]
+unsafe fn ntt_avx2(array_f: &mut Poly) {
+    let mut v = load_poly(array_f);
+
+    // Layers with len 128 down to 16 pair whole vectors, with one zeta per block
+    let mut i = 1;
+    for len in [8, 4, 2, 1] {
+        for start in (0..16).step_by(2 * len) {
+            let (z, zq) = (splat(ZETAS[i][0]), splat(ZETAS[i][1]));
+            i += 1;
+            for j in start..(start + len) {
+                (v[j], v[j + len]) = butterfly(v[j], v[j + len], z, zq);
+            }
+        }
+    }
+
+    // Layers with len 8 down to 2 pair lanes within each (split) pair of vectors
+    for (len, zetas) in [(8, &ZETAS_8), (4, &ZETAS_4), (2, &ZETAS_2)] {
+        for p in 0..8 {
+            let (x, y) = split(len, v[2 * p], v[2 * p + 1]);
+            let (x, y) = butterfly(x, y, load(&zetas[0][p]), load(&zetas[1][p]));
+            (v[2 * p], v[2 * p + 1]) = split(len, x, y);
+        }
+    }
+
+    store_poly_into(&v, array_f);
+}
+
+
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+#[target_feature(enable = "avx2")]
+unsafe fn ntt_inv_avx2(f_hat: &mut Poly, addend: Option<&Poly>) {
+    let mut v = load_poly(f_hat);
+
+    // Layers with len 2 up to 8 pair lanes within each (split) pair of vectors
+    for (len, zetas) in [(2, &ZETAS_INV_2), (4, &ZETAS_INV_4), (8, &ZETAS_INV_8)] {
+        for p in 0..8 {
+            let (x, y) = split(len, v[2 * p], v[2 * p + 1]);
+            let (x, y) = butterfly_inv(x, y, load(&zetas[0][p]), load(&zetas[1][p]));
+            (v[2 * p], v[2 * p + 1]) = split(len, x, y);
+        }
+    }
+
+    // Layers with len 16 up to 128 pair whole vectors, with one zeta per block
+    let mut i = 15;
+    for len in [1, 2, 4, 8] {
+        for start in (0..16).step_by(2 * len) {
+            let (z, zq) = (splat(ZETAS[i][0]), splat(ZETAS[i][1]));
+            i -= 1;
+            for j in start..(start + len) {
+                (v[j], v[j + len]) = butterfly_inv(v[j], v[j + len], z, zq);
+            }
+        }
+    }
+
+    // Multiply every entry by 3303 ≡ 128^{−1} mod q, while reversing the 2^{-16} of the products,
+    // then add any addend
+    let (f, fq) = (splat(F[0]), splat(F[1]));
+    let addend = addend.map(|addend| load_poly(addend));
+    for (i, vec) in v.iter_mut().enumerate() {
+        *vec = normalize(fqmul(*vec, f, fq));
+        if let Some(addend) = &addend {
+            *vec = add(*vec, addend[i]);
+        }
+    }
+
+    store_poly_into(&v, f_hat);
+}
+
+
+#[target_feature(enable = "avx2")]
+unsafe fn multiply_ntts_avx2(f_hat: &Poly, g_hat: &Poly) -> Poly {
+    let (f, g) = (load_poly(f_hat), load_poly(g_hat));
+    let mut h = [_mm256_setzero_si256(); 16];
+
+    // Each chunk of 32 coefficients is de-interleaved into 16 (a0, b0) and 16 (a1, b1) lanes
+    for chunk in 0..8 {
+        let (f0, f1) = (f[2 * chunk], f[2 * chunk + 1]);
+        let (g0, g1) = (g[2 * chunk], g[2 * chunk + 1]);
+        let a0 = _mm256_packus_epi32(even(f0), even(f1));
+        let a1 = _mm256_packus_epi32(odd(f0), odd(f1));
+        let b0 = _mm256_packus_epi32(even(g0), even(g1));
+        let b1 = _mm256_packus_epi32(odd(g0), odd(g1));
+        let (gamma, gamma_q) = (load(&GAMMAS[0][chunk]), load(&GAMMAS[1][chunk]));
+
+        // c0 ← a0·b0 + a1·b1·γ, scaled by 2^{-16} as per the portable `multiply_ntts()`
+        let a1b1_gamma = normalize(fqmul(fqmul_var(a1, b1), gamma, gamma_q));
+        let c0 = add(normalize(fqmul_var(a0, b0)), a1b1_gamma);
+
+        // c1 ← a0·b1 + a1·b0
+        let c1 = add(normalize(fqmul_var(a0, b1)), normalize(fqmul_var(a1, b0)));
+
+        // Re-interleaving undoes the pack order
+        h[2 * chunk] = _mm256_unpacklo_epi16(c0, c1);
+        h[2 * chunk + 1] = _mm256_unpackhi_epi16(c0, c1);
+    }
+
+    store_poly(&h)
+}
+
+
+#[target_feature(enable = "avx2")]
+unsafe fn sample_poly_cbd_avx2(byte_array_b: &[u8]) -> Poly {
+    let eta_2 = byte_array_b.len() == 128;
+    let (shuffle, mul) = if eta_2 {
+        (&CBD2_SHUFFLE, &CBD2_MUL)
+    } else {
+        (&CBD3_SHUFFLE, &CBD3_MUL)
+    };
+    // SAFETY: 32 bytes are readable, and unaligned loads are used
+    let shuffle = unsafe { _mm256_loadu_si256(shuffle.as_ptr().cast()) };
+    let mul = load(mul);
+
+    // Each vector of 16 coefficients consumes 4·η bytes
+    let mut v = [_mm256_setzero_si256(); 16];
+    for (vec, bytes) in v.iter_mut().zip(byte_array_b.chunks_exact(byte_array_b.len() / 16)) {
+        let mut padded = [0u8; 16];
+        padded[..bytes.len()].copy_from_slice(bytes);
+        // SAFETY: 16 bytes are readable, and unaligned loads are used
+        let x = _mm256_broadcastsi128_si256(unsafe { _mm_loadu_si128(padded.as_ptr().cast()) });
+        let x = _mm256_mullo_epi16(_mm256_shuffle_epi8(x, shuffle), mul);
+
+        // Count the ones of x (the bottom η bits) and y (the next η bits) in parallel
+        *vec = if eta_2 {
+            let f = _mm256_srli_epi16(x, 12);
+            let t = _mm256_add_epi16(
+                _mm256_and_si256(f, splat(0x5)),
+                _mm256_and_si256(_mm256_srli_epi16(f, 1), splat(0x5)),
+            );
+            sub(
+                _mm256_and_si256(t, splat(3)),
+                _mm256_and_si256(_mm256_srli_epi16(t, 2), splat(3)),
+            )
+        } else {
+            let f = _mm256_srli_epi16(x, 10);
+            let t = _mm256_add_epi16(
+                _mm256_and_si256(f, splat(0x9)),
+                _mm256_and_si256(_mm256_srli_epi16(f, 1), splat(0x9)),
+            );
+            let t = _mm256_add_epi16(t, _mm256_and_si256(_mm256_srli_epi16(f, 2), splat(0x9)));
+            sub(
+                _mm256_and_si256(t, splat(3)),
+                _mm256_and_si256(_mm256_srli_epi16(t, 3), splat(3)),
+            )
+        };
+    }
+
+    store_poly(&v)
+}
+
+
+// As this only depends upon rho, which crosses the trust boundary in the clear, it is not constant-time
+#[target_feature(enable = "avx2")]
+#[allow(clippy::cast_sign_loss)] // movemask bits
+unsafe fn sample_ntt_block_avx2(block: &[u8; 168], a_hat: &mut Poly, mut j: usize) -> usize {
+    // SAFETY: 32 bytes are readable, and unaligned loads are used
+    let shuffle = unsafe { _mm256_loadu_si256(REJ_SHUFFLE.as_ptr().cast()) };
+    for bytes in block.chunks_exact(24) {
+        if j == 256 {
+            break;
+        }
+        // SAFETY: bytes 0..16 and 8..24 of the 24 are readable, and unaligned loads are used
+        let (lo, hi) = unsafe {
+            (
+                _mm_loadu_si128(bytes.as_ptr().cast()),
+                _mm_loadu_si128(bytes[8..].as_ptr().cast()),
+            )
+        };
+        let f = _mm256_inserti128_si256(_mm256_castsi128_si256(lo), hi, 1);
+
+        // d1 ← C[0] + 256·(C[1] mod 16) in the even lanes, and d2 ← ⌊C[1]/16⌋ + 16·C[2] in the odd
+        let f = _mm256_shuffle_epi8(f, shuffle);
+        let f = _mm256_blend_epi16(f, _mm256_srli_epi16(f, 4), 0xAA);
+        let f = _mm256_and_si256(f, splat(0x0FFF));
+
+        // Candidates below q are accepted, with mask bits 0..8 and 16..24 from the two halves
+        let good = _mm256_packs_epi16(_mm256_cmpgt_epi16(q(), f), _mm256_setzero_si256());
+        let good = _mm256_movemask_epi8(good) as u32;
+        for (half, mask) in [
+            (_mm256_castsi256_si128(f), good & 0xFF),
+            (_mm256_extracti128_si256(f, 1), good >> 16),
+        ] {
+            // SAFETY: 16 bytes are readable, and unaligned loads are used
+            let compact = unsafe { _mm_loadu_si128(REJ_COMPACT[mask as usize].as_ptr().cast()) };
+            let mut candidates = [0u16; 8];
+            // SAFETY: 16 bytes are writable, and unaligned stores are used
+            unsafe {
+                _mm_storeu_si128(candidates.as_mut_ptr().cast(), _mm_shuffle_epi8(half, compact));
+            }
+            let n = (mask.count_ones() as usize).min(256 - j);
+            for (a, &d) in a_hat[j..j + n].iter_mut().zip(&candidates) {
+                a.set_u16(d);
+            }
+            j += n;
+        }
+    }
+    j
+}
+
+
+// Four parallel SHAKE states, with lane `i` of instance `k` at `4·i + k` so that each vector of the
+// Keccak-f[1600] permutation holds the same lane of all four instances
+struct ShakeX4<const RATE: usize> {
+    state: [u64; 100],
+}
+
+impl<const RATE: usize> ShakeX4<RATE> {
+    // Absorbs the inputs, which each fit within a single block, along with the SHAKE padding
+    fn new(inputs: [&[u8]; 4]) -> Self {
+        let mut state = [0u64; 100];
+        for (k, input) in inputs.iter().enumerate() {
+            debug_assert!(input.len() < RATE);
+            let mut block = [0u8; RATE];
+            block[..input.len()].copy_from_slice(input);
+            block[input.len()] ^= 0x1F;
+            block[RATE - 1] ^= 0x80;
+            for (i, word) in block.chunks_exact(8).enumerate() {
+                state[4 * i + k] = u64::from_le_bytes(core::array::from_fn(|j| word[j]));
+            }
+        }
+        Self { state }
+    }
+
+    // Squeezes the next block of each instance
+    #[target_feature(enable = "avx2")]
+    unsafe fn squeeze(&mut self, out: &mut [[u8; RATE]; 4]) {
+        keccak_f1600_x4(&mut self.state);
+        for (k, out) in out.iter_mut().enumerate() {
+            for (i, word) in out.chunks_exact_mut(8).enumerate() {
+                word.copy_from_slice(&self.state[4 * i + k].to_le_bytes());
+            }
+        }
+    }
+}
+
+
+#[inline]
+#[target_feature(enable = "avx2")]
+unsafe fn rotl64(x: __m256i, n: u32) -> __m256i {
+    let left = _mm256_sllv_epi64(x, _mm256_set1_epi64x(i64::from(n)));
+    _mm256_or_si256(left, _mm256_srlv_epi64(x, _mm256_set1_epi64x(64 - i64::from(n))))
+}
+
+
+#[target_feature(enable = "avx2")]
+#[allow(clippy::cast_possible_wrap)] // round constants
+unsafe fn keccak_f1600_x4(state: &mut [u64; 100]) {
+    let mut a = [_mm256_setzero_si256(); 25];
+    for (i, lane) in a.iter_mut().enumerate() {
+        // SAFETY: 32 bytes are readable, and unaligned loads are used
+        *lane = unsafe { _mm256_loadu_si256(state.as_ptr().add(4 * i).cast()) };
+    }
+
+    for rc in KECCAK_RC {
+        // θ
+        let mut c = [_mm256_setzero_si256(); 5];
+        for (x, c) in c.iter_mut().enumerate() {
+            let c01 = _mm256_xor_si256(a[x], a[x + 5]);
+            let c23 = _mm256_xor_si256(a[x + 10], a[x + 15]);
+            *c = _mm256_xor_si256(_mm256_xor_si256(c01, c23), a[x + 20]);
+        }
+        for x in 0..5 {
+            let d = _mm256_xor_si256(c[(x + 4) % 5], rotl64(c[(x + 1) % 5], 1));
+            for y in 0..5 {
+                a[x + 5 * y] = _mm256_xor_si256(a[x + 5 * y], d);
+            }
+        }
+
+        // ρ and π
+        let mut b = [_mm256_setzero_si256(); 25];
+        for x in 0..5 {
+            for y in 0..5 {
+                b[y + 5 * ((2 * x + 3 * y) % 5)] = rotl64(a[x + 5 * y], KECCAK_RHO[x + 5 * y]);
+            }
+        }
+
+        // χ
+        for y in 0..5 {
+            for x in 0..5 {
+                let t = _mm256_andnot_si256(b[(x + 1) % 5 + 5 * y], b[(x + 2) % 5 + 5 * y]);
+                a[x + 5 * y] = _mm256_xor_si256(b[x + 5 * y], t);
+            }
+        }
+
+        // ι
+        a[0] = _mm256_xor_si256(a[0], _mm256_set1_epi64x(rc as i64));
+    }
+
+    for (i, lane) in a.iter().enumerate() {
+        // SAFETY: 32 bytes are writable, and unaligned stores are used
+        unsafe { _mm256_storeu_si256(state.as_mut_ptr().add(4 * i).cast(), *lane) };
+    }
+}
+
+
+// As with `sample_ntt_block_avx2()`, this only depends upon rho and so is not constant-time
+#[target_feature(enable = "avx2")]
+unsafe fn gen_a_hat_avx2<const K: usize>(rho: &[u8; 32]) -> [[Poly; K]; K] {
+    let mut a_hat = [[Poly::default(); K]; K];
+    for first in (0..K * K).step_by(4) {
+        // Each lane samples A_hat[i, j] ← SampleNTT(𝜌‖𝑗‖𝑖) for `K·i + j`, repeating the last as padding
+        let inputs: [[u8; 34]; 4] = core::array::from_fn(|lane| {
+            let n = (first + lane).min(K * K - 1);
+            let mut input = [0u8; 34];
+            input[..32].copy_from_slice(rho);
+            input[32] = (n % K).to_le_bytes()[0];
+            input[33] = (n / K).to_le_bytes()[0];
+            input
+        });
+        let mut shake = ShakeX4::<168>::new(core::array::from_fn(|lane| &inputs[lane][..]));
+
+        let mut polys = [Poly::default(); 4];
+        let mut j = [0usize; 4];
+        let mut blocks = [[0u8; 168]; 4];
+        while j.iter().any(|&j| j < 256) {
+            shake.squeeze(&mut blocks);
+            for lane in 0..4 {
+                j[lane] = sample_ntt_block_avx2(&blocks[lane], &mut polys[lane], j[lane]);
+            }
+        }
+
+        for (lane, poly) in polys.iter().enumerate().take(K * K - first) {
+            a_hat[(first + lane) / K][(first + lane) % K] = *poly;
+        }
+    }
+    a_hat
+}
+
+
+#[target_feature(enable = "avx2")]
+unsafe fn prf_many_avx2<const ETA_64: usize, const L: usize>(
+    s: &[u8; 32], b: u8,
+) -> [[u8; ETA_64]; L] {
+    let mut result = [[0u8; ETA_64]; L];
+    for (first, chunk) in (0..L).step_by(4).zip(result.chunks_mut(4)) {
+        // Each lane computes PRF_η(s, b + n), repeating the last as padding
+        let inputs: [[u8; 33]; 4] = core::array::from_fn(|lane| {
+            let n = (first + lane).min(L - 1);
+            let mut input = [0u8; 33];
+            input[..32].copy_from_slice(s);
+            input[32] = b + n.to_le_bytes()[0];
+            input
+        });
+        let mut shake = ShakeX4::<136>::new(core::array::from_fn(|lane| &inputs[lane][..]));
+
+        let mut blocks = [[0u8; 136]; 4];
+        for offset in (0..ETA_64).step_by(136) {
+            shake.squeeze(&mut blocks);
+            let len = (ETA_64 - offset).min(136);
+            for (out, block) in chunk.iter_mut().zip(&blocks) {
+                out[offset..offset + len].copy_from_slice(&block[..len]);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{cpuid_avx2, QINV};
+    use crate::ntt::{multiply_ntts_portable, ntt_inv_portable, ntt_portable};
+    use crate::sampling::sample_poly_cbd_portable;
+    use crate::types::{Poly, Z};
+    use crate::Q;
+
+    // Deterministic pseudo-random coefficients in [0, q), including the extremes
+    fn poly(seed: u64) -> Poly {
+        let mut state = seed;
+        Poly(core::array::from_fn(|i| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            #[allow(clippy::cast_possible_truncation)] // value < q
+            let value = match i {
+                0 => 0,
+                1 => Q - 1,
+                _ => (state % u64::from(Q)) as u16,
+            };
+            Z(value)
+        }))
+    }
+
+    fn as_u16(f: &Poly) -> [u16; 256] { core::array::from_fn(|i| f[i].0) }
+
+    #[test]
+    fn test_qinv() {
+        #[allow(clippy::cast_possible_wrap)]
+        let q = Q as i16;
+        assert_eq!(q.wrapping_mul(QINV), 1);
+    }
+
+    #[test]
+    fn test_matches_portable() {
+        if !cpuid_avx2::get() {
+            return;
+        }
+        for seed in 1..200 {
+            let (f, g) = (poly(seed), poly(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15)));
+            let (mut ntt, mut ntt_ref) = (f, f);
+            assert!(super::ntt(&mut ntt));
+            ntt_portable(&mut ntt_ref);
+            assert_eq!(as_u16(&ntt), as_u16(&ntt_ref));
+            let (mut ntt_inv, mut ntt_inv_ref) = (f, f);
+            assert!(super::ntt_inv(&mut ntt_inv, None));
+            ntt_inv_portable(&mut ntt_inv_ref, None);
+            assert_eq!(as_u16(&ntt_inv), as_u16(&ntt_inv_ref));
+            let (mut ntt_inv, mut ntt_inv_ref) = (f, f);
+            assert!(super::ntt_inv(&mut ntt_inv, Some(&g)));
+            ntt_inv_portable(&mut ntt_inv_ref, Some(&g));
+            assert_eq!(as_u16(&ntt_inv), as_u16(&ntt_inv_ref));
+            let product = super::multiply_ntts(&f, &g).unwrap();
+            assert_eq!(as_u16(&product), as_u16(&multiply_ntts_portable(&f, &g)));
+            for eta in [2, 3] {
+                let bytes: [u8; 192] = core::array::from_fn(|i| {
+                    #[allow(clippy::cast_possible_truncation)] // intentionally wraps
+                    let byte = (u64::from(f[i].0) ^ seed) as u8;
+                    byte
+                });
+                let cbd = super::sample_poly_cbd(&bytes[..64 * eta]).unwrap();
+                assert_eq!(as_u16(&cbd), as_u16(&sample_poly_cbd_portable(&bytes[..64 * eta])));
+            }
+        }
+    }
+
+    #[test]
+    fn test_sample_ntt_block() {
+        if !cpuid_avx2::get() {
+            return;
+        }
+        for seed in 1..200 {
+            let f = poly(seed);
+            #[allow(clippy::cast_possible_truncation)] // intentionally wraps
+            let block: [u8; 168] = core::array::from_fn(|i| (f[i].0 >> (seed % 5)) as u8);
+            for start in [0, 100, 250] {
+                let mut expected = Poly::default();
+                let mut j = start;
+                for c in block.chunks_exact(3) {
+                    let d1 = u16::from(c[0]) + 256 * (u16::from(c[1]) & 0x0F);
+                    let d2 = (u16::from(c[1]) >> 4) + 16 * u16::from(c[2]);
+                    for d in [d1, d2] {
+                        if d < Q && j < 256 {
+                            expected[j] = Z(d);
+                            j += 1;
+                        }
+                    }
+                }
+                let mut a_hat = Poly::default();
+                assert_eq!(super::sample_ntt_block(&block, &mut a_hat, start), Some(j));
+                assert_eq!(as_u16(&a_hat), as_u16(&expected));
+            }
+        }
+    }
+
+    #[test]
+    fn test_keccak_x4() {
+        use crate::helpers::{prf, xof};
+        use crate::sampling::sample_ntt;
+        use sha3::digest::{ExtendableOutput, Update, XofReader};
+
+        if !cpuid_avx2::get() {
+            return;
+        }
+        let inputs: [[u8; 34]; 4] =
+            core::array::from_fn(|k| core::array::from_fn(|i| (i * k).to_le_bytes()[0]));
+        let mut shake = super::ShakeX4::<168>::new(core::array::from_fn(|k| &inputs[k][..k * 11]));
+        let mut readers: [_; 4] = core::array::from_fn(|k| {
+            let mut hasher = sha3::Shake128::default();
+            hasher.update(&inputs[k][..k * 11]);
+            hasher.finalize_xof()
+        });
+        let mut blocks = [[0u8; 168]; 4];
+        for _ in 0..3 {
+            // SAFETY: AVX2 support was checked above
+            unsafe { shake.squeeze(&mut blocks) };
+            for (block, reader) in blocks.iter().zip(&mut readers) {
+                let mut expected = [0u8; 168];
+                reader.read(&mut expected);
+                assert_eq!(block, &expected);
+            }
+        }
+
+        let s = inputs[3][..32].try_into().unwrap();
+        let Some(prfs) = super::prf_many::<192, 3>(&s, 4) else {
+            return; // a custom Keccak backend is configured
+        };
+        for (b, out) in (4..).zip(&prfs) {
+            assert_eq!(out, &prf::<192>(&s, b));
+        }
+        let a_hat = super::gen_a_hat::<3>(&s).expect("enabled as for prf_many()");
+        for (i, row) in (0..).zip(&a_hat) {
+            for (j, entry) in (0..).zip(row) {
+                assert_eq!(as_u16(entry), as_u16(&sample_ntt(xof(&s, j, i))));
+            }
+        }
+    }
+}
@@ -0,0 +1,425 @@
+// x86_64 AVX-512 backend (with the `avx512` feature) for the NTT, inverse NTT and NTT
+// multiplication (i.e., the matrix arithmetic), selected at runtime via CPUID ahead of the AVX2
+// backend. It follows the AVX2 backend (see there for the Montgomery arithmetic and constants) but
+// with 32 lanes per vector, and uses the AVX-512BW two-source permutation for every rearrangement.
+// IFMA is not used, as its 52-bit multiply-accumulate on 64-bit lanes processes a quarter of the
+// lanes of the 16-bit arithmetic here. As there, the unsafe code is limited to the loads and
+// stores plus the intrinsics, which require the AVX-512 support checked at runtime. The intrinsics
+// require Rust 1.89 or higher.
+#![allow(unsafe_code, unsafe_op_in_unsafe_fn, clippy::incompatible_msrv)]
+
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+use crate::avx2::F;
+use crate::avx2::{mont, zeta, zeta_pow, QINV};
+use crate::types::Poly;
+use crate::Q;
+use core::arch::x86_64::{
+    __m512i, _mm512_add_epi16, _mm512_and_si512, _mm512_load_si512, _mm512_loadu_si512,
+    _mm512_mulhi_epi16, _mm512_mullo_epi16, _mm512_permutex2var_epi16, _mm512_set1_epi16,
+    _mm512_setzero_si512, _mm512_srai_epi16, _mm512_store_si512, _mm512_sub_epi16,
+};
+
+cpufeatures::new!(cpuid_avx512, "avx512f", "avx512bw");
+
+
+/// Computes `NTT(f)` in place when AVX-512 is available, returning whether it did. See `ntt::ntt_portable()`.
+pub(crate) fn ntt(array_f: &mut Poly) -> bool {
+    // SAFETY: AVX-512 support was detected at runtime
+    cpuid_avx512::get().then(|| unsafe { ntt_avx512(array_f) }).is_some()
+}
+
+
+/// Computes `NTTinv(f_hat)` (plus any `addend`) in place when AVX-512 is available, returning whether
+/// it did. See `ntt::ntt_inv_portable()`.
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+pub(crate) fn ntt_inv(f_hat: &mut Poly, addend: Option<&Poly>) -> bool {
+    // SAFETY: AVX-512 support was detected at runtime
+    cpuid_avx512::get().then(|| unsafe { ntt_inv_avx512(f_hat, addend) }).is_some()
+}
+
+
+/// Computes `MultiplyNTTs(f_hat, g_hat)` when AVX-512 is available. See `ntt::multiply_ntts_portable()`.
+pub(crate) fn multiply_ntts(f_hat: &Poly, g_hat: &Poly) -> Option<Poly> {
+    // SAFETY: AVX-512 support was detected at runtime
+    cpuid_avx512::get().then(|| unsafe { multiply_ntts_avx512(f_hat, g_hat) })
+}
+
+
+// ----------
+// Constants, calculated at compile-time. Each pair of adjacent vectors holds 64 coefficients, and
+// the layers with len 16 down to 2 permute the lower halves of their butterflies into `x` and the
+// upper halves into `y` (and back again).
+
+/// Permutation indices for `x`, `y` (from the pair `a`, `b`) then `a`, `b` (from `x`, `y`) per layer
+static SPLITS: [[[i16; 32]; 4]; 4] = [gen_split(16), gen_split(8), gen_split(4), gen_split(2)];
+
+/// The zetas of the NTT layers that pair whole vectors, indexed as in Algorithms 9 and 10
+static ZETAS: [[i16; 2]; 8] = gen_zetas();
+
+/// Per-lane zetas of the NTT layers with len 16 down to 2, after the permutations above
+static ZETAS_LANES: [[[[i16; 32]; 4]; 2]; 4] = [
+    gen_lane_zetas(16, false),
+    gen_lane_zetas(8, false),
+    gen_lane_zetas(4, false),
+    gen_lane_zetas(2, false),
+];
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+static ZETAS_INV_LANES: [[[[i16; 32]; 4]; 2]; 4] = [
+    gen_lane_zetas(16, true),
+    gen_lane_zetas(8, true),
+    gen_lane_zetas(4, true),
+    gen_lane_zetas(2, true),
+];
+
+/// Per-lane gammas of `BaseCaseMultiply()`, in order as the pairs are de-interleaved
+static GAMMAS: [[[i16; 32]; 4]; 2] = gen_gammas();
+
+/// Permutation indices to de-interleave 32 pairs into (even, odd) and to re-interleave them
+static PAIRS: [[i16; 32]; 4] = gen_pairs();
+
+
+// The (local) coefficient index of the lower half of butterfly `lane` within the pair of vectors
+const fn lower(len: usize, lane: usize) -> usize { (lane / len) * 2 * len + lane % len }
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)] // indices < 64
+const fn gen_split(len: usize) -> [[i16; 32]; 4] {
+    let mut result = [[0i16; 32]; 4];
+    let mut lane = 0;
+    while lane < 32 {
+        // Index bit 5 selects the second source vector
+        result[0][lane] = lower(len, lane) as i16;
+        result[1][lane] = (lower(len, lane) + len) as i16;
+        let mut half = 0;
+        while half < 2 {
+            let index = 32 * half + lane;
+            let from_y = if (index / len) % 2 == 0 { 0 } else { 32 };
+            result[2 + half][lane] = ((index / (2 * len)) * len + index % len + from_y) as i16;
+            half += 1;
+        }
+        lane += 1;
+    }
+    result
+}
+
+const fn gen_zetas() -> [[i16; 2]; 8] {
+    let mut result = [[0i16; 2]; 8];
+    let mut i = 0;
+    while i < 8 {
+        result[i] = mont(zeta(i));
+        i += 1;
+    }
+    result
+}
+
+const fn gen_lane_zetas(len: usize, inverse: bool) -> [[[i16; 32]; 4]; 2] {
+    let mut result = [[[0i16; 32]; 4]; 2];
+    let mut p = 0;
+    while p < 4 {
+        let mut lane = 0;
+        while lane < 32 {
+            let block = (64 * p + lower(len, lane)) / (2 * len);
+            // Algorithm 9 increments i from 128/len, while Algorithm 10 decrements i to 128/len
+            let i = if inverse {
+                256 / len - 1 - block
+            } else {
+                128 / len + block
+            };
+            let [z, zq] = mont(zeta(i));
+            result[0][p][lane] = z;
+            result[1][p][lane] = zq;
+            lane += 1;
+        }
+        p += 1;
+    }
+    result
+}
+
+const fn gen_gammas() -> [[[i16; 32]; 4]; 2] {
+    let mut result = [[[0i16; 32]; 4]; 2];
+    let mut chunk = 0;
+    while chunk < 4 {
+        let mut lane = 0;
+        while lane < 32 {
+            // ζ^{2·BitRev_7(i) + 1}
+            #[allow(clippy::cast_possible_truncation)] // const fns cannot use u8::try_from() etc...
+            let i = (32 * chunk + lane) as u8;
+            let [g, gq] = mont(zeta_pow(2 * ((i.reverse_bits() >> 1) as u32) + 1));
+            result[0][chunk][lane] = g;
+            result[1][chunk][lane] = gq;
+            lane += 1;
+        }
+        chunk += 1;
+    }
+    result
+}
+
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap)] // indices < 64
+const fn gen_pairs() -> [[i16; 32]; 4] {
+    let mut result = [[0i16; 32]; 4];
+    let mut lane = 0;
+    while lane < 32 {
+        result[0][lane] = (2 * lane) as i16; // the a0 (or b0) of each pair
+        result[1][lane] = (2 * lane + 1) as i16; // the a1 (or b1) of each pair
+        let from = lane / 2 + if lane % 2 == 0 { 0 } else { 32 };
+        result[2][lane] = from as i16; // c0, c1 of the first 16 pairs
+        result[3][lane] = (from + 16) as i16; // c0, c1 of the last 16 pairs
+        lane += 1;
+    }
+    result
+}
+
+
+// ----------
+// Vector arithmetic, as per the AVX2 backend.
+
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn splat(x: i16) -> __m512i { _mm512_set1_epi16(x) }
+
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+#[allow(clippy::cast_possible_wrap)] // Q < 2^15
+unsafe fn q() -> __m512i { _mm512_set1_epi16(Q as i16) }
+
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn load(x: &[i16; 32]) -> __m512i {
+    // SAFETY: 64 bytes are readable, and unaligned loads are used
+    unsafe { _mm512_loadu_si512(x.as_ptr().cast()) }
+}
+
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn fqmul(a: __m512i, b: __m512i, bq: __m512i) -> __m512i {
+    let hi = _mm512_mulhi_epi16(a, b);
+    let t = _mm512_mullo_epi16(a, bq);
+    _mm512_sub_epi16(hi, _mm512_mulhi_epi16(t, q()))
+}
+
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn fqmul_var(a: __m512i, b: __m512i) -> __m512i {
+    fqmul(a, b, _mm512_mullo_epi16(b, splat(QINV)))
+}
+
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn normalize(a: __m512i) -> __m512i {
+    _mm512_add_epi16(a, _mm512_and_si512(_mm512_srai_epi16(a, 15), q()))
+}
+
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn add(a: __m512i, b: __m512i) -> __m512i {
+    normalize(_mm512_sub_epi16(_mm512_add_epi16(a, b), q()))
+}
+
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn sub(a: __m512i, b: __m512i) -> __m512i { normalize(_mm512_sub_epi16(a, b)) }
+
+// Algorithm 9 steps 8-10
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn butterfly(a: __m512i, b: __m512i, z: __m512i, zq: __m512i) -> (__m512i, __m512i) {
+    let t = normalize(fqmul(b, z, zq));
+    (add(a, t), sub(a, t))
+}
+
+// Algorithm 10 steps 8-10
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn butterfly_inv(a: __m512i, b: __m512i, z: __m512i, zq: __m512i) -> (__m512i, __m512i) {
+    (add(a, b), normalize(fqmul(sub(b, a), z, zq)))
+}
+
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn permute(
+    indices: &[[i16; 32]; 4], a: __m512i, b: __m512i, first: usize,
+) -> (__m512i, __m512i) {
+    let x = _mm512_permutex2var_epi16(a, load(&indices[first]), b);
+    let y = _mm512_permutex2var_epi16(a, load(&indices[first + 1]), b);
+    (x, y)
+}
+
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn load_poly(f: &Poly) -> [__m512i; 8] {
+    let mut v = [_mm512_setzero_si512(); 8];
+    for (i, vec) in v.iter_mut().enumerate() {
+        // SAFETY: `Z` is a transparent `u16` and `Poly` is 64-byte aligned, so each of the 8
+        // aligned loads reads 64 bytes within `f`
+        *vec = unsafe { _mm512_load_si512(f.as_ptr().add(32 * i).cast()) };
+    }
+    v
+}
+
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn store_poly_into(v: &[__m512i; 8], f: &mut Poly) {
+    for (i, vec) in v.iter().enumerate() {
+        // SAFETY: `Z` is a transparent `u16` and `Poly` is 64-byte aligned, so each of the 8
+        // aligned stores writes 64 bytes within `f`
+        unsafe { _mm512_store_si512(f.as_mut_ptr().add(32 * i).cast(), *vec) };
+    }
+}
+
+#[inline]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn store_poly(v: &[__m512i; 8]) -> Poly {
+    let mut f = Poly::default();
+    store_poly_into(v, &mut f);
+    f
+}
+
+
+// ----------
+// The backend functions themselves.
+
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn ntt_avx512(array_f: &mut Poly) {
+    let mut v = load_poly(array_f);
+
+    // Layers with len 128 down to 32 pair whole vectors, with one zeta per block
+    let mut i = 1;
+    for len in [4, 2, 1] {
+        for start in (0..8).step_by(2 * len) {
+            let (z, zq) = (splat(ZETAS[i][0]), splat(ZETAS[i][1]));
+            i += 1;
+            for j in start..(start + len) {
+                (v[j], v[j + len]) = butterfly(v[j], v[j + len], z, zq);
+            }
+        }
+    }
+
+    // Layers with len 16 down to 2 pair lanes within each (permuted) pair of vectors
+    for (split, zetas) in SPLITS.iter().zip(&ZETAS_LANES) {
+        for p in 0..4 {
+            let (x, y) = permute(split, v[2 * p], v[2 * p + 1], 0);
+            let (x, y) = butterfly(x, y, load(&zetas[0][p]), load(&zetas[1][p]));
+            (v[2 * p], v[2 * p + 1]) = permute(split, x, y, 2);
+        }
+    }
+
+    store_poly_into(&v, array_f);
+}
+
+
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn ntt_inv_avx512(f_hat: &mut Poly, addend: Option<&Poly>) {
+    let mut v = load_poly(f_hat);
+
+    // Layers with len 2 up to 16 pair lanes within each (permuted) pair of vectors
+    for (split, zetas) in SPLITS.iter().zip(&ZETAS_INV_LANES).rev() {
+        for p in 0..4 {
+            let (x, y) = permute(split, v[2 * p], v[2 * p + 1], 0);
+            let (x, y) = butterfly_inv(x, y, load(&zetas[0][p]), load(&zetas[1][p]));
+            (v[2 * p], v[2 * p + 1]) = permute(split, x, y, 2);
+        }
+    }
+
+    // Layers with len 32 up to 128 pair whole vectors, with one zeta per block
+    let mut i = 7;
+    for len in [1, 2, 4] {
+        for start in (0..8).step_by(2 * len) {
+            let (z, zq) = (splat(ZETAS[i][0]), splat(ZETAS[i][1]));
+            i -= 1;
+            for j in start..(start + len) {
+                (v[j], v[j + len]) = butterfly_inv(v[j], v[j + len], z, zq);
+            }
+        }
+    }
+
+    // Multiply every entry by 3303 ≡ 128^{−1} mod q, while reversing the 2^{-16} of the products,
+    // then add any addend
+    let (f, fq) = (splat(F[0]), splat(F[1]));
+    let addend = addend.map(|addend| load_poly(addend));
+    for (i, vec) in v.iter_mut().enumerate() {
+        *vec = normalize(fqmul(*vec, f, fq));
+        if let Some(addend) = &addend {
+            *vec = add(*vec, addend[i]);
+        }
+    }
+
+    store_poly_into(&v, f_hat);
+}
+
+
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn multiply_ntts_avx512(f_hat: &Poly, g_hat: &Poly) -> Poly {
+    let (f, g) = (load_poly(f_hat), load_poly(g_hat));
+    let mut h = [_mm512_setzero_si512(); 8];
+
+    // Each chunk of 64 coefficients is de-interleaved into 32 (a0, b0) and 32 (a1, b1) lanes
+    for chunk in 0..4 {
+        let (a0, a1) = permute(&PAIRS, f[2 * chunk], f[2 * chunk + 1], 0);
+        let (b0, b1) = permute(&PAIRS, g[2 * chunk], g[2 * chunk + 1], 0);
+        let (gamma, gamma_q) = (load(&GAMMAS[0][chunk]), load(&GAMMAS[1][chunk]));
+
+        // c0 ← a0·b0 + a1·b1·γ, scaled by 2^{-16} as per the portable `multiply_ntts()`
+        let a1b1_gamma = normalize(fqmul(fqmul_var(a1, b1), gamma, gamma_q));
+        let c0 = add(normalize(fqmul_var(a0, b0)), a1b1_gamma);
+
+        // c1 ← a0·b1 + a1·b0
+        let c1 = add(normalize(fqmul_var(a0, b1)), normalize(fqmul_var(a1, b0)));
+
+        (h[2 * chunk], h[2 * chunk + 1]) = permute(&PAIRS, c0, c1, 2);
+    }
+
+    store_poly(&h)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::cpuid_avx512;
+    use crate::ntt::{multiply_ntts_portable, ntt_inv_portable, ntt_portable};
+    use crate::types::{Poly, Z};
+    use crate::Q;
+
+    // Deterministic pseudo-random coefficients in [0, q), including the extremes
+    fn poly(seed: u64) -> Poly {
+        let mut state = seed;
+        Poly(core::array::from_fn(|i| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            #[allow(clippy::cast_possible_truncation)] // value < q
+            let value = match i {
+                0 => 0,
+                1 => Q - 1,
+                _ => (state % u64::from(Q)) as u16,
+            };
+            Z(value)
+        }))
+    }
+
+    fn as_u16(f: &Poly) -> [u16; 256] { core::array::from_fn(|i| f[i].0) }
+
+    #[test]
+    fn test_matches_portable() {
+        if !cpuid_avx512::get() {
+            return;
+        }
+        for seed in 1..200 {
+            let (f, g) = (poly(seed), poly(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15)));
+            let (mut ntt, mut ntt_ref) = (f, f);
+            assert!(super::ntt(&mut ntt));
+            ntt_portable(&mut ntt_ref);
+            assert_eq!(as_u16(&ntt), as_u16(&ntt_ref));
+            let (mut ntt_inv, mut ntt_inv_ref) = (f, f);
+            assert!(super::ntt_inv(&mut ntt_inv, None));
+            ntt_inv_portable(&mut ntt_inv_ref, None);
+            assert_eq!(as_u16(&ntt_inv), as_u16(&ntt_inv_ref));
+            let (mut ntt_inv, mut ntt_inv_ref) = (f, f);
+            assert!(super::ntt_inv(&mut ntt_inv, Some(&g)));
+            ntt_inv_portable(&mut ntt_inv_ref, Some(&g));
+            assert_eq!(as_u16(&ntt_inv), as_u16(&ntt_inv_ref));
+            let product = super::multiply_ntts(&f, &g).unwrap();
+            assert_eq!(as_u16(&product), as_u16(&multiply_ntts_portable(&f, &g)));
+        }
+    }
+}
@@ -0,0 +1,79 @@
+// Blinding of the secret polynomial arithmetic of decryption (the `blinding` feature), as a cheap
+// hedge against differential power analysis (DPA) for deployments without full masking. For each
+// decapsulation, fresh randomness selects a scalar `α ∈ [1, q)` and a uniform `ρ_hat` (with `ρ =
+// NTT^{−1}(ρ_hat)`), and step 6 of Algorithm 15 computes `NTT^{−1}(s_hat ∘ u_hat)` as
+// `α^{−1}·(NTT^{−1}((α·s_hat) ∘ u_hat + ρ_hat) − ρ)`. Hence neither `s_hat` nor the product from
+// which the message is decoded appear unblinded within the NTT multiplication and inverse NTT,
+// while the result is unchanged.
+
+use crate::ct_mul::mul_wide;
+use crate::helpers::{dot_t_prod, prf};
+use crate::ntt::ntt_inv;
+use crate::types::{Poly, Z};
+use crate::Q;
+use zeroize::{Zeroize, ZeroizeOnDrop, Zeroizing};
+
+
+/// The blinding values of one decapsulation, with `α` and `α^{−1}` in Montgomery form
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub(crate) struct Blinding {
+    alpha: Z,
+    alpha_inv: Z,
+    rho: Poly,
+    rho_hat: Poly,
+}
+
+
+impl Blinding {
+    /// Expands the blinding values from a (fresh) 32-byte seed. Each value is mapped from 32 bits
+    /// to `[0, q)` by a multiplication (rather than a variable-time rejection), with a bias below
+    /// `2^{−20}` that is immaterial to blinding.
+    #[allow(clippy::cast_possible_truncation)] // the top 32 bits, as u16
+    pub(crate) fn new(seed: &[u8; 32]) -> Self {
+        let to_z = |bytes: &[u8], m: u32| {
+            let x = u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]);
+            Z((mul_wide(x, m) >> 32) as u16)
+        };
+        let alpha_bytes = Zeroizing::new(prf::<4>(seed, 0));
+        let alpha = Z(to_z(&*alpha_bytes, u32::from(Q) - 1).0 + 1).to_mont();
+        let rho_bytes = Zeroizing::new(prf::<1024>(seed, 1));
+        let mut rho_hat = Poly::default();
+        for (rho_hat_i, bytes) in rho_hat.iter_mut().zip(rho_bytes.chunks_exact(4)) {
+            *rho_hat_i = to_z(bytes, u32::from(Q));
+        }
+        let mut rho = rho_hat;
+        ntt_inv(&mut rho); // so that `ρ` is removed exactly, by the linearity of NTT^{−1} alone
+
+        // α^{−1} = α^{q−2} by square-and-multiply (over the public exponent), all in Montgomery form
+        let (mut alpha_inv, mut base) = (Z(1).to_mont(), alpha);
+        let mut e = Q - 2;
+        while e > 0 {
+            if e & 1 == 1 {
+                alpha_inv = alpha_inv.mont_mul(base);
+            }
+            base = base.mont_mul(base);
+            e >>= 1;
+        }
+        base.zeroize();
+        Blinding { alpha, alpha_inv, rho, rho_hat }
+    }
+
+    /// Returns `NTT^{−1}(s_hat ∘ u_hat)`, as step 6 of Algorithm 15, computed under the blinding
+    pub(crate) fn ntt_inv_product<const K: usize>(
+        &self, s_hat: &[Poly; K], u_hat: &[Poly; K],
+    ) -> Poly {
+        let mut s_hat_alpha = Zeroizing::new(*s_hat);
+        for s_hat_ij in s_hat_alpha.iter_mut().flat_map(|s_hat_i| s_hat_i.iter_mut()) {
+            *s_hat_ij = s_hat_ij.mont_mul(self.alpha);
+        }
+        let mut yy = dot_t_prod(&s_hat_alpha, u_hat);
+        for (yy_i, rho_hat_i) in yy.iter_mut().zip(self.rho_hat.iter()) {
+            *yy_i = yy_i.add(*rho_hat_i);
+        }
+        ntt_inv(&mut yy);
+        for (yy_i, rho_i) in yy.iter_mut().zip(self.rho.iter()) {
+            *yy_i = yy_i.sub(*rho_i).mont_mul(self.alpha_inv);
+        }
+        yy
+    }
+}
@@ -0,0 +1,339 @@
+// Cortex-M4 backend (with the `cortex-m4-asm` feature) for the NTT, inverse NTT and NTT
+// multiplication, similar to the pqm4 Kyber code. Pairs of adjacent coefficients are packed as
+// signed 16-bit halves of a 32-bit word, so that the Thumb-2 DSP instructions operate on both at
+// once: SMULBB/SMULTB for the products with a zeta, SMLABB for the Montgomery reduction of each
+// product into the top half of a word, PKHTB to repack, SADD16/SSUB16 for the butterflies, and
+// SMUADX for the `a0·b1 + a1·b0` of the base case multiply.
+//
+// The coefficients grow (and are reduced) exactly as in the portable code, and every result is
+// normalized into [0, q) so that outputs are identical to the portable code. There are no
+// secret-dependent branches or memory accesses. On `arm` targets, the DSP extension is required
+// (i.e., `thumbv7em-none-eabi` or `thumbv7em-none-eabihf`, as `target_feature = "dsp"` is not
+// visible to `cfg` on stable Rust). On other architectures, the instructions are emulated in
+// portable Rust. This keeps the feature testable on the host, but it is not intended for use there.
+#![allow(unsafe_code)] // just the inline assembly of the DSP instructions
+#![allow(clippy::inline_always)] // the helpers below are each only a few instructions
+
+use crate::ntt::{GAMMA_TABLE, ZETA_TABLE};
+use crate::types::{Poly, Z};
+use crate::Q;
+
+
+// `q^{-1} mod 2^16` and `-q`, as 16-bit halves for SMULBB/SMLABB
+#[allow(clippy::cast_sign_loss)]
+const QINV: u32 = (-3327i16) as u16 as u32;
+#[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)]
+const NEG_Q: u32 = (-(Q as i16)) as u16 as u32;
+// round(2^26/q) for the Barrett reduction, as in `Z::barrett_reduce()`
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+const V: u32 = 20159;
+
+
+#[cfg(target_arch = "arm")]
+mod dsp {
+    use core::arch::asm;
+
+    // SMULxy cannot overflow (a product of two 16-bit halves fits in 32 bits), so sets no flags.
+    // SMUADX sets the Q flag should its sum overflow, and the parallel add/subtract instructions set
+    // the GE flags, so flags are not preserved for those.
+    macro_rules! dsp_op {
+        ($name:ident, $instr:literal, $out:ty $(, $opt:ident)?) => {
+            #[inline(always)]
+            pub(super) fn $name(a: u32, b: u32) -> $out {
+                let r: $out;
+                // SAFETY: register-only arithmetic, available with the DSP extension
+                unsafe {
+                    asm!(concat!($instr, " {r}, {a}, {b}"), r = lateout(reg) r, a = in(reg) a,
+                        b = in(reg) b, options(pure, nomem, nostack $(, $opt)?));
+                }
+                r
+            }
+        };
+    }
+
+    dsp_op!(smulbb, "smulbb", i32, preserves_flags);
+    dsp_op!(smultb, "smultb", i32, preserves_flags);
+    dsp_op!(smultt, "smultt", i32, preserves_flags);
+    dsp_op!(smuadx, "smuadx", i32);
+    dsp_op!(sadd16, "sadd16", u32);
+    dsp_op!(ssub16, "ssub16", u32);
+
+    // SMLABB sets the Q flag should its accumulation overflow, so flags are not preserved
+    #[inline(always)]
+    pub(super) fn smlabb(a: u32, b: u32, acc: i32) -> i32 {
+        let r: i32;
+        // SAFETY: register-only arithmetic, available with the DSP extension
+        unsafe {
+            asm!("smlabb {r}, {a}, {b}, {acc}", r = lateout(reg) r, a = in(reg) a, b = in(reg) b,
+                acc = in(reg) acc, options(pure, nomem, nostack));
+        }
+        r
+    }
+
+    // Top half of `a` with the top half of `b` (shifted down), i.e., `PKHTB r, a, b, ASR #16`
+    #[inline(always)]
+    pub(super) fn pkhtb(a: i32, b: i32) -> u32 {
+        let r: u32;
+        // SAFETY: register-only arithmetic, available with the DSP extension
+        unsafe {
+            asm!("pkhtb {r}, {a}, {b}, asr #16", r = lateout(reg) r, a = in(reg) a, b = in(reg) b,
+                options(pure, nomem, nostack, preserves_flags));
+        }
+        r
+    }
+}
+
+
+#[cfg(not(target_arch = "arm"))]
+#[allow(clippy::cast_possible_truncation, clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+mod dsp {
+    // Emulations of the DSP instructions above, with the same (wrapping) semantics
+
+    fn lo(a: u32) -> i32 { i32::from(a as i16) }
+
+    fn hi(a: u32) -> i32 { i32::from((a >> 16) as i16) }
+
+    fn pack(lo: i32, hi: i32) -> u32 { u32::from(lo as u16) | (u32::from(hi as u16) << 16) }
+
+    pub(super) fn smulbb(a: u32, b: u32) -> i32 { lo(a) * lo(b) }
+
+    pub(super) fn smultb(a: u32, b: u32) -> i32 { hi(a) * lo(b) }
+
+    pub(super) fn smultt(a: u32, b: u32) -> i32 { hi(a) * hi(b) }
+
+    pub(super) fn smuadx(a: u32, b: u32) -> i32 { lo(a) * hi(b) + hi(a) * lo(b) }
+
+    pub(super) fn sadd16(a: u32, b: u32) -> u32 { pack(lo(a) + lo(b), hi(a) + hi(b)) }
+
+    pub(super) fn ssub16(a: u32, b: u32) -> u32 { pack(lo(a) - lo(b), hi(a) - hi(b)) }
+
+    pub(super) fn smlabb(a: u32, b: u32, acc: i32) -> i32 { acc.wrapping_add(lo(a) * lo(b)) }
+
+    pub(super) fn pkhtb(a: i32, b: i32) -> u32 { (a as u32 & 0xFFFF_0000) | ((b as u32) >> 16) }
+}
+
+
+use dsp::{pkhtb, sadd16, smlabb, smuadx, smulbb, smultb, smultt, ssub16};
+
+
+// Montgomery reduces the two products into the halves of one word, as `Z::mont_reduce_lazy()`
+#[inline(always)]
+#[allow(clippy::cast_sign_loss)] // the products are reinterpreted as words
+fn mont2(p_lo: i32, p_hi: i32) -> u32 {
+    let r_lo = smlabb(smulbb(p_lo as u32, QINV) as u32, NEG_Q, p_lo);
+    let r_hi = smlabb(smulbb(p_hi as u32, QINV) as u32, NEG_Q, p_hi);
+    pkhtb(r_hi, r_lo)
+}
+
+
+// Multiplies both halves of `a` by the (Montgomery form) zeta in the bottom half of `zeta`
+#[inline(always)]
+fn mont_mul2(a: u32, zeta: u32) -> u32 { mont2(smulbb(a, zeta), smultb(a, zeta)) }
+
+
+// Barrett reduces both halves into [-(q-1)/2, (q-1)/2], as `Z::barrett_reduce()` before its
+// final correction (which is not needed for the intermediate values)
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+#[inline(always)]
+#[allow(clippy::cast_sign_loss)]
+fn barrett2(a: u32) -> u32 {
+    let t_lo = (smulbb(a, V) + (1 << 25)) >> 26;
+    let t_hi = (smultb(a, V) + (1 << 25)) >> 26;
+    let tq = ((t_lo * i32::from(Q)) as u32 & 0xFFFF) | (((t_hi * i32::from(Q)) as u32) << 16);
+    ssub16(a, tq)
+}
+
+
+// Cooley-Tukey butterfly of the NTT on both halves: `(a + zeta·b, a - zeta·b)`
+#[inline(always)]
+fn ct_butterfly(a: u32, b: u32, zeta: u32) -> (u32, u32) {
+    let t = mont_mul2(b, zeta);
+    (sadd16(a, t), ssub16(a, t))
+}
+
+
+// Gentleman-Sande butterfly of the inverse NTT on both halves: `(a + b, zeta·(b - a))`
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+#[inline(always)]
+fn gs_butterfly(a: u32, b: u32, zeta: u32) -> (u32, u32) {
+    (sadd16(a, b), mont_mul2(ssub16(b, a), zeta))
+}
+
+
+fn pack(f: &Poly) -> [u32; 128] {
+    core::array::from_fn(|w| u32::from(f[2 * w].0) | (u32::from(f[2 * w + 1].0) << 16))
+}
+
+
+fn zeta(i: usize) -> u32 { u32::from(ZETA_TABLE[i << 1].0) }
+
+
+/// Computes `NTT(f)` in place. See `ntt::ntt_portable()`, which has the same structure.
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // halves of the packed words
+pub(crate) fn ntt(array_f: &mut Poly) {
+    let mut p = pack(array_f);
+
+    // Layers merged in pairs as (128, 64), (32, 16), (8, 4), operating on words of two coefficients
+    for len in [128, 32, 8] {
+        let (len_w, half_w) = (len / 2, len / 4);
+        for start in (0..256).step_by(2 * len) {
+            let i = 128 / len + start / (2 * len);
+            let (z1, z2, z3) = (zeta(i), zeta(2 * i), zeta(2 * i + 1));
+            for w in (start / 2)..(start / 2 + half_w) {
+                let (x0, x2) = ct_butterfly(p[w], p[w + len_w], z1);
+                let (x1, x3) = ct_butterfly(p[w + half_w], p[w + len_w + half_w], z1);
+                (p[w], p[w + half_w]) = ct_butterfly(x0, x1, z2);
+                (p[w + len_w], p[w + len_w + half_w]) = ct_butterfly(x2, x3, z3);
+            }
+        }
+    }
+
+    // The final layer with len = 2, between adjacent words
+    for w in (0..128).step_by(2) {
+        (p[w], p[w + 1]) = ct_butterfly(p[w], p[w + 1], zeta(64 + w / 2));
+    }
+
+    // Reduced into [0, q), written back in place
+    for (f, x) in array_f.chunks_exact_mut(2).zip(p) {
+        f[0] = Z(Z::barrett_reduce(i32::from(x as i16)) as u16);
+        f[1] = Z(Z::barrett_reduce(i32::from((x >> 16) as i16)) as u16);
+    }
+}
+
+
+/// Computes `NTTinv(f)`, plus `addend` when provided, in place. See `ntt::ntt_inv_portable()`,
+/// which has the same structure.
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+#[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // halves of the packed words
+pub(crate) fn ntt_inv(f_hat: &mut Poly, addend: Option<&Poly>) {
+    let mut p = pack(f_hat);
+
+    // Layers merged in pairs as (2, 4), (8, 16), (32, 64), operating on words of two coefficients
+    for len in [2, 8, 32] {
+        let len_w = len / 2;
+        for start in (0..256).step_by(4 * len) {
+            let i = 256 / len - 1 - start / (2 * len);
+            let (z1, z2, z3) = (zeta(i), zeta(i - 1), zeta(i / 2));
+            for w in (start / 2)..(start / 2 + len_w) {
+                let (x0, x1) = gs_butterfly(p[w], p[w + len_w], z1);
+                let (x2, x3) = gs_butterfly(p[w + 2 * len_w], p[w + 3 * len_w], z2);
+                let (x0, x2) = gs_butterfly(x0, x2, z3);
+                let (x1, x3) = gs_butterfly(x1, x3, z3);
+                (p[w], p[w + 2 * len_w]) = (barrett2(x0), x2);
+                (p[w + len_w], p[w + 3 * len_w]) = (barrett2(x1), x3);
+            }
+        }
+    }
+
+    // The final layer with len = 128, with the scaling by 3303 (and reversal of the 2^{-16}
+    // factor) folded into both constants; see `ntt::ntt_inv_portable()`
+    let f_3303 = Z(1441);
+    let zeta_3303 = u32::from(ZETA_TABLE[1 << 1].mont_mul(f_3303).0);
+    let f_3303 = u32::from(f_3303.0);
+    let normalize = |x: u32| {
+        let r = i32::from(x as i16);
+        Z((r + ((r >> 31) & i32::from(Q))) as u16)
+    };
+    for w in 0..64 {
+        let (x0, x1) = (p[w], p[w + 64]);
+        let (sum, diff) = (mont_mul2(sadd16(x0, x1), f_3303), mont_mul2(ssub16(x1, x0), zeta_3303));
+        let (j, k) = (2 * w, 2 * w + 128);
+        f_hat[j] = normalize(sum);
+        f_hat[j + 1] = normalize(sum >> 16);
+        f_hat[k] = normalize(diff);
+        f_hat[k + 1] = normalize(diff >> 16);
+        if let Some(addend) = addend {
+            for idx in [j, j + 1, k, k + 1] {
+                f_hat[idx] = f_hat[idx].add(addend[idx]);
+            }
+        }
+    }
+}
+
+
+/// Computes `Σ_j MultiplyNTTs(f_hat[j], g_hat[j])` over `K ≤ 4` pairs. See
+/// `ntt::multiply_ntts_sum_portable()`, which has the same (lazy) reduction and output scaling.
+#[must_use]
+pub(crate) fn multiply_ntts_sum<const K: usize>(f_hat: [&Poly; K], g_hat: &[Poly; K]) -> Poly {
+    debug_assert!(K <= 4, "Alg 11: lazy reduction bound exceeded");
+    let mut h_hat = Poly::default();
+    for (i, &gamma) in GAMMA_TABLE.iter().enumerate() {
+        let (mut c0, mut c1) = (0i32, 0i32);
+        for j in 0..K {
+            let a = u32::from(f_hat[j][2 * i].0) | (u32::from(f_hat[j][2 * i + 1].0) << 16);
+            let b = u32::from(g_hat[j][2 * i].0) | (u32::from(g_hat[j][2 * i + 1].0) << 16);
+            // c0 ← a0·b0 + (a1·b1·2^{-16})·γ, where γ is in Montgomery form
+            let t = Z::mont_reduce_lazy(smultt(a, b)) * i32::from(gamma.0);
+            c0 += smlabb(a, b, t);
+            // c1 ← a0·b1 + a1·b0
+            c1 += smuadx(a, b);
+        }
+        h_hat[2 * i] = Z::mont_reduce(c0);
+        h_hat[2 * i + 1] = Z::mont_reduce(c1);
+    }
+    h_hat
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::ntt::{multiply_ntts_sum_portable, ntt_inv_portable, ntt_portable};
+    use crate::types::{Poly, Z};
+    use crate::Q;
+
+    // Deterministic pseudo-random coefficients in [0, q), including the extremes
+    fn poly(seed: u64) -> Poly {
+        let mut state = seed;
+        Poly(core::array::from_fn(|i| {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            #[allow(clippy::cast_possible_truncation)] // value < q
+            let value = match i {
+                0 => 0,
+                1 => Q - 1,
+                _ => (state % u64::from(Q)) as u16,
+            };
+            Z(value)
+        }))
+    }
+
+    fn as_u16(f: &Poly) -> [u16; 256] { core::array::from_fn(|i| f[i].0) }
+
+    #[test]
+    fn test_matches_portable() {
+        for seed in 1..200 {
+            let (f, g) = (poly(seed), poly(seed.wrapping_mul(0x9E37_79B9_7F4A_7C15)));
+            let (mut ntt, mut ntt_ref) = (f, f);
+            super::ntt(&mut ntt);
+            ntt_portable(&mut ntt_ref);
+            assert_eq!(as_u16(&ntt), as_u16(&ntt_ref));
+            for addend in [None, Some(&g)] {
+                let (mut ntt_inv, mut ntt_inv_ref) = (f, f);
+                super::ntt_inv(&mut ntt_inv, addend);
+                ntt_inv_portable(&mut ntt_inv_ref, addend);
+                assert_eq!(as_u16(&ntt_inv), as_u16(&ntt_inv_ref));
+            }
+            let h = poly(seed ^ 0x5555);
+            let sum = super::multiply_ntts_sum([&f, &g, &h, &f], &[g, h, f, g]);
+            let sum_ref = multiply_ntts_sum_portable([&f, &g, &h, &f], &[g, h, f, g]);
+            assert_eq!(as_u16(&sum), as_u16(&sum_ref));
+        }
+    }
+
+    // The worst case for the lazy reductions: all coefficients q - 1
+    #[test]
+    fn test_extremes() {
+        let max = Poly([Z(Q - 1); 256]);
+        let (mut ntt, mut ntt_ref) = (max, max);
+        super::ntt(&mut ntt);
+        ntt_portable(&mut ntt_ref);
+        assert_eq!(as_u16(&ntt), as_u16(&ntt_ref));
+        let (mut ntt_inv, mut ntt_inv_ref) = (max, max);
+        super::ntt_inv(&mut ntt_inv, Some(&max));
+        ntt_inv_portable(&mut ntt_inv_ref, Some(&max));
+        assert_eq!(as_u16(&ntt_inv), as_u16(&ntt_inv_ref));
+        let sum = super::multiply_ntts_sum([&max; 4], &[max; 4]);
+        assert_eq!(as_u16(&sum), as_u16(&multiply_ntts_sum_portable([&max; 4], &[max; 4])));
+    }
+}
@@ -0,0 +1,170 @@
+// COSE_Key encodings of ML-KEM keys using the Algorithm Key Pair (AKP) key type, as per the IETF
+// COSE post-quantum drafts. These are not part of FIPS 203, and are injected into each parameter
+// set via the macro. Only the small subset of (deterministic) CBOR needed for this is supported.
+
+use crate::helpers::ensure;
+use alloc::vec::Vec;
+
+
+/// COSE key type `AKP` (Algorithm Key Pair)
+pub(crate) const KTY_AKP: i64 = 7;
+
+// COSE_Key labels
+const LABEL_KTY: i64 = 1;
+const LABEL_ALG: i64 = 3;
+const LABEL_PUB: i64 = -1;
+const LABEL_PRIV: i64 = -2;
+
+// CBOR major types
+const MAJOR_UINT: u8 = 0;
+const MAJOR_NINT: u8 = 1;
+const MAJOR_BSTR: u8 = 2;
+const MAJOR_TSTR: u8 = 3;
+const MAJOR_MAP: u8 = 5;
+
+
+/// The relevant members of a decoded AKP `COSE_Key`; unknown members (e.g., `kid`) are skipped.
+pub(crate) struct CoseKey<'a> {
+    pub(crate) alg: Option<i64>,
+    pub(crate) public: Option<&'a [u8]>,
+    pub(crate) private: Option<&'a [u8]>,
+}
+
+
+/// Encodes an AKP `COSE_Key` map with its members in deterministic (sorted) label order.
+pub(crate) fn encode_key(alg: i64, public: &[u8], private: Option<&[u8]>) -> Vec<u8> {
+    let mut out = Vec::with_capacity(public.len() + private.map_or(0, <[u8]>::len) + 32);
+    write_head(&mut out, MAJOR_MAP, if private.is_some() { 4 } else { 3 });
+    write_int(&mut out, LABEL_KTY);
+    write_int(&mut out, KTY_AKP);
+    write_int(&mut out, LABEL_ALG);
+    write_int(&mut out, alg);
+    write_int(&mut out, LABEL_PUB);
+    write_bstr(&mut out, public);
+    if let Some(private) = private {
+        write_int(&mut out, LABEL_PRIV);
+        write_bstr(&mut out, private);
+    }
+    out
+}
+
+
+/// Decodes an AKP `COSE_Key` map; the key type must be AKP and labels must not repeat.
+pub(crate) fn decode_key(bytes: &[u8]) -> Result<CoseKey<'_>, &'static str> {
+    let mut reader = Reader { bytes };
+    let (major, len) = reader.head()?;
+    ensure!(major == MAJOR_MAP, "COSE_Key is not a map");
+    let (mut kty, mut key) = (None, CoseKey { alg: None, public: None, private: None });
+    let mut seen: Vec<i64> = Vec::new();
+    for _i in 0..len {
+        let label = reader.int()?;
+        ensure!(!seen.contains(&label), "COSE_Key label repeated");
+        seen.push(label);
+        match label {
+            LABEL_KTY => kty = Some(reader.int()?),
+            LABEL_ALG => key.alg = Some(reader.int()?),
+            LABEL_PUB => key.public = Some(reader.bstr()?),
+            LABEL_PRIV => key.private = Some(reader.bstr()?),
+            _ => reader.skip()?,
+        }
+    }
+    ensure!(reader.bytes.is_empty(), "COSE_Key has trailing bytes");
+    ensure!(kty == Some(KTY_AKP), "COSE_Key type is not AKP");
+    Ok(key)
+}
+
+
+fn write_head(out: &mut Vec<u8>, major: u8, arg: u64) {
+    let major = major << 5;
+    #[allow(clippy::cast_possible_truncation)] // each arm checks the range
+    match arg {
+        0..=0x17 => out.push(major | arg as u8),
+        0x18..=0xFF => out.extend_from_slice(&[major | 0x18, arg as u8]),
+        0x100..=0xFFFF => {
+            out.push(major | 0x19);
+            out.extend_from_slice(&(arg as u16).to_be_bytes());
+        }
+        0x1_0000..=0xFFFF_FFFF => {
+            out.push(major | 0x1A);
+            out.extend_from_slice(&(arg as u32).to_be_bytes());
+        }
+        _ => {
+            out.push(major | 0x1B);
+            out.extend_from_slice(&arg.to_be_bytes());
+        }
+    }
+}
+
+
+fn write_int(out: &mut Vec<u8>, value: i64) {
+    #[allow(clippy::cast_sign_loss)] // the sign is checked (and encoded in the major type)
+    match value {
+        0.. => write_head(out, MAJOR_UINT, value as u64),
+        _ => write_head(out, MAJOR_NINT, !value as u64), // -1 - value
+    }
+}
+
+
+fn write_bstr(out: &mut Vec<u8>, bytes: &[u8]) {
+    write_head(out, MAJOR_BSTR, bytes.len() as u64);
+    out.extend_from_slice(bytes);
+}
+
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], &'static str> {
+        ensure!(self.bytes.len() >= len, "COSE_Key truncated");
+        let (head, tail) = self.bytes.split_at(len);
+        self.bytes = tail;
+        Ok(head)
+    }
+
+    // Returns the major type and argument; indefinite lengths are not supported
+    fn head(&mut self) -> Result<(u8, u64), &'static str> {
+        let initial = self.take(1)?[0];
+        let arg_len = match initial & 0x1F {
+            info @ 0..=0x17 => return Ok((initial >> 5, u64::from(info))),
+            0x18 => 1,
+            0x19 => 2,
+            0x1A => 4,
+            0x1B => 8,
+            _ => return Err("COSE_Key encoding not supported"),
+        };
+        let arg = self.take(arg_len)?.iter().fold(0u64, |acc, &b| (acc << 8) | u64::from(b));
+        Ok((initial >> 5, arg))
+    }
+
+    fn int(&mut self) -> Result<i64, &'static str> {
+        let (major, arg) = self.head()?;
+        let arg = i64::try_from(arg).map_err(|_| "COSE_Key integer out of range")?;
+        match major {
+            MAJOR_UINT => Ok(arg),
+            MAJOR_NINT => Ok(!arg), // -1 - arg
+            _ => Err("COSE_Key integer expected"),
+        }
+    }
+
+    fn bstr(&mut self) -> Result<&'a [u8], &'static str> {
+        let (major, len) = self.head()?;
+        ensure!(major == MAJOR_BSTR, "COSE_Key byte string expected");
+        self.take(usize::try_from(len).map_err(|_| "COSE_Key truncated")?)
+    }
+
+    // Skips an integer or string member value; nested structures are not supported
+    fn skip(&mut self) -> Result<(), &'static str> {
+        let (major, arg) = self.head()?;
+        match major {
+            MAJOR_UINT | MAJOR_NINT => Ok(()),
+            MAJOR_BSTR | MAJOR_TSTR => {
+                let _skipped =
+                    self.take(usize::try_from(arg).map_err(|_| "COSE_Key truncated")?)?;
+                Ok(())
+            }
+            _ => Err("COSE_Key member type not supported"),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// Multiplication of (potentially secret) coefficients for the portable arithmetic. By default,
+// these are the native multiply. With the `ct-mul` feature, they are instead computed by
+// shift-and-add over the bits of the second operand using masks rather than branches, for
+// targets (e.g., some ARMv6-M parts) whose multiplier latency depends upon the operand values.
+// Each mask is passed through `black_box()`, as the optimizer otherwise rewrites the masked term as
+// `a·(b & 2^i)` and emits the native multiply once again (as seen for `thumbv6m-none-eabi`, where
+// passing just `b` through `black_box()` was not enough).
+
+/// Returns `a·b` for `0 ≤ b < 2^16` (with the wrapping of `i32` arithmetic under `ct-mul`).
+#[inline(always)]
+#[allow(clippy::inline_always)] // called per coefficient
+pub(crate) fn mul(a: i32, b: i32) -> i32 {
+    debug_assert!((0..1 << 16).contains(&b));
+    #[cfg(not(feature = "ct-mul"))]
+    let product = a * b;
+    #[cfg(feature = "ct-mul")]
+    #[allow(clippy::cast_sign_loss, clippy::cast_possible_wrap)] // two's complement is intended
+    let product = shift_add_16(a as u32, b as u32) as i32;
+    product
+}
+
+
+/// Returns the full `a·b` as a `u64`.
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+#[inline(always)]
+#[allow(clippy::inline_always)] // called per coefficient
+pub(crate) fn mul_wide(a: u32, b: u32) -> u64 {
+    #[cfg(not(feature = "ct-mul"))]
+    let product = u64::from(a) * u64::from(b);
+    #[cfg(feature = "ct-mul")]
+    let product = shift_add_32(u64::from(a), b);
+    product
+}
+
+
+// Sums `a·2^i` over the (16) set bits `i` of `b`, each selected by an all-zeros/ones mask
+#[cfg(feature = "ct-mul")]
+#[inline(always)]
+#[allow(clippy::inline_always)]
+fn shift_add_16(a: u32, b: u32) -> u32 {
+    let mut product = 0u32;
+    for i in 0..16 {
+        let mask = core::hint::black_box(0u32.wrapping_sub((b >> i) & 1));
+        product = product.wrapping_add((a << i) & mask);
+    }
+    product
+}
+
+
+// As above, over the 32 bits of `b` and with a 64-bit sum
+#[cfg(all(feature = "ct-mul", any(feature = "encaps", feature = "decaps", feature = "research")))]
+#[inline(always)]
+#[allow(clippy::inline_always)]
+fn shift_add_32(a: u64, b: u32) -> u64 {
+    let mut product = 0u64;
+    for i in 0..32 {
+        let mask = core::hint::black_box(0u64.wrapping_sub(u64::from((b >> i) & 1)));
+        product = product.wrapping_add((a << i) & mask);
+    }
+    product
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand_core::{RngCore, SeedableRng};
+
+    // Matches the native multiply across the operand ranges (with or without the `ct-mul` feature)
+    #[test]
+    fn test_matches_native() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(123);
+        for _ in 0..100_000 {
+            let (a, b) = (rng.next_u32(), rng.next_u32());
+            #[allow(clippy::cast_possible_wrap)]
+            let (a_16, b_16) = ((a >> 16) as i32 - (1 << 15), (b >> 16) as i32);
+            assert_eq!(super::mul(a_16, b_16), a_16 * b_16);
+            assert_eq!(super::mul_wide(a, b), u64::from(a) * u64::from(b));
+        }
+        assert_eq!(super::mul(-1, (1 << 16) - 1), -((1 << 16) - 1));
+        assert_eq!(super::mul_wide(u32::MAX, u32::MAX), u64::from(u32::MAX) * u64::from(u32::MAX));
+    }
+}
@@ -0,0 +1,92 @@
+// Hex and base64 text encodings (with the `encoding` feature) for configuration files and test
+// fixtures. These are not part of FIPS 203, and are injected into each parameter set via the macro.
+// As decapsulation keys and shared secrets are also encoded, both directions operate in
+// constant-time (outside of the length checks), i.e., without secret-dependent branches or table
+// lookups. The base64 functionality is provided by the constant-time `base64ct` crate.
+
+use crate::helpers::ensure;
+use alloc::string::String;
+use base64ct::{Base64, Encoding};
+
+
+/// Encodes `bytes` as lowercase hex, in constant-time.
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(2 * bytes.len());
+    for byte in bytes {
+        out.push(encode_nibble(byte >> 4));
+        out.push(encode_nibble(byte & 0x0F));
+    }
+    out
+}
+
+
+/// Decodes exactly `out.len()` bytes from (upper or lowercase) hex, in constant-time.
+pub(crate) fn from_hex(hex: &str, out: &mut [u8]) -> Result<(), &'static str> {
+    ensure!(hex.len() == 2 * out.len(), "Hex length wrong");
+    let mut invalid = 0u16;
+    for (byte, pair) in out.iter_mut().zip(hex.as_bytes().chunks_exact(2)) {
+        let (high, low) = (decode_nibble(pair[0]), decode_nibble(pair[1]));
+        invalid |= (high | low) & 0xFF00;
+        #[allow(clippy::cast_possible_truncation)] // the invalid bits are accumulated above
+        let value = (high << 4 | low) as u8;
+        *byte = value;
+    }
+    if invalid != 0 {
+        out.fill(0);
+        return Err("Hex character invalid");
+    }
+    Ok(())
+}
+
+
+/// Encodes `bytes` as standard (padded) base64, in constant-time.
+pub(crate) fn to_base64(bytes: &[u8]) -> String { Base64::encode_string(bytes) }
+
+
+/// Decodes exactly `out.len()` bytes from standard (padded) base64, in constant-time.
+pub(crate) fn from_base64(base64: &str, out: &mut [u8]) -> Result<(), &'static str> {
+    let len = Base64::decode(base64, out).map_err(|_| "Base64 invalid")?.len();
+    ensure!(len == out.len(), "Base64 length wrong");
+    Ok(())
+}
+
+
+// Maps 0..=15 to '0'..='9' or 'a'..='f', adding 0x27 via a mask (rather than a branch) for 10..=15
+fn encode_nibble(nibble: u8) -> char {
+    let nibble = i16::from(nibble);
+    let offset = ((9 - nibble) >> 8) & 0x27;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // the result is ASCII
+    let c = (nibble + 0x30 + offset) as u8;
+    char::from(c)
+}
+
+
+// Maps a hex character to 0..=15, or to 0xFFFF when invalid. Each range check produces a mask
+// from the sign bit of `(lower bound - c) & (c - upper bound)`, rather than a branch.
+fn decode_nibble(c: u8) -> u16 {
+    let c = i16::from(c);
+    let mut value: i16 = -1;
+    value += (((0x2F - c) & (c - 0x3A)) >> 8) & (c - 0x2F); // '0'..='9'
+    value += (((0x40 - c) & (c - 0x47)) >> 8) & (c - 0x36); // 'A'..='F'
+    value += (((0x60 - c) & (c - 0x67)) >> 8) & (c - 0x56); // 'a'..='f'
+    #[allow(clippy::cast_sign_loss)] // -1 (invalid) intentionally maps to 0xFFFF
+    let value = value as u16;
+    value
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{decode_nibble, encode_nibble};
+
+    #[test]
+    fn test_nibbles_exhaustive() {
+        for c in 0..=255u8 {
+            let expected = char::from(c).to_digit(16).map_or(0xFFFF, |d| u16::try_from(d).unwrap());
+            assert_eq!(decode_nibble(c), expected);
+        }
+        for nibble in 0..16u8 {
+            assert_eq!(Some(encode_nibble(nibble)), char::from_digit(u32::from(nibble), 16));
+        }
+    }
+}
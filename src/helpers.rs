@@ -1,8 +1,24 @@
-use crate::ntt::multiply_ntts;
-use crate::types::Z;
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+use crate::ct_mul::mul_wide;
+#[cfg(feature = "custom-keccak")]
+use crate::keccak::{Sha3_256, Sha3_512, Shake128, Shake256};
+use crate::ntt::multiply_ntts_sum;
+use crate::types::Poly;
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
 use crate::Q;
+use rand_core::{CryptoRng, CryptoRngCore, RngCore};
 use sha3::digest::{ExtendableOutput, Update, XofReader};
-use sha3::{Digest, Sha3_256, Sha3_512, Shake128, Shake256};
+use sha3::Digest;
+#[cfg(not(feature = "custom-keccak"))]
+use sha3::{Sha3_256, Sha3_512, Shake128, Shake256};
+#[cfg(all(feature = "decaps", feature = "redundant-decaps"))]
+use subtle::Choice;
+#[cfg(feature = "decaps")]
+use subtle::ConditionallySelectable;
+#[cfg(any(feature = "decaps", feature = "rng-health-checks"))]
+use subtle::ConstantTimeEq;
+#[cfg(all(feature = "decaps", feature = "redundant-decaps"))]
+use zeroize::Zeroize;
 
 
 /// If the condition is not met, return an error message. Borrowed from the `anyhow` crate.
@@ -17,59 +33,70 @@ macro_rules! ensure {
 pub(crate) use ensure; // make available throughout crate
 
 
-/// Vector addition; See commentary on 2.11 page 10: `z_hat` = `u_hat` + `v_hat`
-#[must_use]
-pub(crate) fn add_vecs<const K: usize>(
-    vec_a: &[[Z; 256]; K], vec_b: &[[Z; 256]; K],
-) -> [[Z; 256]; K] {
-    core::array::from_fn(|k| core::array::from_fn(|n| vec_a[k][n].add(vec_b[k][n])))
+/// An internal invariant (e.g., a length already assured by the caller). With the `strict-checks`
+/// feature it is validated at runtime, as `ensure!()`, otherwise it is a `debug_assert!()`.
+macro_rules! check {
+    ($cond:expr, $msg:literal $(,)?) => {
+        #[cfg(feature = "strict-checks")]
+        $crate::helpers::ensure!($cond, $msg);
+        #[cfg(not(feature = "strict-checks"))]
+        debug_assert!($cond, $msg);
+    };
 }
 
+pub(crate) use check; // make available throughout crate
 
-/// Matrix by vector multiplication; See commentary on 2.12 page 10: `w_hat` = `A_hat` mul `u_hat`
+
+/// Row `i` of `t_hat = A_hat ◦ s_hat + e_hat` (see commentary on 2.11 and 2.12 page 10), given row
+/// `i` of `A_hat` and `e_hat[i]`. Key generation encodes each row as soon as it is computed.
+#[cfg(any(feature = "keygen", feature = "research"))]
 #[must_use]
-pub(crate) fn mul_mat_vec<const K: usize>(
-    a_hat: &[[[Z; 256]; K]; K], u_hat: &[[Z; 256]; K],
-) -> [[Z; 256]; K] {
-    let mut w_hat = [[Z::default(); 256]; K];
-    for i in 0..K {
-        #[allow(clippy::needless_range_loop)] // alternative is harder to understand
-        for j in 0..K {
-            let tmp = multiply_ntts(&a_hat[i][j], &u_hat[j]);
-            w_hat[i] = add_vecs(&[w_hat[i]], &[tmp])[0];
-        }
+pub(crate) fn mul_row_vec_add<const K: usize>(
+    a_hat_row: &[Poly; K], s_hat: &[Poly; K], e_hat_i: &Poly,
+) -> Poly {
+    let mut t_hat_i = multiply_ntts_sum(core::array::from_fn(|j| &a_hat_row[j]), s_hat);
+    // The products carry a factor of 2^{-16} (see `multiply_ntts()`), and `t_hat` is not
+    // followed by `ntt_inv()` which would otherwise reverse it
+    for (t, e) in t_hat_i.iter_mut().zip(e_hat_i.iter()) {
+        *t = t.to_mont().add(*e);
     }
-    w_hat
+    t_hat_i
 }
 
 
 /// Matrix transpose by vector multiplication; See commentary on 2.13 page 10: `y_hat` = `A_hat^T` mul `u_hat`
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
 #[must_use]
 pub(crate) fn mul_mat_t_vec<const K: usize>(
-    a_hat: &[[[Z; 256]; K]; K], u_hat: &[[Z; 256]; K],
-) -> [[Z; 256]; K] {
-    let mut y_hat = [[Z::default(); 256]; K];
-    #[allow(clippy::needless_range_loop)] // alternative is harder to understand
-    for i in 0..K {
-        #[allow(clippy::needless_range_loop)] // alternative is harder to understand
-        for j in 0..K {
-            let tmp = multiply_ntts(&a_hat[j][i], &u_hat[j]); // i,j swapped vs above fn
-            y_hat[i] = add_vecs(&[y_hat[i]], &[tmp])[0];
-        }
-    }
-    y_hat
+    a_hat: &[[Poly; K]; K], u_hat: &[Poly; K],
+) -> [Poly; K] {
+    // i,j swapped vs above fn
+    core::array::from_fn(|i| multiply_ntts_sum(core::array::from_fn(|j| &a_hat[j][i]), u_hat))
+}
+
+
+/// Matrix transpose by vector multiplication as in `mul_mat_t_vec()` above, with each column of
+/// `A_hat` sampled on demand by `a_hat_col(i)` so that only a single column is held in memory.
+#[cfg(all(
+    feature = "low-memory",
+    any(feature = "encaps", feature = "decaps", feature = "research")
+))]
+#[must_use]
+pub(crate) fn mul_mat_t_vec_cols<const K: usize>(
+    a_hat_col: impl Fn(usize) -> [Poly; K], u_hat: &[Poly; K],
+) -> [Poly; K] {
+    core::array::from_fn(|i| {
+        let col = a_hat_col(i);
+        multiply_ntts_sum(core::array::from_fn(|j| &col[j]), u_hat)
+    })
 }
 
 
 /// Vector dot product; See commentary on 2.14 page 10: `z_hat` = `u_hat^T` mul `v_hat`
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
 #[must_use]
-pub(crate) fn dot_t_prod<const K: usize>(u_hat: &[[Z; 256]; K], v_hat: &[[Z; 256]; K]) -> [Z; 256] {
-    let mut result = [Z::default(); 256];
-    for j in 0..K {
-        let tmp = multiply_ntts(&u_hat[j], &v_hat[j]);
-        result = add_vecs(&[result], &[tmp])[0];
-    }
-    result
+pub(crate) fn dot_t_prod<const K: usize>(u_hat: &[Poly; K], v_hat: &[Poly; K]) -> Poly {
+    multiply_ntts_sum(core::array::from_fn(|j| &u_hat[j]), v_hat)
 }
 
 
@@ -86,6 +113,19 @@ pub(crate) fn prf<const ETA_64: usize>(s: &[u8; 32], b: u8) -> [u8; ETA_64] {
 }
 
 
+/// Function PRF on page 18 (4.3), for `L` consecutive values of `b`. This is computed four at a
+/// time with the AVX2 backend when enabled and available.
+#[must_use]
+pub(crate) fn prf_many<const ETA_64: usize, const L: usize>(
+    s: &[u8; 32], b: u8,
+) -> [[u8; ETA_64]; L] {
+    #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+    if let Some(result) = crate::avx2::prf_many(s, b) {
+        return result;
+    }
+    core::array::from_fn(|i| prf(s, b + i.to_le_bytes()[0]))
+}
+
 /// Function XOF on page 19 (4.6), used with 32-byte `rho`
 #[must_use]
 pub(crate) fn xof(rho: &[u8; 32], i: u8, j: u8) -> impl XofReader {
@@ -104,10 +144,12 @@ pub(crate) fn xof(rho: &[u8; 32], i: u8, j: u8) -> impl XofReader {
 /// flexibility for reuse and avoiding an unnecessary prior concatenation.
 pub(crate) fn g(bytes: &[&[u8]]) -> ([u8; 32], [u8; 32]) {
     let mut hasher = Sha3_512::new();
-    bytes.iter().for_each(|b| Digest::update(&mut hasher, b));
+    for b in bytes {
+        Digest::update(&mut hasher, b);
+    }
     let digest = hasher.finalize();
-    let a = digest[0..32].try_into().expect("g_a fail");
-    let b = digest[32..64].try_into().expect("g_b fail");
+    let a = core::array::from_fn(|i| digest[i]);
+    let b = core::array::from_fn(|i| digest[32 + i]);
     (a, b)
 }
 
@@ -126,6 +168,7 @@ pub(crate) fn h(bytes: &[u8]) -> [u8; 32] {
 /// Function J n page 18 (4.4). <br>
 /// `j()` is similar to `g()` above in that the second operand is a variable
 /// length `ct`. The signature here is for ease of use.
+#[cfg(feature = "decaps")]
 #[must_use]
 pub(crate) fn j(z: &[u8; 32], ct: &[u8]) -> [u8; 32] {
     let mut hasher = Shake256::default();
@@ -138,28 +181,269 @@ pub(crate) fn j(z: &[u8; 32], ct: &[u8]) -> [u8; 32] {
 }
 
 
+/// Steps 9-11 of Algorithm 18 on page 34: `K′ ← K̄` if `c ≠ c′` ("implicit rejection"). With the
+/// `redundant-decaps` feature, the comparison and selection are each computed a second time along
+/// an independent data flow and cross-checked, so that a single injected fault (e.g., a glitch
+/// skipping the select) is detected and reported rather than releasing an unrejected `K′`.
+#[cfg(feature = "decaps")]
+#[cfg_attr(not(feature = "redundant-decaps"), allow(clippy::unnecessary_wraps))]
+pub(crate) fn implicit_reject(
+    k_prime: &mut [u8; 32], k_bar: &[u8; 32], ct: &[u8], c_prime: &[u8],
+) -> Result<(), &'static str> {
+    let c_ne = ct.ct_ne(c_prime);
+    #[cfg(not(feature = "redundant-decaps"))]
+    {
+        k_prime.conditional_assign(k_bar, c_ne);
+        Ok(())
+    }
+    #[cfg(feature = "redundant-decaps")]
+    select_checked(k_prime, k_bar, c_ne, diff(ct, c_prime))
+}
+
+
+/// The OR of the byte-wise differences of `a` and `b` (which are of equal length), accumulated in
+/// reverse order from the `ct_ne()` in `implicit_reject()` so the two do not share a data flow.
+#[cfg(all(feature = "decaps", feature = "redundant-decaps"))]
+pub(crate) fn diff(a: &[u8], b: &[u8]) -> u8 {
+    let (a, b) = (core::hint::black_box(a), core::hint::black_box(b));
+    a.iter().rev().zip(b.iter().rev()).fold(0, |acc, (x, y)| acc | (x ^ y))
+}
+
+
+/// Assigns `K̄` to `K′` when `c_ne` is set, cross-checked against a second selection made from the
+/// independently accumulated `diff` (with the operands swapped). Upon any disagreement, `K′` is
+/// wiped and an error returned.
+#[cfg(all(feature = "decaps", feature = "redundant-decaps"))]
+pub(crate) fn select_checked(
+    k_prime: &mut [u8; 32], k_bar: &[u8; 32], c_ne: Choice, diff: u8,
+) -> Result<(), &'static str> {
+    let c_ne_2 = !core::hint::black_box(diff).ct_eq(&0);
+    let k_a = <[u8; 32]>::conditional_select(k_prime, k_bar, c_ne);
+    let k_b = <[u8; 32]>::conditional_select(k_bar, k_prime, !c_ne_2);
+    let agree = c_ne.unwrap_u8().ct_eq(&c_ne_2.unwrap_u8()) & k_a.ct_eq(&k_b);
+    if bool::from(core::hint::black_box(agree)) {
+        *k_prime = k_a;
+        Ok(())
+    } else {
+        k_prime.zeroize();
+        Err("Decaps fault detected")
+    }
+}
+
+
+/// Labeled key derivation from a shared secret key (not part of FIPS 203). <br>
+/// `out ← SHAKE256(key ‖ len(label) ‖ label ‖ len(context) ‖ context ‖ len(out))`, with each
+/// length encoded as a little-endian u64 so that the concatenation is unambiguous. The context
+/// is provided in parts which are concatenated, e.g., `ct ‖ ek`.
+pub(crate) fn expand(key: &[u8], label: &[u8], context: &[&[u8]], out: &mut [u8]) {
+    let mut hasher = Shake256::default();
+    hasher.update(key);
+    hasher.update(&(label.len() as u64).to_le_bytes());
+    hasher.update(label);
+    let context_len: usize = context.iter().map(|part| part.len()).sum();
+    hasher.update(&(context_len as u64).to_le_bytes());
+    for part in context {
+        hasher.update(part);
+    }
+    hasher.update(&(out.len() as u64).to_le_bytes());
+    let mut reader = hasher.finalize_xof();
+    reader.read(out);
+}
+
+
+/// Fills `seed` (i.e., `d`, `z` or `m`) from `rng`, returning `err` should the rng fail. With the
+/// `rng-health-checks` feature, the output is also rejected (failing closed) when its bytes are all
+/// equal (e.g., all zero) or it repeats the previous draw, as seen with broken platform RNGs that
+/// silently return a fixed buffer. Seeds supplied via `encaps_from_seed()` bypass this entirely.
+#[cfg(any(feature = "keygen", feature = "encaps", all(feature = "decaps", feature = "blinding")))]
+pub(crate) fn draw_seed(
+    rng: &mut impl CryptoRngCore, seed: &mut [u8; 32], err: &'static str,
+) -> Result<(), &'static str> {
+    rng.try_fill_bytes(seed).map_err(|_| err)?;
+    #[cfg(feature = "rng-health-checks")]
+    ensure!(healthy(seed), "Random number generator failed health check");
+    Ok(())
+}
+
+
+// The previous draw is remembered by a 64-bit fingerprint (from its hash, so the seed itself is not
+// retained), held as two 32-bit atomics accessed by loads and stores only (so as to suit targets
+// without 64-bit atomics or compare-and-swap). A race between threads may miss a repeat, but no
+// more than that.
+#[cfg(feature = "rng-health-checks")]
+static LAST_DRAW: [core::sync::atomic::AtomicU32; 2] = [
+    core::sync::atomic::AtomicU32::new(0),
+    core::sync::atomic::AtomicU32::new(0),
+];
+
+/// The health checks of `draw_seed()`, in constant time (outside of the outcome)
+#[cfg(feature = "rng-health-checks")]
+fn healthy(seed: &[u8; 32]) -> bool {
+    use core::sync::atomic::Ordering;
+    let all_equal = seed.iter().fold(0, |acc, b| acc | (b ^ seed[0])).ct_eq(&0);
+    let h_seed = h(seed);
+    let fingerprint =
+        [0, 1].map(|i| u32::from_le_bytes(core::array::from_fn(|j| h_seed[4 * i + j])));
+    let last = [0, 1].map(|i| LAST_DRAW[i].load(Ordering::Relaxed));
+    for (atomic, value) in LAST_DRAW.iter().zip(fingerprint) {
+        atomic.store(value, Ordering::Relaxed);
+    }
+    !bool::from(all_equal | fingerprint.ct_eq(&last))
+}
+
+
+/// Wraps a (possibly low quality) random number generator for hedged encapsulation (not part of
+/// FIPS 203), such that each draw is `SHAKE256(rng_output ‖ H(ek) ‖ counter)` rather than the raw
+/// rng output. The counter is incremented per draw and encoded as a little-endian u64. All of the
+/// `RngCore` methods are provided; `fill_bytes()` fails exactly as the wrapped rng's does.
+pub(crate) struct HedgedRng<'a, R: CryptoRngCore> {
+    rng: &'a mut R,
+    h_ek: [u8; 32],
+    counter: u64,
+}
+
+impl<'a, R: CryptoRngCore> HedgedRng<'a, R> {
+    pub(crate) fn new(rng: &'a mut R, h_ek: [u8; 32]) -> Self { Self { rng, h_ek, counter: 0 } }
+
+    // Replaces the raw rng output in `out` by the hedged draw
+    fn hedge(&mut self, out: &mut [u8]) {
+        let mut hasher = Shake256::default();
+        hasher.update(out);
+        hasher.update(&self.h_ek);
+        hasher.update(&self.counter.to_le_bytes());
+        let mut reader = hasher.finalize_xof();
+        reader.read(out);
+        self.counter = self.counter.wrapping_add(1);
+    }
+}
+
+impl<R: CryptoRngCore> RngCore for HedgedRng<'_, R> {
+    fn next_u32(&mut self) -> u32 { rand_core::impls::next_u32_via_fill(self) }
+
+    fn next_u64(&mut self) -> u64 { rand_core::impls::next_u64_via_fill(self) }
+
+    fn fill_bytes(&mut self, out: &mut [u8]) {
+        self.rng.fill_bytes(out);
+        self.hedge(out);
+    }
+
+    fn try_fill_bytes(&mut self, out: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.rng.try_fill_bytes(out)?;
+        self.hedge(out);
+        Ok(())
+    }
+}
+
+impl<R: CryptoRngCore> CryptoRng for HedgedRng<'_, R> {}
+
+
 /// Compress<d> from page 21 (4.7).
 /// x → ⌈(2^d/q) · x⌋
 /// `d` comes from fixed security parameter, `inout` saves some allocation.
+/// The values of `d` used by ML-KEM are dispatched to monomorphized (and vectorizable) loops.
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+pub(crate) fn compress_vector(d: u32, inout: &mut Poly) {
+    match d {
+        1 => compress_d::<1>(inout),
+        4 => compress_d::<4>(inout),
+        5 => compress_d::<5>(inout),
+        10 => compress_d::<10>(inout),
+        11 => compress_d::<11>(inout),
+        _ => compress_d_var(d, inout),
+    }
+}
+
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+fn compress_d<const D: u32>(inout: &mut Poly) { compress_d_var(D, inout); }
+
 /// This works for all odd q = 17 to 6307, d = 0 to 11, and x = 0 to q-1.
-#[allow(clippy::cast_possible_truncation)] // last line (and const)
-pub(crate) fn compress_vector(d: u32, inout: &mut [Z]) {
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+#[inline(always)] // so that each `compress_d()` has a constant `d`
+#[allow(clippy::cast_possible_truncation, clippy::inline_always)] // last line (and const)
+fn compress_d_var(d: u32, inout: &mut Poly) {
     const M: u32 = (((1u64 << 36) + Q as u64 - 1) / Q as u64) as u32;
     for x_ref in &mut *inout {
         let y = (x_ref.get_u32() << d) + (u32::from(Q) >> 1);
-        let result = (u64::from(y) * u64::from(M)) >> 36;
+        let result = mul_wide(y, M) >> 36;
         x_ref.set_u16(result as u16);
     }
 }
 
 
+/// `ByteEncode_1(Compress_1(w))` from step 7 of Algorithm 15, extracting the (secret) message `m`.
+/// Rather than relying upon the generic path being branch-free after optimization, each bit is a
+/// threshold comparison computed by borrows: `Compress_1(x) = 1` exactly when `⌊q/4⌋ < x < ⌈3q/4⌉`,
+/// i.e., when both `⌊q/4⌋ − x` and `x − ⌈3q/4⌉` are negative, as seen in their sign (top) bits.
+#[cfg(any(feature = "decaps", feature = "research"))]
+#[allow(clippy::cast_possible_truncation)] // the sign bits, as u8
+pub(crate) fn compress_1_encode(w: &Poly) -> [u8; 32] {
+    const LOW: u32 = Q as u32 / 4; // 832
+    const HIGH: u32 = (3 * Q as u32 + 3) / 4; // 2497
+    debug_assert!(w.iter().all(|w_i| w_i.get_u32() < u32::from(Q)), "Alg 15: w out of range");
+    let mut m = [0u8; 32];
+    for (m_i, w_8) in m.iter_mut().zip(w.chunks_exact(8)) {
+        for (j, w_ij) in w_8.iter().enumerate() {
+            let x = w_ij.get_u32();
+            let bit = (LOW.wrapping_sub(x) & x.wrapping_sub(HIGH)) >> 31;
+            *m_i |= (bit as u8) << j;
+        }
+    }
+    m
+}
+
+
 /// Decompress<d> from page 21 (4.8).
 /// y → ⌈(q/2^d) · y⌋
-/// `d` comes from fixed security parameter, `inout` saves some allocation
-#[allow(clippy::cast_possible_truncation)] // last line
-pub(crate) fn decompress_vector(d: u32, inout: &mut [Z]) {
+/// `d` comes from fixed security parameter, `inout` saves some allocation.
+/// The values of `d` used by ML-KEM are dispatched to monomorphized (and vectorizable) loops.
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+pub(crate) fn decompress_vector(d: u32, inout: &mut Poly) {
+    match d {
+        1 => decompress_d::<1>(inout),
+        4 => decompress_d::<4>(inout),
+        5 => decompress_d::<5>(inout),
+        10 => decompress_d::<10>(inout),
+        11 => decompress_d::<11>(inout),
+        _ => decompress_d_var(d, inout),
+    }
+}
+
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+fn decompress_d<const D: u32>(inout: &mut Poly) { decompress_d_var(D, inout); }
+
+/// Rounds half up, i.e., `⌊(q·y + 2^{d−1}) / 2^d⌋`, for d = 1 to 11 and y = 0 to 2^d − 1.
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+#[inline(always)] // so that each `decompress_d()` has a constant `d`
+#[allow(clippy::cast_possible_truncation, clippy::inline_always)] // last line
+fn decompress_d_var(d: u32, inout: &mut Poly) {
     for y_ref in &mut *inout {
-        let qy = u32::from(Q) * y_ref.get_u32() + (1 << d) - 1;
+        let qy = mul_wide(y_ref.get_u32(), u32::from(Q)) + (1 << (d - 1));
         y_ref.set_u16((qy >> d) as u16);
     }
 }
+
+
+#[cfg(test)]
+mod tests {
+    use super::HedgedRng;
+    use rand_chacha::rand_core::{RngCore, SeedableRng};
+
+    // The infallible `RngCore` methods draw the same hedged stream as `try_fill_bytes()`
+    #[test]
+    fn test_hedged_rng_methods() {
+        let mut rng1 = rand_chacha::ChaCha8Rng::seed_from_u64(4772);
+        let mut rng2 = rand_chacha::ChaCha8Rng::seed_from_u64(4772);
+        let mut hedged1 = HedgedRng::new(&mut rng1, [7u8; 32]);
+        let mut hedged2 = HedgedRng::new(&mut rng2, [7u8; 32]);
+        let mut expected = [0u8; 4];
+        hedged1.try_fill_bytes(&mut expected).unwrap();
+        assert_eq!(hedged2.next_u32(), u32::from_le_bytes(expected));
+        let mut expected = [0u8; 8];
+        hedged1.try_fill_bytes(&mut expected).unwrap();
+        assert_eq!(hedged2.next_u64(), u64::from_le_bytes(expected));
+        let (mut expected, mut out) = ([0u8; 32], [0u8; 32]);
+        hedged1.try_fill_bytes(&mut expected).unwrap();
+        hedged2.fill_bytes(&mut out);
+        assert_eq!(out, expected);
+    }
+}
@@ -0,0 +1,96 @@
+// The service indicator, in the style of SP 800-227 and the FIPS 140-3 implementation guidance,
+// reporting whether the most recent key generation, encapsulation or decapsulation ran in the
+// approved mode. The state is process-wide (so as to suit `no_std`), and is held in an atomic
+// accessed by loads and stores only (so as to suit targets without compare-and-swap).
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+
+/// The outcome of the most recent key generation, encapsulation or decapsulation, as returned by
+/// [`service_indicator()`]. All three parameter sets are approved.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ServiceIndicator {
+    /// No service has run yet.
+    None = 0,
+    /// The service ran in the approved mode: its parameter set passed its self-tests, and any
+    /// randomness was drawn from the random number generator.
+    Approved = 1,
+    /// The self-tests of the parameter set had not (yet) passed, see `self_test()`. Note that the
+    /// self-tests require the `keygen`, `encaps` and `decaps` features.
+    SelfTestNotPassed = 2,
+    /// The randomness was supplied directly rather than drawn from the random number generator,
+    /// e.g., via `keygen_from_seed()` or `encaps_from_seed()`, which are for testing only.
+    UnapprovedRng = 3,
+}
+
+
+static LAST: AtomicU8 = AtomicU8::new(0);
+
+/// Returns the service indicator, i.e., whether the most recent key generation, encapsulation or
+/// decapsulation (by any thread) ran in the approved mode, so that a FIPS 140-3 module embedding
+/// this crate can surface it. The indicator is set by the one-shot operations (including their
+/// workspace, expanded-key and borrowed-key variants), but not by `KeyGenOp`, `DecapsOp` or a
+/// `StoredDecapsKey`. Operations that fail leave it unchanged.
+/// # Examples
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # #[cfg(all(feature = "ml-kem-512", feature = "default-rng"))] {
+/// use fips203::ml_kem_512;
+/// use fips203::traits::{Encaps, KeyGen};
+/// use fips203::{service_indicator, ServiceIndicator};
+///
+/// fips203::self_test()?; // e.g., at power-up
+/// let (ek, _dk) = ml_kem_512::KG::try_keygen()?;
+/// assert_eq!(service_indicator(), ServiceIndicator::Approved);
+/// let (_ssk, _ct) = ek.encaps_from_seed(&[0x42; 32]); // deterministic, for testing only
+/// assert_eq!(service_indicator(), ServiceIndicator::UnapprovedRng);
+/// # }
+/// # Ok(())}
+/// ```
+#[must_use]
+pub fn service_indicator() -> ServiceIndicator {
+    match LAST.load(Ordering::Acquire) {
+        1 => ServiceIndicator::Approved,
+        2 => ServiceIndicator::SelfTestNotPassed,
+        3 => ServiceIndicator::UnapprovedRng,
+        _ => ServiceIndicator::None,
+    }
+}
+
+
+/// Records the outcome of a service for the parameter set with dimension `K`, where `rng_drawn`
+/// indicates that any randomness was drawn from the random number generator.
+pub(crate) fn record<const K: usize>(rng_drawn: bool) {
+    #[cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+    let self_tested = crate::self_test::passed(K);
+    #[cfg(not(all(feature = "keygen", feature = "encaps", feature = "decaps")))]
+    let self_tested = false;
+    let outcome = if !self_tested {
+        ServiceIndicator::SelfTestNotPassed
+    } else if !rng_drawn {
+        ServiceIndicator::UnapprovedRng
+    } else {
+        ServiceIndicator::Approved
+    };
+    LAST.store(outcome as u8, Ordering::Release);
+}
+
+/// Marks the service just recorded as having had its randomness supplied directly, for callers
+/// (such as `encaps_from_seed()`) that route a fixed seed through the random number generator path.
+pub(crate) fn record_supplied_rng() {
+    if LAST.load(Ordering::Acquire) == ServiceIndicator::Approved as u8 {
+        LAST.store(ServiceIndicator::UnapprovedRng as u8, Ordering::Release);
+    }
+}
+
+/// Runs `f` (i.e., the self-tests, which run the services internally but are not services
+/// themselves) and then restores the recorded outcome. An outcome recorded by another thread in
+/// the meantime is overwritten, just as it would be by a subsequent service.
+#[cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+pub(crate) fn unrecorded<T>(f: impl FnOnce() -> T) -> T {
+    let last = LAST.load(Ordering::Acquire);
+    let result = f();
+    LAST.store(last, Ordering::Release);
+    result
+}
@@ -0,0 +1,109 @@
+// JWK encodings of ML-KEM keys using the Algorithm Key Pair (AKP) key type, as per the IETF JOSE
+// post-quantum drafts. These are not part of FIPS 203, and are injected into each parameter set
+// via the macro. The key members are base64url (unpadded) encoded in constant-time.
+
+use crate::helpers::ensure;
+use alloc::string::String;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use core::fmt::{self, Write};
+use serde::de::{Deserializer, Error, IgnoredAny, MapAccess, Visitor};
+use zeroize::Zeroizing;
+
+
+/// JWK key type `AKP` (Algorithm Key Pair)
+pub(crate) const KTY_AKP: &str = "AKP";
+
+
+/// The relevant members of a decoded AKP JWK; unknown members (e.g., `kid`) are skipped.
+#[derive(Default)]
+pub(crate) struct Jwk<'a> {
+    kty: Option<String>,
+    pub(crate) alg: Option<String>,
+    pub(crate) public: Option<&'a str>,
+    pub(crate) private: Option<&'a str>,
+}
+
+
+/// Encodes an AKP JWK as a JSON object. The string is allocated up front so that the private key
+/// is never left behind in a reallocated buffer.
+pub(crate) fn encode_key(alg: &str, public: &[u8], private: Option<&[u8]>) -> String {
+    let private_len = private.map_or(0, |private| Base64UrlUnpadded::encoded_len(private) + 10);
+    let capacity = 32 + 6 * alg.len() + Base64UrlUnpadded::encoded_len(public) + private_len;
+    let mut out = String::with_capacity(capacity);
+    out.push_str("{\"kty\":\"AKP\",\"alg\":\"");
+    for c in alg.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            c if c.is_control() => {
+                let _written = write!(out, "\\u{:04x}", u32::from(c));
+            }
+            c => out.push(c),
+        }
+    }
+    out.push_str("\",\"pub\":\"");
+    push_base64(&mut out, public);
+    if let Some(private) = private {
+        out.push_str("\",\"priv\":\"");
+        push_base64(&mut out, private);
+    }
+    out.push_str("\"}");
+    out
+}
+
+
+/// Decodes an AKP JWK from a JSON object; the key type must be AKP and members must not repeat.
+pub(crate) fn decode_key(json: &str) -> Result<Jwk<'_>, &'static str> {
+    let mut deserializer = serde_json::Deserializer::from_str(json);
+    let jwk = deserializer.deserialize_map(JwkVisitor).map_err(|_| "JWK malformed")?;
+    deserializer.end().map_err(|_| "JWK has trailing characters")?;
+    ensure!(jwk.kty.as_deref() == Some(KTY_AKP), "JWK type is not AKP");
+    Ok(jwk)
+}
+
+
+/// Decodes a base64url (unpadded) key member of exactly `N` bytes, in constant-time.
+pub(crate) fn decode_base64<const N: usize>(
+    encoded: &str, out: &mut [u8; N],
+) -> Result<(), &'static str> {
+    let decoded = Base64UrlUnpadded::decode(encoded, out).map_err(|_| "JWK key malformed")?;
+    ensure!(decoded.len() == N, "JWK key wrong size");
+    Ok(())
+}
+
+
+// Encodes via a small (zeroized) stack buffer, as `String` offers no safe mutable byte access
+fn push_base64(out: &mut String, bytes: &[u8]) {
+    let mut buf = Zeroizing::new([0u8; 64]);
+    for chunk in bytes.chunks(48) {
+        if let Ok(encoded) = Base64UrlUnpadded::encode(chunk, &mut buf[..]) {
+            out.push_str(encoded); // 48 bytes always fit the 64-character buffer
+        }
+    }
+}
+
+
+struct JwkVisitor;
+
+impl<'de> Visitor<'de> for JwkVisitor {
+    type Value = Jwk<'de>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { f.write_str("a JWK object") }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut jwk = Jwk::default();
+        while let Some(name) = map.next_key::<&'de str>()? {
+            let repeated = match name {
+                "kty" => jwk.kty.replace(map.next_value()?).is_some(),
+                "alg" => jwk.alg.replace(map.next_value()?).is_some(),
+                "pub" => jwk.public.replace(map.next_value()?).is_some(),
+                "priv" => jwk.private.replace(map.next_value()?).is_some(),
+                _ => map.next_value::<IgnoredAny>().map(|_| false)?,
+            };
+            if repeated {
+                return Err(A::Error::custom("JWK member repeated"));
+            }
+        }
+        Ok(jwk)
+    }
+}
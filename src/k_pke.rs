@@ -1,11 +1,30 @@
-use crate::byte_fns::{byte_decode, byte_encode};
-use crate::helpers::{
-    add_vecs, compress_vector, decompress_vector, dot_t_prod, g, mul_mat_t_vec, mul_mat_vec, prf,
-    xof,
-};
-use crate::ntt::{ntt, ntt_inv};
+#[cfg(all(feature = "blinding", feature = "decaps"))]
+use crate::blinding::Blinding;
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+use crate::byte_fns::byte_decode;
+use crate::byte_fns::byte_encode;
+#[cfg(any(feature = "decaps", feature = "research"))]
+use crate::helpers::compress_1_encode;
+#[cfg(all(
+    feature = "low-memory",
+    any(feature = "encaps", feature = "decaps", feature = "research")
+))]
+use crate::helpers::mul_mat_t_vec_cols;
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+use crate::helpers::{check, compress_vector, decompress_vector, dot_t_prod, mul_mat_t_vec, prf};
+#[cfg(any(feature = "keygen", feature = "research"))]
+use crate::helpers::{g, mul_row_vec_add};
+use crate::helpers::{prf_many, xof};
+use crate::ntt::ntt;
+#[cfg(any(feature = "decaps", feature = "research"))]
+use crate::ntt::ntt_inv;
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+use crate::ntt::ntt_inv_add;
 use crate::sampling::{sample_ntt, sample_poly_cbd};
-use crate::types::Z;
+use crate::types::Poly;
+#[cfg(any(feature = "keygen", feature = "research"))]
+use zeroize::Zeroize;
+use zeroize::Zeroizing;
 
 
 /// Algorithm 13 `K-PKE.KeyGen(d)` on page 29.
@@ -14,84 +33,193 @@ use crate::types::Z;
 /// Input: randomness `d ∈ B^{32}` <br>
 /// Output: encryption key `ek_PKE ∈ B^{384·k+32}` <br>
 /// Output: decryption key `dk_PKE ∈ B^{384·k}`
+///
+/// The seeds `d` and `σ`, the PRF outputs and `s`/`e` are wiped before returning.
+#[cfg(any(feature = "keygen", feature = "research"))]
 #[allow(clippy::similar_names)]
+#[cfg_attr(feature = "small-code", allow(dead_code))] // then only used by the `research` API
 pub(crate) fn k_pke_key_gen<const K: usize, const ETA1_64: usize>(
-    d: [u8; 32], ek_pke: &mut [u8], dk_pke: &mut [u8],
+    mut d: [u8; 32], ek_pke: &mut [u8], dk_pke: &mut [u8],
 ) {
     debug_assert_eq!(ek_pke.len(), 384 * K + 32, "Alg 13: ek_pke not 384 * K + 32");
     debug_assert_eq!(dk_pke.len(), 384 * K, "Alg 13: dk_pke not 384 * K");
 
     // 1: (𝜌, 𝜎) ← G(𝑑 ‖ 𝑘)    ▷ expand 32+1 bytes to two pseudorandom 32-byte seeds
-    let mut dk = [0u8; 33]; // Last byte is 'final' FIPS 203 fix; 'domain' separator
+    let mut dk = Zeroizing::new([0u8; 33]); // Last byte is 'final' FIPS 203 fix; 'domain' separator
     dk[0..32].copy_from_slice(&d);
+    d.zeroize();
     dk[32] = K.to_le_bytes()[0];
-    let (rho, sigma) = g(&[&dk]);
+    let (rho, sigma) = g(&[&*dk]);
+    let sigma = Zeroizing::new(sigma);
 
-    // 2: N ← 0
-    let mut n = 0;
+    // 2: N ← 0    ▷ N is K·i + j below, as the PRF outputs are calculated together
+    let k = K.to_le_bytes()[0];
 
-    // Steps 3-7 in gen_a_hat() below
+    // Steps 3-7 in gen_a_hat() below (or row by row in step 18 with the `low-memory` feature)
+    #[cfg(not(feature = "low-memory"))]
     let a_hat = gen_a_hat(&rho);
 
     // 8: for (i ← 0; i < k; i ++)    ▷ generate s ∈ (Z_q^{256})^k
     // 9: s[i] ← SamplePolyCBD_η1(PRFη1(σ, N))    ▷ s[i] ∈ Z^{256}_q sampled from CBD
     // 10: N ← N +1
     // 11: end for
-    let s: [[Z; 256]; K] = core::array::from_fn(|_| {
-        let x = sample_poly_cbd(&prf::<ETA1_64>(&sigma, n));
-        n += 1;
-        x
-    });
+    // Note: s (and e below) are transformed in place by steps 16 and 17, hence their naming
+    let prf_s = Zeroizing::new(prf_many::<ETA1_64, K>(&sigma, 0));
+    let mut s_hat: Zeroizing<[Poly; K]> =
+        Zeroizing::new(core::array::from_fn(|i| sample_poly_cbd(&prf_s[i])));
 
     // 12: for (i ← 0; i < k; i++)    ▷ generate e ∈ (Z_q^{256})^k
     // 13: e[i] ← SamplePolyCBD_η1(PRFη1(σ, N))    ▷ e[i] ∈ Z^{256}_q sampled from CBD
     // 14: N ← N +1
     // 15: end for
-    let e: [[Z; 256]; K] = core::array::from_fn(|_| {
-        let x = sample_poly_cbd(&prf::<ETA1_64>(&sigma, n));
-        n += 1;
-        x
-    });
+    let prf_e = Zeroizing::new(prf_many::<ETA1_64, K>(&sigma, k));
+    let mut e_hat: Zeroizing<[Poly; K]> =
+        Zeroizing::new(core::array::from_fn(|i| sample_poly_cbd(&prf_e[i])));
 
     // 16: s_hat ← NTT(s)    ▷ NTT is run k times (once for each coordinate of s)
-    let s_hat: [[Z; 256]; K] = core::array::from_fn(|i| ntt(&s[i]));
+    // 20: dk_PKE ← ByteEncode_12(ŝ)    ▷ run ByteEncode12 𝑘 times, each as soon as s_hat[i] is ready
+    for (s_i, chunk) in s_hat.iter_mut().zip(dk_pke.chunks_mut(384)) {
+        ntt(s_i);
+        byte_encode(12, s_i, chunk);
+    }
 
     // 17: ê ← NTT(e)    ▷ NTT is run k times
-    let e_hat: [[Z; 256]; K] = core::array::from_fn(|i| ntt(&e[i]));
-
-    // 18: t̂ ← Â ◦ ŝ + ê
-    let as_hat = mul_mat_vec(&a_hat, &s_hat);
-    let t_hat = add_vecs(&as_hat, &e_hat);
+    for e_i in e_hat.iter_mut() {
+        ntt(e_i);
+    }
 
+    // 18: t̂ ← Â ◦ ŝ + ê    ▷ row by row, so that t̂ is never materialized in full
     // 19: ek_PKE ← ByteEncode_12(t̂) ∥ ρ    ▷ run ByteEncode12 𝑘 times, then append 𝐀-seed
     for (i, chunk) in ek_pke.chunks_mut(384).enumerate().take(K) {
-        byte_encode(12, &t_hat[i], chunk);
+        #[cfg(not(feature = "low-memory"))]
+        let a_hat_i = &a_hat[i];
+        #[cfg(feature = "low-memory")]
+        let a_hat_i = &sample_a_hat_row(&rho, i, false);
+        byte_encode(12, &mul_row_vec_add(a_hat_i, &s_hat, &e_hat[i]), chunk);
     }
     ek_pke[K * 384..].copy_from_slice(&rho);
 
-    // 20: dk_PKE ← ByteEncode_12(ŝ)    ▷ run ByteEncode12 𝑘 times
-    for (i, chunk) in dk_pke.chunks_mut(384).enumerate() {
-        byte_encode(12, &s_hat[i], chunk);
+    // 21: return (ek_PKE , dk_PKE )
+}
+
+
+/// As `k_pke_key_gen()` above, but with `s_hat`, `e_hat` and `A_hat` held in caller-provided
+/// storage (e.g., a `Workspace`) rather than on the stack.
+///
+/// Input: randomness `d ∈ B^{32}` <br>
+/// Output: encryption key `ek_PKE ∈ B^{384·k+32}` <br>
+/// Output: decryption key `dk_PKE ∈ B^{384·k}`
+///
+/// As the caller's storage outlives the call, `s_hat` and `e_hat` are wiped there too.
+#[cfg(feature = "keygen")]
+pub(crate) fn k_pke_key_gen_into<const K: usize, const ETA1_64: usize>(
+    mut d: [u8; 32], ek_pke: &mut [u8], dk_pke: &mut [u8], s_hat: &mut [Poly; K],
+    e_hat: &mut [Poly; K], a_hat: &mut [[Poly; K]; K],
+) {
+    debug_assert_eq!(ek_pke.len(), 384 * K + 32, "Alg 13: ek_pke not 384 * K + 32");
+    debug_assert_eq!(dk_pke.len(), 384 * K, "Alg 13: dk_pke not 384 * K");
+
+    // 1: (𝜌, 𝜎) ← G(𝑑 ‖ 𝑘)    ▷ expand 32+1 bytes to two pseudorandom 32-byte seeds
+    let mut dk = Zeroizing::new([0u8; 33]);
+    dk[0..32].copy_from_slice(&d);
+    d.zeroize();
+    dk[32] = K.to_le_bytes()[0];
+    let (rho, sigma) = g(&[&*dk]);
+    let sigma = Zeroizing::new(sigma);
+
+    // Steps 3-7 in gen_a_hat_into() below
+    gen_a_hat_into(&rho, a_hat);
+
+    // Steps 8-15: s and e are sampled from CBD, with N running from 0 to 2k - 1
+    let k = K.to_le_bytes()[0];
+    let prf_s = Zeroizing::new(prf_many::<ETA1_64, K>(&sigma, 0));
+    for (s_i, prf_i) in s_hat.iter_mut().zip(prf_s.iter()) {
+        *s_i = sample_poly_cbd(prf_i);
+    }
+    let prf_e = Zeroizing::new(prf_many::<ETA1_64, K>(&sigma, k));
+    for (e_i, prf_i) in e_hat.iter_mut().zip(prf_e.iter()) {
+        *e_i = sample_poly_cbd(prf_i);
     }
 
-    // 21: return (ek_PKE , dk_PKE )
+    // 16: s_hat ← NTT(s)    ▷ with 20: dk_PKE ← ByteEncode_12(ŝ) as each s_hat[i] is ready
+    for (s_i, chunk) in s_hat.iter_mut().zip(dk_pke.chunks_mut(384)) {
+        ntt(s_i);
+        byte_encode(12, s_i, chunk);
+    }
+
+    // 17: ê ← NTT(e)
+    for e_i in e_hat.iter_mut() {
+        ntt(e_i);
+    }
+
+    // 18: t̂ ← Â ◦ ŝ + ê    ▷ with 19: ek_PKE ← ByteEncode_12(t̂) ∥ ρ, row by row
+    for ((a_hat_i, e_i), chunk) in a_hat.iter().zip(e_hat.iter()).zip(ek_pke.chunks_mut(384)) {
+        byte_encode(12, &mul_row_vec_add(a_hat_i, s_hat, e_i), chunk);
+    }
+    ek_pke[K * 384..].copy_from_slice(&rho);
+    s_hat.zeroize();
+    e_hat.zeroize();
 }
 
 
 /// Shared function for `k_pke_key_gen()` steps 3-7, and `k_pke_encrypt()` steps 4-8
-fn gen_a_hat<const K: usize>(rho: &[u8; 32]) -> [[[Z; 256]; K]; K] {
+pub(crate) fn gen_a_hat<const K: usize>(rho: &[u8; 32]) -> [[Poly; K]; K] {
     //
     // 3: for (i ← 0; i < k; i++)    ▷ generate matrix A ∈ (Z^{256}_q)^{k×k}
     // 4:   for (j ← 0; j < k; j++)
     // 5:     A_hat[i, j] ← SampleNTT(𝜌‖𝑗‖𝑖)    ▷ 𝑗 and 𝑖 are bytes 33 and 34 of the input
     // 6:   end for
     // 7: end for
+    // The AVX2 backend (when enabled and available) samples four entries at a time
+    #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+    if let Some(a_hat) = crate::avx2::gen_a_hat(rho) {
+        return a_hat;
+    }
+    // With the `rayon` feature, the rows of the (largest) ML-KEM-1024 matrix are sampled in parallel
+    #[cfg(feature = "rayon")]
+    if K == 4 {
+        use rayon::iter::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
+        let mut a_hat = [[Poly::default(); K]; K];
+        a_hat.par_iter_mut().enumerate().for_each(|(i, row)| {
+            *row = core::array::from_fn(|j| {
+                sample_ntt(xof(rho, j.to_le_bytes()[0], i.to_le_bytes()[0]))
+            });
+        });
+        return a_hat;
+    }
     core::array::from_fn(|i| {
         core::array::from_fn(|j| sample_ntt(xof(rho, j.to_le_bytes()[0], i.to_le_bytes()[0])))
     })
 }
 
 
+/// As `gen_a_hat()` above, but sampling into caller-provided storage (e.g., a `Workspace`) rather
+/// than returning the K²·512-byte matrix on the stack. The SIMD and parallel paths sample the
+/// whole matrix at once, so defer to `gen_a_hat()`.
+pub(crate) fn gen_a_hat_into<const K: usize>(rho: &[u8; 32], a_hat: &mut [[Poly; K]; K]) {
+    if cfg!(any(all(feature = "avx2", target_arch = "x86_64"), feature = "rayon")) {
+        *a_hat = gen_a_hat(rho);
+        return;
+    }
+    for (i, row) in a_hat.iter_mut().enumerate() {
+        for (j, a_hat_ij) in row.iter_mut().enumerate() {
+            *a_hat_ij = sample_ntt(xof(rho, j.to_le_bytes()[0], i.to_le_bytes()[0]));
+        }
+    }
+}
+
+
+/// Row `i` of `A_hat` (or column `i` when `transpose`) per `k_pke_key_gen()` steps 3-7 and
+/// `k_pke_encrypt()` steps 4-8, sampled on demand so the full matrix is never materialized.
+#[cfg(feature = "low-memory")]
+fn sample_a_hat_row<const K: usize>(rho: &[u8; 32], i: usize, transpose: bool) -> [Poly; K] {
+    core::array::from_fn(|j| {
+        let (row, col) = if transpose { (j, i) } else { (i, j) };
+        sample_ntt(xof(rho, col.to_le_bytes()[0], row.to_le_bytes()[0]))
+    })
+}
+
+
 /// Algorithm 14 `K-PKE.Encrypt(ek_PKE , m, r)` on page 30.
 /// Uses the encryption key to encrypt a plaintext message using the randomness r.
 ///
@@ -99,80 +227,132 @@ fn gen_a_hat<const K: usize>(rho: &[u8; 32]) -> [[[Z; 256]; K]; K] {
 /// Input: message `m ∈ B^{32}` <br>
 /// Input: randomness `r ∈ B^{32}` <br>
 /// Output: ciphertext `c ∈ B^{32(du·k+dv)}` <br>
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
 #[allow(clippy::many_single_char_names, clippy::too_many_arguments)]
+#[cfg_attr(feature = "small-code", allow(dead_code))] // then only used by the `research` API
 pub(crate) fn k_pke_encrypt<const K: usize, const ETA1_64: usize, const ETA2_64: usize>(
     du: u32, dv: u32, ek_pke: &[u8], m: &[u8], r: &[u8; 32], ct: &mut [u8],
 ) -> Result<(), &'static str> {
-    debug_assert_eq!(ek_pke.len(), 384 * K + 32, "Alg 14: ek len not 384 * K + 32");
-    debug_assert_eq!(m.len(), 32, "Alg 14: m len not 32");
-
-    // 1: N ← 0
-    let mut n = 0;
+    check!(ek_pke.len() == 384 * K + 32, "Alg 14: ek len not 384 * K + 32");
+    check!(m.len() == 32, "Alg 14: m len not 32");
 
     // 2: t̂ ← ByteDecode_12 (ek_PKE [0 : 384k])    ▷ run ByteDecode_12 𝑘 times to decode `𝐭  ∈ (ℤ^{256}_𝑞)^k`
-    let mut t_hat = [[Z::default(); 256]; K];
-    for (i, chunk) in ek_pke.chunks(384).enumerate().take(K) {
-        t_hat[i] = byte_decode(12, chunk)?;
+    let mut t_hat = [Poly::default(); K];
+    for (t_hat_i, chunk) in t_hat.iter_mut().zip(ek_pke.chunks(384)) {
+        *t_hat_i = byte_decode(12, chunk)?;
     }
 
     // 3: ρ ← ek_PKE [384k : 384k + 32]    ▷ extract 32-byte seed from ek_PKE
-    let rho = &ek_pke[384 * K..(384 * K + 32)].try_into().unwrap();
+    let rho: &[u8; 32] = ek_pke
+        .get(384 * K..)
+        .and_then(|rho| rho.try_into().ok())
+        .ok_or("Alg 14: ek len not 384 * K + 32")?;
+
+    // Steps 4-8 in gen_a_hat() above, then steps 9-24 in k_pke_encrypt_hat() below
+    #[cfg(not(feature = "low-memory"))]
+    return k_pke_encrypt_hat::<K, ETA1_64, ETA2_64>(du, dv, &t_hat, &gen_a_hat(rho), m, r, ct);
+
+    // Alternatively, with the `low-memory` feature, steps 4-8 are run column by column in step 19
+    #[cfg(feature = "low-memory")]
+    k_pke_encrypt_with::<K, ETA1_64, ETA2_64>(
+        du,
+        dv,
+        &t_hat,
+        |y_hat| mul_mat_t_vec_cols(|i| sample_a_hat_row(rho, i, true), y_hat),
+        m,
+        r,
+        ct,
+    )
+}
+
+
+/// Steps 9-24 of Algorithm 14 `K-PKE.Encrypt(ek_PKE , m, r)` on page 30, operating on an
+/// already decoded `t_hat` and sampled `A_hat`. This supports both `k_pke_encrypt()` above
+/// and encapsulation via an expanded encapsulation key.
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+pub(crate) fn k_pke_encrypt_hat<const K: usize, const ETA1_64: usize, const ETA2_64: usize>(
+    du: u32, dv: u32, t_hat: &[Poly; K], a_hat: &[[Poly; K]; K], m: &[u8], r: &[u8; 32],
+    ct: &mut [u8],
+) -> Result<(), &'static str> {
+    k_pke_encrypt_with::<K, ETA1_64, ETA2_64>(
+        du,
+        dv,
+        t_hat,
+        |y_hat| mul_mat_t_vec(a_hat, y_hat),
+        m,
+        r,
+        ct,
+    )
+}
+
 
-    // Steps 4-8 in gen_a_hat() above
-    let a_hat = gen_a_hat(rho);
+/// Steps 9-24 of Algorithm 14, with `A_hat^T ◦ y_hat` in step 19 computed by `mul_a_hat_t`.
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+#[allow(clippy::many_single_char_names)]
+fn k_pke_encrypt_with<const K: usize, const ETA1_64: usize, const ETA2_64: usize>(
+    du: u32, dv: u32, t_hat: &[Poly; K], mul_a_hat_t: impl FnOnce(&[Poly; K]) -> [Poly; K],
+    m: &[u8], r: &[u8; 32], ct: &mut [u8],
+) -> Result<(), &'static str> {
+    check!(m.len() == 32, "Alg 14: m len not 32");
+
+    // 1: N ← 0    ▷ N is K·i + j below, as the PRF outputs are calculated together
+    let k = K.to_le_bytes()[0];
 
     // 9: for (i ← 0; i < k; i ++)
     // 10: y[i] ← SamplePolyCBD_η1(PRF_η1(r, N))    ▷ r[i] ∈ Z^{256}_q sampled from CBD
     // 11: N ← N +1
     // 12: end for
-    let y: [[Z; 256]; K] = core::array::from_fn(|_| {
-        let x = sample_poly_cbd(&prf::<ETA1_64>(r, n));
-        n += 1;
-        x
-    });
+    // Note: y is transformed in place by step 18, hence its naming
+    let prf_y = Zeroizing::new(prf_many::<ETA1_64, K>(r, 0));
+    let mut y_hat: Zeroizing<[Poly; K]> =
+        Zeroizing::new(core::array::from_fn(|i| sample_poly_cbd(&prf_y[i])));
 
     // 13: for (i ← 0; i < k; i ++)    ▷ generate e1 ∈ (Z_q^{256})^k
     // 14: e1 [i] ← SamplePolyCBD_η2(PRF_η2(r, N))    ▷ e1 [i] ∈ Z^{256}_q sampled from CBD
     // 15: N ← N +1
     // 16: end for
-    let e1: [[Z; 256]; K] = core::array::from_fn(|_| {
-        let x = sample_poly_cbd(&prf::<ETA2_64>(r, n));
-        n += 1;
-        x
-    });
+    let prf_e1 = Zeroizing::new(prf_many::<ETA2_64, K>(r, k));
+    let e1: Zeroizing<[Poly; K]> =
+        Zeroizing::new(core::array::from_fn(|i| sample_poly_cbd(&prf_e1[i])));
 
     // 17: e2 ← SamplePolyCBD_η2(PRF_η2(r, N))    ▷ sample e2 ∈ Z^{256}_q from CBD
-    let e2 = sample_poly_cbd(&prf::<ETA2_64>(r, n));
+    let prf_e2 = Zeroizing::new(prf::<ETA2_64>(r, 2 * k));
+    let e2 = Zeroizing::new(sample_poly_cbd(&*prf_e2));
 
     // 18: 𝐲̂ ← NTT(𝐲)    ▷ NTT is run k times
-    let y_hat: [[Z; 256]; K] = core::array::from_fn(|i| ntt(&y[i]));
+    for y_i in y_hat.iter_mut() {
+        ntt(y_i);
+    }
 
-    // 19: u ← NTT−1 (Â⊺ ◦ r̂) + e1
-    let mut u = mul_mat_t_vec(&a_hat, &y_hat);
-    for u_i in &mut u {
-        *u_i = ntt_inv(u_i);
+    // 19: u ← NTT−1 (Â⊺ ◦ r̂) + e1    ▷ the addition is folded into NTT−1
+    let mut u = mul_a_hat_t(&y_hat);
+    for (u_i, e1_i) in u.iter_mut().zip(e1.iter()) {
+        ntt_inv_add(u_i, e1_i);
     }
-    u = add_vecs(&u, &e1);
 
     // 20: µ ← Decompress1(ByteDecode_1(m)))
-    let mut mu = byte_decode(1, m)?;
+    let mut mu = Zeroizing::new(byte_decode(1, m)?);
     decompress_vector(1, &mut mu);
 
-    // 21: v ← NTT−1 (t̂⊺ ◦ r̂) + e2 + µ    ▷ encode plaintext m into polynomial v.
-    let mut v = ntt_inv(&dot_t_prod(&t_hat, &y_hat));
-    v = add_vecs(&add_vecs(&[v], &[e2]), &[mu])[0];
+    // 21: v ← NTT−1 (t̂⊺ ◦ r̂) + e2 + µ    ▷ encode plaintext m into polynomial v (e2 + µ folded into NTT−1)
+    let mut v = dot_t_prod(t_hat, &y_hat);
+    for (mu_i, e2_i) in mu.iter_mut().zip(e2.iter()) {
+        *mu_i = mu_i.add(*e2_i);
+    }
+    ntt_inv_add(&mut v, &mu);
 
     // 22: c1 ← ByteEncode_du(Compress_du(u))    ▷ ByteEncode_du is run k times
     let step = 32 * du as usize;
-    for (i, chunk) in ct.chunks_mut(step).enumerate().take(K) {
-        compress_vector(du, &mut u[i]);
-        byte_encode(du, &u[i], chunk);
+    let (c1, c2) = ct.split_at_mut(ct.len().min(K * step));
+    for (u_i, chunk) in u.iter_mut().zip(c1.chunks_mut(step)) {
+        compress_vector(du, u_i);
+        byte_encode(du, u_i, chunk);
     }
 
 
     // 23: c2 ← ByteEncode_dv(Compress_dv(v))
     compress_vector(dv, &mut v);
-    byte_encode(dv, &v, &mut ct[K * step..]);
+    byte_encode(dv, &v, c2);
 
     // 24: return c ← (c1 ∥ c2)
     Ok(())
@@ -185,13 +365,61 @@ pub(crate) fn k_pke_encrypt<const K: usize, const ETA1_64: usize, const ETA2_64:
 /// Input: decryption key `dk_PKE ∈ B^{384·k}`
 /// Input: ciphertext `c ∈ B^{32(du·k+dv)}`
 /// Output: message `m ∈ B^{32}`
+#[cfg(any(feature = "decaps", feature = "research"))]
+#[cfg_attr(feature = "small-code", allow(dead_code))] // then only used by the `research` API
 pub(crate) fn k_pke_decrypt<const K: usize>(
     du: u32, dv: u32, dk_pke: &[u8], ct: &[u8],
 ) -> Result<[u8; 32], &'static str> {
-    debug_assert_eq!(dk_pke.len(), 384 * K, "Alg 15: dk len not 384 * K");
-    debug_assert_eq!(
-        ct.len(),
-        32 * (du as usize * K + dv as usize),
+    check!(dk_pke.len() == 384 * K, "Alg 15: dk len not 384 * K");
+    check!(
+        ct.len() == 32 * (du as usize * K + dv as usize),
+        "Alg 15: ct len not 32 * (DU * K + DV)"
+    );
+
+    // 5: s_hat ← ByteDecode_12(dk_PKE)    ▷ (hoisted from below)
+    let mut s_hat = Zeroizing::new([Poly::default(); K]);
+    for (i, chunk) in dk_pke.chunks(384).enumerate() {
+        s_hat[i] = byte_decode(12, chunk)?;
+    }
+
+    // Steps 1-4 and 6-8 in k_pke_decrypt_hat() below
+    k_pke_decrypt_hat::<K>(du, dv, &s_hat, ct)
+}
+
+
+/// Steps 1-4 and 6-8 of Algorithm 15 `K-PKE.Decrypt(dk_PKE, c)` on page 31, operating on an
+/// already decoded `s_hat`. This supports both `k_pke_decrypt()` above and decapsulation via
+/// an expanded decapsulation key.
+#[cfg(any(feature = "decaps", feature = "research"))]
+pub(crate) fn k_pke_decrypt_hat<const K: usize>(
+    du: u32, dv: u32, s_hat: &[Poly; K], ct: &[u8],
+) -> Result<[u8; 32], &'static str> {
+    decrypt_with::<K>(du, dv, ct, |u_hat| {
+        let mut yy = dot_t_prod(s_hat, u_hat);
+        ntt_inv(&mut yy);
+        yy
+    })
+}
+
+
+/// As `k_pke_decrypt_hat()` above, but with the secret arithmetic of step 6 blinded, see
+/// `blinding.rs`.
+#[cfg(all(feature = "blinding", feature = "decaps"))]
+pub(crate) fn k_pke_decrypt_blinded<const K: usize>(
+    du: u32, dv: u32, s_hat: &[Poly; K], ct: &[u8], blinding: &Blinding,
+) -> Result<[u8; 32], &'static str> {
+    decrypt_with::<K>(du, dv, ct, |u_hat| blinding.ntt_inv_product(s_hat, u_hat))
+}
+
+
+// Steps 1-4 and 6-8 of Algorithm 15, with `NTT^{−1}(s_hat ∘ u_hat)` of step 6 computed by
+// `ntt_inv_product()`
+#[cfg(any(feature = "decaps", feature = "research"))]
+fn decrypt_with<const K: usize>(
+    du: u32, dv: u32, ct: &[u8], ntt_inv_product: impl FnOnce(&[Poly; K]) -> Poly,
+) -> Result<[u8; 32], &'static str> {
+    check!(
+        ct.len() == 32 * (du as usize * K + dv as usize),
         "Alg 15: ct len not 32 * (DU * K + DV)"
     );
 
@@ -202,42 +430,37 @@ pub(crate) fn k_pke_decrypt<const K: usize>(
     let c2 = &ct[32 * du as usize * K..32 * (du as usize * K + dv as usize)];
 
     // 3: 𝐮′ ← Decompress_𝑑(ByteDecode_𝑑(𝑐1))   ▷ run Decompress𝑑 and ByteDecode𝑑 𝑘 times
-    let mut u = [[Z::default(); 256]; K];
+    let mut u = [Poly::default(); K];
     for (i, chunk) in c1.chunks(32 * du as usize).enumerate().take(K) {
         u[i] = byte_decode(du, chunk)?;
         decompress_vector(du, &mut u[i]);
     }
 
     // 4: v ← Decompress_{dv}(ByteDecode_dv(c_2))
-    let mut v = byte_decode(dv, c2)?;
+    let mut v = Zeroizing::new(byte_decode(dv, c2)?);
     decompress_vector(dv, &mut v);
 
-    // 5: s_hat ← ByteDecode_12(dk_PKE)
-    let mut s_hat = [[Z::default(); 256]; K];
-    for (i, chunk) in dk_pke.chunks(384).enumerate() {
-        s_hat[i] = byte_decode(12, chunk)?;
-    }
+    // 5: s_hat ← ByteDecode_12(dk_PKE)    ▷ s_hat is provided to ntt_inv_product()
 
-    // 6: 𝑤 ← 𝑣 − NTT (𝐬 ̂ ∘ NTT(𝐮))    ▷ run NTT 𝑘 times; run NTT^{−1} once
-    let mut w = [Z::default(); 256];
-    let ntt_u: [[Z; 256]; K] = core::array::from_fn(|i| ntt(&u[i]));
-    let st_ntt_u = dot_t_prod(&s_hat, &ntt_u);
-    let yy = ntt_inv(&st_ntt_u);
-    for i in 0..256 {
-        w[i] = v[i].sub(yy[i]);
+    // 6: 𝑤 ← 𝑣 − NTT (𝐬 ̂ ∘ NTT(𝐮))    ▷ run NTT 𝑘 times; run NTT^{−1} once (all in place)
+    for u_i in &mut u {
+        ntt(u_i);
+    }
+    let yy = Zeroizing::new(ntt_inv_product(&u));
+    let w = &mut *v; // w overwrites v
+    for (w_i, yy_i) in w.iter_mut().zip(yy.iter()) {
+        *w_i = w_i.sub(*yy_i);
     }
 
     // 7: m ← ByteEncode_1(Compress_1(w))    ▷ decode plaintext m from polynomial v
-    compress_vector(1, &mut w);
-    let mut m = [0u8; 32];
-    byte_encode(1, &w, &mut m);
+    let m = compress_1_encode(w);
 
     // 8: return m
     Ok(m)
 }
 
 
-#[cfg(test)]
+#[cfg(all(test, feature = "keygen", feature = "encaps", feature = "decaps"))]
 mod tests {
     use rand_core::{RngCore, SeedableRng};
 
@@ -279,4 +502,20 @@ mod tests {
         let res = k_pke_decrypt::<K>(DU, DV, &dk[0..384 * K], &ct);
         assert!(res.is_ok());
     }
+
+    #[test]
+    #[cfg(feature = "low-memory")]
+    #[allow(clippy::needless_range_loop)] // i and j index both the rows/columns and `a_hat`
+    fn test_sample_a_hat_row() {
+        let rho = [0x5Au8; 32];
+        let a_hat = super::gen_a_hat::<K>(&rho);
+        for i in 0..K {
+            let row = super::sample_a_hat_row::<K>(&rho, i, false);
+            let col = super::sample_a_hat_row::<K>(&rho, i, true);
+            for j in 0..K {
+                assert!(row[j].iter().zip(a_hat[i][j].iter()).all(|(a, b)| a.0 == b.0));
+                assert!(col[j].iter().zip(a_hat[j][i].iter()).all(|(a, b)| a.0 == b.0));
+            }
+        }
+    }
 }
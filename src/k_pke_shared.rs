@@ -0,0 +1,263 @@
+// With the `small-code` feature, K-PKE (Algorithms 13-15) is provided by the single implementation
+// below for all parameter sets, taking `k` and `η` as runtime values (as `du` and `dv` already are),
+// rather than being monomorphized per parameter set. Vectors and the matrix are held at the maximum
+// size (k = 4) with unused entries zero, so that the `K = 4` helpers apply unchanged. The generic
+// wrappers at the top match the signatures in `k_pke.rs`, so `ml_kem.rs` simply swaps imports.
+
+#[cfg(any(feature = "encaps", feature = "decaps"))]
+use crate::byte_fns::byte_decode;
+use crate::byte_fns::byte_encode;
+#[cfg(feature = "decaps")]
+use crate::helpers::compress_1_encode;
+#[cfg(any(feature = "encaps", feature = "decaps"))]
+use crate::helpers::{check, compress_vector, decompress_vector, dot_t_prod, mul_mat_t_vec};
+#[cfg(feature = "keygen")]
+use crate::helpers::{g, mul_row_vec_add};
+use crate::helpers::{prf, xof};
+use crate::ntt::ntt;
+#[cfg(feature = "decaps")]
+use crate::ntt::ntt_inv;
+#[cfg(any(feature = "encaps", feature = "decaps"))]
+use crate::ntt::ntt_inv_add;
+use crate::sampling::{sample_ntt, sample_poly_cbd};
+use crate::types::Poly;
+#[cfg(feature = "keygen")]
+use zeroize::Zeroize;
+use zeroize::Zeroizing;
+
+const K_MAX: usize = 4;
+const ETA_64_MAX: usize = 3 * 64;
+
+
+/// Algorithm 13 `K-PKE.KeyGen(d)` on page 29, via the shared implementation below.
+#[cfg(feature = "keygen")]
+pub(crate) fn k_pke_key_gen<const K: usize, const ETA1_64: usize>(
+    d: [u8; 32], ek_pke: &mut [u8], dk_pke: &mut [u8],
+) {
+    key_gen(K, ETA1_64, d, ek_pke, dk_pke);
+}
+
+
+/// Algorithm 14 `K-PKE.Encrypt(ek_PKE , m, r)` on page 30, via the shared implementation below.
+#[cfg(any(feature = "encaps", feature = "decaps"))]
+pub(crate) fn k_pke_encrypt<const K: usize, const ETA1_64: usize, const ETA2_64: usize>(
+    du: u32, dv: u32, ek_pke: &[u8], m: &[u8], r: &[u8; 32], ct: &mut [u8],
+) -> Result<(), &'static str> {
+    encrypt(K, ETA1_64, ETA2_64, du, dv, ek_pke, m, r, ct)
+}
+
+
+/// Algorithm 15 `K-PKE.Decrypt(dk_PKE, c)` on page 31, via the shared implementation below.
+#[cfg(feature = "decaps")]
+pub(crate) fn k_pke_decrypt<const K: usize>(
+    du: u32, dv: u32, dk_pke: &[u8], ct: &[u8],
+) -> Result<[u8; 32], &'static str> {
+    decrypt(K, du, dv, dk_pke, ct)
+}
+
+
+// Steps 3-7 of Algorithm 13 (and steps 4-8 of Algorithm 14), for the leading k×k entries
+fn sample_a_hat(k: usize, rho: &[u8; 32]) -> [[Poly; K_MAX]; K_MAX] {
+    let mut a_hat = [[Poly::default(); K_MAX]; K_MAX];
+    for (i, row) in a_hat.iter_mut().enumerate().take(k) {
+        for (j, entry) in row.iter_mut().enumerate().take(k) {
+            *entry = sample_ntt(xof(rho, j.to_le_bytes()[0], i.to_le_bytes()[0]));
+        }
+    }
+    a_hat
+}
+
+
+// `SamplePolyCBD_η(PRF_η(seed, n + i))` for the leading k entries, with `eta_64 = 64·η`
+fn sample_vec(k: usize, eta_64: usize, seed: &[u8; 32], n: u8) -> [Poly; K_MAX] {
+    let mut vec = [Poly::default(); K_MAX];
+    for (i, entry) in vec.iter_mut().enumerate().take(k) {
+        // The PRF outputs for each η are prefixes of the longest (as SHAKE256 is an XOF)
+        let prf_out = Zeroizing::new(prf::<ETA_64_MAX>(seed, n + i.to_le_bytes()[0]));
+        *entry = sample_poly_cbd(&prf_out[..eta_64]);
+    }
+    vec
+}
+
+
+// Shared implementation of `k_pke::k_pke_key_gen()`
+#[cfg(feature = "keygen")]
+fn key_gen(k: usize, eta1_64: usize, mut d: [u8; 32], ek_pke: &mut [u8], dk_pke: &mut [u8]) {
+    debug_assert_eq!(ek_pke.len(), 384 * k + 32, "Alg 13: ek_pke not 384 * K + 32");
+    debug_assert_eq!(dk_pke.len(), 384 * k, "Alg 13: dk_pke not 384 * K");
+
+    // 1: (𝜌, 𝜎) ← G(𝑑 ‖ 𝑘)
+    let mut dk = Zeroizing::new([0u8; 33]);
+    dk[0..32].copy_from_slice(&d);
+    d.zeroize();
+    dk[32] = k.to_le_bytes()[0];
+    let (rho, sigma) = g(&[&*dk]);
+    let sigma = Zeroizing::new(sigma);
+
+    // 3-7: generate matrix A_hat
+    let a_hat = sample_a_hat(k, &rho);
+
+    // 8-17: sample s and e, then s_hat ← NTT(s) and ê ← NTT(e)
+    let mut s_hat = Zeroizing::new(sample_vec(k, eta1_64, &sigma, 0));
+    let mut e_hat = Zeroizing::new(sample_vec(k, eta1_64, &sigma, k.to_le_bytes()[0]));
+    // 20: dk_PKE ← ByteEncode_12(ŝ), as soon as each s_hat[i] is ready
+    for (i, chunk) in dk_pke.chunks_mut(384).enumerate() {
+        ntt(&mut s_hat[i]);
+        ntt(&mut e_hat[i]);
+        byte_encode(12, &s_hat[i], chunk);
+    }
+
+    // 18-19: t̂ ← Â ◦ ŝ + ê row by row, then ek_PKE ← ByteEncode_12(t̂) ∥ ρ
+    for (i, chunk) in ek_pke.chunks_mut(384).enumerate().take(k) {
+        byte_encode(12, &mul_row_vec_add(&a_hat[i], &s_hat, &e_hat[i]), chunk);
+    }
+    ek_pke[k * 384..].copy_from_slice(&rho);
+}
+
+
+// Shared implementation of `k_pke::k_pke_encrypt()`
+#[cfg(any(feature = "encaps", feature = "decaps"))]
+#[allow(clippy::many_single_char_names, clippy::too_many_arguments)]
+fn encrypt(
+    k: usize, eta1_64: usize, eta2_64: usize, du: u32, dv: u32, ek_pke: &[u8], m: &[u8],
+    r: &[u8; 32], ct: &mut [u8],
+) -> Result<(), &'static str> {
+    check!(ek_pke.len() == 384 * k + 32, "Alg 14: ek len not 384 * K + 32");
+    check!(m.len() == 32, "Alg 14: m len not 32");
+
+    // 2: t̂ ← ByteDecode_12 (ek_PKE [0 : 384k])
+    let mut t_hat = [Poly::default(); K_MAX];
+    for (i, chunk) in ek_pke.chunks(384).enumerate().take(k) {
+        t_hat[i] = byte_decode(12, chunk)?;
+    }
+
+    // 3-8: ρ ← ek_PKE [384k : 384k + 32], then generate matrix A_hat
+    let rho: &[u8; 32] = ek_pke
+        .get(384 * k..)
+        .and_then(|rho| rho.try_into().ok())
+        .ok_or("Alg 14: ek len not 384 * K + 32")?;
+    let a_hat = sample_a_hat(k, rho);
+
+    // 9-18: sample y, e1 and e2, then 𝐲̂ ← NTT(𝐲)
+    let kb = k.to_le_bytes()[0];
+    let mut y_hat = Zeroizing::new(sample_vec(k, eta1_64, r, 0));
+    for y in y_hat.iter_mut().take(k) {
+        ntt(y);
+    }
+    let e1 = Zeroizing::new(sample_vec(k, eta2_64, r, kb));
+    let prf_e2 = Zeroizing::new(prf::<ETA_64_MAX>(r, 2 * kb));
+    let e2 = Zeroizing::new(sample_poly_cbd(&prf_e2[..eta2_64]));
+
+    // 19: u ← NTT−1 (Â⊺ ◦ r̂) + e1    ▷ the addition is folded into NTT−1
+    let mut u = mul_mat_t_vec(&a_hat, &y_hat);
+    for (u_i, e1_i) in u.iter_mut().zip(e1.iter()).take(k) {
+        ntt_inv_add(u_i, e1_i);
+    }
+
+    // 20: µ ← Decompress1(ByteDecode_1(m)))
+    let mut mu = Zeroizing::new(byte_decode(1, m)?);
+    decompress_vector(1, &mut mu);
+
+    // 21: v ← NTT−1 (t̂⊺ ◦ r̂) + e2 + µ    ▷ e2 + µ is folded into NTT−1
+    let mut v = dot_t_prod(&t_hat, &y_hat);
+    for (mu_i, e2_i) in mu.iter_mut().zip(e2.iter()) {
+        *mu_i = mu_i.add(*e2_i);
+    }
+    ntt_inv_add(&mut v, &mu);
+
+    // 22: c1 ← ByteEncode_du(Compress_du(u))
+    let step = 32 * du as usize;
+    let (c1, c2) = ct.split_at_mut(ct.len().min(k * step));
+    for (u_i, chunk) in u.iter_mut().zip(c1.chunks_mut(step)).take(k) {
+        compress_vector(du, u_i);
+        byte_encode(du, u_i, chunk);
+    }
+
+    // 23: c2 ← ByteEncode_dv(Compress_dv(v))
+    compress_vector(dv, &mut v);
+    byte_encode(dv, &v, c2);
+
+    // 24: return c ← (c1 ∥ c2)
+    Ok(())
+}
+
+
+// Shared implementation of `k_pke::k_pke_decrypt()`
+#[cfg(feature = "decaps")]
+fn decrypt(k: usize, du: u32, dv: u32, dk_pke: &[u8], ct: &[u8]) -> Result<[u8; 32], &'static str> {
+    check!(dk_pke.len() == 384 * k, "Alg 15: dk len not 384 * K");
+    check!(ct.len() == 32 * (du as usize * k + dv as usize), "Alg 15: ct len not ...");
+
+    // 5: s_hat ← ByteDecode_12(dk_PKE)    ▷ (hoisted from below)
+    let mut s_hat = Zeroizing::new([Poly::default(); K_MAX]);
+    for (i, chunk) in dk_pke.chunks(384).enumerate().take(k) {
+        s_hat[i] = byte_decode(12, chunk)?;
+    }
+
+    // 1-2: c1 ← c[0 : 32·du·k], c2 ← c[32du·k : 32·(du·k + dv)]
+    let (c1, c2) = ct.split_at(32 * du as usize * k);
+
+    // 3: 𝐮′ ← Decompress_𝑑(ByteDecode_𝑑(𝑐1)), then NTT(𝐮′) as used in step 6
+    let mut ntt_u = [Poly::default(); K_MAX];
+    for (i, chunk) in c1.chunks(32 * du as usize).enumerate().take(k) {
+        ntt_u[i] = byte_decode(du, chunk)?;
+        decompress_vector(du, &mut ntt_u[i]);
+        ntt(&mut ntt_u[i]);
+    }
+
+    // 4: v ← Decompress_{dv}(ByteDecode_dv(c_2))
+    let mut v = byte_decode(dv, c2)?;
+    decompress_vector(dv, &mut v);
+
+    // 6: 𝑤 ← 𝑣 − NTT (𝐬 ̂ ∘ NTT(𝐮))
+    let mut yy = Zeroizing::new(dot_t_prod(&s_hat, &ntt_u));
+    ntt_inv(&mut yy);
+    let mut w = Zeroizing::new(Poly::default());
+    for i in 0..256 {
+        w[i] = v[i].sub(yy[i]);
+    }
+
+    // 7: m ← ByteEncode_1(Compress_1(w))
+    let m = compress_1_encode(&w);
+
+    // 8: return m
+    Ok(m)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use rand_core::{RngCore, SeedableRng};
+
+    // The shared implementation must match the monomorphized one for each parameter set
+    fn check<const K: usize, const ETA1_64: usize, const ETA2_64: usize>(du: u32, dv: u32) {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(K as u64);
+        let (mut d, mut m, mut r) = ([0u8; 32], [0u8; 32], [0u8; 32]);
+        let (mut ek1, mut ek2) = ([0u8; 1568], [0u8; 1568]);
+        let (mut dk1, mut dk2) = ([0u8; 1536], [0u8; 1536]);
+        let (mut ct1, mut ct2) = ([0u8; 1568], [0u8; 1568]);
+        let (ek_len, dk_len) = (384 * K + 32, 384 * K);
+        let ct_len = 32 * (du as usize * K + dv as usize);
+        for _i in 0..10 {
+            rng.fill_bytes(&mut d);
+            rng.fill_bytes(&mut m);
+            rng.fill_bytes(&mut r);
+            crate::k_pke::k_pke_key_gen::<K, ETA1_64>(d, &mut ek1[..ek_len], &mut dk1[..dk_len]);
+            super::k_pke_key_gen::<K, ETA1_64>(d, &mut ek2[..ek_len], &mut dk2[..dk_len]);
+            assert_eq!(ek1, ek2);
+            assert_eq!(dk1, dk2);
+            let (ek, ct1, ct2) = (&ek1[..ek_len], &mut ct1[..ct_len], &mut ct2[..ct_len]);
+            crate::k_pke::k_pke_encrypt::<K, ETA1_64, ETA2_64>(du, dv, ek, &m, &r, ct1).unwrap();
+            super::k_pke_encrypt::<K, ETA1_64, ETA2_64>(du, dv, ek, &m, &r, ct2).unwrap();
+            assert_eq!(ct1, ct2);
+            assert_eq!(super::k_pke_decrypt::<K>(du, dv, &dk1[..dk_len], ct2).unwrap(), m);
+        }
+    }
+
+    #[test]
+    fn test_matches_monomorphized() {
+        check::<2, 192, 128>(10, 4);
+        check::<3, 128, 128>(10, 4);
+        check::<4, 128, 128>(11, 5);
+    }
+}
@@ -0,0 +1,209 @@
+// With the `custom-keccak` feature, SHA3-256, SHA3-512, SHAKE128 and SHAKE256 (FIPS 202) are
+// computed by the sponge below rather than the `sha3` crate, over a Keccak-f[1600] permutation
+// that may be provided by the application via `set_keccak_backend()`. The sponge types implement
+// the same `digest` traits as their `sha3` counterparts, so `helpers.rs` simply swaps imports.
+#![allow(unsafe_code)] // just the conversion of the registered permutation back to a fn pointer
+
+use crate::traits::KeccakBackend;
+use core::sync::atomic::{AtomicPtr, Ordering};
+use sha3::digest::consts::{U32, U64};
+use sha3::digest::{
+    ExtendableOutput, FixedOutput, HashMarker, Output, OutputSizeUser, Update, XofReader,
+};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+
+/// The registered `KeccakBackend::keccak_f1600()` as a type-erased `fn(&mut [u64; 25])`, or null
+/// for the software permutation of the `keccak` crate.
+static PERMUTATION: AtomicPtr<()> = AtomicPtr::new(core::ptr::null_mut());
+
+
+/// Registers `B` as the `Keccak-f[1600]` permutation underlying all subsequent hashing, e.g., to use
+/// a hardware accelerator or a validated Keccak core. Until a backend is registered, the software
+/// permutation of the `keccak` crate is used. Available with the `custom-keccak` feature.
+/// # Examples
+/// ```rust
+/// use fips203::traits::KeccakBackend;
+///
+/// struct MyAccelerator;
+///
+/// impl KeccakBackend for MyAccelerator {
+///     fn keccak_f1600(state: &mut [u64; 25]) {
+///         // e.g., copy the state to the peripheral, trigger it and copy the state back
+///         # keccak::f1600(state);
+///     }
+/// }
+///
+/// fips203::set_keccak_backend::<MyAccelerator>();
+/// ```
+pub fn set_keccak_backend<B: KeccakBackend>() {
+    let permutation: fn(&mut [u64; 25]) = B::keccak_f1600;
+    PERMUTATION.store(permutation as *mut (), Ordering::Release);
+}
+
+
+fn keccak_f1600(state: &mut [u64; 25]) {
+    let ptr = PERMUTATION.load(Ordering::Acquire);
+    if ptr.is_null() {
+        keccak::f1600(state);
+    } else {
+        // SAFETY: non-null values are only ever stored by `set_keccak_backend()` above, from a
+        // `fn(&mut [u64; 25])`, so this converts back to the original function pointer
+        let permutation = unsafe { core::mem::transmute::<*mut (), fn(&mut [u64; 25])>(ptr) };
+        permutation(state);
+    }
+}
+
+
+/// FIPS 202 sponge with rate `RATE` bytes and domain separation/padding byte `PAD`, absorbing.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub(crate) struct Sponge<const RATE: usize, const PAD: u8> {
+    state: [u64; 25],
+    pos: usize,
+}
+
+/// FIPS 202 sponge with rate `RATE` bytes, squeezing.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+pub(crate) struct SpongeReader<const RATE: usize> {
+    state: [u64; 25],
+    pos: usize,
+}
+
+pub(crate) type Sha3_256 = Sponge<136, 0x06>;
+pub(crate) type Sha3_512 = Sponge<72, 0x06>;
+pub(crate) type Shake128 = Sponge<168, 0x1F>;
+pub(crate) type Shake256 = Sponge<136, 0x1F>;
+
+
+impl<const RATE: usize, const PAD: u8> Default for Sponge<RATE, PAD> {
+    fn default() -> Self { Self { state: [0u64; 25], pos: 0 } }
+}
+
+impl<const RATE: usize, const PAD: u8> Sponge<RATE, PAD> {
+    // Applies the final padding (pad10*1 following the domain separation bits), then permutes
+    fn pad(&self) -> SpongeReader<RATE> {
+        let (mut state, mut pos) = (self.state, self.pos);
+        if pos >= RATE {
+            keccak_f1600(&mut state);
+            pos = 0;
+        }
+        state[pos / 8] ^= u64::from(PAD) << (8 * (pos % 8));
+        state[(RATE - 1) / 8] ^= 0x80 << (8 * ((RATE - 1) % 8));
+        keccak_f1600(&mut state);
+        SpongeReader { state, pos: 0 }
+    }
+}
+
+// A full block is only permuted when the next byte arrives (or on padding), so that `pos < RATE`
+// is evident wherever the state is indexed and no bounds checks (i.e., panics) remain
+impl<const RATE: usize, const PAD: u8> Update for Sponge<RATE, PAD> {
+    fn update(&mut self, data: &[u8]) {
+        for byte in data {
+            if self.pos >= RATE {
+                keccak_f1600(&mut self.state);
+                self.pos = 0;
+            }
+            self.state[self.pos / 8] ^= u64::from(*byte) << (8 * (self.pos % 8));
+            self.pos += 1;
+        }
+    }
+}
+
+impl<const RATE: usize> XofReader for SpongeReader<RATE> {
+    fn read(&mut self, buffer: &mut [u8]) {
+        for byte in buffer {
+            if self.pos >= RATE {
+                keccak_f1600(&mut self.state);
+                self.pos = 0;
+            }
+            *byte = (self.state[self.pos / 8] >> (8 * (self.pos % 8))).to_le_bytes()[0];
+            self.pos += 1;
+        }
+    }
+}
+
+impl<const RATE: usize> ExtendableOutput for Sponge<RATE, 0x1F> {
+    type Reader = SpongeReader<RATE>;
+
+    fn finalize_xof(self) -> Self::Reader { self.pad() }
+}
+
+impl OutputSizeUser for Sha3_256 {
+    type OutputSize = U32;
+}
+
+impl OutputSizeUser for Sha3_512 {
+    type OutputSize = U64;
+}
+
+impl<const RATE: usize> HashMarker for Sponge<RATE, 0x06> {}
+
+impl<const RATE: usize> FixedOutput for Sponge<RATE, 0x06>
+where
+    Self: OutputSizeUser,
+{
+    fn finalize_into(self, out: &mut Output<Self>) { self.pad().read(out); }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use sha3::digest::{Digest, ExtendableOutput, Update, XofReader};
+
+    // The sponge (over the default software permutation) must match the `sha3` crate, including
+    // inputs and outputs around the rate boundaries
+    #[test]
+    fn test_matches_sha3() {
+        let input: [u8; 400] = core::array::from_fn(|i| i.to_le_bytes()[0].wrapping_mul(7));
+        for len in [0, 1, 71, 72, 73, 135, 136, 137, 167, 168, 169, 400] {
+            assert_eq!(
+                super::Sha3_256::digest(&input[..len]),
+                sha3::Sha3_256::digest(&input[..len])
+            );
+            assert_eq!(
+                super::Sha3_512::digest(&input[..len]),
+                sha3::Sha3_512::digest(&input[..len])
+            );
+
+            let (mut expected, mut actual) = ([0u8; 400], [0u8; 400]);
+            let mut hasher = sha3::Shake128::default();
+            hasher.update(&input[..len]);
+            hasher.finalize_xof().read(&mut expected);
+            let mut hasher = super::Shake128::default();
+            hasher.update(&input[..len]);
+            let mut reader = hasher.finalize_xof();
+            reader.read(&mut actual[..len]);
+            reader.read(&mut actual[len..]);
+            assert_eq!(actual, expected);
+
+            let mut hasher = sha3::Shake256::default();
+            hasher.update(&input[..len]);
+            hasher.finalize_xof().read(&mut expected);
+            let mut hasher = super::Shake256::default();
+            hasher.update(&input[..len]);
+            hasher.finalize_xof().read(&mut actual);
+            assert_eq!(actual, expected);
+        }
+    }
+
+    // A registered backend is used for all subsequent hashing (this one is functionally identical,
+    // so other tests running concurrently are unaffected)
+    #[test]
+    fn test_set_keccak_backend() {
+        use core::sync::atomic::{AtomicUsize, Ordering};
+        static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+        struct Counting;
+        impl crate::traits::KeccakBackend for Counting {
+            fn keccak_f1600(state: &mut [u64; 25]) {
+                let _ = CALLS.fetch_add(1, Ordering::Relaxed);
+                keccak::f1600(state);
+            }
+        }
+
+        crate::set_keccak_backend::<Counting>();
+        let before = CALLS.load(Ordering::Relaxed);
+        assert_eq!(super::Sha3_256::digest([0u8; 200]), sha3::Sha3_256::digest([0u8; 200]));
+        assert!(CALLS.load(Ordering::Relaxed) >= before + 2);
+    }
+}
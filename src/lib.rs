@@ -10,6 +10,12 @@
 #![deny(trivial_numeric_casts, unreachable_pub, unsafe_op_in_unsafe_fn, unstable_features)]
 #![deny(unused_extern_crates, unused_import_braces, unused_lifetimes, unused_macro_rules)]
 #![deny(unused_qualifications, unused_results, variant_size_differences)]
+// Internal items are gated by the role features (keygen/encaps/decaps) that use them; with no role
+// enabled there is no ML-KEM API at all, so everything below it would otherwise be reported.
+#![cfg_attr(
+    not(any(feature = "keygen", feature = "encaps", feature = "decaps")),
+    allow(dead_code, unused_imports)
+)]
 //
 #![doc = include_str!("../README.md")]
 
@@ -60,29 +66,108 @@
 // remainder of the security parameters are generally passed as normal function parameters.
 //
 // The ensure!() instances are for validation purposes and cannot be turned off. The
-// debug_assert!() instances are (effectively) targeted by the fuzzer in /fips203/fuzz and
-// will support quicker future changes/fixes from any FIPS 203 specification update.
+// debug_assert!() and check!() instances are (effectively) targeted by the fuzzer in /fips203/fuzz
+// and will support quicker future changes/fixes from any FIPS 203 specification update. The
+// check!() instances become ensure!() runtime validations with the `strict-checks` feature.
 
 
 /// These `rand_core` types are re-exported so that users of fips203 do not
 /// have to worry about using the exactly correct version of `rand_core`.
 pub use rand_core::{CryptoRng, Error as RngError, RngCore};
 
+/// These `subtle` types are re-exported so that users of fips203 can compare and select
+/// shared secrets in constant-time without worrying about the exactly correct version of `subtle`.
+pub use subtle::{Choice, ConstantTimeEq};
+
+/// These `secrecy` types are re-exported (with the `secrecy` feature) so that applications standardized
+/// on `secrecy` can wrap shared secrets and decapsulation keys without worrying about the exact version.
+#[cfg(feature = "secrecy")]
+pub use secrecy::{ExposeSecret, Secret};
+
+pub use indicator::{service_indicator, ServiceIndicator};
+#[cfg(feature = "custom-keccak")]
+pub use keccak::set_keccak_backend;
+#[cfg(all(feature = "std", unix))]
+pub use locked::Locked;
+
 use crate::traits::SerDes;
-use subtle::ConstantTimeEq;
+use subtle::ConditionallySelectable;
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+
+#[cfg(feature = "pkcs8")]
+mod asn1;
+#[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+mod avx2;
+#[cfg(all(feature = "avx512", target_arch = "x86_64"))]
+mod avx512;
+#[cfg(all(feature = "blinding", feature = "decaps"))]
+mod blinding;
 mod byte_fns;
+#[cfg(feature = "cortex-m4-asm")]
+mod cortex_m4;
+#[cfg(feature = "cose")]
+mod cose;
+mod ct_mul;
+#[cfg(feature = "encoding")]
+mod encoding;
 mod helpers;
+mod indicator;
+#[cfg(feature = "jwk")]
+mod jwk;
 mod k_pke;
+#[cfg(feature = "small-code")]
+mod k_pke_shared;
+#[cfg(feature = "custom-keccak")]
+mod keccak;
+#[cfg(all(feature = "std", unix))]
+mod locked;
 mod ml_kem;
 mod ntt;
+#[cfg(any(feature = "keygen", feature = "decaps"))]
+mod resumable;
 mod sampling;
+#[cfg(feature = "seal")]
+mod seal;
+#[cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+mod self_test;
+#[cfg(feature = "serde")]
+mod serialization;
 mod types;
 
+/// Deterministic test utilities for known-answer tests against the public API. Available with the
+/// `test-utils` feature.
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+
 /// All functionality is covered by traits, such that consumers can utilize trait objects if desired.
 pub mod traits;
 
+/// The traits (and shared secret) needed for typical usage, for a one-line import.
+/// # Examples
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # #[cfg(feature = "ml-kem-768")] {
+/// use fips203::prelude::*;
+/// use fips203::recommended;
+///
+/// let (ek, dk) = recommended::KG::try_keygen()?;
+/// let (ssk1, ct) = ek.try_encaps()?;
+/// let ssk2 = dk.try_decaps(&ct)?;
+/// assert_eq!(ssk1.into_bytes(), ssk2.into_bytes());
+/// # }
+/// # Ok(())}
+/// ```
+pub mod prelude {
+    pub use crate::traits::{Decaps, Encaps, EntropySource, Kem, KeyGen, SerDes};
+    pub use crate::SharedSecretKey;
+}
+
 // Relevant to all parameter sets
 const Q: u16 = 3329;
 const ZETA: u16 = 17;
@@ -92,10 +177,17 @@ const ZETA: u16 = 17;
 pub const SSK_LEN: usize = 32;
 
 /// The (opaque) secret key that can be de/serialized by each party.
-#[derive(Clone, Debug, Zeroize, ZeroizeOnDrop)]
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
 pub struct SharedSecretKey([u8; SSK_LEN]);
 
 
+impl core::fmt::Debug for SharedSecretKey {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.write_str("SharedSecretKey(<redacted>)")
+    }
+}
+
+
 impl SerDes for SharedSecretKey {
     type ByteArray = [u8; SSK_LEN];
 
@@ -107,26 +199,495 @@ impl SerDes for SharedSecretKey {
     fn try_from_bytes(ssk: Self::ByteArray) -> Result<Self, &'static str> {
         Ok(SharedSecretKey(ssk))
     }
+
+    fn try_from_bytes_ref(ssk: &Self::ByteArray) -> Result<Self, &'static str> {
+        Ok(SharedSecretKey(*ssk))
+    }
+}
+
+
+/// The error of the `TryFrom<&[u8]>` constructors, which distinguishes a slice of the wrong length
+/// from bytes that fail the validation of `try_from_bytes()`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SliceError {
+    /// The slice is not the length of the serialized object
+    Length {
+        /// The length of the serialized object (in bytes)
+        expected: usize,
+        /// The length of the slice (in bytes)
+        actual: usize,
+    },
+    /// The bytes fail validation, for the reason given by `try_from_bytes()`
+    Invalid(&'static str),
+}
+
+impl core::fmt::Display for SliceError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            SliceError::Length { expected, actual } => {
+                write!(f, "Slice length wrong: expected {expected} bytes, got {actual}")
+            }
+            SliceError::Invalid(reason) => f.write_str(reason),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for SliceError {}
+
+// For callers returning the `&'static str` errors used throughout the rest of the API
+impl From<SliceError> for &'static str {
+    fn from(err: SliceError) -> Self {
+        match err {
+            SliceError::Length { .. } => "Slice length wrong",
+            SliceError::Invalid(reason) => reason,
+        }
+    }
+}
+
+// Borrows the slice as an array of the serialized length, or returns `SliceError::Length`
+fn slice_array<const N: usize>(bytes: &[u8]) -> Result<&[u8; N], SliceError> {
+    bytes.try_into().map_err(|_| SliceError::Length { expected: N, actual: bytes.len() })
+}
+
+
+impl TryFrom<&[u8]> for SharedSecretKey {
+    type Error = SliceError;
+
+    fn try_from(ssk: &[u8]) -> Result<Self, Self::Error> {
+        SharedSecretKey::try_from_bytes_ref(slice_array(ssk)?).map_err(SliceError::Invalid)
+    }
+}
+
+
+impl SharedSecretKey {
+    /// Borrows the shared secret bytes, without consuming (or cloning) the key.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8; SSK_LEN] { &self.0 }
+
+    /// Derives `OUT_LEN` bytes of keying material from the shared secret, bound to an application
+    /// `label` (e.g., "client traffic key") and optional `context`, so that the raw 32-byte shared
+    /// secret need not be used directly as a traffic key. The derivation is
+    /// `SHAKE256(K ‖ len(label) ‖ label ‖ len(context) ‖ context ‖ OUT_LEN)` with each length
+    /// encoded as a little-endian u64. This function is intended to operate in constant-time.
+    /// # Examples
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # #[cfg(feature = "ml-kem-512")] {
+    /// use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
+    /// use fips203::traits::{Decaps, Encaps, KeyGen};
+    /// use rand_core::OsRng;
+    ///
+    /// let (ek, dk) = ml_kem_512::KG::try_keygen_with_rng(&mut OsRng)?;
+    /// let (ssk1, ct) = ek.try_encaps_with_rng(&mut OsRng)?;
+    /// let ssk2 = dk.try_decaps(&ct)?;
+    ///
+    /// // Each party derives the same labeled traffic keys
+    /// let tx_key: [u8; 16] = ssk1.expand(b"client to server", b"session 1");
+    /// assert_eq!(tx_key, ssk2.expand::<16>(b"client to server", b"session 1"));
+    /// assert_ne!(tx_key, ssk2.expand::<16>(b"server to client", b"session 1"));
+    /// # }
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn expand<const OUT_LEN: usize>(&self, label: &[u8], context: &[u8]) -> [u8; OUT_LEN] {
+        let mut out = [0u8; OUT_LEN];
+        helpers::expand(&self.0, label, &[context], &mut out);
+        out
+    }
+
+    // Binds the label, ciphertext and encapsulation key into a new shared secret (for `LabeledKem`)
+    #[cfg(any(feature = "encaps", feature = "decaps"))]
+    fn bind(&self, label: &[u8], ct: &[u8], ek: &[u8]) -> SharedSecretKey {
+        let mut out = SharedSecretKey([0u8; SSK_LEN]);
+        helpers::expand(&self.0, label, &[ct, ek], &mut out.0);
+        out
+    }
+
+    /// Derives `OUT_LEN` bytes of keying material from the shared secret via HKDF-SHA256
+    /// (RFC 5869), using the shared secret as the input keying material along with the provided
+    /// `salt` and `info`. This is available with the `hkdf` feature for protocols that specify HKDF.
+    /// # Errors
+    /// Returns an error when `OUT_LEN` exceeds the HKDF-SHA256 maximum of 255 * 32 bytes.
+    /// # Examples
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use fips203::traits::SerDes;
+    /// use fips203::SharedSecretKey;
+    ///
+    /// let ssk = SharedSecretKey::try_from_bytes([1u8; 32])?;
+    /// let okm: [u8; 42] = ssk.try_expand_hkdf(b"salt", b"info")?;
+    /// # Ok(())}
+    /// ```
+    #[cfg(feature = "hkdf")]
+    pub fn try_expand_hkdf<const OUT_LEN: usize>(
+        &self, salt: &[u8], info: &[u8],
+    ) -> Result<[u8; OUT_LEN], &'static str> {
+        let mut out = [0u8; OUT_LEN];
+        hkdf::Hkdf::<sha2::Sha256>::new(Some(salt), &self.0)
+            .expand(info, &mut out)
+            .map_err(|_| "HKDF output length too large")?;
+        Ok(out)
+    }
+
+    /// Conditionally assigns `other` to `self` in constant-time, when `choice` is set. This mirrors
+    /// `subtle::ConditionallySelectable::conditional_assign()`; that trait requires `Copy` which
+    /// is not implemented as the shared secret is zeroized on drop.
+    /// # Examples
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// use fips203::traits::SerDes;
+    /// use fips203::{Choice, ConstantTimeEq, SharedSecretKey};
+    ///
+    /// let mut ssk1 = SharedSecretKey::try_from_bytes([1u8; 32])?;
+    /// let ssk2 = SharedSecretKey::try_from_bytes([2u8; 32])?;
+    /// assert!(bool::from(!ssk1.ct_eq(&ssk2)));
+    /// ssk1.conditional_assign(&ssk2, Choice::from(1));
+    /// assert!(bool::from(ssk1.ct_eq(&ssk2)));
+    /// # Ok(())}
+    /// ```
+    pub fn conditional_assign(&mut self, other: &Self, choice: Choice) {
+        self.0.conditional_assign(&other.0, choice);
+    }
+}
+
+
+#[cfg(feature = "encoding")]
+impl SharedSecretKey {
+    /// Encodes the shared secret as lowercase hex, in constant-time. Available with the `encoding`
+    /// feature.
+    #[must_use]
+    pub fn to_hex(&self) -> zeroize::Zeroizing<alloc::string::String> {
+        zeroize::Zeroizing::new(encoding::to_hex(&self.0))
+    }
+
+    /// Decodes the shared secret from upper or lowercase hex, in constant-time (outside of the
+    /// length check). Available with the `encoding` feature.
+    /// # Errors
+    /// Returns an error on invalid hex or a wrong length.
+    pub fn try_from_hex(hex: &str) -> Result<Self, &'static str> {
+        let mut ssk = SharedSecretKey([0u8; SSK_LEN]);
+        encoding::from_hex(hex, &mut ssk.0)?;
+        Ok(ssk)
+    }
+
+    /// Encodes the shared secret as standard (padded) base64, in constant-time. Available with the
+    /// `encoding` feature.
+    #[must_use]
+    pub fn to_base64(&self) -> zeroize::Zeroizing<alloc::string::String> {
+        zeroize::Zeroizing::new(encoding::to_base64(&self.0))
+    }
+
+    /// Decodes the shared secret from standard (padded) base64, in constant-time (outside of the
+    /// length check). Available with the `encoding` feature.
+    /// # Errors
+    /// Returns an error on invalid base64 or a wrong length.
+    pub fn try_from_base64(base64: &str) -> Result<Self, &'static str> {
+        let mut ssk = SharedSecretKey([0u8; SSK_LEN]);
+        encoding::from_base64(base64, &mut ssk.0)?;
+        Ok(ssk)
+    }
+}
+
+
+/// **Serializes secret key material.** Only available with the separate `serde-secrets` feature,
+/// so that a shared secret cannot be logged or persisted unintentionally.
+#[cfg(feature = "serde-secrets")]
+impl serde::Serialize for SharedSecretKey {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialization::serialize_array(&self.0, serializer)
+    }
+}
+
+#[cfg(feature = "serde-secrets")]
+impl<'de> serde::Deserialize<'de> for SharedSecretKey {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        serialization::deserialize_array(deserializer).map(SharedSecretKey)
+    }
+}
+
+
+// With the `secrecy` feature, `Secret<SharedSecretKey>` supports `Clone` and a redacted `Debug`
+#[cfg(feature = "secrecy")]
+impl secrecy::CloneableSecret for SharedSecretKey {}
+
+#[cfg(feature = "secrecy")]
+impl secrecy::DebugSecret for SharedSecretKey {}
+
+#[cfg(feature = "secrecy")]
+impl SharedSecretKey {
+    /// Converts the shared secret into a `secrecy::Secret` over its raw bytes, for applications
+    /// that standardize on `secrecy` for secret hygiene. The shared secret key is zeroized. Note
+    /// that `Secret::new(ssk)` (or `ssk.into()`) also wraps the shared secret key itself.
+    /// # Examples
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # #[cfg(feature = "ml-kem-512")] {
+    /// use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
+    /// use fips203::traits::{Decaps, Encaps, KeyGen, SerDes};
+    /// use fips203::{ExposeSecret, Secret};
+    /// use rand_core::OsRng;
+    ///
+    /// let (ek, dk) = ml_kem_512::KG::try_keygen_with_rng(&mut OsRng)?;
+    /// let dk = Secret::new(dk); // The decapsulation key can also be wrapped
+    /// let (ssk1, ct) = ek.try_encaps_with_rng(&mut OsRng)?;
+    /// let ssk2 = dk.expose_secret().try_decaps(&ct)?;
+    /// let secret1 = ssk1.into_secret();
+    /// assert_eq!(secret1.expose_secret(), Secret::new(ssk2).expose_secret().as_bytes());
+    /// # }
+    /// # Ok(())}
+    /// ```
+    #[must_use]
+    pub fn into_secret(self) -> Secret<[u8; SSK_LEN]> { Secret::new(self.0) }
+}
+
+
+// Conversions to and from the shared key of the RustCrypto `ml-kem` crate (with the
+// `ml-kem-interop` feature), which is the same 32-byte array for all parameter sets
+#[cfg(feature = "ml-kem-interop")]
+impl From<SharedSecretKey> for ::ml_kem::SharedKey<::ml_kem::MlKem512> {
+    fn from(ssk: SharedSecretKey) -> Self { ssk.0.into() }
+}
+
+#[cfg(feature = "ml-kem-interop")]
+impl From<::ml_kem::SharedKey<::ml_kem::MlKem512>> for SharedSecretKey {
+    fn from(ssk: ::ml_kem::SharedKey<::ml_kem::MlKem512>) -> Self { SharedSecretKey(ssk.into()) }
+}
+
+
+impl ConstantTimeEq for SharedSecretKey {
+    fn ct_eq(&self, other: &Self) -> Choice { self.0.ct_eq(&other.0) }
 }
 
 
 // Conservative constant-time support
 impl PartialEq for SharedSecretKey {
-    fn eq(&self, other: &Self) -> bool { bool::from(self.0.ct_eq(&other.0)) }
+    fn eq(&self, other: &Self) -> bool { bool::from(self.ct_eq(other)) }
+}
+
+
+/// Wraps an encapsulation or decapsulation key such that an application-supplied `label` is bound
+/// into the resulting shared secret, giving cross-protocol domain separation. The shared secret is
+/// derived as `ssk' = SharedSecretKey::expand(ssk, label, ct ‖ ek)` on both sides, so it also
+/// commits to the ciphertext and encapsulation key. Both parties must use the same label.
+/// # Examples
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # #[cfg(feature = "ml-kem-512")] {
+/// use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
+/// use fips203::traits::{Decaps, Encaps, KeyGen};
+/// use fips203::LabeledKem;
+/// use rand_core::OsRng;
+///
+/// let (ek, dk) = ml_kem_512::KG::try_keygen_with_rng(&mut OsRng)?;
+/// let ek = LabeledKem::new(ek, b"my protocol v1");
+/// let dk = LabeledKem::new(dk, b"my protocol v1");
+///
+/// let (ssk1, ct) = ek.try_encaps_with_rng(&mut OsRng)?;
+/// let ssk2 = dk.try_decaps(&ct)?;
+/// assert_eq!(ssk1, ssk2);
+/// assert_ne!(ssk2, dk.into_inner().try_decaps(&ct)?); // Differs from the unlabeled secret
+/// # }
+/// # Ok(())}
+/// ```
+#[cfg(any(feature = "encaps", feature = "decaps"))]
+pub struct LabeledKem<'a, T> {
+    inner: T,
+    label: &'a [u8],
+}
+
+
+#[cfg(any(feature = "encaps", feature = "decaps"))]
+impl<'a, T> LabeledKem<'a, T> {
+    /// Wraps the encapsulation or decapsulation `key` with the application-supplied `label`.
+    pub fn new(key: T, label: &'a [u8]) -> Self { Self { inner: key, label } }
+
+    /// Returns the wrapped (unlabeled) key.
+    pub fn into_inner(self) -> T { self.inner }
+}
+
+
+/// The error returned by a `BoundedDecapsKey` once its maximum number of decapsulations is reached.
+#[cfg(feature = "decaps")]
+pub const DECAPS_LIMIT_REACHED: &str = "Decapsulation limit reached";
+
+/// The error returned by a `BoundedDecapsKey` once its deadline has passed.
+#[cfg(feature = "decaps")]
+pub const DECAPS_KEY_EXPIRED: &str = "Decapsulation key expired";
+
+
+/// Wraps a decapsulation key such that it serves at most a maximum number of decapsulations and,
+/// optionally, only until a deadline, as operational (e.g., FIPS 140-3 or Common Criteria) usage
+/// policies require. Afterward, decapsulation returns `DECAPS_LIMIT_REACHED` or
+/// `DECAPS_KEY_EXPIRED`. Each attempt counts toward the limit, including one that the inner key
+/// fails. The count is held in a `Cell` (so as to suit targets without compare-and-swap), so the
+/// wrapper is not `Sync`; share it across threads within a `Mutex`.
+/// # Examples
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # #[cfg(feature = "ml-kem-512")] {
+/// use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
+/// use fips203::traits::{Decaps, Encaps, KeyGen};
+/// use fips203::{BoundedDecapsKey, DECAPS_LIMIT_REACHED};
+/// use rand_core::OsRng;
+///
+/// let (ek, dk) = ml_kem_512::KG::try_keygen_with_rng(&mut OsRng)?;
+/// let dk = BoundedDecapsKey::new(dk, 2);
+///
+/// let (ssk1, ct) = ek.try_encaps_with_rng(&mut OsRng)?;
+/// assert_eq!(ssk1, dk.try_decaps(&ct)?);
+/// assert_eq!(ssk1, dk.try_decaps(&ct)?);
+/// assert_eq!(dk.try_decaps(&ct).err(), Some(DECAPS_LIMIT_REACHED));
+/// # }
+/// # Ok(())}
+/// ```
+#[cfg(feature = "decaps")]
+pub struct BoundedDecapsKey<T> {
+    inner: T,
+    remaining: core::cell::Cell<u64>,
+    deadline: Option<(u64, fn() -> u64)>,
 }
 
 
+#[cfg(feature = "decaps")]
+impl<T> BoundedDecapsKey<T> {
+    /// Wraps the decapsulation `key` to serve at most `max_decaps` decapsulations.
+    pub const fn new(key: T, max_decaps: u64) -> Self {
+        Self {
+            inner: key,
+            remaining: core::cell::Cell::new(max_decaps),
+            deadline: None,
+        }
+    }
+
+    /// Additionally expires the key once `now()` reaches `deadline`, where the clock is supplied by
+    /// the caller (so as to suit `no_std`), e.g., seconds since the Unix epoch or an RTC's ticks.
+    #[must_use]
+    pub fn with_deadline(self, deadline: u64, now: fn() -> u64) -> Self {
+        Self { deadline: Some((deadline, now)), ..self }
+    }
+
+    /// Returns the number of decapsulations remaining (ignoring any deadline).
+    pub fn remaining(&self) -> u64 { self.remaining.get() }
+
+    /// Returns the wrapped (unbounded) key.
+    pub fn into_inner(self) -> T { self.inner }
+}
+
+
+#[cfg(feature = "decaps")]
+impl<T: traits::Decaps> traits::Decaps for BoundedDecapsKey<T> {
+    type CipherText = T::CipherText;
+    type SharedSecretKey = T::SharedSecretKey;
+
+    fn try_decaps(&self, ct: &Self::CipherText) -> Result<Self::SharedSecretKey, &'static str> {
+        let expired = self.deadline.is_some_and(|(deadline, now)| now() >= deadline);
+        if expired {
+            return Err(DECAPS_KEY_EXPIRED);
+        }
+        let remaining = self.remaining.get().checked_sub(1).ok_or(DECAPS_LIMIT_REACHED)?;
+        self.remaining.set(remaining);
+        self.inner.try_decaps(ct)
+    }
+}
+
 // This common functionality is injected into each parameter set module
 macro_rules! functionality {
     () => {
         use crate::byte_fns::byte_decode;
+        #[cfg(feature = "encaps")]
+        use crate::helpers::HedgedRng;
         use crate::helpers::{ensure, h};
+        #[cfg(any(
+            feature = "acvp",
+            all(feature = "keygen", feature = "encaps", feature = "decaps")
+        ))]
+        use crate::ml_kem::ml_kem_encaps_internal;
+        #[cfg(feature = "decaps")]
+        use crate::ml_kem::{
+            ml_kem_decaps, ml_kem_decaps_expanded, ml_kem_decaps_workspace, ml_kem_expand_dk,
+        };
+        #[cfg(all(feature = "decaps", feature = "blinding"))]
+        use crate::ml_kem::ml_kem_decaps_blinded;
+        #[cfg(feature = "encaps")]
         use crate::ml_kem::{
-            ml_kem_decaps, ml_kem_encaps, ml_kem_key_gen, ml_kem_key_gen_internal,
+            ml_kem_encaps, ml_kem_encaps_expanded, ml_kem_encaps_expanded_seeded,
+            ml_kem_encaps_seeded, ml_kem_encaps_workspace, ml_kem_expand_ek,
         };
-        use crate::traits::{Decaps, Encaps, KeyGen, SerDes};
-        use crate::SharedSecretKey;
+        #[cfg(feature = "keygen")]
+        use crate::ml_kem::{ml_kem_key_gen, ml_kem_key_gen_internal, ml_kem_key_gen_workspace};
+        #[cfg(feature = "decaps")]
+        use crate::traits::Decaps;
+        #[cfg(feature = "encaps")]
+        use crate::traits::Encaps;
+        #[cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+        use crate::traits::Kem;
+        #[cfg(feature = "keygen")]
+        use crate::traits::KeyGen;
+        #[cfg(feature = "decaps")]
+        use crate::traits::KeyStore;
+        #[cfg(all(feature = "std", unix, any(feature = "keygen", feature = "decaps")))]
+        use crate::Locked;
+        use crate::traits::SerDes;
+        #[cfg(all(
+            any(feature = "encaps", feature = "seal"),
+            any(feature = "default-rng", feature = "getrandom")
+        ))]
+        use crate::traits::{DefaultRng, EntropyRng};
+        #[cfg(any(feature = "encaps", feature = "decaps"))]
+        use crate::{LabeledKem, SharedSecretKey, SSK_LEN};
+        #[cfg(feature = "alloc")]
+        use alloc::boxed::Box;
+        #[cfg(all(
+            feature = "rayon",
+            any(
+                feature = "decaps",
+                all(
+                    any(feature = "keygen", feature = "encaps"),
+                    any(feature = "default-rng", feature = "getrandom")
+                )
+            )
+        ))]
+        use alloc::vec::Vec;
+        #[cfg(any(
+            feature = "keygen",
+            feature = "encaps",
+            feature = "seal",
+            all(feature = "decaps", feature = "blinding")
+        ))]
         use rand_core::CryptoRngCore;
+        #[cfg(all(
+            feature = "rayon",
+            feature = "keygen",
+            any(feature = "default-rng", feature = "getrandom")
+        ))]
+        use rayon::iter::IntoParallelIterator;
+        #[cfg(all(
+            feature = "rayon",
+            any(
+                feature = "decaps",
+                all(feature = "encaps", any(feature = "default-rng", feature = "getrandom"))
+            )
+        ))]
+        use rayon::iter::IntoParallelRefIterator;
+        #[cfg(all(
+            feature = "rayon",
+            any(
+                feature = "decaps",
+                all(
+                    any(feature = "keygen", feature = "encaps"),
+                    any(feature = "default-rng", feature = "getrandom")
+                )
+            )
+        ))]
+        use rayon::iter::ParallelIterator;
 
 
         /// Correctly sized encapsulation key specific to the target security parameter set.
@@ -138,10 +699,120 @@ macro_rules! functionality {
         /// Correctly sized ciphertext specific to the target security parameter set.
         pub type CipherText = crate::types::CipherText<CT_LEN>;
 
+        /// Validated encapsulation key borrowed from caller-owned bytes, specific to the target
+        /// security parameter set, as produced by `EncapsKeyRef::try_from_bytes()`.
+        pub type EncapsKeyRef<'a> = crate::types::EncapsKeyRef<'a, EK_LEN>;
+
+        /// Validated decapsulation key borrowed from caller-owned bytes, specific to the target
+        /// security parameter set, as produced by `DecapsKeyRef::try_from_bytes()`.
+        pub type DecapsKeyRef<'a> = crate::types::DecapsKeyRef<'a, DK_LEN>;
+
+        /// Decapsulation key held by a `KeyStore` under an opaque handle, specific to the target
+        /// security parameter set, as produced by `StoredDecapsKey::new()`.
+        #[cfg(feature = "decaps")]
+        pub type StoredDecapsKey<'a, S> = crate::types::StoredDecapsKey<'a, S, CT_LEN>;
+
+        /// Encapsulation key with cached internal state specific to the target security parameter
+        /// set, as produced by `EncapsKey::try_precompute()` for repeated encapsulation.
+        #[cfg(feature = "encaps")]
+        pub type EncapsKeyExpanded = crate::types::EncapsKeyExpanded<K>;
+
+        /// Decapsulation key with cached internal state specific to the target security parameter
+        /// set, as produced by `DecapsKey::try_precompute()` for repeated decapsulation.
+        #[cfg(feature = "decaps")]
+        pub type DecapsKeyExpanded = crate::types::DecapsKeyExpanded<K>;
+
+        /// Key generation advanced a bounded number of steps per `poll()`, specific to the target
+        /// security parameter set, as produced by `KeyGenOp::try_new_with_rng()`.
+        #[cfg(feature = "keygen")]
+        pub type KeyGenOp = crate::resumable::KeyGenOp<K, { ETA1 as usize * 64 }, EK_LEN, DK_LEN>;
+
+        /// Decapsulation advanced a bounded number of steps per `poll()`, specific to the target
+        /// security parameter set, as produced by `DecapsOp::new()`.
+        #[cfg(feature = "decaps")]
+        pub type DecapsOp<'a> = crate::resumable::DecapsOp<
+            'a,
+            K,
+            { ETA1 as usize * 64 },
+            { ETA2 as usize * 64 },
+            CT_LEN,
+        >;
+
+        /// Caller-owned scratch space specific to the target security parameter set, which is
+        /// reused across calls to `KG::try_keygen_in_workspace_with_rng()`,
+        /// `EncapsKey::try_encaps_in_workspace()` and `DecapsKey::try_decaps_in_workspace()`.
+        /// Create one with `Workspace::new()` (also usable for a `static`) or `Workspace::default()`.
+        #[cfg(any(feature = "keygen", feature = "encaps", feature = "decaps"))]
+        pub type Workspace = crate::types::Workspace<K, CT_LEN>;
+
+        /// Encapsulation key accessed in place (zero-copy) within an `rkyv` archive, specific to
+        /// the target security parameter set. Note that deserializing it into an `EncapsKey` via
+        /// `rkyv::Deserialize` does not repeat the modulus check. Available with the `rkyv` feature.
+        #[cfg(feature = "rkyv")]
+        pub type ArchivedEncapsKey = crate::types::ArchivedEncapsKey<EK_LEN>;
+
+        /// Ciphertext accessed in place (zero-copy) within an `rkyv` archive, specific to the
+        /// target security parameter set. Available with the `rkyv` feature.
+        #[cfg(feature = "rkyv")]
+        pub type ArchivedCipherText = crate::types::ArchivedCipherText<CT_LEN>;
+
         /// Supports the `KeyGen` trait, allowing for keypair generation
+        #[cfg(feature = "keygen")]
         pub struct KG();
 
 
+        /// Runs the known-answer self-tests of this security parameter set from embedded
+        /// vectors, i.e., key generation, encapsulation and decapsulation (including implicit
+        /// rejection), as required of a FIPS 140-3 module by its cryptographic algorithm
+        /// self-tests (CASTs). The tests run on the first call only, with the outcome retained
+        /// for subsequent calls. See `fips203::self_test()` for all enabled parameter sets.
+        /// # Errors
+        /// Returns an error when a known-answer test fails (on this or an earlier call).
+        /// # Examples
+        /// ```rust
+        /// # #[cfg(feature = "ml-kem-768")] {
+        /// use fips203::ml_kem_768;
+        ///
+        /// ml_kem_768::self_test().expect("ML-KEM-768 self-test failed");
+        /// # }
+        /// ```
+        #[cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+        pub fn self_test() -> Result<(), &'static str> {
+            use crate::self_test::{D, M, Z};
+            crate::self_test::state(K).run(|| {
+                let (mut ek, mut dk, mut ct) = ([0u8; EK_LEN], [0u8; DK_LEN], [0u8; CT_LEN]);
+                ml_kem_key_gen_internal::<K, { ETA1 as usize * 64 }>(D, Z, &mut ek, &mut dk);
+                let ssk = ml_kem_encaps_internal::<K, { ETA1 as usize * 64 }, { ETA2 as usize * 64 }>(
+                    DU, DV, &M, &ek, &mut ct,
+                );
+                let h_ct = h(&ct);
+                let decaps = |ct: &[u8; CT_LEN]| {
+                    ml_kem_decaps::<
+                        K,
+                        { ETA1 as usize * 64 },
+                        { ETA2 as usize * 64 },
+                        { 32 + 32 * (DU as usize * K + DV as usize) },
+                        CT_LEN,
+                    >(DU, DV, &dk, ct)
+                };
+                let ssk_decaps = decaps(&ct);
+                ct[0] ^= 0x01;
+                let ssk_rejected = decaps(&ct);
+                let (Ok(ssk), Ok(ssk_decaps), Ok(ssk_rejected)) = (ssk, ssk_decaps, ssk_rejected)
+                else {
+                    return false;
+                };
+                (h(&ek) == KAT.h_ek)
+                    & (h(&dk) == KAT.h_dk)
+                    & (h_ct == KAT.h_ct)
+                    & (ssk.0 == KAT.ssk)
+                    & (ssk_decaps.0 == KAT.ssk)
+                    & (ssk_rejected.0 == KAT.ssk_rejected)
+            })
+        }
+
+
+        #[cfg(feature = "keygen")]
         impl KeyGen for KG {
             type DecapsByteArray = [u8; DK_LEN];
             type DecapsKey = DecapsKey;
@@ -162,6 +833,7 @@ macro_rules! functionality {
                 (EncapsKey { 0: ek }, DecapsKey { 0: dk })
             }
 
+            #[cfg(all(feature = "encaps", feature = "decaps"))]
             fn validate_keypair_with_rng_vartime(
                 rng: &mut impl CryptoRngCore, ek: &Self::EncapsByteArray,
                 dk: &Self::DecapsByteArray,
@@ -196,9 +868,79 @@ macro_rules! functionality {
                 // 6. encaps and decaps should produce the same shared secret
                 return ek_res.unwrap().0 == dk_res.unwrap();
             }
+
+            #[cfg(not(all(feature = "encaps", feature = "decaps")))]
+            fn validate_keypair_with_rng_vartime(
+                _rng: &mut impl CryptoRngCore, _ek: &Self::EncapsByteArray,
+                _dk: &Self::DecapsByteArray,
+            ) -> bool {
+                // Steps 4-6 above need both encaps and decaps, so no pair can be validated
+                false
+            }
+        }
+
+
+        #[cfg(feature = "keygen")]
+        impl KG {
+            /// Generates an encapsulation and decapsulation key pair specific to this security
+            /// parameter set into the caller-provided byte arrays, using the caller-owned
+            /// `Workspace` for the sampled vectors and matrix rather than fresh temporaries. With a
+            /// `static` workspace and key buffers, the large intermediates and keys stay off the
+            /// stack; the keys are then used in place via `EncapsKeyRef` and `DecapsKeyRef`. This
+            /// function utilizes a provided random number generator.
+            /// # Errors
+            /// Returns an error when the random number generator fails.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-768")] {
+            /// use fips203::ml_kem_768::{self, DecapsKeyRef, EncapsKeyRef, Workspace, DK_LEN, EK_LEN};
+            /// use rand_core::OsRng;
+            /// use zeroize::Zeroize;
+            ///
+            /// // Placed (and accounted for) by the linker, e.g., with `#[link_section = ".sram2"]`
+            /// static mut WS: Workspace = Workspace::new();
+            /// static mut EK: [u8; EK_LEN] = [0u8; EK_LEN];
+            /// static mut DK: [u8; DK_LEN] = [0u8; DK_LEN];
+            ///
+            /// // Taken exactly once, e.g., at startup
+            /// let (ws, ek, dk) = unsafe {
+            ///     use core::ptr::addr_of_mut;
+            ///     (&mut *addr_of_mut!(WS), &mut *addr_of_mut!(EK), &mut *addr_of_mut!(DK))
+            /// };
+            /// ml_kem_768::KG::try_keygen_in_workspace_with_rng(&mut OsRng, ws, ek, dk)?;
+            /// let (ek, dk) = (EncapsKeyRef::try_from_bytes(ek)?, DecapsKeyRef::try_from_bytes(dk)?);
+            /// let (ssk1, ct) = ek.try_encaps_in_workspace_with_rng(&mut OsRng, ws)?;
+            /// let ssk2 = dk.try_decaps_in_workspace(&ct, ws)?;
+            /// assert_eq!(ssk1, ssk2);
+            /// ws.zeroize(); // A static is never dropped
+            /// # }
+            /// # Ok(())}
+            /// ```
+            pub fn try_keygen_in_workspace_with_rng(
+                rng: &mut impl CryptoRngCore, ws: &mut Workspace, ek_out: &mut [u8; EK_LEN],
+                dk_out: &mut [u8; DK_LEN],
+            ) -> Result<(), &'static str> {
+                ml_kem_key_gen_workspace::<K, { ETA1 as usize * 64 }, CT_LEN>(rng, ek_out, dk_out, ws)
+            }
         }
 
 
+        #[cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+        impl Kem for KG {
+            type CipherText = CipherText;
+            type DecapsKey = DecapsKey;
+            type EncapsKey = EncapsKey;
+            type SharedSecretKey = SharedSecretKey;
+
+            const CT_LEN: usize = CT_LEN;
+            const DK_LEN: usize = DK_LEN;
+            const EK_LEN: usize = EK_LEN;
+        }
+
+
+        #[cfg(feature = "encaps")]
         impl Encaps for EncapsKey {
             type CipherText = CipherText;
             type SharedSecretKey = SharedSecretKey;
@@ -212,118 +954,2608 @@ macro_rules! functionality {
                 )?;
                 Ok((ssk, CipherText { 0: ct }))
             }
+
+            fn try_encaps_hedged_with_rng(
+                &self, rng: &mut impl CryptoRngCore,
+            ) -> Result<(Self::SharedSecretKey, Self::CipherText), &'static str> {
+                self.try_encaps_with_rng(&mut HedgedRng::new(rng, h(&self.0)))
+            }
+
+            fn encaps_from_seed(&self, seed: &[u8; 32]) -> (Self::SharedSecretKey, Self::CipherText) {
+                let mut ct = [0u8; CT_LEN];
+                let ssk = ml_kem_encaps_seeded::<K, { ETA1 as usize * 64 }, { ETA2 as usize * 64 }>(
+                    DU, DV, seed, &self.0, &mut ct,
+                );
+                (ssk, CipherText { 0: ct })
+            }
         }
 
 
-        impl Decaps for DecapsKey {
-            type CipherText = CipherText;
-            type SharedSecretKey = SharedSecretKey;
+        impl EncapsKey {
+            /// Returns the fingerprint `H(ek)` of this encapsulation key, which is the same value that
+            /// ML-KEM itself uses, so that applications can index and identify keys consistently.
+            /// # Examples
+            /// ```rust
+            /// # #[cfg(feature = "ml-kem-512")] {
+            /// use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
+            /// use fips203::traits::KeyGen;
+            ///
+            /// let (ek, dk) = ml_kem_512::KG::keygen_from_seed([1u8; 32], [2u8; 32]);
+            /// assert_eq!(ek.fingerprint(), dk.fingerprint());
+            /// # }
+            /// ```
+            #[must_use]
+            pub fn fingerprint(&self) -> [u8; 32] { h(&self.0) }
+
+            /// Deserializes an encapsulation key **without** the modulus check performed by
+            /// `try_from_bytes()`, for keys loaded from trusted local storage that were validated
+            /// when first stored. The caller must ensure that the bytes were previously validated;
+            /// operations on an invalid key may return errors or incorrect results.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-1024")] {
+            /// use fips203::ml_kem_1024; // Could also be ml_kem_512 or ml_kem_768.
+            /// use fips203::traits::{KeyGen, SerDes};
+            /// use rand_core::OsRng;
+            ///
+            /// let (ek, _dk) = ml_kem_1024::KG::try_keygen_with_rng(&mut OsRng)?;
+            /// let stored = ml_kem_1024::EncapsKey::try_from_bytes(ek.into_bytes())?.into_bytes(); // validated once
+            /// let ek = ml_kem_1024::EncapsKey::from_bytes_unchecked(stored); // ...then trusted on later loads
+            /// # }
+            /// # Ok(())}
+            /// ```
+            #[must_use]
+            pub fn from_bytes_unchecked(ek: [u8; EK_LEN]) -> Self { EncapsKey { 0: ek } }
+
+            /// Generates a shared secret and ciphertext from this encapsulation key, writing them
+            /// directly into caller-provided buffers (e.g., a packet buffer) rather than returning
+            /// owned structs. This function utilizes the OS default random number generator.
+            /// # Errors
+            /// Returns an error when the random number generator fails or an internal error condition arises.
+            #[cfg(all(feature = "encaps", any(feature = "default-rng", feature = "getrandom")))]
+            pub fn try_encaps_into(
+                &self, ct_out: &mut [u8; CT_LEN], ssk_out: &mut [u8; SSK_LEN],
+            ) -> Result<(), &'static str> {
+                let mut rng = EntropyRng { src: &mut DefaultRng };
+                self.try_encaps_into_with_rng(&mut rng, ct_out, ssk_out)
+            }
 
-            fn try_decaps(&self, ct: &CipherText) -> Result<SharedSecretKey, &'static str> {
-                let ssk = ml_kem_decaps::<
+            /// Generates a shared secret and ciphertext from this encapsulation key, writing them
+            /// directly into caller-provided buffers (e.g., a packet buffer) rather than returning
+            /// owned structs. This function utilizes a provided random number generator.
+            /// # Errors
+            /// Returns an error when the random number generator fails or an internal error condition arises.
+            /// On error, both `ct_out` and `ssk_out` are zeroized rather than left partially written.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-512")] {
+            /// use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
+            /// use fips203::traits::KeyGen;
+            /// use rand_core::OsRng;
+            ///
+            /// let (ek, dk) = ml_kem_512::KG::try_keygen_with_rng(&mut OsRng)?;
+            /// let mut packet = [0u8; ml_kem_512::CT_LEN]; // Caller-owned buffers
+            /// let (mut ssk1, mut ssk2) = ([0u8; fips203::SSK_LEN], [0u8; fips203::SSK_LEN]);
+            /// ek.try_encaps_into_with_rng(&mut OsRng, &mut packet, &mut ssk1)?;
+            /// dk.try_decaps_into(&packet, &mut ssk2)?;
+            /// assert_eq!(ssk1, ssk2);
+            /// # }
+            /// # Ok(())}
+            /// ```
+            #[cfg(feature = "encaps")]
+            pub fn try_encaps_into_with_rng(
+                &self, rng: &mut impl CryptoRngCore, ct_out: &mut [u8; CT_LEN],
+                ssk_out: &mut [u8; SSK_LEN],
+            ) -> Result<(), &'static str> {
+                let ssk = ml_kem_encaps::<K, { ETA1 as usize * 64 }, { ETA2 as usize * 64 }>(
+                    rng, DU, DV, &self.0, ct_out,
+                )
+                .map_err(|e| {
+                    zeroize::Zeroize::zeroize(ct_out);
+                    zeroize::Zeroize::zeroize(ssk_out);
+                    e
+                })?;
+                ssk_out.copy_from_slice(ssk.as_bytes());
+                Ok(())
+            }
+
+            /// Generates a shared secret and ciphertext from this encapsulation key, using the
+            /// caller-owned `Workspace` for the decoded key and sampled matrix rather than fresh
+            /// temporaries. This function utilizes the OS default random number generator.
+            /// # Errors
+            /// Returns an error when the random number generator fails or an internal error condition arises.
+            #[cfg(all(feature = "encaps", any(feature = "default-rng", feature = "getrandom")))]
+            pub fn try_encaps_in_workspace(
+                &self, ws: &mut Workspace,
+            ) -> Result<(SharedSecretKey, CipherText), &'static str> {
+                let mut rng = EntropyRng { src: &mut DefaultRng };
+                self.try_encaps_in_workspace_with_rng(&mut rng, ws)
+            }
+
+            /// Generates a shared secret and ciphertext from this encapsulation key, using the
+            /// caller-owned `Workspace` for the decoded key and sampled matrix rather than fresh
+            /// temporaries. This function utilizes a provided random number generator.
+            /// # Errors
+            /// Returns an error when the random number generator fails or an internal error condition arises.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-768")] {
+            /// use fips203::ml_kem_768; // Could also be ml_kem_512 or ml_kem_1024.
+            /// use fips203::traits::KeyGen;
+            /// use rand_core::OsRng;
+            ///
+            /// let (ek, dk) = ml_kem_768::KG::try_keygen_with_rng(&mut OsRng)?;
+            /// let mut ws = ml_kem_768::Workspace::default(); // Allocate the scratch space once...
+            /// for _i in 0..10 {
+            ///     let (ssk1, ct) = ek.try_encaps_in_workspace_with_rng(&mut OsRng, &mut ws)?;
+            ///     let ssk2 = dk.try_decaps_in_workspace(&ct, &mut ws)?; // ...then reuse it
+            ///     assert_eq!(ssk1, ssk2);
+            /// }
+            /// # }
+            /// # Ok(())}
+            /// ```
+            #[cfg(feature = "encaps")]
+            pub fn try_encaps_in_workspace_with_rng(
+                &self, rng: &mut impl CryptoRngCore, ws: &mut Workspace,
+            ) -> Result<(SharedSecretKey, CipherText), &'static str> {
+                let mut ct = [0u8; CT_LEN];
+                let ssk = ml_kem_encaps_workspace::<
                     K,
                     { ETA1 as usize * 64 },
                     { ETA2 as usize * 64 },
-                    { 32 + 32 * (DU as usize * K + DV as usize) },
                     CT_LEN,
-                >(DU, DV, &self.0, &ct.0);
-                ssk
+                >(rng, DU, DV, &self.0, &mut ct, ws)?;
+                Ok((ssk, CipherText { 0: ct }))
             }
-        }
 
+            /// Generates a shared secret and ciphertext from this encapsulation key using the
+            /// explicitly provided message `m`, via Algorithm 17 `ML-KEM.Encaps_internal(ek, m)`.
+            /// This is intended for validation labs (ACVP) and KAT generation only; `m` must
+            /// otherwise be fresh randomness for each encapsulation. Available with the `acvp` feature.
+            /// # Errors
+            /// Returns an error if an internal error condition arises.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-512")] {
+            /// use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
+            /// use fips203::traits::{Decaps, KeyGen};
+            ///
+            /// let (ek, dk) = ml_kem_512::KG::keygen_from_seed([1u8; 32], [2u8; 32]);
+            /// let (ssk1, ct) = ek.try_encaps_with_m(&[3u8; 32])?;
+            /// let ssk2 = dk.try_decaps(&ct)?;
+            /// assert_eq!(ssk1, ssk2);
+            /// # }
+            /// # Ok(())}
+            /// ```
+            #[cfg(feature = "acvp")]
+            pub fn try_encaps_with_m(
+                &self, m: &[u8; 32],
+            ) -> Result<(SharedSecretKey, CipherText), &'static str> {
+                let mut ct = [0u8; CT_LEN];
+                let ssk =
+                    ml_kem_encaps_internal::<K, { ETA1 as usize * 64 }, { ETA2 as usize * 64 }>(
+                        DU, DV, m, &self.0, &mut ct,
+                    )?;
+                Ok((ssk, CipherText { 0: ct }))
+            }
 
-        impl SerDes for EncapsKey {
-            type ByteArray = [u8; EK_LEN];
+            /// Precomputes the decoded `t_hat`, sampled `A_hat` and `H(ek)` of this encapsulation
+            /// key, so that repeated encapsulation to the same key skips this work on every call.
+            /// # Errors
+            /// Returns an error when the encapsulation key is malformed (not expected as it is validated).
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-512")] {
+            /// use rand_core::OsRng;
+            /// use fips203::ml_kem_512;  // Could also be ml_kem_768 or ml_kem_1024.
+            /// use fips203::traits::{KeyGen, Decaps, Encaps};
+            ///
+            /// let (ek, dk) = ml_kem_512::KG::try_keygen_with_rng(&mut OsRng)?;
+            /// let ek_expanded = ek.try_precompute()?;  // Expand the encaps key once...
+            /// for _i in 0..10 {
+            ///     let (ssk1, ct) = ek_expanded.try_encaps_with_rng(&mut OsRng)?;  // ...then encaps many times
+            ///     let ssk2 = dk.try_decaps(&ct)?;
+            ///     assert_eq!(ssk1, ssk2);
+            /// }
+            /// # }
+            /// # Ok(())}
+            /// ```
+            #[cfg(feature = "encaps")]
+            pub fn try_precompute(&self) -> Result<EncapsKeyExpanded, &'static str> {
+                let (t_hat, a_hat, h_ek) = ml_kem_expand_ek::<K>(&self.0)?;
+                Ok(EncapsKeyExpanded { t_hat, a_hat, h_ek })
+            }
+        }
 
-            fn into_bytes(self) -> Self::ByteArray { self.0 }
 
-            fn try_from_bytes(ek: Self::ByteArray) -> Result<Self, &'static str> {
-                // Validation per pg 36 #2 "This check ensures that the integers encoded
-                // in the public key are in the valid range [0, 𝑞 − 1]". Note that
-                // accepting a byte array of fixed size, rather than a slice of varied
-                // size, addresses check #1.
-                for i in 0..K {
-                    let _ek_hat = byte_decode(12, &ek[384 * i..384 * (i + 1)])?;
-                }
-                Ok(EncapsKey { 0: ek })
+        impl<'a> EncapsKeyRef<'a> {
+            /// Validates the caller-owned encapsulation key bytes (e.g., in flash or a static buffer)
+            /// and borrows them for encapsulation without copying the key material.
+            /// # Errors
+            /// Returns an error when the encapsulation key fails validation.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-512")] {
+            /// use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
+            /// use fips203::traits::{Decaps, Encaps, KeyGen, SerDes};
+            /// use rand_core::OsRng;
+            ///
+            /// let (ek, dk) = ml_kem_512::KG::try_keygen_with_rng(&mut OsRng)?;
+            /// let (ek_bytes, dk_bytes) = (ek.into_bytes(), dk.into_bytes()); // e.g., stored in flash
+            ///
+            /// let ek_ref = ml_kem_512::EncapsKeyRef::try_from_bytes(&ek_bytes)?;
+            /// let dk_ref = ml_kem_512::DecapsKeyRef::try_from_bytes(&dk_bytes)?;
+            /// let (ssk1, ct) = ek_ref.try_encaps_with_rng(&mut OsRng)?;
+            /// let ssk2 = dk_ref.try_decaps(&ct)?;
+            /// assert_eq!(ssk1, ssk2);
+            /// # }
+            /// # Ok(())}
+            /// ```
+            pub fn try_from_bytes(ek: &'a [u8; EK_LEN]) -> Result<Self, &'static str> {
+                validate_ek(ek)?;
+                Ok(EncapsKeyRef { 0: ek })
+            }
+
+            /// Returns the fingerprint `H(ek)` of this encapsulation key. See `EncapsKey::fingerprint()`.
+            #[must_use]
+            pub fn fingerprint(&self) -> [u8; 32] { h(self.0) }
+
+            /// Generates a shared secret and ciphertext from this encapsulation key, using the
+            /// caller-owned `Workspace`. See `EncapsKey::try_encaps_in_workspace_with_rng()`.
+            /// # Errors
+            /// Returns an error when the random number generator fails or an internal error condition arises.
+            #[cfg(feature = "encaps")]
+            pub fn try_encaps_in_workspace_with_rng(
+                &self, rng: &mut impl CryptoRngCore, ws: &mut Workspace,
+            ) -> Result<(SharedSecretKey, CipherText), &'static str> {
+                let mut ct = [0u8; CT_LEN];
+                let ssk = ml_kem_encaps_workspace::<
+                    K,
+                    { ETA1 as usize * 64 },
+                    { ETA2 as usize * 64 },
+                    CT_LEN,
+                >(rng, DU, DV, self.0, &mut ct, ws)?;
+                Ok((ssk, CipherText { 0: ct }))
             }
         }
 
 
-        impl SerDes for DecapsKey {
-            type ByteArray = [u8; DK_LEN];
+        #[cfg(feature = "encaps")]
+        impl Encaps for EncapsKeyRef<'_> {
+            type CipherText = CipherText;
+            type SharedSecretKey = SharedSecretKey;
 
-            fn into_bytes(self) -> Self::ByteArray { self.0 }
+            fn try_encaps_with_rng(
+                &self, rng: &mut impl CryptoRngCore,
+            ) -> Result<(Self::SharedSecretKey, Self::CipherText), &'static str> {
+                let mut ct = [0u8; CT_LEN];
+                let ssk = ml_kem_encaps::<K, { ETA1 as usize * 64 }, { ETA2 as usize * 64 }>(
+                    rng, DU, DV, self.0, &mut ct,
+                )?;
+                Ok((ssk, CipherText { 0: ct }))
+            }
 
-            fn try_from_bytes(dk: Self::ByteArray) -> Result<Self, &'static str> {
-                // Validation per pg 31. Note that the two checks specify fixed sizes, and these
-                // functions take only byte arrays of correct size. Nonetheless, we take the
-                // opportunity to validate the ek and h(ek).
-                let len_ek_pke = 384 * K + 32;
-                let len_dk_pke = 384 * K;
-                let ek = &dk[len_dk_pke..len_dk_pke + EK_LEN];
-                let _res =
-                    EncapsKey::try_from_bytes(ek.try_into().map_err(|_| "Malformed encaps key")?)?;
-                ensure!(
-                    h(ek) == dk[(len_dk_pke + len_ek_pke)..(len_dk_pke + len_ek_pke + 32)],
-                    "Encaps hash wrong"
+            fn try_encaps_hedged_with_rng(
+                &self, rng: &mut impl CryptoRngCore,
+            ) -> Result<(Self::SharedSecretKey, Self::CipherText), &'static str> {
+                self.try_encaps_with_rng(&mut HedgedRng::new(rng, h(self.0)))
+            }
+
+            fn encaps_from_seed(&self, seed: &[u8; 32]) -> (Self::SharedSecretKey, Self::CipherText) {
+                let mut ct = [0u8; CT_LEN];
+                let ssk = ml_kem_encaps_seeded::<K, { ETA1 as usize * 64 }, { ETA2 as usize * 64 }>(
+                    DU, DV, seed, self.0, &mut ct,
                 );
-                Ok(DecapsKey { 0: dk })
+                (ssk, CipherText { 0: ct })
             }
         }
 
 
-        impl SerDes for CipherText {
-            type ByteArray = [u8; CT_LEN];
+        #[cfg(feature = "encaps")]
+        impl EncapsKeyExpanded {
+            /// Returns the fingerprint `H(ek)` of the original encapsulation key. See `EncapsKey::fingerprint()`.
+            #[must_use]
+            pub fn fingerprint(&self) -> [u8; 32] { self.h_ek }
+        }
 
-            fn into_bytes(self) -> Self::ByteArray { self.0 }
 
-            fn try_from_bytes(ct: Self::ByteArray) -> Result<Self, &'static str> {
-                // Validation per pg 31. Note that the two checks specify fixed sizes, and these
-                // functions take only byte arrays of correct size. Nonetheless, we use a Result
-                // here in case future opportunities for further validation arise.
-                Ok(CipherText { 0: ct })
+        #[cfg(feature = "encaps")]
+        impl Encaps for EncapsKeyExpanded {
+            type CipherText = CipherText;
+            type SharedSecretKey = SharedSecretKey;
+
+            fn try_encaps_with_rng(
+                &self, rng: &mut impl CryptoRngCore,
+            ) -> Result<(Self::SharedSecretKey, Self::CipherText), &'static str> {
+                let mut ct = [0u8; CT_LEN];
+                let ssk = ml_kem_encaps_expanded::<
+                    K,
+                    { ETA1 as usize * 64 },
+                    { ETA2 as usize * 64 },
+                >(rng, DU, DV, &self.t_hat, &self.a_hat, &self.h_ek, &mut ct)?;
+                Ok((ssk, CipherText { 0: ct }))
+            }
+
+            fn try_encaps_hedged_with_rng(
+                &self, rng: &mut impl CryptoRngCore,
+            ) -> Result<(Self::SharedSecretKey, Self::CipherText), &'static str> {
+                self.try_encaps_with_rng(&mut HedgedRng::new(rng, self.h_ek))
+            }
+
+            fn encaps_from_seed(&self, seed: &[u8; 32]) -> (Self::SharedSecretKey, Self::CipherText) {
+                let mut ct = [0u8; CT_LEN];
+                let ssk = ml_kem_encaps_expanded_seeded::<
+                    K,
+                    { ETA1 as usize * 64 },
+                    { ETA2 as usize * 64 },
+                >(DU, DV, &self.t_hat, &self.a_hat, &self.h_ek, seed, &mut ct);
+                (ssk, CipherText { 0: ct })
             }
         }
 
 
-        #[cfg(test)]
-        mod tests {
-            use super::*;
-            use crate::types::EncapsKey;
-            use rand_chacha::rand_core::SeedableRng;
+        #[cfg(feature = "encaps")]
+        impl Encaps for LabeledKem<'_, EncapsKey> {
+            type CipherText = CipherText;
+            type SharedSecretKey = SharedSecretKey;
 
-            #[test]
-            fn smoke_test() {
-                let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(123);
-                let (ek, dk) = KG::keygen_from_seed([1u8; 32], [2u8; 32]);
-                let (ssk1, ct) = ek.encaps_from_seed(&[3u8; 32]);
-                let ssk2 = dk.try_decaps(&ct).unwrap();
-                assert_eq!(ssk1, ssk2);
-                for _i in 0..100 {
-                    let (ek, dk) = KG::try_keygen_with_rng(&mut rng).unwrap();
-                    let (ssk1, ct) = ek.try_encaps_with_rng(&mut rng).unwrap();
-                    let ssk2 = dk.try_decaps(&ct).unwrap();
-                    assert!(KG::validate_keypair_with_rng_vartime(
-                        &mut rng,
-                        &ek.clone().into_bytes(),
-                        &dk.clone().into_bytes()
-                    ));
-                    assert_eq!(ssk1, ssk2);
-                    assert_eq!(ek.clone().0, EncapsKey::try_from_bytes(ek.into_bytes()).unwrap().0);
-                    assert_eq!(dk.clone().0, DecapsKey::try_from_bytes(dk.into_bytes()).unwrap().0);
-                }
+            fn try_encaps_with_rng(
+                &self, rng: &mut impl CryptoRngCore,
+            ) -> Result<(Self::SharedSecretKey, Self::CipherText), &'static str> {
+                let (ssk, ct) = self.inner.try_encaps_with_rng(rng)?;
+                Ok((ssk.bind(self.label, &ct.0, &self.inner.0), ct))
+            }
+
+            fn try_encaps_hedged_with_rng(
+                &self, rng: &mut impl CryptoRngCore,
+            ) -> Result<(Self::SharedSecretKey, Self::CipherText), &'static str> {
+                let (ssk, ct) = self.inner.try_encaps_hedged_with_rng(rng)?;
+                Ok((ssk.bind(self.label, &ct.0, &self.inner.0), ct))
+            }
+
+            fn encaps_from_seed(&self, seed: &[u8; 32]) -> (Self::SharedSecretKey, Self::CipherText) {
+                let (ssk, ct) = self.inner.encaps_from_seed(seed);
+                (ssk.bind(self.label, &ct.0, &self.inner.0), ct)
             }
         }
-    };
-}
 
 
-#[cfg(feature = "ml-kem-512")]
-pub mod ml_kem_512 {
-    //! Functionality for the ML-KEM-512 security parameter set, which is claimed to be in security category 1, see
-    //! table 2 & 3 on page 39 of spec.
-    //!
+        #[cfg(feature = "decaps")]
+        impl Decaps for DecapsKey {
+            type CipherText = CipherText;
+            type SharedSecretKey = SharedSecretKey;
+
+            fn try_decaps(&self, ct: &CipherText) -> Result<SharedSecretKey, &'static str> {
+                let ssk = ml_kem_decaps::<
+                    K,
+                    { ETA1 as usize * 64 },
+                    { ETA2 as usize * 64 },
+                    { 32 + 32 * (DU as usize * K + DV as usize) },
+                    CT_LEN,
+                >(DU, DV, &self.0, &ct.0);
+                ssk
+            }
+        }
+
+
+        #[cfg(feature = "decaps")]
+        impl Decaps for LabeledKem<'_, DecapsKey> {
+            type CipherText = CipherText;
+            type SharedSecretKey = SharedSecretKey;
+
+            fn try_decaps(&self, ct: &CipherText) -> Result<SharedSecretKey, &'static str> {
+                let ssk = self.inner.try_decaps(ct)?;
+                let ek = &self.inner.0[384 * K..(768 * K + 32)]; // dk contains ek after dk_pke
+                Ok(ssk.bind(self.label, &ct.0, ek))
+            }
+        }
+
+
+        impl DecapsKey {
+            /// Returns the fingerprint `H(ek)` of the encapsulation key embedded within this
+            /// decapsulation key, which is the same value that ML-KEM itself uses (and which is stored
+            /// and validated within the decapsulation key). See `EncapsKey::fingerprint()`.
+            #[must_use]
+            pub fn fingerprint(&self) -> [u8; 32] {
+                let mut h_ek = [0u8; 32];
+                h_ek.copy_from_slice(&self.0[(768 * K + 32)..(768 * K + 64)]);
+                h_ek
+            }
+
+            /// Deserializes a decapsulation key **without** the modulus and hash checks performed
+            /// by `try_from_bytes()`, for keys loaded from trusted local storage that were validated
+            /// when first stored. The caller must ensure that the bytes were previously validated;
+            /// operations on an invalid key may return errors or incorrect results. See
+            /// `EncapsKey::from_bytes_unchecked()` for an example.
+            #[must_use]
+            pub fn from_bytes_unchecked(dk: [u8; DK_LEN]) -> Self { DecapsKey { 0: dk } }
+
+            /// Generates a shared secret from this decapsulation key and serialized ciphertext,
+            /// writing it directly into a caller-provided buffer rather than returning an owned
+            /// struct. The ciphertext is used in place, e.g., directly from a packet buffer. This
+            /// function is intended to operate in constant-time.
+            /// # Errors
+            /// Returns an error if an internal error condition arises, in which case `ssk_out` is
+            /// zeroized.
+            #[cfg(feature = "decaps")]
+            pub fn try_decaps_into(
+                &self, ct: &[u8; CT_LEN], ssk_out: &mut [u8; SSK_LEN],
+            ) -> Result<(), &'static str> {
+                let ssk = ml_kem_decaps::<
+                    K,
+                    { ETA1 as usize * 64 },
+                    { ETA2 as usize * 64 },
+                    { 32 + 32 * (DU as usize * K + DV as usize) },
+                    CT_LEN,
+                >(DU, DV, &self.0, ct)
+                .map_err(|e| {
+                    zeroize::Zeroize::zeroize(ssk_out);
+                    e
+                })?;
+                ssk_out.copy_from_slice(ssk.as_bytes());
+                Ok(())
+            }
+
+            /// Generates a shared secret from this decapsulation key and ciphertext as `try_decaps()`,
+            /// but with the secret arithmetic of decryption blinded by fresh randomness from `rng`, as
+            /// a cheap hedge against differential power analysis. The shared secret is the same. This
+            /// function is intended to operate in constant-time.
+            /// # Errors
+            /// Returns an error when the random number generator fails or an internal error condition
+            /// arises.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-768")] {
+            /// use fips203::ml_kem_768; // Could also be ml_kem_512 or ml_kem_1024.
+            /// use fips203::traits::{Encaps, KeyGen};
+            /// use rand_core::OsRng;
+            ///
+            /// let (ek, dk) = ml_kem_768::KG::try_keygen_with_rng(&mut OsRng)?;
+            /// let (ssk1, ct) = ek.try_encaps_with_rng(&mut OsRng)?;
+            /// let ssk2 = dk.try_decaps_blinded_with_rng(&mut OsRng, &ct)?;
+            /// assert_eq!(ssk1, ssk2);
+            /// # }
+            /// # Ok(())}
+            /// ```
+            #[cfg(all(feature = "decaps", feature = "blinding"))]
+            pub fn try_decaps_blinded_with_rng(
+                &self, rng: &mut impl CryptoRngCore, ct: &CipherText,
+            ) -> Result<SharedSecretKey, &'static str> {
+                ml_kem_decaps_blinded::<
+                    K,
+                    { ETA1 as usize * 64 },
+                    { ETA2 as usize * 64 },
+                    CT_LEN,
+                >(rng, DU, DV, &self.0, &ct.0)
+            }
+
+            /// Generates a shared secret from this decapsulation key and ciphertext, using the
+            /// caller-owned `Workspace` for the decoded key, sampled matrix and re-encrypted
+            /// ciphertext rather than fresh temporaries. The workspace retains secret material
+            /// until it is reused or dropped. This function is intended to operate in constant-time.
+            /// See `EncapsKey::try_encaps_in_workspace_with_rng()` for an example.
+            /// # Errors
+            /// Returns an error if an internal error condition arises.
+            #[cfg(feature = "decaps")]
+            pub fn try_decaps_in_workspace(
+                &self, ct: &CipherText, ws: &mut Workspace,
+            ) -> Result<SharedSecretKey, &'static str> {
+                ml_kem_decaps_workspace::<K, { ETA1 as usize * 64 }, { ETA2 as usize * 64 }, CT_LEN>(
+                    DU, DV, &self.0, &ct.0, ws,
+                )
+            }
+
+            /// Precomputes the decoded `s_hat`, `t_hat`, sampled `A_hat`, `H(ek)` and `z` of this
+            /// decapsulation key, so that repeated decapsulation with the same key skips this work
+            /// on every call. The expanded key holds secret material and is zeroized on drop.
+            /// # Errors
+            /// Returns an error when the decapsulation key is malformed (not expected as it is validated).
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-512")] {
+            /// use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
+            /// use fips203::traits::{Decaps, Encaps, KeyGen};
+            /// use rand_core::OsRng;
+            ///
+            /// let (ek, dk) = ml_kem_512::KG::try_keygen_with_rng(&mut OsRng)?;
+            /// let dk_expanded = dk.try_precompute()?; // Expand the decaps key once...
+            /// for _i in 0..10 {
+            ///     let (ssk1, ct) = ek.try_encaps_with_rng(&mut OsRng)?;
+            ///     let ssk2 = dk_expanded.try_decaps(&ct)?; // ...then decaps many times
+            ///     assert_eq!(ssk1, ssk2);
+            /// }
+            /// # }
+            /// # Ok(())}
+            /// ```
+            #[cfg(feature = "decaps")]
+            pub fn try_precompute(&self) -> Result<DecapsKeyExpanded, &'static str> {
+                let (s_hat, t_hat, a_hat, h_ek, z) = ml_kem_expand_dk::<K>(&self.0)?;
+                Ok(DecapsKeyExpanded { s_hat, t_hat, a_hat, h_ek, z })
+            }
+
+            /// Generates the shared secret for each of the ciphertexts under this decapsulation
+            /// key, decoding the key and sampling `A_hat` only once for the whole batch (see
+            /// `try_precompute()`). The shared secrets are returned in ciphertext order. This function
+            /// is intended to operate in constant-time. Available with the `alloc` feature.
+            /// # Errors
+            /// Returns an error when an internal error condition arises.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(all(feature = "ml-kem-768", feature = "alloc"))] {
+            /// use fips203::ml_kem_768; // Could also be ml_kem_512 or ml_kem_1024.
+            /// use fips203::traits::{Encaps, KeyGen};
+            ///
+            /// let (ek, dk) = ml_kem_768::KG::try_keygen()?;
+            /// let encapsulated = (0..100).map(|_| ek.try_encaps()).collect::<Result<Vec<_>, _>>()?;
+            /// let (ssks, cts): (Vec<_>, Vec<_>) = encapsulated.into_iter().unzip();
+            /// assert_eq!(dk.try_decaps_batch(&cts)?, ssks);
+            /// # }
+            /// # Ok(())}
+            /// ```
+            #[cfg(all(feature = "alloc", feature = "decaps"))]
+            pub fn try_decaps_batch(
+                &self, cts: &[CipherText],
+            ) -> Result<alloc::vec::Vec<SharedSecretKey>, &'static str> {
+                let dk_expanded = self.try_precompute()?;
+                cts.iter().map(|ct| dk_expanded.try_decaps(ct)).collect()
+            }
+        }
+
+
+        impl<'a> DecapsKeyRef<'a> {
+            /// Validates the caller-owned decapsulation key bytes (e.g., in flash or a static buffer)
+            /// and borrows them for decapsulation without copying the key material. See
+            /// `EncapsKeyRef::try_from_bytes()` for an example.
+            /// # Errors
+            /// Returns an error when the decapsulation key fails validation.
+            pub fn try_from_bytes(dk: &'a [u8; DK_LEN]) -> Result<Self, &'static str> {
+                validate_dk(dk)?;
+                Ok(DecapsKeyRef { 0: dk })
+            }
+
+            /// Returns the fingerprint `H(ek)` of the embedded encapsulation key. See `EncapsKey::fingerprint()`.
+            #[must_use]
+            pub fn fingerprint(&self) -> [u8; 32] {
+                let mut h_ek = [0u8; 32];
+                h_ek.copy_from_slice(&self.0[(768 * K + 32)..(768 * K + 64)]);
+                h_ek
+            }
+
+            /// Generates a shared secret from this decapsulation key and ciphertext, using the
+            /// caller-owned `Workspace`. See `DecapsKey::try_decaps_in_workspace()`.
+            /// # Errors
+            /// Returns an error if an internal error condition arises.
+            #[cfg(feature = "decaps")]
+            pub fn try_decaps_in_workspace(
+                &self, ct: &CipherText, ws: &mut Workspace,
+            ) -> Result<SharedSecretKey, &'static str> {
+                ml_kem_decaps_workspace::<K, { ETA1 as usize * 64 }, { ETA2 as usize * 64 }, CT_LEN>(
+                    DU, DV, self.0, &ct.0, ws,
+                )
+            }
+        }
+
+
+        #[cfg(feature = "decaps")]
+        impl Decaps for DecapsKeyRef<'_> {
+            type CipherText = CipherText;
+            type SharedSecretKey = SharedSecretKey;
+
+            fn try_decaps(&self, ct: &CipherText) -> Result<SharedSecretKey, &'static str> {
+                ml_kem_decaps::<
+                    K,
+                    { ETA1 as usize * 64 },
+                    { ETA2 as usize * 64 },
+                    { 32 + 32 * (DU as usize * K + DV as usize) },
+                    CT_LEN,
+                >(DU, DV, self.0, &ct.0)
+            }
+        }
+
+
+        #[cfg(feature = "decaps")]
+        impl<S: KeyStore> Decaps for StoredDecapsKey<'_, S> {
+            type CipherText = CipherText;
+            type SharedSecretKey = SharedSecretKey;
+
+            fn try_decaps(&self, ct: &CipherText) -> Result<SharedSecretKey, &'static str> {
+                // Zeroized on drop should the store fail part way
+                let mut ssk = SharedSecretKey([0u8; SSK_LEN]);
+                self.store.decaps(&self.handle, &ct.0, &mut ssk.0)?;
+                Ok(ssk)
+            }
+        }
+
+
+        #[cfg(feature = "decaps")]
+        impl DecapsKeyExpanded {
+            /// Returns the fingerprint `H(ek)` of the embedded encapsulation key. See `EncapsKey::fingerprint()`.
+            #[must_use]
+            pub fn fingerprint(&self) -> [u8; 32] { self.h_ek }
+        }
+
+
+        #[cfg(feature = "decaps")]
+        impl Decaps for DecapsKeyExpanded {
+            type CipherText = CipherText;
+            type SharedSecretKey = SharedSecretKey;
+
+            fn try_decaps(&self, ct: &CipherText) -> Result<SharedSecretKey, &'static str> {
+                ml_kem_decaps_expanded::<K, { ETA1 as usize * 64 }, { ETA2 as usize * 64 }, CT_LEN>(
+                    DU,
+                    DV,
+                    &self.s_hat,
+                    &self.t_hat,
+                    &self.a_hat,
+                    &self.h_ek,
+                    &self.z,
+                    &ct.0,
+                )
+            }
+        }
+
+
+        // Validation per pg 36 #2 "This check ensures that the integers encoded in the public key
+        // are in the valid range [0, 𝑞 − 1]". This is shared by both encaps and decaps key
+        // deserialization, and operates on a reference to avoid copying the key material.
+        fn validate_ek(ek: &[u8]) -> Result<(), &'static str> {
+            for i in 0..K {
+                let _ek_hat = byte_decode(12, &ek[384 * i..384 * (i + 1)])?;
+            }
+            Ok(())
+        }
+
+
+        // Validation per pg 31. Note that the two checks specify fixed sizes, and the callers
+        // take only byte arrays of correct size. Nonetheless, we take the opportunity to validate
+        // the ek and h(ek).
+        fn validate_dk(dk: &[u8]) -> Result<(), &'static str> {
+            let len_ek_pke = 384 * K + 32;
+            let len_dk_pke = 384 * K;
+            let ek = &dk[len_dk_pke..len_dk_pke + EK_LEN];
+            validate_ek(ek)?;
+            ensure!(
+                h(ek) == dk[(len_dk_pke + len_ek_pke)..(len_dk_pke + len_ek_pke + 32)],
+                "Encaps hash wrong"
+            );
+            Ok(())
+        }
+
+
+        impl SerDes for EncapsKey {
+            type ByteArray = [u8; EK_LEN];
+
+            fn into_bytes(self) -> Self::ByteArray { self.0 }
+
+            fn try_from_bytes(ek: Self::ByteArray) -> Result<Self, &'static str> {
+                Self::try_from_bytes_ref(&ek)
+            }
+
+            fn try_from_bytes_ref(ek: &Self::ByteArray) -> Result<Self, &'static str> {
+                // Note that accepting a byte array of fixed size, rather than a slice of varied
+                // size, addresses check #1 on pg 36.
+                validate_ek(ek)?;
+                Ok(EncapsKey { 0: *ek })
+            }
+        }
+
+
+        impl SerDes for DecapsKey {
+            type ByteArray = [u8; DK_LEN];
+
+            fn into_bytes(self) -> Self::ByteArray { self.0 }
+
+            fn try_from_bytes(dk: Self::ByteArray) -> Result<Self, &'static str> {
+                Self::try_from_bytes_ref(&dk)
+            }
+
+            fn try_from_bytes_ref(dk: &Self::ByteArray) -> Result<Self, &'static str> {
+                validate_dk(dk)?;
+                Ok(DecapsKey { 0: *dk })
+            }
+        }
+
+
+        impl SerDes for CipherText {
+            type ByteArray = [u8; CT_LEN];
+
+            fn into_bytes(self) -> Self::ByteArray { self.0 }
+
+            fn try_from_bytes(ct: Self::ByteArray) -> Result<Self, &'static str> {
+                Self::try_from_bytes_ref(&ct)
+            }
+
+            fn try_from_bytes_ref(ct: &Self::ByteArray) -> Result<Self, &'static str> {
+                // Validation per pg 31. Note that the two checks specify fixed sizes, and these
+                // functions take only byte arrays of correct size. Nonetheless, we use a Result
+                // here in case future opportunities for further validation arise.
+                Ok(CipherText { 0: *ct })
+            }
+        }
+
+
+        impl EncapsKey {
+            /// Borrows the serialized encaps key, without consuming (or cloning) it.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-512")] {
+            /// use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
+            /// use fips203::traits::{Decaps, Encaps, KeyGen, SerDes};
+            /// use rand_core::OsRng;
+            ///
+            /// let (ek, dk) = ml_kem_512::KG::try_keygen_with_rng(&mut OsRng)?;
+            /// let ek_bytes: &[u8; ml_kem_512::EK_LEN] = ek.as_bytes(); // Borrow the serialized encaps key
+            /// assert_eq!(ek_bytes, &ek.clone().into_bytes()); // ...which matches the consuming variant
+            ///
+            /// let (ssk1, ct) = ek.try_encaps_with_rng(&mut OsRng)?; // The encaps key remains usable
+            /// let ssk2 = dk.try_decaps(&ct)?;
+            /// assert_eq!(ssk1.as_bytes(), ssk2.as_bytes());
+            /// # }
+            /// # Ok(())}
+            /// ```
+            #[must_use]
+            pub fn as_bytes(&self) -> &[u8; EK_LEN] { &self.0 }
+        }
+
+
+        impl DecapsKey {
+            /// Borrows the serialized decaps key, without consuming (or cloning) it.
+            #[must_use]
+            pub fn as_bytes(&self) -> &[u8; DK_LEN] { &self.0 }
+        }
+
+
+        impl CipherText {
+            /// Borrows the serialized ciphertext, without consuming (or cloning) it.
+            #[must_use]
+            pub fn as_bytes(&self) -> &[u8; CT_LEN] { &self.0 }
+        }
+
+
+        // The slice-based constructors below check the length and then defer to `try_from_bytes()`
+        // for full validation, so that network code reading into a `Vec<u8>` needs no conversion.
+
+        impl TryFrom<&[u8]> for EncapsKey {
+            type Error = crate::SliceError;
+
+            fn try_from(ek: &[u8]) -> Result<Self, Self::Error> {
+                EncapsKey::try_from_bytes_ref(crate::slice_array(ek)?).map_err(crate::SliceError::Invalid)
+            }
+        }
+
+
+        impl TryFrom<&[u8]> for DecapsKey {
+            type Error = crate::SliceError;
+
+            fn try_from(dk: &[u8]) -> Result<Self, Self::Error> {
+                DecapsKey::try_from_bytes_ref(crate::slice_array(dk)?).map_err(crate::SliceError::Invalid)
+            }
+        }
+
+
+        impl TryFrom<&[u8]> for CipherText {
+            type Error = crate::SliceError;
+
+            fn try_from(ct: &[u8]) -> Result<Self, Self::Error> {
+                CipherText::try_from_bytes_ref(crate::slice_array(ct)?).map_err(crate::SliceError::Invalid)
+            }
+        }
+
+
+        /// Heap-allocated encapsulation key, so that many keys can be moved around without
+        /// copying the underlying bytes on the stack. Available with the `alloc` feature.
+        #[cfg(feature = "alloc")]
+        pub type BoxedEncapsKey = Box<EncapsKey>;
+
+        /// Heap-allocated decapsulation key. Available with the `alloc` feature.
+        #[cfg(feature = "alloc")]
+        pub type BoxedDecapsKey = Box<DecapsKey>;
+
+        /// Heap-allocated ciphertext. Available with the `alloc` feature.
+        #[cfg(feature = "alloc")]
+        pub type BoxedCipherText = Box<CipherText>;
+
+
+        // The boxed constructors below write directly into the heap allocation, rather than
+        // building the (potentially large) value on the stack and then moving it.
+
+        #[cfg(all(feature = "alloc", feature = "keygen"))]
+        impl KG {
+            /// Generates a heap-allocated encapsulation and decapsulation key pair specific to this
+            /// security parameter set. This function utilizes a provided random number generator.
+            /// # Errors
+            /// Returns an error when the random number generator fails.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-1024")] {
+            /// use fips203::ml_kem_1024; // Could also be ml_kem_512 or ml_kem_768.
+            /// use fips203::traits::{Decaps, Encaps};
+            /// use rand_core::OsRng;
+            ///
+            /// let mut keys = Vec::new();
+            /// for _i in 0..10 {
+            ///     keys.push(ml_kem_1024::KG::try_keygen_boxed_with_rng(&mut OsRng)?);
+            /// }
+            /// let (ek, dk) = &keys[3];
+            /// let (ssk1, ct) = ek.try_encaps_boxed_with_rng(&mut OsRng)?;
+            /// let ssk2 = dk.try_decaps(&ct)?;
+            /// assert_eq!(ssk1, ssk2);
+            /// # }
+            /// # Ok(())}
+            /// ```
+            pub fn try_keygen_boxed_with_rng(
+                rng: &mut impl CryptoRngCore,
+            ) -> Result<(BoxedEncapsKey, BoxedDecapsKey), &'static str> {
+                let mut ek = Box::new(EncapsKey { 0: [0u8; EK_LEN] });
+                let mut dk = Box::new(DecapsKey { 0: [0u8; DK_LEN] });
+                ml_kem_key_gen::<K, { ETA1 as usize * 64 }>(rng, &mut ek.0, &mut dk.0)?;
+                Ok((ek, dk))
+            }
+        }
+
+
+        #[cfg(all(feature = "alloc", feature = "encaps"))]
+        impl EncapsKey {
+            /// Generates a shared secret and heap-allocated ciphertext from this encapsulation key.
+            /// This function utilizes a provided random number generator.
+            /// # Errors
+            /// Returns an error when the random number generator fails or an internal error condition arises.
+            pub fn try_encaps_boxed_with_rng(
+                &self, rng: &mut impl CryptoRngCore,
+            ) -> Result<(SharedSecretKey, BoxedCipherText), &'static str> {
+                let mut ct = Box::new(CipherText { 0: [0u8; CT_LEN] });
+                let ssk = ml_kem_encaps::<K, { ETA1 as usize * 64 }, { ETA2 as usize * 64 }>(
+                    rng, DU, DV, &self.0, &mut ct.0,
+                )?;
+                Ok((ssk, ct))
+            }
+        }
+
+
+        impl<'a> TryFrom<&'a [u8]> for EncapsKeyRef<'a> {
+            type Error = crate::SliceError;
+
+            fn try_from(ek: &'a [u8]) -> Result<Self, Self::Error> {
+                EncapsKeyRef::try_from_bytes(crate::slice_array(ek)?).map_err(crate::SliceError::Invalid)
+            }
+        }
+
+
+        impl<'a> TryFrom<&'a [u8]> for DecapsKeyRef<'a> {
+            type Error = crate::SliceError;
+
+            fn try_from(dk: &'a [u8]) -> Result<Self, Self::Error> {
+                DecapsKeyRef::try_from_bytes(crate::slice_array(dk)?).map_err(crate::SliceError::Invalid)
+            }
+        }
+
+
+        #[cfg(feature = "alloc")]
+        impl TryFrom<&[u8]> for BoxedEncapsKey {
+            type Error = crate::SliceError;
+
+            fn try_from(ek: &[u8]) -> Result<Self, Self::Error> {
+                let ek: &[u8; EK_LEN] = crate::slice_array(ek)?;
+                validate_ek(ek).map_err(crate::SliceError::Invalid)?;
+                let mut boxed = Box::new(EncapsKey { 0: [0u8; EK_LEN] });
+                boxed.0.copy_from_slice(ek);
+                Ok(boxed)
+            }
+        }
+
+
+        #[cfg(feature = "alloc")]
+        impl TryFrom<&[u8]> for BoxedDecapsKey {
+            type Error = crate::SliceError;
+
+            fn try_from(dk: &[u8]) -> Result<Self, Self::Error> {
+                let dk: &[u8; DK_LEN] = crate::slice_array(dk)?;
+                validate_dk(dk).map_err(crate::SliceError::Invalid)?;
+                let mut boxed = Box::new(DecapsKey { 0: [0u8; DK_LEN] });
+                boxed.0.copy_from_slice(dk);
+                Ok(boxed)
+            }
+        }
+
+
+        #[cfg(feature = "alloc")]
+        impl TryFrom<&[u8]> for BoxedCipherText {
+            type Error = crate::SliceError;
+
+            fn try_from(ct: &[u8]) -> Result<Self, Self::Error> {
+                let ct: &[u8; CT_LEN] = crate::slice_array(ct)?;
+                let mut boxed = Box::new(CipherText { 0: [0u8; CT_LEN] });
+                boxed.0.copy_from_slice(ct);
+                Ok(boxed)
+            }
+        }
+
+
+        // The resumable operations below run the same algorithms as `try_keygen()` and
+        // `try_decaps()`, a bounded number of steps per `poll()`, so that a long operation on a
+        // small microcontroller can be interleaved with (e.g., hard real-time) tasks.
+
+        #[cfg(feature = "keygen")]
+        impl KeyGenOp {
+            /// Starts key generation specific to this security parameter set, drawing the seeds
+            /// from a provided random number generator. The keys are produced by `poll()`.
+            /// # Errors
+            /// Returns an error when the random number generator fails.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-768")] {
+            /// use core::task::Poll;
+            /// use fips203::ml_kem_768; // Could also be ml_kem_512 or ml_kem_1024.
+            /// use fips203::traits::Encaps;
+            /// use rand_core::OsRng;
+            ///
+            /// let mut keygen = ml_kem_768::KeyGenOp::try_new_with_rng(&mut OsRng)?;
+            /// let (ek, dk) = loop {
+            ///     if let Poll::Ready(keys) = keygen.poll(4) {
+            ///         break keys?;
+            ///     }
+            ///     // ...yield to other tasks here
+            /// };
+            ///
+            /// let (ssk1, ct) = ek.try_encaps_with_rng(&mut OsRng)?;
+            /// let mut decaps = ml_kem_768::DecapsOp::new(&dk, &ct);
+            /// let ssk2 = loop {
+            ///     if let Poll::Ready(ssk) = decaps.poll(4) {
+            ///         break ssk?;
+            ///     }
+            ///     // ...yield to other tasks here
+            /// };
+            /// assert_eq!(ssk1, ssk2);
+            /// # }
+            /// # Ok(())}
+            /// ```
+            pub fn try_new_with_rng(rng: &mut impl CryptoRngCore) -> Result<Self, &'static str> {
+                let mut d = zeroize::Zeroizing::new([0u8; 32]);
+                let mut z = zeroize::Zeroizing::new([0u8; 32]);
+                crate::helpers::draw_seed(rng, &mut d, "Alg 19: Random number generator failed for d")?;
+                crate::helpers::draw_seed(rng, &mut z, "Alg 19: Random number generator failed for z")?;
+                Ok(KeyGenOp::start(*d, *z))
+            }
+
+            /// Starts key generation from the seeds `d` and `z`, as `KeyGen::keygen_from_seed()`.
+            #[must_use]
+            pub fn from_seed(d: [u8; 32], z: [u8; 32]) -> Self { KeyGenOp::start(d, z) }
+        }
+
+
+        #[cfg(feature = "decaps")]
+        impl<'a> DecapsOp<'a> {
+            /// Starts decapsulation of `ct` with `dk`, specific to this security parameter set.
+            /// The shared secret is produced by `poll()`. See `KeyGenOp::try_new_with_rng()` for
+            /// an example.
+            #[must_use]
+            pub fn new(dk: &'a DecapsKey, ct: &'a CipherText) -> Self {
+                DecapsOp::start(DU, DV, &dk.0, &ct.0)
+            }
+        }
+
+
+        // The parallel batch operations below spread independent operations across the rayon
+        // thread pool, each with its own draw from the default random number generator.
+
+        #[cfg(all(
+            feature = "rayon",
+            feature = "keygen",
+            any(feature = "default-rng", feature = "getrandom")
+        ))]
+        impl KG {
+            /// Generates `count` encapsulation and decapsulation key pairs in parallel, utilizing the
+            /// OS default random number generator. Available with the `rayon` feature.
+            /// # Errors
+            /// Returns an error when the random number generator fails.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(all(feature = "ml-kem-1024", feature = "rayon"))] {
+            /// use fips203::ml_kem_1024; // Could also be ml_kem_512 or ml_kem_768.
+            ///
+            /// let keys = ml_kem_1024::KG::try_keygen_par(64)?;
+            /// let eks: Vec<_> = keys.iter().map(|(ek, _dk)| ek.clone()).collect();
+            /// let encapsulated = ml_kem_1024::EncapsKey::try_encaps_par(&eks)?;
+            /// let pairs: Vec<_> =
+            ///     keys.iter().zip(&encapsulated).map(|((_ek, dk), (_ssk, ct))| (dk, ct)).collect();
+            /// let ssks = ml_kem_1024::DecapsKey::try_decaps_par(&pairs)?;
+            /// assert!(ssks.iter().zip(&encapsulated).all(|(ssk2, (ssk1, _ct))| ssk1 == ssk2));
+            /// # }
+            /// # Ok(())}
+            /// ```
+            pub fn try_keygen_par(
+                count: usize,
+            ) -> Result<Vec<(EncapsKey, DecapsKey)>, &'static str> {
+                (0..count).into_par_iter().map(|_| KG::try_keygen()).collect()
+            }
+        }
+
+
+        #[cfg(all(
+            feature = "rayon",
+            feature = "encaps",
+            any(feature = "default-rng", feature = "getrandom")
+        ))]
+        impl EncapsKey {
+            /// Generates a shared secret and ciphertext for each of the encapsulation keys in parallel,
+            /// utilizing the OS default random number generator. Available with the `rayon` feature.
+            /// # Errors
+            /// Returns an error when the random number generator fails or an internal error condition arises.
+            pub fn try_encaps_par(
+                eks: &[EncapsKey],
+            ) -> Result<Vec<(SharedSecretKey, CipherText)>, &'static str> {
+                eks.par_iter().map(Encaps::try_encaps).collect()
+            }
+        }
+
+
+        #[cfg(all(feature = "rayon", feature = "decaps"))]
+        impl DecapsKey {
+            /// Generates the shared secret for each of the decapsulation key and ciphertext pairs in
+            /// parallel. Available with the `rayon` feature.
+            /// # Errors
+            /// Returns an error when an internal error condition arises.
+            pub fn try_decaps_par(
+                pairs: &[(&DecapsKey, &CipherText)],
+            ) -> Result<Vec<SharedSecretKey>, &'static str> {
+                pairs.par_iter().map(|(dk, ct)| dk.try_decaps(ct)).collect()
+            }
+        }
+
+
+        // The locked constructors below likewise write directly into the locked pages, so that
+        // the secret material is never held in (swappable) ordinary memory.
+
+        #[cfg(all(feature = "std", unix, feature = "keygen"))]
+        impl KG {
+            /// Generates an encapsulation and decapsulation key pair specific to this security
+            /// parameter set, with the decapsulation key generated directly into locked memory
+            /// (see `Locked`). This function utilizes a provided random number generator.
+            /// Available with the `std` feature on Unix hosts.
+            /// # Errors
+            /// Returns an error when the memory cannot be locked or the random number generator fails.
+            pub fn try_keygen_locked_with_rng(
+                rng: &mut impl CryptoRngCore,
+            ) -> Result<(EncapsKey, Locked<DecapsKey>), &'static str> {
+                let mut ek = EncapsKey { 0: [0u8; EK_LEN] };
+                let mut dk = Locked::try_new(DecapsKey { 0: [0u8; DK_LEN] })?;
+                ml_kem_key_gen::<K, { ETA1 as usize * 64 }>(rng, &mut ek.0, &mut dk.0)?;
+                Ok((ek, dk))
+            }
+        }
+
+
+        #[cfg(all(feature = "std", unix, feature = "decaps"))]
+        impl DecapsKey {
+            /// Deserializes and validates a decapsulation key directly into locked memory (see
+            /// `Locked`), e.g., when loading a long-lived key from storage. Available with the
+            /// `std` feature on Unix hosts.
+            /// # Errors
+            /// Returns an error when the memory cannot be locked or the key fails validation.
+            pub fn try_from_bytes_locked(dk: &[u8; DK_LEN]) -> Result<Locked<DecapsKey>, &'static str> {
+                validate_dk(dk)?;
+                let mut locked = Locked::try_new(DecapsKey { 0: [0u8; DK_LEN] })?;
+                locked.0.copy_from_slice(dk);
+                Ok(locked)
+            }
+
+            /// Generates a shared secret from this decapsulation key and ciphertext directly into
+            /// locked memory (see `Locked`). This function is intended to operate in constant-time.
+            /// Available with the `std` feature on Unix hosts.
+            /// # Errors
+            /// Returns an error when the memory cannot be locked or an internal error condition arises.
+            pub fn try_decaps_locked(
+                &self, ct: &CipherText,
+            ) -> Result<Locked<SharedSecretKey>, &'static str> {
+                let mut ssk = Locked::try_new(SharedSecretKey([0u8; SSK_LEN]))?;
+                self.try_decaps_into(&ct.0, &mut ssk.0)?;
+                Ok(ssk)
+            }
+        }
+
+
+        #[cfg(feature = "rkyv")]
+        impl ArchivedEncapsKey {
+            /// Borrows the archived encapsulation key as an `EncapsKeyRef` without copying, after
+            /// the modulus check of `EncapsKeyRef::try_from_bytes()`.
+            /// # Errors
+            /// Returns an error when the archived key fails validation.
+            pub fn try_as_ref(&self) -> Result<EncapsKeyRef<'_>, &'static str> {
+                EncapsKeyRef::try_from_bytes(&self.0)
+            }
+
+            /// Borrows the archived encapsulation key as an `EncapsKeyRef` without copying and
+            /// **without** the modulus check, for archives whose keys were validated when written.
+            /// See `EncapsKey::from_bytes_unchecked()`.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-512")] {
+            /// use fips203::ml_kem_512;
+            /// use fips203::traits::{Encaps, KeyGen};
+            /// use rkyv::ser::{serializers::BufferSerializer, Serializer};
+            ///
+            /// let (ek, _dk) = ml_kem_512::KG::try_keygen()?;
+            /// let mut serializer = BufferSerializer::new(rkyv::AlignedBytes([0u8; 1024]));
+            /// let pos = serializer.serialize_value(&ek).unwrap();
+            /// let archive = serializer.into_inner();
+            ///
+            /// // Later, e.g., from a memory-mapped prekey store
+            /// let end = pos + core::mem::size_of::<ml_kem_512::ArchivedEncapsKey>();
+            /// let archived =
+            ///     rkyv::check_archived_root::<ml_kem_512::EncapsKey>(&archive[..end]).unwrap();
+            /// let (ssk, ct) = archived.as_ref_unchecked().try_encaps()?;
+            /// # }
+            /// # Ok(())}
+            /// ```
+            #[must_use]
+            pub fn as_ref_unchecked(&self) -> EncapsKeyRef<'_> { EncapsKeyRef { 0: &self.0 } }
+        }
+
+        #[cfg(feature = "rkyv")]
+        impl ArchivedCipherText {
+            /// Returns the archived ciphertext bytes without copying.
+            #[must_use]
+            pub fn as_bytes(&self) -> &[u8; CT_LEN] { &self.0 }
+        }
+
+
+        /// Passphrase-sealed Decapsulation Key Length (in bytes). Available with the `seal` feature.
+        #[cfg(feature = "seal")]
+        pub const SEALED_DK_LEN: usize = DK_LEN + crate::seal::OVERHEAD;
+
+        // Encrypted-at-rest storage (with the `seal` feature) using Argon2id and ChaCha20-Poly1305
+        #[cfg(feature = "seal")]
+        impl DecapsKey {
+            /// Seals (encrypts) the decapsulation key under a passphrase for storage at rest, using
+            /// the operating system random number generator. See `try_seal_with_rng()`.
+            /// # Errors
+            /// Returns an error when the random number generator fails or an internal error
+            /// condition arises.
+            #[cfg(any(feature = "default-rng", feature = "getrandom"))]
+            pub fn try_seal(&self, passphrase: &[u8]) -> Result<[u8; SEALED_DK_LEN], &'static str> {
+                self.try_seal_with_rng(&mut EntropyRng { src: &mut DefaultRng }, passphrase)
+            }
+
+            /// Seals (encrypts) the decapsulation key under a passphrase for storage at rest. The
+            /// versioned container holds the parameter set identifier, a random salt and nonce, and
+            /// the decapsulation key encrypted with ChaCha20-Poly1305 under an Argon2id-derived key
+            /// (m = 19 MiB, t = 2, p = 1). Available with the `seal` feature.
+            /// # Errors
+            /// Returns an error when the random number generator fails or an internal error
+            /// condition arises.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-512")] {
+            /// use fips203::ml_kem_512;
+            /// use fips203::traits::KeyGen;
+            /// use rand_core::OsRng;
+            ///
+            /// let (_ek, dk) = ml_kem_512::KG::try_keygen()?;
+            /// let sealed = dk.try_seal_with_rng(&mut OsRng, b"correct horse battery staple")?;
+            ///
+            /// // Later, e.g., after loading `sealed` from disk
+            /// let dk2 = ml_kem_512::DecapsKey::try_unseal(&sealed, b"correct horse battery staple")?;
+            /// assert_eq!(dk, dk2);
+            /// assert!(ml_kem_512::DecapsKey::try_unseal(&sealed, b"wrong passphrase").is_err());
+            /// # }
+            /// # Ok(())}
+            /// ```
+            pub fn try_seal_with_rng(
+                &self, rng: &mut impl CryptoRngCore, passphrase: &[u8],
+            ) -> Result<[u8; SEALED_DK_LEN], &'static str> {
+                let mut sealed = [0u8; SEALED_DK_LEN];
+                crate::seal::seal(rng, passphrase, PARAM_ID, &self.0, &mut sealed)?;
+                Ok(sealed)
+            }
+
+            /// Unseals (decrypts) and validates a decapsulation key sealed by `try_seal()` or
+            /// `try_seal_with_rng()` under the same passphrase and parameter set. Available with the
+            /// `seal` feature.
+            /// # Errors
+            /// Returns an error on a wrong passphrase, a corrupted or unsupported container, a
+            /// different parameter set, or an invalid decapsulation key.
+            pub fn try_unseal(sealed: &[u8], passphrase: &[u8]) -> Result<Self, &'static str> {
+                let mut dk = zeroize::Zeroizing::new([0u8; DK_LEN]);
+                crate::seal::unseal(sealed, passphrase, PARAM_ID, &mut *dk)?;
+                DecapsKey::try_from_bytes_ref(&dk)
+            }
+        }
+
+
+        // Serde support (with the `serde` feature) as fixed-length byte arrays; deserialization
+        // re-runs the same validation as `try_from_bytes()`
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for EncapsKey {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                crate::serialization::serialize_array(&self.0, serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for EncapsKey {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let ek = crate::serialization::deserialize_array(deserializer)?;
+                EncapsKey::try_from_bytes(ek).map_err(serde::de::Error::custom)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl serde::Serialize for CipherText {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                crate::serialization::serialize_array(&self.0, serializer)
+            }
+        }
+
+        #[cfg(feature = "serde")]
+        impl<'de> serde::Deserialize<'de> for CipherText {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let ct = crate::serialization::deserialize_array(deserializer)?;
+                CipherText::try_from_bytes(ct).map_err(serde::de::Error::custom)
+            }
+        }
+
+        /// **Serializes secret key material.** Only available with the separate `serde-secrets`
+        /// feature, so that a decapsulation key cannot be logged or persisted unintentionally.
+        #[cfg(feature = "serde-secrets")]
+        impl serde::Serialize for DecapsKey {
+            fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                crate::serialization::serialize_array(&self.0, serializer)
+            }
+        }
+
+        #[cfg(feature = "serde-secrets")]
+        impl<'de> serde::Deserialize<'de> for DecapsKey {
+            fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let mut dk = crate::serialization::deserialize_array(deserializer)?;
+                let result = DecapsKey::try_from_bytes_ref(&dk).map_err(serde::de::Error::custom);
+                zeroize::Zeroize::zeroize(&mut dk);
+                result
+            }
+        }
+
+
+        // Conversions to and from the RustCrypto `ml-kem` crate (with the `ml-kem-interop` feature);
+        // conversions into this crate re-run the same validation as `try_from_bytes()`
+        #[cfg(feature = "ml-kem-interop")]
+        impl From<EncapsKey> for ::ml_kem::kem::EncapsulationKey<MlKemParams> {
+            fn from(ek: EncapsKey) -> Self { ::ml_kem::EncodedSizeUser::from_bytes((&ek.0).into()) }
+        }
+
+        #[cfg(feature = "ml-kem-interop")]
+        impl TryFrom<::ml_kem::kem::EncapsulationKey<MlKemParams>> for EncapsKey {
+            type Error = &'static str;
+
+            fn try_from(
+                ek: ::ml_kem::kem::EncapsulationKey<MlKemParams>,
+            ) -> Result<Self, Self::Error> {
+                EncapsKey::try_from_bytes(::ml_kem::EncodedSizeUser::as_bytes(&ek).into())
+            }
+        }
+
+        #[cfg(feature = "ml-kem-interop")]
+        impl From<DecapsKey> for ::ml_kem::kem::DecapsulationKey<MlKemParams> {
+            fn from(dk: DecapsKey) -> Self { ::ml_kem::EncodedSizeUser::from_bytes((&dk.0).into()) }
+        }
+
+        #[cfg(feature = "ml-kem-interop")]
+        impl TryFrom<::ml_kem::kem::DecapsulationKey<MlKemParams>> for DecapsKey {
+            type Error = &'static str;
+
+            fn try_from(
+                dk: ::ml_kem::kem::DecapsulationKey<MlKemParams>,
+            ) -> Result<Self, Self::Error> {
+                let mut dk: [u8; DK_LEN] = ::ml_kem::EncodedSizeUser::as_bytes(&dk).into();
+                let result = DecapsKey::try_from_bytes_ref(&dk);
+                zeroize::Zeroize::zeroize(&mut dk);
+                result
+            }
+        }
+
+        #[cfg(feature = "ml-kem-interop")]
+        impl From<CipherText> for ::ml_kem::Ciphertext<::ml_kem::kem::Kem<MlKemParams>> {
+            fn from(ct: CipherText) -> Self { ct.0.into() }
+        }
+
+        #[cfg(feature = "ml-kem-interop")]
+        impl TryFrom<::ml_kem::Ciphertext<::ml_kem::kem::Kem<MlKemParams>>> for CipherText {
+            type Error = &'static str;
+
+            fn try_from(
+                ct: ::ml_kem::Ciphertext<::ml_kem::kem::Kem<MlKemParams>>,
+            ) -> Result<Self, Self::Error> {
+                CipherText::try_from_bytes(ct.into())
+            }
+        }
+
+
+        impl EncapsKey {
+            /// Serializes the encapsulation key in the self-describing tagged format, i.e.,
+            /// [`PARAM_ID`] followed by the key bytes, so that the parameter set need not be
+            /// tracked out of band. See [`crate::decode_encaps_key()`].
+            #[must_use]
+            pub fn to_tagged_bytes(&self) -> [u8; EK_LEN + 1] {
+                let mut out = [0u8; EK_LEN + 1];
+                out[0] = PARAM_ID;
+                out[1..].copy_from_slice(&self.0);
+                out
+            }
+
+            /// Deserializes (and validates) an encapsulation key in the tagged format produced
+            /// by `to_tagged_bytes()`.
+            /// # Errors
+            /// Returns an error on a wrong length, a different parameter set or an invalid key.
+            pub fn try_from_tagged_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+                ensure!(bytes.len() == EK_LEN + 1, "Tagged encaps key length wrong");
+                ensure!(bytes[0] == PARAM_ID, "Tagged encaps key parameter set wrong");
+                EncapsKey::try_from(&bytes[1..]).map_err(Into::into)
+            }
+        }
+
+        impl DecapsKey {
+            /// Serializes the decapsulation key in the tagged format, i.e., [`PARAM_ID`] followed by
+            /// the key bytes, so that a stored key is bound to its parameter set and cannot be loaded
+            /// as another in deployments using several. **The output holds secret key material**,
+            /// and is zeroized on drop.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(all(feature = "ml-kem-512", feature = "ml-kem-768"))] {
+            /// use fips203::{ml_kem_512, ml_kem_768};
+            /// use fips203::traits::KeyGen;
+            ///
+            /// let (_ek, dk) = ml_kem_512::KG::try_keygen()?;
+            /// let tagged = dk.to_tagged_bytes(); // e.g., to store
+            /// assert_eq!(dk, ml_kem_512::DecapsKey::try_from_tagged_bytes(&*tagged)?);
+            /// assert!(ml_kem_768::DecapsKey::try_from_tagged_bytes(&*tagged).is_err());
+            /// # }
+            /// # Ok(())}
+            /// ```
+            #[must_use]
+            pub fn to_tagged_bytes(&self) -> zeroize::Zeroizing<[u8; DK_LEN + 1]> {
+                let mut out = zeroize::Zeroizing::new([0u8; DK_LEN + 1]);
+                out[0] = PARAM_ID;
+                out[1..].copy_from_slice(&self.0);
+                out
+            }
+
+            /// Deserializes (and validates) a decapsulation key in the tagged format produced by
+            /// `to_tagged_bytes()`, checking the parameter set identifier before the key itself.
+            /// # Errors
+            /// Returns an error on a wrong length, a different parameter set or an invalid key.
+            pub fn try_from_tagged_bytes(bytes: &[u8]) -> Result<Self, &'static str> {
+                ensure!(bytes.len() == DK_LEN + 1, "Tagged decaps key length wrong");
+                ensure!(bytes[0] == PARAM_ID, "Tagged decaps key parameter set wrong");
+                DecapsKey::try_from(&bytes[1..]).map_err(Into::into)
+            }
+        }
+
+
+        #[cfg(feature = "encoding")]
+        impl EncapsKey {
+            /// Encodes the encapsulation key as lowercase hex. Available with the `encoding` feature.
+            #[must_use]
+            pub fn to_hex(&self) -> alloc::string::String { crate::encoding::to_hex(&self.0) }
+
+            /// Decodes (and validates) the encapsulation key from upper or lowercase hex. This is also
+            /// available via `str::parse()`. Available with the `encoding` feature.
+            /// # Errors
+            /// Returns an error on invalid hex, a wrong length or an invalid encapsulation key.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-512")] {
+            /// use fips203::ml_kem_512;
+            /// use fips203::traits::KeyGen;
+            ///
+            /// let (ek, _dk) = ml_kem_512::KG::try_keygen()?;
+            /// let hex = ek.to_hex();
+            /// assert_eq!(ek, ml_kem_512::EncapsKey::try_from_hex(&hex)?);
+            /// assert_eq!(ek, hex.parse()?);
+            /// assert_eq!(ek, ml_kem_512::EncapsKey::try_from_base64(&ek.to_base64())?);
+            /// # }
+            /// # Ok(())}
+            /// ```
+            pub fn try_from_hex(hex: &str) -> Result<Self, &'static str> {
+                let mut ek = [0u8; EK_LEN];
+                crate::encoding::from_hex(hex, &mut ek)?;
+                EncapsKey::try_from_bytes(ek)
+            }
+
+            /// Encodes the encapsulation key as standard (padded) base64. Available with the `encoding`
+            /// feature.
+            #[must_use]
+            pub fn to_base64(&self) -> alloc::string::String { crate::encoding::to_base64(&self.0) }
+
+            /// Decodes (and validates) the encapsulation key from standard (padded) base64. Available with
+            /// the `encoding` feature.
+            /// # Errors
+            /// Returns an error on invalid base64, a wrong length or an invalid encapsulation key.
+            pub fn try_from_base64(base64: &str) -> Result<Self, &'static str> {
+                let mut ek = [0u8; EK_LEN];
+                crate::encoding::from_base64(base64, &mut ek)?;
+                EncapsKey::try_from_bytes(ek)
+            }
+        }
+
+        #[cfg(feature = "encoding")]
+        impl core::str::FromStr for EncapsKey {
+            type Err = &'static str;
+
+            fn from_str(hex: &str) -> Result<Self, Self::Err> { EncapsKey::try_from_hex(hex) }
+        }
+
+
+        #[cfg(feature = "encoding")]
+        impl CipherText {
+            /// Encodes the ciphertext as lowercase hex. Available with the `encoding` feature.
+            #[must_use]
+            pub fn to_hex(&self) -> alloc::string::String { crate::encoding::to_hex(&self.0) }
+
+            /// Decodes (and validates) the ciphertext from upper or lowercase hex. This is also
+            /// available via `str::parse()`. Available with the `encoding` feature.
+            /// # Errors
+            /// Returns an error on invalid hex, a wrong length or an invalid ciphertext.
+            pub fn try_from_hex(hex: &str) -> Result<Self, &'static str> {
+                let mut ct = [0u8; CT_LEN];
+                crate::encoding::from_hex(hex, &mut ct)?;
+                CipherText::try_from_bytes(ct)
+            }
+
+            /// Encodes the ciphertext as standard (padded) base64. Available with the `encoding`
+            /// feature.
+            #[must_use]
+            pub fn to_base64(&self) -> alloc::string::String { crate::encoding::to_base64(&self.0) }
+
+            /// Decodes (and validates) the ciphertext from standard (padded) base64. Available with
+            /// the `encoding` feature.
+            /// # Errors
+            /// Returns an error on invalid base64, a wrong length or an invalid ciphertext.
+            pub fn try_from_base64(base64: &str) -> Result<Self, &'static str> {
+                let mut ct = [0u8; CT_LEN];
+                crate::encoding::from_base64(base64, &mut ct)?;
+                CipherText::try_from_bytes(ct)
+            }
+        }
+
+        #[cfg(feature = "encoding")]
+        impl core::str::FromStr for CipherText {
+            type Err = &'static str;
+
+            fn from_str(hex: &str) -> Result<Self, Self::Err> { CipherText::try_from_hex(hex) }
+        }
+
+        #[cfg(feature = "encoding")]
+        impl DecapsKey {
+            /// Encodes the decapsulation key as lowercase hex, in constant-time. Available with
+            /// the `encoding` feature.
+            #[must_use]
+            pub fn to_hex(&self) -> zeroize::Zeroizing<alloc::string::String> {
+                zeroize::Zeroizing::new(crate::encoding::to_hex(&self.0))
+            }
+
+            /// Decodes (and validates) the decapsulation key from upper or lowercase hex, in
+            /// constant-time (outside of the length check), e.g., when loading it from a
+            /// configuration file. Available with the `encoding` feature.
+            /// # Errors
+            /// Returns an error on invalid hex, a wrong length or an invalid decapsulation key.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-512")] {
+            /// use fips203::ml_kem_512;
+            /// use fips203::traits::KeyGen;
+            ///
+            /// let (_ek, dk) = ml_kem_512::KG::try_keygen()?;
+            /// assert_eq!(dk, ml_kem_512::DecapsKey::try_from_hex(&dk.to_hex())?);
+            /// assert_eq!(dk, ml_kem_512::DecapsKey::try_from_base64(&dk.to_base64())?);
+            /// # }
+            /// # Ok(())}
+            /// ```
+            pub fn try_from_hex(hex: &str) -> Result<Self, &'static str> {
+                let mut dk = zeroize::Zeroizing::new([0u8; DK_LEN]);
+                crate::encoding::from_hex(hex, &mut *dk)?;
+                DecapsKey::try_from_bytes_ref(&dk)
+            }
+
+            /// Encodes the decapsulation key as standard (padded) base64, in constant-time.
+            /// Available with the `encoding` feature.
+            #[must_use]
+            pub fn to_base64(&self) -> zeroize::Zeroizing<alloc::string::String> {
+                zeroize::Zeroizing::new(crate::encoding::to_base64(&self.0))
+            }
+
+            /// Decodes (and validates) the decapsulation key from standard (padded) base64, in
+            /// constant-time (outside of the length check). Available with the `encoding` feature.
+            /// # Errors
+            /// Returns an error on invalid base64, a wrong length or an invalid decapsulation key.
+            pub fn try_from_base64(base64: &str) -> Result<Self, &'static str> {
+                let mut dk = zeroize::Zeroizing::new([0u8; DK_LEN]);
+                crate::encoding::from_base64(base64, &mut *dk)?;
+                DecapsKey::try_from_bytes_ref(&dk)
+            }
+        }
+
+
+        // COSE_Key support (with the `cose` feature) using the AKP key type. As the COSE algorithm
+        // identifiers for ML-KEM are not yet registered, the `alg` value is provided by the caller.
+        #[cfg(feature = "cose")]
+        impl EncapsKey {
+            /// Encodes the encapsulation key as a CBOR `COSE_Key` of key type AKP (Algorithm Key
+            /// Pair), with the given COSE algorithm identifier `alg` and the key bytes as `pub`.
+            /// Available with the `cose` feature.
+            #[must_use]
+            pub fn to_cose_key(&self, alg: i64) -> alloc::vec::Vec<u8> {
+                crate::cose::encode_key(alg, &self.0, None)
+            }
+
+            /// Decodes (and validates) an encapsulation key from a CBOR `COSE_Key` of key type AKP,
+            /// which must carry the COSE algorithm identifier `alg`. Any private key member is
+            /// ignored. Available with the `cose` feature.
+            /// # Errors
+            /// Returns an error on a malformed `COSE_Key`, a different `alg` or an invalid key.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-512")] {
+            /// use fips203::ml_kem_512;
+            /// use fips203::traits::KeyGen;
+            ///
+            /// const ALG: i64 = -65_000; // The application's (provisional) COSE algorithm value
+            /// let (ek, _dk) = ml_kem_512::KG::try_keygen()?;
+            /// let cose_key = ek.to_cose_key(ALG);
+            /// assert_eq!(ek, ml_kem_512::EncapsKey::try_from_cose_key(&cose_key, ALG)?);
+            /// # }
+            /// # Ok(())}
+            /// ```
+            pub fn try_from_cose_key(cose_key: &[u8], alg: i64) -> Result<Self, &'static str> {
+                let cose_key = crate::cose::decode_key(cose_key)?;
+                ensure!(cose_key.alg == Some(alg), "COSE_Key algorithm wrong");
+                let ek = cose_key.public.ok_or("COSE_Key public key missing")?;
+                let ek = <&[u8; EK_LEN]>::try_from(ek).map_err(|_| "Encaps key wrong size")?;
+                EncapsKey::try_from_bytes_ref(ek)
+            }
+        }
+
+        #[cfg(feature = "cose")]
+        impl DecapsKey {
+            /// Encodes the decapsulation key as a CBOR `COSE_Key` of key type AKP (Algorithm Key
+            /// Pair), with the given COSE algorithm identifier `alg`, the embedded encapsulation
+            /// key as `pub` and the (expanded) decapsulation key bytes as `priv`. Available with
+            /// the `cose` feature.
+            #[must_use]
+            pub fn to_cose_key(&self, alg: i64) -> zeroize::Zeroizing<alloc::vec::Vec<u8>> {
+                let ek = &self.0[384 * K..768 * K + 32];
+                zeroize::Zeroizing::new(crate::cose::encode_key(alg, ek, Some(&self.0)))
+            }
+
+            /// Decodes (and validates) a decapsulation key from a CBOR `COSE_Key` of key type AKP,
+            /// which must carry the COSE algorithm identifier `alg`. When present, the `pub` member
+            /// must match the encapsulation key embedded in `priv`. Available with the `cose`
+            /// feature.
+            /// # Errors
+            /// Returns an error on a malformed `COSE_Key`, a different `alg` or an invalid key.
+            pub fn try_from_cose_key(cose_key: &[u8], alg: i64) -> Result<Self, &'static str> {
+                let cose_key = crate::cose::decode_key(cose_key)?;
+                ensure!(cose_key.alg == Some(alg), "COSE_Key algorithm wrong");
+                let dk = cose_key.private.ok_or("COSE_Key private key missing")?;
+                let dk = <&[u8; DK_LEN]>::try_from(dk).map_err(|_| "Decaps key wrong size")?;
+                if let Some(ek) = cose_key.public {
+                    ensure!(ek == &dk[384 * K..768 * K + 32], "COSE_Key public key mismatch");
+                }
+                DecapsKey::try_from_bytes_ref(dk)
+            }
+        }
+
+
+        // JWK support (with the `jwk` feature) using the AKP key type. As with COSE, the JOSE
+        // algorithm name is provided by the caller as ML-KEM registrations are not yet final.
+        #[cfg(feature = "jwk")]
+        impl EncapsKey {
+            /// Encodes the encapsulation key as a JSON Web Key of key type AKP (Algorithm Key
+            /// Pair), with the given JOSE algorithm name `alg` and the base64url-encoded key as
+            /// `pub`. Available with the `jwk` feature.
+            #[must_use]
+            pub fn to_jwk(&self, alg: &str) -> alloc::string::String {
+                crate::jwk::encode_key(alg, &self.0, None)
+            }
+
+            /// Decodes (and validates) an encapsulation key from a JSON Web Key of key type AKP,
+            /// which must carry the JOSE algorithm name `alg`. Any `priv` member is ignored.
+            /// Available with the `jwk` feature.
+            /// # Errors
+            /// Returns an error on a malformed JWK, a different `alg` or an invalid key.
+            /// # Examples
+            /// ```rust
+            /// # use std::error::Error;
+            /// # fn main() -> Result<(), Box<dyn Error>> {
+            /// # #[cfg(feature = "ml-kem-512")] {
+            /// use fips203::ml_kem_512;
+            /// use fips203::traits::KeyGen;
+            ///
+            /// let (ek, _dk) = ml_kem_512::KG::try_keygen()?;
+            /// let jwk = ek.to_jwk("ML-KEM-512");
+            /// assert!(jwk.starts_with(r#"{"kty":"AKP","alg":"ML-KEM-512","pub":""#));
+            /// assert_eq!(ek, ml_kem_512::EncapsKey::try_from_jwk(&jwk, "ML-KEM-512")?);
+            /// # }
+            /// # Ok(())}
+            /// ```
+            pub fn try_from_jwk(jwk: &str, alg: &str) -> Result<Self, &'static str> {
+                let jwk = crate::jwk::decode_key(jwk)?;
+                ensure!(jwk.alg.as_deref() == Some(alg), "JWK algorithm wrong");
+                let mut ek = [0u8; EK_LEN];
+                crate::jwk::decode_base64(jwk.public.ok_or("JWK public key missing")?, &mut ek)?;
+                EncapsKey::try_from_bytes(ek)
+            }
+        }
+
+        #[cfg(feature = "jwk")]
+        impl DecapsKey {
+            /// Encodes the decapsulation key as a JSON Web Key of key type AKP (Algorithm Key
+            /// Pair), with the given JOSE algorithm name `alg`, the embedded encapsulation key as
+            /// `pub` and the (expanded) decapsulation key as `priv`, both base64url-encoded.
+            /// Available with the `jwk` feature.
+            #[must_use]
+            pub fn to_jwk(&self, alg: &str) -> zeroize::Zeroizing<alloc::string::String> {
+                let ek = &self.0[384 * K..768 * K + 32];
+                zeroize::Zeroizing::new(crate::jwk::encode_key(alg, ek, Some(&self.0)))
+            }
+
+            /// Decodes (and validates) a decapsulation key from a JSON Web Key of key type AKP,
+            /// which must carry the JOSE algorithm name `alg`. When present, the `pub` member
+            /// must match the encapsulation key embedded in `priv`. Available with the `jwk`
+            /// feature.
+            /// # Errors
+            /// Returns an error on a malformed JWK, a different `alg` or an invalid key.
+            pub fn try_from_jwk(jwk: &str, alg: &str) -> Result<Self, &'static str> {
+                let jwk = crate::jwk::decode_key(jwk)?;
+                ensure!(jwk.alg.as_deref() == Some(alg), "JWK algorithm wrong");
+                let private = jwk.private.ok_or("JWK private key missing")?;
+                let mut dk = zeroize::Zeroizing::new([0u8; DK_LEN]);
+                crate::jwk::decode_base64(private, &mut dk)?;
+                if let Some(public) = jwk.public {
+                    let mut ek = [0u8; EK_LEN];
+                    crate::jwk::decode_base64(public, &mut ek)?;
+                    ensure!(ek == dk[384 * K..768 * K + 32], "JWK public key mismatch");
+                }
+                DecapsKey::try_from_bytes_ref(&dk)
+            }
+        }
+
+
+        /// The `AlgorithmIdentifier` of this parameter set, i.e., [`OID`] with absent parameters
+        /// per the IETF LAMPS ML-KEM draft. Available with the `spki` feature.
+        #[cfg(feature = "spki")]
+        pub const ALGORITHM_IDENTIFIER: spki::AlgorithmIdentifierRef<'static> =
+            spki::AlgorithmIdentifierRef { oid: OID, parameters: None };
+
+
+        /// Verifies that an `AlgorithmIdentifier` (e.g., from a certificate or CSR) identifies this
+        /// parameter set, i.e., it holds [`OID`] and the parameters are absent. Available with the
+        /// `spki` feature.
+        /// # Errors
+        /// Returns an error on a different algorithm or on present parameters.
+        /// # Examples
+        /// ```rust
+        /// # #[cfg(feature = "spki")] {
+        /// use fips203::{ml_kem_512, ml_kem_768};
+        ///
+        /// assert!(
+        ///     ml_kem_512::verify_algorithm_identifier(&ml_kem_512::ALGORITHM_IDENTIFIER).is_ok()
+        /// );
+        /// assert!(
+        ///     ml_kem_512::verify_algorithm_identifier(&ml_kem_768::ALGORITHM_IDENTIFIER).is_err()
+        /// );
+        /// # }
+        /// ```
+        #[cfg(feature = "spki")]
+        pub fn verify_algorithm_identifier(
+            algorithm: &spki::AlgorithmIdentifierRef<'_>,
+        ) -> Result<(), spki::Error> {
+            let _oid = algorithm.assert_algorithm_oid(OID)?;
+            if algorithm.parameters.is_some() {
+                return Err(spki::der::Tag::Sequence.value_error().into());
+            }
+            Ok(())
+        }
+
+
+        #[cfg(feature = "spki")]
+        impl spki::AssociatedAlgorithmIdentifier for EncapsKey {
+            type Params = spki::der::AnyRef<'static>;
+
+            const ALGORITHM_IDENTIFIER: spki::AlgorithmIdentifierRef<'static> =
+                ALGORITHM_IDENTIFIER;
+        }
+
+
+        #[cfg(feature = "spki")]
+        impl spki::AssociatedAlgorithmIdentifier for DecapsKey {
+            type Params = spki::der::AnyRef<'static>;
+
+            const ALGORITHM_IDENTIFIER: spki::AlgorithmIdentifierRef<'static> =
+                ALGORITHM_IDENTIFIER;
+        }
+
+
+        // SubjectPublicKeyInfo support per the IETF LAMPS ML-KEM draft; the algorithm parameters
+        // must be absent and the subjectPublicKey holds the encapsulation key bytes
+        #[cfg(feature = "spki")]
+        impl TryFrom<spki::SubjectPublicKeyInfoRef<'_>> for EncapsKey {
+            type Error = spki::Error;
+
+            fn try_from(spki: spki::SubjectPublicKeyInfoRef<'_>) -> Result<Self, Self::Error> {
+                verify_algorithm_identifier(&spki.algorithm)?;
+                let ek = spki.subject_public_key.as_bytes().ok_or(spki::Error::KeyMalformed)?;
+                let ek = <&[u8; EK_LEN]>::try_from(ek).map_err(|_| spki::Error::KeyMalformed)?;
+                EncapsKey::try_from_bytes(*ek).map_err(|_| spki::Error::KeyMalformed)
+            }
+        }
+
+
+        /// Encodes the encapsulation key as a `SubjectPublicKeyInfo` document.
+        #[cfg(feature = "spki")]
+        impl spki::EncodePublicKey for EncapsKey {
+            fn to_public_key_der(&self) -> spki::Result<spki::Document> {
+                let spki = spki::SubjectPublicKeyInfoRef {
+                    algorithm: ALGORITHM_IDENTIFIER,
+                    subject_public_key: spki::der::asn1::BitStringRef::from_bytes(&self.0)?,
+                };
+                spki::Document::try_from(spki)
+            }
+        }
+
+
+        // PKCS#8 support per the IETF LAMPS ML-KEM draft; the algorithm parameters must be absent
+        #[cfg(feature = "pkcs8")]
+        impl TryFrom<pkcs8::PrivateKeyInfo<'_>> for DecapsKey {
+            type Error = pkcs8::Error;
+
+            fn try_from(pki: pkcs8::PrivateKeyInfo<'_>) -> Result<Self, Self::Error> {
+                use crate::asn1::{decode_private_key, PrivateKeyChoice};
+                use subtle::ConstantTimeEq;
+                let _oid = pki.algorithm.assert_algorithm_oid(OID)?;
+                if pki.algorithm.parameters.is_some() {
+                    return Err(pkcs8::Error::ParametersMalformed);
+                }
+                // The seed is d ‖ z, from which the decaps key is (re)generated
+                #[cfg(feature = "keygen")]
+                let from_seed = |seed: &[u8; 64]| {
+                    let mut d = zeroize::Zeroizing::new([0u8; 32]);
+                    let mut z = zeroize::Zeroizing::new([0u8; 32]);
+                    d.copy_from_slice(&seed[0..32]);
+                    z.copy_from_slice(&seed[32..64]);
+                    Ok(KG::keygen_from_seed(*d, *z).1)
+                };
+                #[cfg(not(feature = "keygen"))]
+                let from_seed = |_seed: &[u8; 64]| Err(pkcs8::Error::KeyMalformed);
+                let from_expanded = |dk: &[u8]| {
+                    let dk =
+                        <&[u8; DK_LEN]>::try_from(dk).map_err(|_| pkcs8::Error::KeyMalformed)?;
+                    DecapsKey::try_from_bytes_ref(dk).map_err(|_| pkcs8::Error::KeyMalformed)
+                };
+                match decode_private_key(pki.private_key)? {
+                    PrivateKeyChoice::Seed(seed) => from_seed(seed),
+                    PrivateKeyChoice::ExpandedKey(expanded_key) => from_expanded(expanded_key),
+                    PrivateKeyChoice::Both { seed, expanded_key } => {
+                        let dk = from_seed(seed)?;
+                        if bool::from(dk.0.ct_eq(expanded_key)) {
+                            Ok(dk)
+                        } else {
+                            Err(pkcs8::Error::KeyMalformed)
+                        }
+                    }
+                }
+            }
+        }
+
+
+        /// Encodes the decapsulation key as a PKCS#8 `PrivateKeyInfo` document using the
+        /// `expandedKey` form of the IETF LAMPS ML-KEM draft (as the seed is not retained).
+        #[cfg(feature = "pkcs8")]
+        impl pkcs8::EncodePrivateKey for DecapsKey {
+            fn to_pkcs8_der(&self) -> pkcs8::Result<pkcs8::SecretDocument> {
+                use pkcs8::der::Encode;
+                let mut buf = [0u8; DK_LEN + 4]; // OCTET STRING tag and length + expandedKey
+                let expanded_key = pkcs8::der::asn1::OctetStringRef::new(&self.0)?;
+                let len = expanded_key.encode_to_slice(&mut buf)?.len();
+                let algorithm = ALGORITHM_IDENTIFIER;
+                let pki = pkcs8::PrivateKeyInfo::new(algorithm, &buf[..len]);
+                let doc = pkcs8::SecretDocument::try_from(pki);
+                zeroize::Zeroize::zeroize(&mut buf);
+                doc
+            }
+        }
+
+
+        /// **Not a secure API.** The underlying IND-CPA secure K-PKE scheme (Algorithms 13-15) is exposed
+        /// for research, protocol analysis and teaching only; it must not be used to protect data. Available
+        /// with the `research` feature.
+        #[cfg(feature = "research")]
+        pub mod k_pke {
+            use super::{CT_LEN, DU, DV, EK_LEN, ETA1, ETA2, K};
+
+            /// Serialized K-PKE Decryption Key Length (in bytes)
+            pub const DK_PKE_LEN: usize = 384 * K;
+
+            /// Algorithm 13 `K-PKE.KeyGen(d)` on page 29. **Not a secure API.** <br>
+            /// Uses randomness `d` to generate an encryption key `ek_PKE` and decryption key `dk_PKE`.
+            #[must_use]
+            pub fn k_pke_key_gen(d: [u8; 32]) -> ([u8; EK_LEN], [u8; DK_PKE_LEN]) {
+                let (mut ek_pke, mut dk_pke) = ([0u8; EK_LEN], [0u8; DK_PKE_LEN]);
+                crate::k_pke::k_pke_key_gen::<K, { ETA1 as usize * 64 }>(
+                    d,
+                    &mut ek_pke,
+                    &mut dk_pke,
+                );
+                (ek_pke, dk_pke)
+            }
+
+            /// Algorithm 14 `K-PKE.Encrypt(ek_PKE, m, r)` on page 30. **Not a secure API.** <br>
+            /// Uses the encryption key to encrypt the message `m` using the randomness `r`.
+            /// # Errors
+            /// Returns an error when the encryption key fails to decode.
+            pub fn k_pke_encrypt(
+                ek_pke: &[u8; EK_LEN], m: &[u8; 32], r: &[u8; 32],
+            ) -> Result<[u8; CT_LEN], &'static str> {
+                let mut ct = [0u8; CT_LEN];
+                crate::k_pke::k_pke_encrypt::<K, { ETA1 as usize * 64 }, { ETA2 as usize * 64 }>(
+                    DU, DV, ek_pke, m, r, &mut ct,
+                )?;
+                Ok(ct)
+            }
+
+            /// Algorithm 15 `K-PKE.Decrypt(dk_PKE, c)` on page 31. **Not a secure API.** <br>
+            /// Uses the decryption key to decrypt the ciphertext `c`.
+            /// # Errors
+            /// Returns an error when the decryption key fails to decode.
+            pub fn k_pke_decrypt(
+                dk_pke: &[u8; DK_PKE_LEN], ct: &[u8; CT_LEN],
+            ) -> Result<[u8; 32], &'static str> {
+                crate::k_pke::k_pke_decrypt::<K>(DU, DV, dk_pke, ct)
+            }
+        }
+
+
+        #[cfg(all(test, feature = "keygen", feature = "encaps", feature = "decaps"))]
+        mod tests {
+            use super::*;
+            use crate::types::EncapsKey;
+            use rand_chacha::rand_core::SeedableRng;
+
+            #[test]
+            fn smoke_test() {
+                let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(123);
+                let (ek, dk) = KG::keygen_from_seed([1u8; 32], [2u8; 32]);
+                let (ssk1, ct) = ek.encaps_from_seed(&[3u8; 32]);
+                let ssk2 = dk.try_decaps(&ct).unwrap();
+                assert_eq!(ssk1, ssk2);
+                for _i in 0..100 {
+                    let (ek, dk) = KG::try_keygen_with_rng(&mut rng).unwrap();
+                    let (ssk1, ct) = ek.try_encaps_with_rng(&mut rng).unwrap();
+                    let ssk2 = dk.try_decaps(&ct).unwrap();
+                    assert!(KG::validate_keypair_with_rng_vartime(
+                        &mut rng,
+                        &ek.clone().into_bytes(),
+                        &dk.clone().into_bytes()
+                    ));
+                    assert_eq!(ssk1, ssk2);
+                    assert_eq!(ek.as_bytes(), &ek.clone().into_bytes());
+                    assert_eq!(dk.as_bytes(), &dk.clone().into_bytes());
+                    assert_eq!(ct.as_bytes(), &ct.clone().into_bytes());
+                    assert_eq!(ssk1.as_bytes(), &ssk1.clone().into_bytes());
+                    assert_eq!(ek.clone().0, EncapsKey::try_from_bytes(ek.into_bytes()).unwrap().0);
+                    assert_eq!(dk.clone().0, DecapsKey::try_from_bytes(dk.into_bytes()).unwrap().0);
+                    assert_eq!(ct.0, CipherText::try_from_bytes_ref(ct.as_bytes()).unwrap().0);
+                }
+            }
+
+            #[test]
+            fn try_from_slices() {
+                extern crate std;
+                let (ek, dk) = KG::keygen_from_seed([8u8; 32], [9u8; 32]);
+                let (ssk, ct) = ek.encaps_from_seed(&[10u8; 32]);
+                let ek2 = super::EncapsKey::try_from(&ek.as_bytes()[..]).unwrap();
+                let dk2 = DecapsKey::try_from(&dk.as_bytes()[..]).unwrap();
+                let ct2 = CipherText::try_from(&ct.as_bytes()[..]).unwrap();
+                let ssk2 = SharedSecretKey::try_from(&ssk.as_bytes()[..]).unwrap();
+                assert_eq!(ek.0, ek2.0);
+                assert_eq!(dk.0, dk2.0);
+                assert_eq!(ct.0, ct2.0);
+                assert_eq!(ssk, ssk2);
+                let short = |expected: usize| crate::SliceError::Length { expected, actual: expected - 1 };
+                assert_eq!(super::EncapsKey::try_from(&ek.as_bytes()[1..]), Err(short(EK_LEN)));
+                assert_eq!(DecapsKey::try_from(&dk.as_bytes()[1..]), Err(short(DK_LEN)));
+                assert_eq!(CipherText::try_from(&ct.as_bytes()[1..]), Err(short(CT_LEN)));
+                assert_eq!(SharedSecretKey::try_from(&ssk.as_bytes()[1..]), Err(short(SSK_LEN)));
+                let invalid = super::EncapsKey::try_from(&[0xFFu8; EK_LEN][..]);
+                assert!(matches!(invalid, Err(crate::SliceError::Invalid(_))));
+                assert_eq!(
+                    std::format!("{}", short(EK_LEN)),
+                    std::format!("Slice length wrong: expected {EK_LEN} bytes, got {}", EK_LEN - 1)
+                );
+            }
+
+            #[test]
+            fn encaps_decaps_into() {
+                let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(456);
+                let (ek, dk) = KG::try_keygen_with_rng(&mut rng).unwrap();
+                let mut ct = [0u8; CT_LEN];
+                let (mut ssk1, mut ssk2) = ([0u8; SSK_LEN], [0u8; SSK_LEN]);
+                ek.try_encaps_into_with_rng(&mut rng, &mut ct, &mut ssk1).unwrap();
+                dk.try_decaps_into(&ct, &mut ssk2).unwrap();
+                assert_eq!(ssk1, ssk2);
+                let ssk3 = dk.try_decaps(&CipherText::try_from_bytes(ct).unwrap()).unwrap();
+                assert_eq!(&ssk1, ssk3.as_bytes());
+            }
+
+            #[test]
+            fn encaps_into_wipes_on_error() {
+                struct FailRng; // as an exhausted entropy source
+                impl rand_core::RngCore for FailRng {
+                    fn next_u32(&mut self) -> u32 { unimplemented!() }
+
+                    fn next_u64(&mut self) -> u64 { unimplemented!() }
+
+                    fn fill_bytes(&mut self, _out: &mut [u8]) { unimplemented!() }
+
+                    fn try_fill_bytes(&mut self, out: &mut [u8]) -> Result<(), rand_core::Error> {
+                        out.fill(0xAA); // partially written before failing
+                        Err(rand_core::Error::new("exhausted"))
+                    }
+                }
+                impl rand_core::CryptoRng for FailRng {}
+
+                let (ek, _dk) = KG::keygen_from_seed([13u8; 32], [14u8; 32]);
+                let (mut ct, mut ssk) = ([0x55u8; CT_LEN], [0x55u8; SSK_LEN]); // stale contents
+                assert!(ek.try_encaps_into_with_rng(&mut FailRng, &mut ct, &mut ssk).is_err());
+                assert_eq!(ct, [0u8; CT_LEN]);
+                assert_eq!(ssk, [0u8; SSK_LEN]);
+            }
+
+            #[cfg(feature = "acvp")]
+            #[test]
+            fn encaps_with_m() {
+                let (ek, dk) = KG::keygen_from_seed([11u8; 32], [12u8; 32]);
+                for i in 0..10u8 {
+                    let (ssk1, ct1) = ek.try_encaps_with_m(&[i; 32]).unwrap();
+                    let (ssk2, ct2) = ek.encaps_from_seed(&[i; 32]);
+                    assert_eq!((&ssk1, ct1.0), (&ssk2, ct2.0));
+                    assert_eq!(ssk1, dk.try_decaps(&ct1).unwrap());
+                }
+            }
+
+            #[cfg(feature = "research")]
+            #[test]
+            fn k_pke_round_trip() {
+                let (ek_pke, dk_pke) = k_pke::k_pke_key_gen([13u8; 32]);
+                for i in 0..10u8 {
+                    let ct = k_pke::k_pke_encrypt(&ek_pke, &[i; 32], &[14u8; 32]).unwrap();
+                    assert_eq!(k_pke::k_pke_decrypt(&dk_pke, &ct).unwrap(), [i; 32]);
+                }
+            }
+
+            #[test]
+            fn fingerprints() {
+                let (ek1, dk1) = KG::keygen_from_seed([15u8; 32], [16u8; 32]);
+                let (ek2, _dk2) = KG::keygen_from_seed([17u8; 32], [16u8; 32]);
+                assert_eq!(ek1.fingerprint(), h(ek1.as_bytes()));
+                assert_eq!(ek1.fingerprint(), dk1.fingerprint());
+                assert_eq!(ek1.fingerprint(), ek1.try_precompute().unwrap().fingerprint());
+                assert_eq!(dk1.fingerprint(), dk1.try_precompute().unwrap().fingerprint());
+                assert_ne!(ek1.fingerprint(), ek2.fingerprint());
+            }
+
+            #[test]
+            fn equality() {
+                let (ek1, dk1) = KG::keygen_from_seed([18u8; 32], [19u8; 32]);
+                let (ek2, dk2) = KG::keygen_from_seed([18u8; 32], [20u8; 32]);
+                assert_eq!(ek1, ek2); // z only affects the dk
+                assert_eq!(dk1, dk1.clone());
+                assert_ne!(dk1, dk2);
+                let (_ssk1, ct1) = ek1.encaps_from_seed(&[21u8; 32]);
+                let (_ssk2, ct2) = ek2.encaps_from_seed(&[22u8; 32]);
+                assert_eq!(ct1, ct1.clone());
+                assert_ne!(ct1, ct2);
+            }
+
+            #[test]
+            fn debug_redacted() {
+                extern crate std;
+                use std::format;
+                let (ek, dk) = KG::keygen_from_seed([23u8; 32], [24u8; 32]);
+                let (ssk, ct) = ek.encaps_from_seed(&[25u8; 32]);
+                let ek_dbg = format!("{ek:?}");
+                assert!(ek_dbg.contains(&format!("len: {EK_LEN}")));
+                assert!(ek_dbg.contains(&format!(
+                    "{:02x}{:02x}",
+                    ek.fingerprint()[0],
+                    ek.fingerprint()[1]
+                )));
+                assert!(format!("{ct:?}").contains(&format!("len: {CT_LEN}")));
+                assert_eq!(format!("{dk:?}"), "DecapsKey(<redacted>)");
+                assert_eq!(format!("{ssk:?}"), "SharedSecretKey(<redacted>)");
+                assert_eq!(
+                    format!("{:?}", dk.try_precompute().unwrap()),
+                    "DecapsKeyExpanded(<redacted>)"
+                );
+            }
+
+            #[test]
+            fn display_fingerprint() {
+                extern crate std;
+                use std::format;
+                let (ek, _dk) = KG::keygen_from_seed([23u8; 32], [24u8; 32]);
+                let (_ssk, ct) = ek.encaps_from_seed(&[25u8; 32]);
+                let ek_hex = format!("{ek:?}");
+                let ek_fp = format!("{ek}");
+                assert_eq!(ek_fp.len(), 16);
+                assert!(ek_hex.contains(&ek_fp));
+                assert_eq!(format!("{ek:x}"), ek_fp);
+                assert_eq!(format!("{ek:#x}"), format!("0x{ek_fp}"));
+                let ct_fp = format!("{ct}");
+                assert_eq!(ct_fp.len(), 16);
+                assert!(format!("{ct:?}").contains(&ct_fp));
+                assert_eq!(format!("{ct:#x}"), format!("0x{ct_fp}"));
+            }
+
+            #[test]
+            fn hash_set() {
+                extern crate std;
+                use std::collections::HashSet;
+                let (ek1, _dk1) = KG::keygen_from_seed([26u8; 32], [27u8; 32]);
+                let (ek2, _dk2) = KG::keygen_from_seed([28u8; 32], [27u8; 32]);
+                let (_ssk, ct) = ek1.encaps_from_seed(&[29u8; 32]);
+                let peers: HashSet<_> = [ek1.clone(), ek2, ek1.clone()].into_iter().collect();
+                assert_eq!(peers.len(), 2);
+                assert!(peers.contains(&ek1));
+                let cts: HashSet<_> = [ct.clone(), ct].into_iter().collect();
+                assert_eq!(cts.len(), 1);
+            }
+
+            #[cfg(all(feature = "rayon", feature = "default-rng"))]
+            #[test]
+            fn parallel() {
+                let keys = KG::try_keygen_par(9).unwrap();
+                let eks: Vec<_> = keys.iter().map(|(ek, _dk)| ek.clone()).collect();
+                let encapsulated = super::EncapsKey::try_encaps_par(&eks).unwrap();
+                assert_eq!(encapsulated.len(), 9);
+                let pairs: Vec<_> = keys
+                    .iter()
+                    .zip(&encapsulated)
+                    .map(|((_ek, dk), (_ssk, ct))| (dk, ct))
+                    .collect();
+                let ssks = super::DecapsKey::try_decaps_par(&pairs).unwrap();
+                for (ssk2, (ssk1, _ct)) in ssks.iter().zip(&encapsulated) {
+                    assert_eq!(ssk1, ssk2);
+                }
+            }
+
+            #[cfg(feature = "alloc")]
+            #[test]
+            fn batch() {
+                use alloc::vec::Vec;
+                let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(320);
+                let (ek, dk) = KG::try_keygen_with_rng(&mut rng).unwrap();
+                let (ssks, cts): (Vec<_>, Vec<_>) =
+                    (0..5).map(|_| ek.try_encaps_with_rng(&mut rng).unwrap()).unzip();
+                assert_eq!(dk.try_decaps_batch(&cts).unwrap(), ssks);
+                assert!(dk.try_decaps_batch(&[]).unwrap().is_empty());
+            }
+
+            #[cfg(feature = "alloc")]
+            #[test]
+            fn boxed() {
+                let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(321);
+                let (ek, dk) = KG::try_keygen_boxed_with_rng(&mut rng).unwrap();
+                let (ssk1, ct) = ek.try_encaps_boxed_with_rng(&mut rng).unwrap();
+                assert_eq!(ssk1, dk.try_decaps(&ct).unwrap());
+                let ek2 = BoxedEncapsKey::try_from(&ek.as_bytes()[..]).unwrap();
+                let dk2 = BoxedDecapsKey::try_from(&dk.as_bytes()[..]).unwrap();
+                let ct2 = BoxedCipherText::try_from(&ct.as_bytes()[..]).unwrap();
+                assert_eq!((&ek, &dk, &ct), (&ek2, &dk2, &ct2));
+                assert!(BoxedEncapsKey::try_from(&ct2.as_bytes()[..]).is_err());
+                let mut bad_dk = *dk2.as_bytes();
+                bad_dk[DK_LEN - 40] ^= 1; // h(ek) mismatch
+                assert!(BoxedDecapsKey::try_from(&bad_dk[..]).is_err());
+            }
+
+            #[cfg(all(feature = "std", unix))]
+            #[test]
+            fn locked() {
+                let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(987);
+                let (ek, dk) = KG::try_keygen_locked_with_rng(&mut rng).unwrap();
+                let (ssk1, ct) = ek.try_encaps_with_rng(&mut rng).unwrap();
+                assert_eq!(ssk1, *dk.try_decaps_locked(&ct).unwrap());
+                let dk2 = DecapsKey::try_from_bytes_locked(dk.as_bytes()).unwrap();
+                assert_eq!(ssk1, dk2.try_decaps(&ct).unwrap());
+                let mut bad_dk = *dk.as_bytes();
+                bad_dk[DK_LEN - 40] ^= 1; // h(ek) mismatch
+                assert!(DecapsKey::try_from_bytes_locked(&bad_dk).is_err());
+            }
+
+            #[test]
+            fn known_answers() {
+                assert!(self_test().is_ok());
+                assert!(self_test().is_ok()); // the retained outcome
+                assert!(crate::self_test().is_ok());
+                let failing = crate::self_test::SelfTest::new();
+                assert!(failing.run(|| false).is_err());
+                assert!(failing.run(|| true).is_err()); // the failure is retained
+            }
+
+            #[test]
+            fn resumable() {
+                let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(246);
+                let mut op = KeyGenOp::from_seed([35u8; 32], [36u8; 32]);
+                assert_eq!(op.steps_remaining(), KeyGenOp::STEPS);
+                assert!(op.poll(0).is_pending());
+                let (ek, dk) = loop {
+                    if let core::task::Poll::Ready(keys) = op.poll(3) {
+                        break keys.unwrap();
+                    }
+                };
+                assert!(matches!(op.poll(1), core::task::Poll::Ready(Err(_))));
+                let (ek2, dk2) = KG::keygen_from_seed([35u8; 32], [36u8; 32]);
+                assert_eq!((&ek, &dk), (&ek2, &dk2));
+                for i in 0..4u8 {
+                    let (ssk1, mut ct) = ek.try_encaps_with_rng(&mut rng).unwrap();
+                    ct.0[usize::from(i)] ^= u8::from(i % 2 == 1); // implicitly reject every other
+                    let mut op = DecapsOp::new(&dk, &ct);
+                    let mut polls = 0;
+                    let ssk2 = loop {
+                        polls += 1;
+                        if let core::task::Poll::Ready(ssk) = op.poll(usize::from(i) + 1) {
+                            break ssk.unwrap();
+                        }
+                    };
+                    assert_eq!(polls, (DecapsOp::STEPS + usize::from(i)) / (usize::from(i) + 1));
+                    assert_eq!(ssk2, dk.try_decaps(&ct).unwrap());
+                    assert_eq!(ssk1 == ssk2, i % 2 == 0);
+                }
+            }
+
+            #[test]
+            fn borrowed_refs() {
+                let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(654);
+                let (ek, dk) = KG::keygen_from_seed([30u8; 32], [31u8; 32]);
+                let (ek_bytes, dk_bytes) = (*ek.as_bytes(), *dk.as_bytes());
+                let ek_ref = EncapsKeyRef::try_from_bytes(&ek_bytes).unwrap();
+                let dk_ref = DecapsKeyRef::try_from(&dk_bytes[..]).unwrap();
+                assert_eq!(ek_ref.fingerprint(), ek.fingerprint());
+                assert_eq!(dk_ref.fingerprint(), dk.fingerprint());
+                let (ssk1, ct1) = ek_ref.encaps_from_seed(&[32u8; 32]);
+                let (ssk2, ct2) = ek.encaps_from_seed(&[32u8; 32]);
+                assert_eq!((&ssk1, &ct1), (&ssk2, &ct2));
+                assert_eq!(ssk1, dk_ref.try_decaps(&ct1).unwrap());
+                let (ssk3, ct3) = ek_ref.try_encaps_hedged_with_rng(&mut rng).unwrap();
+                assert_eq!(ssk3, dk_ref.try_decaps(&ct3).unwrap());
+                assert!(EncapsKeyRef::try_from(&dk_bytes[..]).is_err());
+                let mut bad_dk = dk_bytes;
+                bad_dk[DK_LEN - 40] ^= 1; // h(ek) mismatch
+                assert!(DecapsKeyRef::try_from_bytes(&bad_dk).is_err());
+            }
+
+            #[test]
+            fn unchecked() {
+                let (ek, dk) = KG::keygen_from_seed([33u8; 32], [34u8; 32]);
+                let ek2 = super::EncapsKey::from_bytes_unchecked(*ek.as_bytes());
+                let dk2 = DecapsKey::from_bytes_unchecked(*dk.as_bytes());
+                assert_eq!((&ek, &dk), (&ek2, &dk2));
+                let (ssk1, ct) = ek2.encaps_from_seed(&[35u8; 32]);
+                assert_eq!(ssk1, dk2.try_decaps(&ct).unwrap());
+            }
+
+            #[cfg(feature = "secrecy")]
+            #[test]
+            fn secrecy_wrappers() {
+                use crate::{ExposeSecret, Secret};
+                extern crate std;
+                use std::format;
+                let (ek, dk) = KG::keygen_from_seed([36u8; 32], [37u8; 32]);
+                let dk: Secret<DecapsKey> = dk.into();
+                let (ssk1, ct) = ek.encaps_from_seed(&[38u8; 32]);
+                let ssk2 = Secret::new(dk.clone().expose_secret().try_decaps(&ct).unwrap());
+                assert_eq!(&ssk1, ssk2.expose_secret());
+                assert_eq!(ssk1.clone().into_secret().expose_secret(), ssk1.as_bytes());
+                assert!(!format!("{dk:?}").contains("DecapsKey("));
+            }
+
+            #[cfg(feature = "pkcs8")]
+            #[test]
+            fn pkcs8_round_trip() {
+                extern crate std;
+                use pkcs8::der::asn1::OctetStringRef;
+                use pkcs8::der::Encode;
+                use pkcs8::{DecodePrivateKey, EncodePrivateKey};
+                use std::vec::Vec;
+                let (d, z) = ([39u8; 32], [40u8; 32]);
+                let (_ek, dk) = KG::keygen_from_seed(d, z);
+                let seed = [d, z].concat();
+                let algorithm = ALGORITHM_IDENTIFIER;
+                let decode = |private_key: &[u8]| {
+                    let pki = pkcs8::PrivateKeyInfo::new(algorithm, private_key);
+                    let doc = pkcs8::SecretDocument::try_from(pki).unwrap();
+                    DecapsKey::from_pkcs8_der(doc.as_bytes())
+                };
+                let both = |seed: &[u8], expanded_key: &[u8]| {
+                    let mut inner = OctetStringRef::new(seed).unwrap().to_der().unwrap();
+                    inner.extend(OctetStringRef::new(expanded_key).unwrap().to_der().unwrap());
+                    let len = u16::try_from(inner.len()).unwrap().to_be_bytes();
+                    [&[0x30, 0x82, len[0], len[1]][..], &inner].concat() // SEQUENCE
+                };
+                // expandedKey form
+                let doc = dk.to_pkcs8_der().unwrap();
+                assert_eq!(dk, DecapsKey::from_pkcs8_der(doc.as_bytes()).unwrap());
+                // seed form
+                let seed_der: Vec<u8> = [&[0x80u8, 64][..], &seed].concat();
+                assert_eq!(dk, decode(&seed_der).unwrap());
+                assert!(decode(&seed_der[..65]).is_err());
+                // both form, which must be consistent
+                assert_eq!(dk, decode(&both(&seed, dk.as_bytes())).unwrap());
+                let other = KG::keygen_from_seed(z, d).1;
+                assert!(decode(&both(&seed, other.as_bytes())).is_err());
+                // wrong algorithm
+                let mut bad = pkcs8::PrivateKeyInfo::new(algorithm, &seed_der);
+                bad.algorithm.oid = pkcs8::ObjectIdentifier::new_unwrap("1.2.3.4");
+                let doc = pkcs8::SecretDocument::try_from(bad).unwrap();
+                assert!(DecapsKey::from_pkcs8_der(doc.as_bytes()).is_err());
+            }
+
+            #[cfg(feature = "serde")]
+            #[test]
+            fn serde_round_trip() {
+                let (ek, dk) = KG::keygen_from_seed([43u8; 32], [44u8; 32]);
+                let (ssk, ct) = ek.encaps_from_seed(&[45u8; 32]);
+                assert_eq!(ssk, dk.try_decaps(&ct).unwrap());
+                let json = serde_json::to_string(&ek).unwrap();
+                assert_eq!(ek, serde_json::from_str::<super::EncapsKey>(&json).unwrap());
+                let json = serde_json::to_string(&ct).unwrap();
+                assert_eq!(ct, serde_json::from_str::<super::CipherText>(&json).unwrap());
+                // deserialization validates both the length and the content
+                let bad = serde_json::to_string(&[0xFFu8; EK_LEN].as_slice()).unwrap();
+                assert!(serde_json::from_str::<super::EncapsKey>(&bad).is_err());
+                assert!(serde_json::from_str::<super::CipherText>("[1,2,3]").is_err());
+                #[cfg(feature = "serde-secrets")]
+                {
+                    let json = serde_json::to_string(&dk).unwrap();
+                    assert_eq!(dk, serde_json::from_str::<super::DecapsKey>(&json).unwrap());
+                    let json = serde_json::to_string(&ssk).unwrap();
+                    assert_eq!(ssk, serde_json::from_str::<SharedSecretKey>(&json).unwrap());
+                }
+            }
+
+            #[cfg(feature = "cose")]
+            #[test]
+            fn cose_round_trip() {
+                const ALG: i64 = -65_000;
+                let (ek, dk) = KG::keygen_from_seed([46u8; 32], [47u8; 32]);
+                let cose_key = ek.to_cose_key(ALG);
+                assert_eq!(ek, super::EncapsKey::try_from_cose_key(&cose_key, ALG).unwrap());
+                assert!(super::EncapsKey::try_from_cose_key(&cose_key, ALG + 1).is_err());
+                assert!(super::EncapsKey::try_from_cose_key(&cose_key[1..], ALG).is_err());
+                let cose_key = dk.to_cose_key(ALG);
+                assert_eq!(dk, super::DecapsKey::try_from_cose_key(&cose_key, ALG).unwrap());
+                // the public key is also decodable from the private COSE_Key
+                assert_eq!(ek, super::EncapsKey::try_from_cose_key(&cose_key, ALG).unwrap());
+                // a mismatched public key is rejected
+                let mut bad = cose_key.clone();
+                let pub_start = cose_key.len() - DK_LEN - 4 - EK_LEN;
+                bad[pub_start] ^= 1;
+                assert!(super::DecapsKey::try_from_cose_key(&bad, ALG).is_err());
+                assert!(super::DecapsKey::try_from_cose_key(&ek.to_cose_key(ALG), ALG).is_err());
+            }
+
+            #[cfg(feature = "encoding")]
+            #[test]
+            fn text_encodings() {
+                let (ek, dk) = KG::keygen_from_seed([50u8; 32], [51u8; 32]);
+                let (_ssk, ct) = ek.encaps_from_seed(&[52u8; 32]);
+                let hex = ek.to_hex();
+                assert_eq!(hex.len(), 2 * EK_LEN);
+                assert_eq!(ek, super::EncapsKey::try_from_hex(&hex).unwrap());
+                assert_eq!(ek, super::EncapsKey::try_from_hex(&hex.to_uppercase()).unwrap());
+                assert_eq!(ek, hex.parse().unwrap());
+                assert!(super::EncapsKey::try_from_hex(&hex[2..]).is_err());
+                assert!(super::EncapsKey::try_from_hex(&hex.replacen('0', "g", 1)).is_err());
+                assert!(super::EncapsKey::try_from_hex(&"ff".repeat(EK_LEN)).is_err());
+                assert_eq!(ek, super::EncapsKey::try_from_base64(&ek.to_base64()).unwrap());
+                assert!(super::EncapsKey::try_from_base64(&ct.to_base64()).is_err());
+                assert_eq!(ct, ct.to_hex().parse().unwrap());
+                assert_eq!(ct, super::CipherText::try_from_base64(&ct.to_base64()).unwrap());
+                // secret material
+                assert_eq!(dk, DecapsKey::try_from_hex(&dk.to_hex()).unwrap());
+                assert_eq!(dk, DecapsKey::try_from_hex(&dk.to_hex().to_uppercase()).unwrap());
+                assert_eq!(dk, DecapsKey::try_from_base64(&dk.to_base64()).unwrap());
+                assert!(DecapsKey::try_from_hex(&dk.to_hex().replacen('0', "G", 1)).is_err());
+                let ssk = dk.try_decaps(&ct).unwrap();
+                assert_eq!(ssk, SharedSecretKey::try_from_hex(&ssk.to_hex()).unwrap());
+                assert_eq!(ssk, SharedSecretKey::try_from_base64(&ssk.to_base64()).unwrap());
+                assert!(SharedSecretKey::try_from_hex(&ssk.to_hex()[1..]).is_err());
+            }
+
+            #[cfg(feature = "rkyv")]
+            #[test]
+            fn rkyv_archive() {
+                use rkyv::ser::{serializers::BufferSerializer, Serializer};
+                let (ek, dk) = KG::keygen_from_seed([53u8; 32], [54u8; 32]);
+                let (ssk, ct) = ek.encaps_from_seed(&[55u8; 32]);
+                let mut serializer = BufferSerializer::new(rkyv::AlignedBytes([0u8; 4096]));
+                let ek_pos = serializer.serialize_value(&ek).unwrap();
+                let ct_pos = serializer.serialize_value(&ct).unwrap();
+                let archive = serializer.into_inner();
+                let ek_end = ek_pos + core::mem::size_of::<ArchivedEncapsKey>();
+                let archived_ek =
+                    rkyv::check_archived_root::<super::EncapsKey>(&archive[..ek_end]).unwrap();
+                let ct_end = ct_pos + core::mem::size_of::<ArchivedCipherText>();
+                let archived_ct =
+                    rkyv::check_archived_root::<super::CipherText>(&archive[ct_pos..ct_end])
+                        .unwrap();
+                assert_eq!(archived_ct.as_bytes(), ct.as_bytes());
+                let ek_ref = archived_ek.try_as_ref().unwrap();
+                assert_eq!(ek_ref.fingerprint(), archived_ek.as_ref_unchecked().fingerprint());
+                let (ssk2, ct2) = ek_ref
+                    .try_encaps_with_rng(&mut rand_chacha::ChaCha8Rng::seed_from_u64(1))
+                    .unwrap();
+                assert_eq!(ssk2, dk.try_decaps(&ct2).unwrap());
+                assert_eq!(ssk, dk.try_decaps(&ct).unwrap());
+            }
+
+            #[cfg(feature = "seal")]
+            #[test]
+            fn seal_unseal() {
+                let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(456);
+                let (_ek, dk) = KG::keygen_from_seed([56u8; 32], [57u8; 32]);
+                let sealed = dk.try_seal_with_rng(&mut rng, b"passphrase").unwrap();
+                assert_eq!(sealed[1], PARAM_ID);
+                assert_eq!(dk, DecapsKey::try_unseal(&sealed, b"passphrase").unwrap());
+                assert!(DecapsKey::try_unseal(&sealed, b"Passphrase").is_err());
+                assert!(DecapsKey::try_unseal(&sealed[1..], b"passphrase").is_err());
+                // the header is authenticated and the body is integrity protected
+                for i in [0, 1, 2, 20, sealed.len() - 1] {
+                    let mut bad = sealed;
+                    bad[i] ^= 1;
+                    assert!(DecapsKey::try_unseal(&bad, b"passphrase").is_err());
+                }
+            }
+
+            #[cfg(feature = "jwk")]
+            #[test]
+            fn jwk_round_trip() {
+                extern crate std;
+                use std::format;
+                const ALG: &str = "ML-KEM";
+                let (ek, dk) = KG::keygen_from_seed([48u8; 32], [49u8; 32]);
+                let jwk = ek.to_jwk(ALG);
+                assert_eq!(ek, super::EncapsKey::try_from_jwk(&jwk, ALG).unwrap());
+                assert!(super::EncapsKey::try_from_jwk(&jwk, "ML-KEM-X").is_err());
+                assert!(super::EncapsKey::try_from_jwk(&jwk[1..], ALG).is_err());
+                // unknown members are skipped, repeated ones are rejected
+                let extended = format!("{{\"kid\":\"1\",\"key_ops\":[\"decrypt\"],{}", &jwk[1..]);
+                assert_eq!(ek, super::EncapsKey::try_from_jwk(&extended, ALG).unwrap());
+                let repeated = format!("{{\"alg\":\"{ALG}\",{}", &jwk[1..]);
+                assert!(super::EncapsKey::try_from_jwk(&repeated, ALG).is_err());
+                // the (escaped) algorithm name survives a round trip
+                let odd_alg = "a\"b\\c\u{1}";
+                assert_eq!(
+                    ek,
+                    super::EncapsKey::try_from_jwk(&ek.to_jwk(odd_alg), odd_alg).unwrap()
+                );
+                let jwk = dk.to_jwk(ALG);
+                assert_eq!(dk, super::DecapsKey::try_from_jwk(&jwk, ALG).unwrap());
+                assert_eq!(ek, super::EncapsKey::try_from_jwk(&jwk, ALG).unwrap());
+                let other = KG::keygen_from_seed([49u8; 32], [48u8; 32]).0.to_jwk(ALG);
+                let mismatched = format!(
+                    "{}{}",
+                    &other[..other.len() - 1],
+                    &jwk[jwk.find(",\"priv").unwrap()..]
+                );
+                assert!(super::DecapsKey::try_from_jwk(&mismatched, ALG).is_err());
+                assert!(super::DecapsKey::try_from_jwk(&ek.to_jwk(ALG), ALG).is_err());
+            }
+
+            #[cfg(feature = "spki")]
+            #[test]
+            fn spki_round_trip() {
+                use spki::{DecodePublicKey, EncodePublicKey};
+                let (ek, _dk) = KG::keygen_from_seed([41u8; 32], [42u8; 32]);
+                let doc = ek.to_public_key_der().unwrap();
+                assert_eq!(ek, super::EncapsKey::from_public_key_der(doc.as_bytes()).unwrap());
+                // wrong algorithm
+                let mut bad = spki::SubjectPublicKeyInfoRef::try_from(doc.as_bytes()).unwrap();
+                bad.algorithm.oid = spki::ObjectIdentifier::new_unwrap("1.2.3.4");
+                let doc = spki::Document::try_from(bad).unwrap();
+                assert!(super::EncapsKey::from_public_key_der(doc.as_bytes()).is_err());
+            }
+
+            #[cfg(feature = "spki")]
+            #[test]
+            fn algorithm_identifier() {
+                use spki::AssociatedAlgorithmIdentifier;
+                assert!(verify_algorithm_identifier(&ALGORITHM_IDENTIFIER).is_ok());
+                assert_eq!(
+                    <DecapsKey as AssociatedAlgorithmIdentifier>::ALGORITHM_IDENTIFIER.oid,
+                    OID
+                );
+                let mut with_params = ALGORITHM_IDENTIFIER;
+                with_params.parameters = Some(spki::der::asn1::AnyRef::NULL);
+                assert!(verify_algorithm_identifier(&with_params).is_err());
+                let mut other = ALGORITHM_IDENTIFIER;
+                other.oid = spki::ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.4.4");
+                assert!(verify_algorithm_identifier(&other).is_err());
+            }
+
+            #[test]
+            fn hedged_encaps() {
+                let (ek, dk) = KG::keygen_from_seed([9u8; 32], [10u8; 32]);
+                let ek_expanded = ek.try_precompute().unwrap();
+                // A seed unique to this test (and parameter set), with the raw draw in between the
+                // replayed hedged draws, so that no draw repeats the one before it (as would fail the
+                // `rng-health-checks`)
+                let seed = 7890 + K as u64;
+                let mut rng1 = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+                let mut rng2 = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+                let mut rng3 = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+                let (ssk1, ct1) = ek.try_encaps_hedged_with_rng(&mut rng1).unwrap();
+                let (ssk3, ct3) = ek.try_encaps_with_rng(&mut rng3).unwrap();
+                let (ssk2, ct2) = ek_expanded.try_encaps_hedged_with_rng(&mut rng2).unwrap();
+                assert_eq!((&ssk1, ct1.0), (&ssk2, ct2.0));
+                assert_ne!(ct1.0, ct3.0); // The hedged message differs from the raw rng output
+                assert_ne!(ssk1, ssk3);
+                assert_eq!(ssk1, dk.try_decaps(&ct1).unwrap());
+            }
+
+            #[test]
+            fn labeled_kem() {
+                let (ek, dk) = KG::keygen_from_seed([6u8; 32], [7u8; 32]);
+                let (ek_a, dk_a) = (LabeledKem::new(ek, b"a"), LabeledKem::new(dk.clone(), b"a"));
+                let dk_b = LabeledKem::new(dk.clone(), b"b");
+                let (ssk1, ct) = ek_a.encaps_from_seed(&[8u8; 32]);
+                assert_eq!(ssk1, dk_a.try_decaps(&ct).unwrap());
+                assert_ne!(ssk1, dk_b.try_decaps(&ct).unwrap());
+                assert_ne!(ssk1, dk.try_decaps(&ct).unwrap());
+                let (ssk2, _) = ek_a.into_inner().encaps_from_seed(&[8u8; 32]);
+                assert_ne!(ssk1, ssk2);
+            }
+
+            #[test]
+            fn expanded_encaps() {
+                let (ek, dk) = KG::keygen_from_seed([4u8; 32], [5u8; 32]);
+                let ek_expanded = ek.try_precompute().unwrap();
+                for i in 0..10u8 {
+                    let (ssk1, ct1) = ek.encaps_from_seed(&[i; 32]);
+                    let (ssk2, ct2) = ek_expanded.encaps_from_seed(&[i; 32]);
+                    assert_eq!(ct1.0, ct2.0);
+                    assert_eq!(ssk1, ssk2);
+                    assert_eq!(ssk2, dk.try_decaps(&ct2).unwrap());
+                }
+            }
+
+            #[test]
+            fn expanded_decaps() {
+                let (ek, dk) = KG::keygen_from_seed([6u8; 32], [7u8; 32]);
+                let dk_expanded = dk.try_precompute().unwrap();
+                for i in 0..10u8 {
+                    let (ssk1, ct) = ek.encaps_from_seed(&[i; 32]);
+                    assert_eq!(ssk1, dk_expanded.try_decaps(&ct).unwrap());
+                    // Implicit rejection must also match the non-expanded decaps key
+                    let mut bad_ct = ct.clone();
+                    bad_ct.0[0] ^= 0x01;
+                    let ssk2 = dk_expanded.try_decaps(&bad_ct).unwrap();
+                    assert_eq!(ssk2, dk.try_decaps(&bad_ct).unwrap());
+                    assert!(ssk1 != ssk2);
+                }
+            }
+
+            #[test]
+            fn bounded_decaps() {
+                use crate::{BoundedDecapsKey, DECAPS_KEY_EXPIRED, DECAPS_LIMIT_REACHED};
+                use core::sync::atomic::{AtomicU64, Ordering};
+                static NOW: AtomicU64 = AtomicU64::new(100);
+                let (ek, dk) = KG::keygen_from_seed([10u8; 32], [11u8; 32]);
+                let (ssk, ct) = ek.encaps_from_seed(&[12u8; 32]);
+                let bounded = BoundedDecapsKey::new(dk.clone(), 3);
+                for remaining in (0..3).rev() {
+                    assert_eq!(bounded.try_decaps(&ct), Ok(ssk.clone()));
+                    assert_eq!(bounded.remaining(), remaining);
+                }
+                assert_eq!(bounded.try_decaps(&ct), Err(DECAPS_LIMIT_REACHED));
+                let bounded = BoundedDecapsKey::new(dk, u64::MAX)
+                    .with_deadline(101, || NOW.load(Ordering::Relaxed));
+                assert_eq!(bounded.try_decaps(&ct), Ok(ssk));
+                NOW.store(101, Ordering::Relaxed);
+                assert_eq!(bounded.try_decaps(&ct), Err(DECAPS_KEY_EXPIRED));
+                assert_eq!(bounded.remaining(), u64::MAX - 1);
+            }
+
+            #[test]
+            #[cfg(feature = "ml-kem-interop")]
+            fn ml_kem_interop() {
+                use ::ml_kem::kem::{Decapsulate, DecapsulationKey, Encapsulate, EncapsulationKey};
+                let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(4800);
+                for _i in 0..10 {
+                    let (ek, dk) = KG::try_keygen_with_rng(&mut rng).unwrap();
+                    let other_ek = EncapsulationKey::<MlKemParams>::from(ek.clone());
+                    let other_dk = DecapsulationKey::<MlKemParams>::from(dk.clone());
+                    // Encapsulation by either crate decapsulates to the same shared secret in the other
+                    let (other_ct, other_ssk) = other_ek.encapsulate(&mut rng).unwrap();
+                    let ct = CipherText::try_from(other_ct).unwrap();
+                    assert_eq!(dk.try_decaps(&ct).unwrap(), SharedSecretKey::from(other_ssk));
+                    let (ssk, ct) = ek.try_encaps_with_rng(&mut rng).unwrap();
+                    let other_ssk = other_dk.decapsulate(&ct.clone().into()).unwrap();
+                    assert_eq!(ssk, SharedSecretKey::from(other_ssk));
+                    // Keys, ciphertexts and shared secrets round trip through the other crate
+                    let ek2 = EncapsKey::try_from(other_ek).unwrap();
+                    assert_eq!(ek2.into_bytes(), ek.into_bytes());
+                    let dk2 = DecapsKey::try_from(other_dk).unwrap();
+                    assert_eq!(dk2.into_bytes(), dk.into_bytes());
+                    let other_ct: ::ml_kem::Ciphertext<::ml_kem::kem::Kem<MlKemParams>> =
+                        ct.clone().into();
+                    let ct2 = CipherText::try_from(other_ct).unwrap();
+                    assert_eq!(ct2.into_bytes(), ct.into_bytes());
+                    let ssk2 = SharedSecretKey::from(::ml_kem::SharedKey::<::ml_kem::MlKem512>::from(
+                        ssk.clone(),
+                    ));
+                    assert_eq!(ssk2, ssk);
+                }
+            }
+
+            #[test]
+            #[cfg(feature = "blinding")]
+            fn blinded_decaps() {
+                let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(246);
+                let (ek, dk) = KG::keygen_from_seed([8u8; 32], [9u8; 32]);
+                for i in 0..10u8 {
+                    let (ssk1, mut ct) = ek.encaps_from_seed(&[i; 32]);
+                    assert_eq!(ssk1, dk.try_decaps_blinded_with_rng(&mut rng, &ct).unwrap());
+                    ct.0[usize::from(i)] ^= 0x01; // implicit rejection must also match
+                    let ssk2 = dk.try_decaps_blinded_with_rng(&mut rng, &ct).unwrap();
+                    assert_eq!(ssk2, dk.try_decaps(&ct).unwrap());
+                }
+            }
+        }
+    };
+}
+
+
+#[cfg(feature = "ml-kem-512")]
+pub mod ml_kem_512 {
+    //! Functionality for the ML-KEM-512 security parameter set, which is claimed to be in security category 1, see
+    //! table 2 & 3 on page 39 of spec.
+    //!
     //! See <https://nvlpubs.nist.gov/nistpubs/FIPS/NIST.FIPS.203.pdf>
     //!
     //! Typical usage flow entails:
@@ -340,8 +3572,11 @@ pub mod ml_kem_512 {
 
     const K: usize = 2;
     const ETA1: u32 = 3;
+    #[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
     const ETA2: u32 = 2;
+    #[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
     const DU: u32 = 10;
+    #[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
     const DV: u32 = 4;
 
     /// Serialized Encapsulation Key Length (in bytes)
@@ -351,6 +3586,49 @@ pub mod ml_kem_512 {
     /// Serialized Ciphertext Key Length (in bytes)
     pub const CT_LEN: usize = 768;
 
+    /// Upper bound on the stack used by key generation in optimized builds (in bytes); see the
+    /// crate-level notes on stack usage.
+    pub const MAX_STACK_KEYGEN: usize = 32 * 1024;
+    /// Upper bound on the stack used by encapsulation in optimized builds (in bytes); see the
+    /// crate-level notes on stack usage.
+    pub const MAX_STACK_ENCAP: usize = 36 * 1024;
+    /// Upper bound on the stack used by decapsulation in optimized builds (in bytes); see the
+    /// crate-level notes on stack usage.
+    pub const MAX_STACK_DECAP: usize = 36 * 1024;
+
+    /// Object identifier `id-alg-ml-kem-512` of this parameter set per NIST CSOR. Available with
+    /// the `spki` feature.
+    #[cfg(feature = "spki")]
+    pub const OID: spki::ObjectIdentifier =
+        spki::ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.4.1");
+
+    // The matching parameter set of the RustCrypto `ml-kem` crate, for the `ml-kem-interop` feature
+    #[cfg(feature = "ml-kem-interop")]
+    type MlKemParams = ::ml_kem::MlKem512Params;
+
+    /// Parameter set identifier, i.e., the leading byte of the tagged serialization
+    pub const PARAM_ID: u8 = 1;
+
+    // Expected results of the known-answer self-tests, see `self_test()`
+    #[cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+    const KAT: crate::self_test::Kat = crate::self_test::Kat {
+        h_ek: crate::self_test::hex32(
+            "57c0d859d49cfd70149736fe36714ab58f264815fd7cdc23610ecd940f62a820",
+        ),
+        h_dk: crate::self_test::hex32(
+            "78bfbafecadbe7c87830f43b0a7f81b6997c7a21c10463e3d4e462a214d0bf30",
+        ),
+        h_ct: crate::self_test::hex32(
+            "096a94a634a024cbcd87f4034617c32298dfdf8fd1f92e00ae64393d5a223cef",
+        ),
+        ssk: crate::self_test::hex32(
+            "cf286edb4905dae30df31667454dc6024b6332361219ef1a444df2b149c9cf7b",
+        ),
+        ssk_rejected: crate::self_test::hex32(
+            "0e46123e9defc5d366b783a2df56365095b8b9693cb707d1668181c7e673f1e0",
+        ),
+    };
+
     functionality!();
 }
 
@@ -376,8 +3654,11 @@ pub mod ml_kem_768 {
 
     const K: usize = 3;
     const ETA1: u32 = 2;
+    #[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
     const ETA2: u32 = 2;
+    #[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
     const DU: u32 = 10;
+    #[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
     const DV: u32 = 4;
 
     /// Serialized Encapsulation Key Length (in bytes)
@@ -387,6 +3668,49 @@ pub mod ml_kem_768 {
     /// Serialized Ciphertext Key Length (in bytes)
     pub const CT_LEN: usize = 1088;
 
+    /// Upper bound on the stack used by key generation in optimized builds (in bytes); see the
+    /// crate-level notes on stack usage.
+    pub const MAX_STACK_KEYGEN: usize = 44 * 1024;
+    /// Upper bound on the stack used by encapsulation in optimized builds (in bytes); see the
+    /// crate-level notes on stack usage.
+    pub const MAX_STACK_ENCAP: usize = 44 * 1024;
+    /// Upper bound on the stack used by decapsulation in optimized builds (in bytes); see the
+    /// crate-level notes on stack usage.
+    pub const MAX_STACK_DECAP: usize = 36 * 1024;
+
+    /// Object identifier `id-alg-ml-kem-768` of this parameter set per NIST CSOR. Available with
+    /// the `spki` feature.
+    #[cfg(feature = "spki")]
+    pub const OID: spki::ObjectIdentifier =
+        spki::ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.4.2");
+
+    // The matching parameter set of the RustCrypto `ml-kem` crate, for the `ml-kem-interop` feature
+    #[cfg(feature = "ml-kem-interop")]
+    type MlKemParams = ::ml_kem::MlKem768Params;
+
+    /// Parameter set identifier, i.e., the leading byte of the tagged serialization
+    pub const PARAM_ID: u8 = 2;
+
+    // Expected results of the known-answer self-tests, see `self_test()`
+    #[cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+    const KAT: crate::self_test::Kat = crate::self_test::Kat {
+        h_ek: crate::self_test::hex32(
+            "f1347d50af257fa3e577ed74dfa38736702fd6e2fee25db52ec64f471bd360e7",
+        ),
+        h_dk: crate::self_test::hex32(
+            "0d64dbe2dc8cfaeac2cd5bc709e00aa00630acf658d1467766e00fa8a0d48ed4",
+        ),
+        h_ct: crate::self_test::hex32(
+            "8de9c83dcfa6f9d0fd668972a395580e5ff3ada2a855edc9937bd970ab25008e",
+        ),
+        ssk: crate::self_test::hex32(
+            "dea5fdd2340a17c7507d1fe5c0609bcba4190e08007d5f7f98c8fecab10bc8fa",
+        ),
+        ssk_rejected: crate::self_test::hex32(
+            "7b44396de30419db086c8d4a00a05e05e23503bd590fc069ac7e79ac5930fdd9",
+        ),
+    };
+
     functionality!();
 }
 
@@ -411,8 +3735,11 @@ pub mod ml_kem_1024 {
 
     const K: usize = 4;
     const ETA1: u32 = 2;
+    #[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
     const ETA2: u32 = 2;
+    #[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
     const DU: u32 = 11;
+    #[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
     const DV: u32 = 5;
 
     /// Serialized Encapsulation Key Length (in bytes)
@@ -422,5 +3749,177 @@ pub mod ml_kem_1024 {
     /// Serialized Ciphertext Key Length (in bytes)
     pub const CT_LEN: usize = 1568;
 
+    /// Upper bound on the stack used by key generation in optimized builds (in bytes); see the
+    /// crate-level notes on stack usage.
+    pub const MAX_STACK_KEYGEN: usize = 64 * 1024;
+    /// Upper bound on the stack used by encapsulation in optimized builds (in bytes); see the
+    /// crate-level notes on stack usage.
+    pub const MAX_STACK_ENCAP: usize = 62 * 1024;
+    /// Upper bound on the stack used by decapsulation in optimized builds (in bytes); see the
+    /// crate-level notes on stack usage.
+    pub const MAX_STACK_DECAP: usize = 48 * 1024;
+
+    /// Object identifier `id-alg-ml-kem-1024` of this parameter set per NIST CSOR. Available with
+    /// the `spki` feature.
+    #[cfg(feature = "spki")]
+    pub const OID: spki::ObjectIdentifier =
+        spki::ObjectIdentifier::new_unwrap("2.16.840.1.101.3.4.4.3");
+
+    // The matching parameter set of the RustCrypto `ml-kem` crate, for the `ml-kem-interop` feature
+    #[cfg(feature = "ml-kem-interop")]
+    type MlKemParams = ::ml_kem::MlKem1024Params;
+
+    /// Parameter set identifier, i.e., the leading byte of the tagged serialization
+    pub const PARAM_ID: u8 = 3;
+
+    // Expected results of the known-answer self-tests, see `self_test()`
+    #[cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+    const KAT: crate::self_test::Kat = crate::self_test::Kat {
+        h_ek: crate::self_test::hex32(
+            "723a17d314c8fbae88ba58b4046aacfb38196d8a79d4b02296211beb150a97b8",
+        ),
+        h_dk: crate::self_test::hex32(
+            "464edc4996bdb25f643246057992cbefc305746c87beec351c4a6716ecb00ea9",
+        ),
+        h_ct: crate::self_test::hex32(
+            "fbd7e1f9307242aaec462d987580b3f90d0265fd2d93536efe69d4c3f8e4a0ff",
+        ),
+        ssk: crate::self_test::hex32(
+            "d44bd532fca43fa5943a8be247b35b53e5928ed0cc4105ef4846dd1507c8dd80",
+        ),
+        ssk_rejected: crate::self_test::hex32(
+            "d9027c372cec1d705fa107fd2608b519bf832252bf6fba777a013a61e3cceeae",
+        ),
+    };
+
     functionality!();
 }
+
+
+/// The recommended parameter set for general use, i.e., ML-KEM-768 (security category 3), per
+/// section 8 of FIPS 203 which recommends it as the default.
+#[cfg(feature = "ml-kem-768")]
+pub use ml_kem_768 as recommended;
+
+
+/// Runs the known-answer self-tests of every enabled security parameter set, e.g., at module
+/// power-up per the FIPS 140-3 cryptographic algorithm self-test (CAST) requirements. The tests of
+/// each parameter set run on its first call only (here or via its own `self_test()`), with the
+/// outcome retained for subsequent calls.
+/// # Errors
+/// Returns an error when a known-answer test of any enabled parameter set fails.
+/// # Examples
+/// ```rust
+/// fips203::self_test().expect("ML-KEM self-test failed");
+/// ```
+#[cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+pub fn self_test() -> Result<(), &'static str> {
+    #[cfg(feature = "ml-kem-512")]
+    ml_kem_512::self_test()?;
+    #[cfg(feature = "ml-kem-768")]
+    ml_kem_768::self_test()?;
+    #[cfg(feature = "ml-kem-1024")]
+    ml_kem_1024::self_test()?;
+    Ok(())
+}
+
+
+/// An encapsulation key of any (enabled) parameter set, as returned by [`decode_encaps_key()`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[allow(clippy::large_enum_variant)] // the keys are not heap-allocated elsewhere either (no_std)
+pub enum AnyEncapsKey {
+    /// An ML-KEM-512 encapsulation key
+    #[cfg(feature = "ml-kem-512")]
+    MlKem512(ml_kem_512::EncapsKey),
+    /// An ML-KEM-768 encapsulation key
+    #[cfg(feature = "ml-kem-768")]
+    MlKem768(ml_kem_768::EncapsKey),
+    /// An ML-KEM-1024 encapsulation key
+    #[cfg(feature = "ml-kem-1024")]
+    MlKem1024(ml_kem_1024::EncapsKey),
+}
+
+
+impl AnyEncapsKey {
+    /// Returns the parameter set identifier of this encapsulation key, e.g., `ml_kem_512::PARAM_ID`.
+    #[must_use]
+    pub fn param_id(&self) -> u8 {
+        match self {
+            #[cfg(feature = "ml-kem-512")]
+            AnyEncapsKey::MlKem512(_) => ml_kem_512::PARAM_ID,
+            #[cfg(feature = "ml-kem-768")]
+            AnyEncapsKey::MlKem768(_) => ml_kem_768::PARAM_ID,
+            #[cfg(feature = "ml-kem-1024")]
+            AnyEncapsKey::MlKem1024(_) => ml_kem_1024::PARAM_ID,
+        }
+    }
+
+    /// Returns the (untagged) encapsulation key bytes.
+    #[must_use]
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            #[cfg(feature = "ml-kem-512")]
+            AnyEncapsKey::MlKem512(ek) => ek.as_bytes(),
+            #[cfg(feature = "ml-kem-768")]
+            AnyEncapsKey::MlKem768(ek) => ek.as_bytes(),
+            #[cfg(feature = "ml-kem-1024")]
+            AnyEncapsKey::MlKem1024(ek) => ek.as_bytes(),
+        }
+    }
+}
+
+
+/// Deserializes (and validates) an encapsulation key of any enabled parameter set. Keys in the
+/// tagged format of `EncapsKey::to_tagged_bytes()` are identified by their leading parameter set
+/// identifier, while raw keys (as from `into_bytes()`) are identified by their length. The tagged
+/// and raw lengths of all parameter sets are distinct, so the two formats cannot be confused.
+/// # Errors
+/// Returns an error on an unknown length or parameter set, or an invalid key.
+/// # Examples
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # #[cfg(all(feature = "ml-kem-512", feature = "ml-kem-768"))] {
+/// use fips203::traits::{KeyGen, SerDes};
+/// use fips203::{decode_encaps_key, ml_kem_512, ml_kem_768, AnyEncapsKey};
+///
+/// let (ek512, _dk512) = ml_kem_512::KG::try_keygen()?;
+/// let (ek768, _dk768) = ml_kem_768::KG::try_keygen()?;
+///
+/// // Tagged keys are identified by their parameter set identifier, raw keys by their length
+/// let any = decode_encaps_key(&ek512.to_tagged_bytes())?;
+/// assert_eq!(any, AnyEncapsKey::MlKem512(ek512));
+/// let any = decode_encaps_key(ek768.as_bytes())?;
+/// assert_eq!(any.param_id(), ml_kem_768::PARAM_ID);
+/// # }
+/// # Ok(())}
+/// ```
+pub fn decode_encaps_key(bytes: &[u8]) -> Result<AnyEncapsKey, &'static str> {
+    match (bytes.first(), bytes.len()) {
+        #[cfg(feature = "ml-kem-512")]
+        (Some(&ml_kem_512::PARAM_ID), len) if len == ml_kem_512::EK_LEN + 1 => {
+            ml_kem_512::EncapsKey::try_from_tagged_bytes(bytes).map(AnyEncapsKey::MlKem512)
+        }
+        #[cfg(feature = "ml-kem-768")]
+        (Some(&ml_kem_768::PARAM_ID), len) if len == ml_kem_768::EK_LEN + 1 => {
+            ml_kem_768::EncapsKey::try_from_tagged_bytes(bytes).map(AnyEncapsKey::MlKem768)
+        }
+        #[cfg(feature = "ml-kem-1024")]
+        (Some(&ml_kem_1024::PARAM_ID), len) if len == ml_kem_1024::EK_LEN + 1 => {
+            ml_kem_1024::EncapsKey::try_from_tagged_bytes(bytes).map(AnyEncapsKey::MlKem1024)
+        }
+        #[cfg(feature = "ml-kem-512")]
+        (_, ml_kem_512::EK_LEN) => {
+            ml_kem_512::EncapsKey::try_from(bytes).map(AnyEncapsKey::MlKem512).map_err(Into::into)
+        }
+        #[cfg(feature = "ml-kem-768")]
+        (_, ml_kem_768::EK_LEN) => {
+            ml_kem_768::EncapsKey::try_from(bytes).map(AnyEncapsKey::MlKem768).map_err(Into::into)
+        }
+        #[cfg(feature = "ml-kem-1024")]
+        (_, ml_kem_1024::EK_LEN) => {
+            ml_kem_1024::EncapsKey::try_from(bytes).map(AnyEncapsKey::MlKem1024).map_err(Into::into)
+        }
+        _ => Err("Encaps key length or parameter set unknown"),
+    }
+}
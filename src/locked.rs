@@ -0,0 +1,138 @@
+// Page-locked storage for long-lived secrets on Unix hosts, available with the `std` feature.
+// Each value is placed in its own anonymous mapping, which is locked into RAM via `mlock()` so
+// that it never reaches swap, and (on Linux/Android) marked `MADV_DONTDUMP` so that it is left
+// out of core dumps. Beyond the SIMD and assembly backends, this is one of two modules that use
+// unsafe code (alongside keccak.rs, with `custom-keccak`), which here is limited to the mapping
+// calls and the accesses through the mapping's pointer.
+#![allow(unsafe_code)]
+
+use core::mem::size_of;
+use core::ops::{Deref, DerefMut};
+use core::ptr::NonNull;
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+
+/// A secret value (e.g., a `DecapsKey` or `SharedSecretKey`) held in its own memory pages, which
+/// are locked into RAM so they are never swapped out and, on Linux, excluded from core dumps. The
+/// value is zeroized, and its pages unlocked and unmapped, on drop. The value is otherwise used as
+/// normal via `Deref`. Available with the `std` feature on Unix hosts.
+///
+/// Note that locking may fail for lack of privilege or against `RLIMIT_MEMLOCK` (see `ulimit -l`),
+/// and that each value occupies (at least) one whole page.
+/// # Examples
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # #[cfg(all(feature = "ml-kem-768", feature = "std", unix))] {
+/// use fips203::ml_kem_768;
+/// use fips203::traits::{Decaps, Encaps};
+/// use rand_core::OsRng;
+///
+/// // The decapsulation key is generated directly into locked memory
+/// let (ek, dk) = ml_kem_768::KG::try_keygen_locked_with_rng(&mut OsRng)?;
+/// let (ssk1, ct) = ek.try_encaps_with_rng(&mut OsRng)?;
+/// let ssk2 = dk.try_decaps_locked(&ct)?; // As is the shared secret
+/// assert_eq!(ssk1, *ssk2);
+/// # }
+/// # Ok(())}
+/// ```
+pub struct Locked<T: Zeroize> {
+    ptr: NonNull<T>,
+    len: usize,
+}
+
+// SAFETY: the mapping is exclusively owned (as by a `Box`), so thread-safety follows that of `T`
+unsafe impl<T: Zeroize + Send> Send for Locked<T> {}
+unsafe impl<T: Zeroize + Sync> Sync for Locked<T> {}
+
+
+impl<T: Zeroize> Locked<T> {
+    /// Moves `value` into newly mapped and locked pages. Note that any copies of `value` made
+    /// before the move (e.g., on the stack) are not wiped by this; prefer the constructors that
+    /// write directly into locked memory, such as `KG::try_keygen_locked_with_rng()`.
+    /// # Errors
+    /// Returns an error when the pages cannot be mapped or locked.
+    pub fn try_new(value: T) -> Result<Self, &'static str> {
+        // SAFETY: `sysconf()` only reads the configuration value; a failure (-1) is caught below
+        let page = usize::try_from(unsafe { libc::sysconf(libc::_SC_PAGESIZE) })
+            .map_err(|_| "Page size unavailable")?;
+        // Round up to whole pages; `mmap()` returns page-aligned (and so `T`-aligned) memory
+        let len = (size_of::<T>().max(1) + page - 1) / page * page;
+        // SAFETY: a new private anonymous mapping (with no address hint or file), so no existing
+        // memory is affected; the result is checked against `MAP_FAILED` below
+        let addr = unsafe {
+            libc::mmap(
+                core::ptr::null_mut(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_PRIVATE | libc::MAP_ANONYMOUS,
+                -1,
+                0,
+            )
+        };
+        if addr == libc::MAP_FAILED {
+            return Err("Memory mapping failed");
+        }
+        let unmap = |msg| {
+            // SAFETY: `addr` and `len` are exactly the mapping above, which is not used afterwards
+            let _ = unsafe { libc::munmap(addr, len) };
+            Err(msg)
+        };
+        // SAFETY: `addr` and `len` are exactly the (live) mapping above
+        if unsafe { libc::mlock(addr, len) } != 0 {
+            return unmap("Memory locking failed");
+        }
+        #[cfg(any(target_os = "linux", target_os = "android"))]
+        // SAFETY: as above; `MADV_DONTDUMP` only changes how the range is treated in core dumps
+        if unsafe { libc::madvise(addr, len, libc::MADV_DONTDUMP) } != 0 {
+            return unmap("Memory exclusion from core dumps failed");
+        }
+        let Some(ptr) = NonNull::new(addr.cast::<T>()) else {
+            return unmap("Memory mapping failed");
+        };
+        // SAFETY: the mapping is writable, page-aligned (so aligned for `T`, whose alignment is at
+        // most the page size) and at least `size_of::<T>()` bytes; `write()` does not drop the
+        // (zeroed, not yet valid) previous contents
+        unsafe { ptr.as_ptr().write(value) };
+        Ok(Self { ptr, len })
+    }
+}
+
+
+impl<T: Zeroize> Deref for Locked<T> {
+    type Target = T;
+
+    // SAFETY: `ptr` holds the value written by `try_new()` until drop, and the shared borrow of
+    // `self` prevents any mutable access for the lifetime of the reference
+    fn deref(&self) -> &T { unsafe { self.ptr.as_ref() } }
+}
+
+
+impl<T: Zeroize> DerefMut for Locked<T> {
+    // SAFETY: as above, with the exclusive borrow of `self` preventing any other access
+    fn deref_mut(&mut self) -> &mut T { unsafe { self.ptr.as_mut() } }
+}
+
+
+impl<T: Zeroize> Drop for Locked<T> {
+    fn drop(&mut self) {
+        self.deref_mut().zeroize();
+        // SAFETY: the value is valid (zeroized in place) and dropped exactly once here, after which
+        // the mapping from `try_new()` is unlocked and unmapped and never accessed again
+        unsafe {
+            self.ptr.as_ptr().drop_in_place();
+            let _ = libc::munlock(self.ptr.as_ptr().cast(), self.len);
+            let _ = libc::munmap(self.ptr.as_ptr().cast(), self.len);
+        }
+    }
+}
+
+
+impl<T: Zeroize> ZeroizeOnDrop for Locked<T> {}
+
+
+impl<T: Zeroize> core::fmt::Debug for Locked<T> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Locked").finish_non_exhaustive()
+    }
+}
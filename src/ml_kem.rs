@@ -1,9 +1,55 @@
-use crate::byte_fns::{byte_decode, byte_encode};
-use crate::helpers::{g, h, j};
-use crate::k_pke::{k_pke_decrypt, k_pke_encrypt, k_pke_key_gen};
+#[cfg(all(feature = "blinding", feature = "decaps"))]
+use crate::blinding::Blinding;
+#[cfg(any(feature = "encaps", feature = "decaps"))]
+use crate::byte_fns::byte_decode;
+#[cfg(feature = "encaps")]
+use crate::byte_fns::byte_encode;
+#[cfg(any(
+    feature = "keygen",
+    feature = "encaps",
+    all(feature = "decaps", feature = "blinding")
+))]
+use crate::helpers::draw_seed;
+#[cfg(any(feature = "encaps", feature = "decaps"))]
+use crate::helpers::g;
+use crate::helpers::{check, h};
+#[cfg(feature = "decaps")]
+use crate::helpers::{implicit_reject, j};
+#[cfg(all(feature = "decaps", not(feature = "small-code")))]
+use crate::k_pke::k_pke_decrypt;
+#[cfg(all(feature = "blinding", feature = "decaps"))]
+use crate::k_pke::k_pke_decrypt_blinded;
+#[cfg(feature = "decaps")]
+use crate::k_pke::k_pke_decrypt_hat;
+#[cfg(all(any(feature = "encaps", feature = "decaps"), not(feature = "small-code")))]
+use crate::k_pke::k_pke_encrypt;
+#[cfg(all(feature = "keygen", not(feature = "small-code")))]
+use crate::k_pke::k_pke_key_gen;
+#[cfg(feature = "keygen")]
+use crate::k_pke::k_pke_key_gen_into;
+#[cfg(any(feature = "encaps", feature = "decaps"))]
+use crate::k_pke::{gen_a_hat_into, k_pke_encrypt_hat};
+#[cfg(all(feature = "decaps", feature = "small-code"))]
+use crate::k_pke_shared::k_pke_decrypt;
+#[cfg(all(any(feature = "encaps", feature = "decaps"), feature = "small-code"))]
+use crate::k_pke_shared::k_pke_encrypt;
+#[cfg(all(feature = "keygen", feature = "small-code"))]
+use crate::k_pke_shared::k_pke_key_gen;
+#[cfg(any(feature = "encaps", feature = "decaps"))]
+use crate::types::Poly;
+#[cfg(any(feature = "keygen", feature = "encaps", feature = "decaps"))]
+use crate::types::Workspace;
+#[cfg(any(feature = "encaps", feature = "decaps"))]
 use crate::SharedSecretKey;
+#[cfg(any(
+    feature = "keygen",
+    feature = "encaps",
+    all(feature = "decaps", feature = "blinding")
+))]
 use rand_core::CryptoRngCore;
-use subtle::{ConditionallySelectable, ConstantTimeEq};
+#[cfg(feature = "keygen")]
+use zeroize::Zeroize;
+use zeroize::Zeroizing;
 
 
 /// Algorithm 16 `ML-KEM.KeyGen_internal(d,z)` on page 32.
@@ -13,8 +59,9 @@ use subtle::{ConditionallySelectable, ConstantTimeEq};
 /// Input:  randomness `𝑧 ∈ 𝔹^{32}`.
 /// Output: encapsulation key `ek ∈ 𝔹^{384·𝑘+32}`.
 /// Output: decapsulation key `dk ∈ 𝔹^{768·𝑘+96}`.
+#[cfg(feature = "keygen")]
 pub(crate) fn ml_kem_key_gen_internal<const K: usize, const ETA1_64: usize>(
-    d: [u8; 32], z: [u8; 32], ek: &mut [u8], dk: &mut [u8],
+    mut d: [u8; 32], mut z: [u8; 32], ek: &mut [u8], dk: &mut [u8],
 ) {
     debug_assert_eq!(ek.len(), 384 * K + 32, "Alg 16: ek len not 384 * K + 32");
     debug_assert_eq!(dk.len(), 768 * K + 96, "Alg 16: dk len not 768 * K + 96");
@@ -25,14 +72,25 @@ pub(crate) fn ml_kem_key_gen_internal<const K: usize, const ETA1_64: usize>(
     k_pke_key_gen::<K, ETA1_64>(d, ek, &mut dk[..p1]); // writes ek and first part of dk
 
     // 3: dk ← (dk_PKE ‖ ek ‖ H(ek) ‖ 𝑧)    ▷ KEM decaps key includes PKE decryption key
+    append_dk(p1, z, ek, dk);
+    d.zeroize();
+    z.zeroize();
+    crate::indicator::record::<K>(false); // the seeds were supplied, not drawn
+
+    // 4: return (ek, dk)
+}
+
+
+/// Step 3 of Algorithm 16 `ML-KEM.KeyGen_internal(d,z)`, appending `ek ‖ H(ek) ‖ z` to the
+/// `dk_PKE` already written into `dk[..p1]`.
+#[cfg(feature = "keygen")]
+fn append_dk(p1: usize, z: [u8; 32], ek: &[u8], dk: &mut [u8]) {
     let h_ek = h(ek);
     let p2 = p1 + ek.len();
     let p3 = p2 + h_ek.len();
     dk[p1..p2].copy_from_slice(ek);
     dk[p2..p3].copy_from_slice(&h_ek);
     dk[p3..].copy_from_slice(&z);
-
-    // 4: return (ek, dk)
 }
 
 
@@ -43,20 +101,50 @@ pub(crate) fn ml_kem_key_gen_internal<const K: usize, const ETA1_64: usize>(
 /// Input:  randomness `𝑚 ∈ 𝔹^{32}` <br>
 /// Output: shared secret key `K ∈ B^{32}` <br>
 /// Output: ciphertext `c ∈ B^{32(du·k+dv)}` <br>
-fn ml_kem_encaps_internal<const K: usize, const ETA1_64: usize, const ETA2_64: usize>(
+#[cfg(feature = "encaps")]
+pub(crate) fn ml_kem_encaps_internal<const K: usize, const ETA1_64: usize, const ETA2_64: usize>(
     du: u32, dv: u32, m: &[u8; 32], ek: &[u8], ct: &mut [u8],
 ) -> Result<SharedSecretKey, &'static str> {
-    // Note: this is only called via ml_kem_encaps() which validates slice sizes and correct decode
+    // Note: this is only called via ml_kem_encaps(), try_encaps_with_m() or encaps_from_seed() on
+    // a validated ek (at top level) which ensures correct slice sizes and decode
+    let (k, result) = encaps_internal::<K, ETA1_64, ETA2_64>(du, dv, m, ek, ct);
+    result.map(|()| k)
+}
+
+
+/// As `ml_kem_encaps_internal()` above, for `encaps_from_seed()` on a validated `ek`, for which
+/// encryption cannot fail. Were it to, the ciphertext is zeroed (and so implicitly rejected by the
+/// peer) rather than panicking.
+#[cfg(feature = "encaps")]
+pub(crate) fn ml_kem_encaps_seeded<const K: usize, const ETA1_64: usize, const ETA2_64: usize>(
+    du: u32, dv: u32, m: &[u8; 32], ek: &[u8], ct: &mut [u8],
+) -> SharedSecretKey {
+    let (k, result) = encaps_internal::<K, ETA1_64, ETA2_64>(du, dv, m, ek, ct);
+    if result.is_err() {
+        ct.fill(0);
+    }
+    k
+}
+
 
+// Steps 1 to 3 of Algorithm 17, returning `K` along with the outcome of the encryption into `ct`
+#[cfg(feature = "encaps")]
+fn encaps_internal<const K: usize, const ETA1_64: usize, const ETA2_64: usize>(
+    du: u32, dv: u32, m: &[u8; 32], ek: &[u8], ct: &mut [u8],
+) -> (SharedSecretKey, Result<(), &'static str>) {
     // 1: (K, r) ← G(m ∥ H(ek))    ▷ derive shared secret key K and randomness r
     let h_ek = h(ek);
     let (k, r) = g(&[m, &h_ek]);
+    let (k, r) = (Zeroizing::new(k), Zeroizing::new(r));
 
     // 2: c ← K-PKE.Encrypt(ek, m, r)    ▷ encrypt m using K-PKE with randomness r
-    k_pke_encrypt::<K, ETA1_64, ETA2_64>(du, dv, ek, m, &r, ct)?;
+    let result = k_pke_encrypt::<K, ETA1_64, ETA2_64>(du, dv, ek, m, &r, ct);
+    if result.is_ok() {
+        crate::indicator::record::<K>(false); // m was supplied, not drawn
+    }
 
     // 3: return (K, c)  (note: ct is mutable input)
-    Ok(SharedSecretKey(k))
+    (SharedSecretKey(*k), result)
 }
 
 
@@ -66,6 +154,7 @@ fn ml_kem_encaps_internal<const K: usize, const ETA1_64: usize, const ETA2_64: u
 /// Validated input: decapsulation key `dk ∈ B^{768·k+96}` <br>
 /// Validated input: ciphertext `c ∈ B^{32(du·k+dv)}` <br>
 /// Output: shared key `K ∈ B^{32}`
+#[cfg(feature = "decaps")]
 #[allow(clippy::similar_names)]
 fn ml_kem_decaps_internal<
     const K: usize,
@@ -77,9 +166,10 @@ fn ml_kem_decaps_internal<
     du: u32, dv: u32, dk: &[u8], ct: &[u8; CT_LEN],
 ) -> Result<SharedSecretKey, &'static str> {
     // Decapsulation key type check
-    debug_assert_eq!(dk.len(), 768 * K + 96, "Alg 18: dk len not 768 ...");
+    check!(dk.len() == 768 * K + 96, "Alg 18: dk len not 768 ...");
     // Note: decaps key is either correctly sourced from KeyGen, or validated by try_from_bytes(). As
-    // such, the two above checks are redundant but will be removed in release builds. The are left
+    // such, the two above checks are redundant but will be removed in release builds (unless the
+    // `strict-checks` feature is enabled). The are left
     // here for A) caution, B) give guardrails for future changes
 
     // 1: dk_PKE ← dk[0 : 384·k]    ▷ extract (from KEM decaps key) the PKE decryption key
@@ -92,24 +182,28 @@ fn ml_kem_decaps_internal<
     let h = &dk[768 * K + 32..768 * K + 64];
 
     // 4: z ← dk[768·k + 64 : 768·k + 96]    ▷ extract implicit rejection value
-    let z = &dk[768 * K + 64..768 * K + 96];
+    let z: &[u8; 32] = dk
+        .get(768 * K + 64..)
+        .and_then(|z| z.try_into().ok())
+        .ok_or("Alg 18: dk len not 768 * K + 96")?;
 
     // 5: m′ ← K-PKE.Decrypt(dk_PKE,c)
-    let m_prime = k_pke_decrypt::<K>(du, dv, dk_pke, ct)?;
+    let m_prime = Zeroizing::new(k_pke_decrypt::<K>(du, dv, dk_pke, ct)?);
 
     // 6: (K′, r′) ← G(m′ ∥ h)
-    let (mut k_prime, r_prime) = g(&[&m_prime, h]);
+    let (k_prime, r_prime) = g(&[&*m_prime, h]);
+    let (mut k_prime, r_prime) = (Zeroizing::new(k_prime), Zeroizing::new(r_prime));
 
     // 7: K̄ ← J(z ∥ c, 32)
-    let k_bar = j(z.try_into().unwrap(), ct);
+    let k_bar = Zeroizing::new(j(z, ct));
 
     // 8: c′ ← K-PKE.Encrypt(ek_PKE , m′ , r′ )    ▷ re-encrypt using the derived randomness r′
-    let mut c_prime = [0u8; CT_LEN];
+    let mut c_prime = Zeroizing::new([0u8; CT_LEN]);
     k_pke_encrypt::<K, ETA1_64, ETA2_64>(
         du,
         dv,
         ek_pke,
-        &m_prime,
+        &*m_prime,
         &r_prime,
         &mut c_prime[0..ct.len()],
     )?;
@@ -117,10 +211,12 @@ fn ml_kem_decaps_internal<
     // 9:  if 𝑐 ≠ 𝑐 ′ then
     // 10:   𝐾 ′ ← 𝐾̄    ▷ if ciphertexts do not match, “implicitly reject”
     // 11: end if
-    k_prime.conditional_assign(&k_bar, ct.ct_ne(&c_prime));
+    implicit_reject(&mut k_prime, &k_bar, ct, &*c_prime)?;
+
+    crate::indicator::record::<K>(true);
 
     // 12: return 𝐾 ′
-    Ok(SharedSecretKey(k_prime))
+    Ok(SharedSecretKey(*k_prime))
 }
 
 
@@ -129,19 +225,20 @@ fn ml_kem_decaps_internal<
 ///
 /// Output: Encapsulation key `ek` ∈ `B^{384·k+32}` <br>
 /// Output: Decapsulation key `dk` ∈ `B^{768·k+96}`
+#[cfg(feature = "keygen")]
 pub(crate) fn ml_kem_key_gen<const K: usize, const ETA1_64: usize>(
     rng: &mut impl CryptoRngCore, ek: &mut [u8], dk: &mut [u8],
 ) -> Result<(), &'static str> {
-    debug_assert_eq!(ek.len(), 384 * K + 32, "Alg 19: ek len not 384 * K + 32");
-    debug_assert_eq!(dk.len(), 768 * K + 96, "Alg 19: dk len not 768 * K + 96");
+    check!(ek.len() == 384 * K + 32, "Alg 19: ek len not 384 * K + 32");
+    check!(dk.len() == 768 * K + 96, "Alg 19: dk len not 768 * K + 96");
 
     // 1: d ←− B^{32}    ▷ d is 32 random bytes (see Section 3.3)
-    let mut d = [0u8; 32];
-    rng.try_fill_bytes(&mut d).map_err(|_| "Alg 19: Random number generator failed for d")?;
+    let mut d = Zeroizing::new([0u8; 32]);
+    draw_seed(rng, &mut d, "Alg 19: Random number generator failed for d")?;
 
     // 2: z ←− B^{32}    ▷ z is 32 random bytes (see Section 3.3)
-    let mut z = [0u8; 32];
-    rng.try_fill_bytes(&mut z).map_err(|_| "Alg 19: Random number generator failed for z")?;
+    let mut z = Zeroizing::new([0u8; 32]);
+    draw_seed(rng, &mut z, "Alg 19: Random number generator failed for z")?;
 
     // 3: if 𝑑 == NULL or 𝑧 == NULL then
     // 4:   return ⊥    ▷ return an error indication if random bit generation failed
@@ -149,7 +246,9 @@ pub(crate) fn ml_kem_key_gen<const K: usize, const ETA1_64: usize>(
     // Note: the above functionality is present in the map_err() in step 1 and 2
 
     // 6: (ek, dk) ← ML-KEM.KeyGen_internal(𝑑, 𝑧)    ▷ run internal key generation algorithm
-    ml_kem_key_gen_internal::<K, ETA1_64>(d, z, ek, dk);
+    ml_kem_key_gen_internal::<K, ETA1_64>(*d, *z, ek, dk);
+
+    crate::indicator::record::<K>(true);
 
     // 7: return (ek, dk)
     Ok(())
@@ -162,31 +261,29 @@ pub(crate) fn ml_kem_key_gen<const K: usize, const ETA1_64: usize>(
 /// Checked input: encapsulation key `ek ∈ B^{384·k+32}` <br>
 /// Output: shared secret key `K ∈ B^{32}` <br>
 /// Output: ciphertext `c ∈ B^{32·(du·k+dv)}` <br>
+#[cfg(feature = "encaps")]
 pub(crate) fn ml_kem_encaps<const K: usize, const ETA1_64: usize, const ETA2_64: usize>(
     rng: &mut impl CryptoRngCore, du: u32, dv: u32, ek: &[u8], ct: &mut [u8],
 ) -> Result<SharedSecretKey, &'static str> {
-    debug_assert_eq!(ek.len(), 384 * K + 32, "Alg 20: ek len not 384 * K + 32"); // also: size check at top level
-    debug_assert_eq!(
-        ct.len(),
-        32 * (du as usize * K + dv as usize),
+    check!(ek.len() == 384 * K + 32, "Alg 20: ek len not 384 * K + 32"); // also: size check at top level
+    check!(
+        ct.len() == 32 * (du as usize * K + dv as usize),
         "Alg 20: ct len not 32*(DU*K+DV)"
     ); // also: size check at top level
 
     // modulus check: perform/confirm the computation ek ← ByteEncode12(ByteDecode12(ek_tilde).
     // Note: An *external* ek can only arrive via try_from_bytes() which does this validation already.
     // As such, this check is redundant but is left in for caution and as a fuzz target, as it is
-    // removed in release builds anyway. It also supports quicker changes if the spec moves...
-    debug_assert!(
-        {
-            let mut pass = true;
-            for i in 0..K {
-                let mut ek_tilde = [0u8; 384];
-                let ek_hat = byte_decode(12, &ek[384 * i..384 * (i + 1)]).unwrap(); // btw, going to panic
+    // removed in release builds anyway (unless the `strict-checks` feature is enabled). It also
+    // supports quicker changes if the spec moves...
+    check!(
+        ek.chunks(384).take(K).all(|ek_i| {
+            let mut ek_tilde = [0u8; 384];
+            byte_decode(12, ek_i).is_ok_and(|ek_hat| {
                 byte_encode(12, &ek_hat, &mut ek_tilde);
-                pass &= ek_tilde == ek[384 * i..384 * (i + 1)];
-            }
-            pass
-        },
+                ek_tilde == *ek_i
+            })
+        }),
         "Alg 20: ek fails modulus check"
     );
 
@@ -194,20 +291,261 @@ pub(crate) fn ml_kem_encaps<const K: usize, const ETA1_64: usize, const ETA2_64:
     // 2: if 𝑚 == NULL then
     // 3:   return ⊥    ▷ return an error indication if random bit generation failed
     // 4: end if
-    let mut m = [0u8; 32];
-    rng.try_fill_bytes(&mut m).map_err(|_| "Alg 20: random number generator failed")?;
+    let mut m = Zeroizing::new([0u8; 32]);
+    draw_seed(rng, &mut m, "Alg 20: random number generator failed")?;
 
     let k = ml_kem_encaps_internal::<K, ETA1_64, ETA2_64>(du, dv, &m, ek, ct)?;
+    crate::indicator::record::<K>(true);
     Ok(k)
 }
 
 
+/// Expands a (validated) encapsulation key into its decoded `t_hat`, sampled `A_hat` and `H(ek)`.
+/// This corresponds to steps 2-8 of Algorithm 14 `K-PKE.Encrypt(ek_PKE , m, r)` on page 30 and
+/// the `H(ek)` in step 1 of Algorithm 17 `ML-KEM.Encaps_internal(ek, m)` on page 33, all of which
+/// depend only upon `ek`.
+///
+/// Input:  encapsulation key `ek ∈ B^{384·k+32}` <br>
+/// Output: `t_hat ∈ (Z^{256}_q)^k`, `A_hat ∈ (Z^{256}_q)^{k×k}` and `H(ek) ∈ B^{32}`
+#[cfg(feature = "encaps")]
+#[allow(clippy::type_complexity)]
+pub(crate) fn ml_kem_expand_ek<const K: usize>(
+    ek: &[u8],
+) -> Result<([Poly; K], [[Poly; K]; K], [u8; 32]), &'static str> {
+    let (mut t_hat, mut a_hat) = ([Poly::default(); K], [[Poly::default(); K]; K]);
+    let h_ek = ml_kem_expand_ek_into::<K>(ek, &mut t_hat, &mut a_hat)?;
+    Ok((t_hat, a_hat, h_ek))
+}
+
+
+/// As `ml_kem_expand_ek()` above, but decoding `t_hat` and sampling `A_hat` into caller-provided
+/// storage (e.g., a `Workspace`).
+///
+/// Input:  encapsulation key `ek ∈ B^{384·k+32}` <br>
+/// Output: `t_hat ∈ (Z^{256}_q)^k` and `A_hat ∈ (Z^{256}_q)^{k×k}` (in place), and `H(ek) ∈ B^{32}`
+#[cfg(any(feature = "encaps", feature = "decaps"))]
+pub(crate) fn ml_kem_expand_ek_into<const K: usize>(
+    ek: &[u8], t_hat: &mut [Poly; K], a_hat: &mut [[Poly; K]; K],
+) -> Result<[u8; 32], &'static str> {
+    check!(ek.len() == 384 * K + 32, "Expand: ek len not 384 * K + 32");
+
+    // t̂ ← ByteDecode_12 (ek_PKE [0 : 384k])    ▷ run ByteDecode_12 𝑘 times
+    for (i, chunk) in ek.chunks(384).enumerate().take(K) {
+        t_hat[i] = byte_decode(12, chunk)?;
+    }
+
+    // ρ ← ek_PKE [384k : 384k + 32]    ▷ then A_hat is sampled from ρ
+    let rho = &ek[384 * K..(384 * K + 32)].try_into().map_err(|_| "Expand: rho malformed")?;
+    gen_a_hat_into(rho, a_hat);
+
+    Ok(h(ek))
+}
+
+
+/// Algorithm 20 `ML-KEM.Encaps(ek)` on page 37, operating on an expanded encapsulation key.
+/// Uses the cached `t_hat`, `A_hat` and `H(ek)` to generate a shared key and an associated ciphertext.
+///
+/// Input: expanded encapsulation key `t_hat`, `A_hat` and `H(ek)` <br>
+/// Output: shared secret key `K ∈ B^{32}` <br>
+/// Output: ciphertext `c ∈ B^{32·(du·k+dv)}` <br>
+#[cfg(feature = "encaps")]
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn ml_kem_encaps_expanded<const K: usize, const ETA1_64: usize, const ETA2_64: usize>(
+    rng: &mut impl CryptoRngCore, du: u32, dv: u32, t_hat: &[Poly; K], a_hat: &[[Poly; K]; K],
+    h_ek: &[u8; 32], ct: &mut [u8],
+) -> Result<SharedSecretKey, &'static str> {
+    check!(
+        ct.len() == 32 * (du as usize * K + dv as usize),
+        "Alg 20: ct len not 32*(DU*K+DV)"
+    ); // also: size check at top level
+
+    // 1: m ← B^{32}          ▷ m is 32 random bytes (see Section 3.3)
+    // 2: if 𝑚 == NULL then
+    // 3:   return ⊥    ▷ return an error indication if random bit generation failed
+    // 4: end if
+    let mut m = Zeroizing::new([0u8; 32]);
+    draw_seed(rng, &mut m, "Alg 20: random number generator failed")?;
+
+    let (k, result) =
+        encaps_expanded_internal::<K, ETA1_64, ETA2_64>(du, dv, t_hat, a_hat, h_ek, &m, ct);
+    result?;
+    crate::indicator::record::<K>(true);
+    Ok(k)
+}
+
+
+/// As `ml_kem_encaps_expanded()` above, for `encaps_from_seed()` with the supplied `m` rather than
+/// drawing it. Encryption cannot fail for a correctly sized `ct`; were it to, the ciphertext is
+/// zeroed (and so implicitly rejected by the peer) rather than panicking.
+#[cfg(feature = "encaps")]
+pub(crate) fn ml_kem_encaps_expanded_seeded<
+    const K: usize,
+    const ETA1_64: usize,
+    const ETA2_64: usize,
+>(
+    du: u32, dv: u32, t_hat: &[Poly; K], a_hat: &[[Poly; K]; K], h_ek: &[u8; 32], m: &[u8; 32],
+    ct: &mut [u8],
+) -> SharedSecretKey {
+    let (k, result) =
+        encaps_expanded_internal::<K, ETA1_64, ETA2_64>(du, dv, t_hat, a_hat, h_ek, m, ct);
+    if result.is_ok() {
+        crate::indicator::record::<K>(false); // m was supplied, not drawn
+    } else {
+        ct.fill(0);
+    }
+    k
+}
+
+
+// Steps 1 to 3 of Algorithm 17 with the cached `t_hat`, `A_hat` and `H(ek)`, returning `K` along
+// with the outcome of the encryption into `ct`
+#[cfg(feature = "encaps")]
+fn encaps_expanded_internal<const K: usize, const ETA1_64: usize, const ETA2_64: usize>(
+    du: u32, dv: u32, t_hat: &[Poly; K], a_hat: &[[Poly; K]; K], h_ek: &[u8; 32], m: &[u8; 32],
+    ct: &mut [u8],
+) -> (SharedSecretKey, Result<(), &'static str>) {
+    // Alg 17 step 1: (K, r) ← G(m ∥ H(ek))    ▷ H(ek) is cached
+    let (k, r) = g(&[m, h_ek]);
+    let (k, r) = (Zeroizing::new(k), Zeroizing::new(r));
+
+    // Alg 17 step 2: c ← K-PKE.Encrypt(ek, m, r)    ▷ t_hat and A_hat are cached
+    let result = k_pke_encrypt_hat::<K, ETA1_64, ETA2_64>(du, dv, t_hat, a_hat, m, &r, ct);
+
+    // Alg 17 step 3: return (K, c)  (note: ct is mutable input)
+    (SharedSecretKey(*k), result)
+}
+
+
+/// Expands a (validated) decapsulation key into its decoded `s_hat` along with the expanded
+/// encapsulation key material, i.e. `t_hat`, `A_hat` and `H(ek)`, as well as `z`. This corresponds
+/// to steps 1-4 of Algorithm 18 `ML-KEM.Decaps_internal(dk, c)` on page 34 with the subsequent
+/// decoding/sampling that depends only upon `dk`.
+///
+/// Input:  decapsulation key `dk ∈ B^{768·k+96}` <br>
+/// Output: `s_hat`, `t_hat`, `A_hat`, `H(ek)` and `z`
+#[cfg(feature = "decaps")]
+#[allow(clippy::type_complexity)]
+pub(crate) fn ml_kem_expand_dk<const K: usize>(
+    dk: &[u8],
+) -> Result<([Poly; K], [Poly; K], [[Poly; K]; K], [u8; 32], [u8; 32]), &'static str> {
+    let mut s_hat = [Poly::default(); K];
+    let (mut t_hat, mut a_hat) = ([Poly::default(); K], [[Poly::default(); K]; K]);
+    let (h, z) = ml_kem_expand_dk_into::<K>(dk, &mut s_hat, &mut t_hat, &mut a_hat)?;
+    Ok((s_hat, t_hat, a_hat, h, z))
+}
+
+
+/// As `ml_kem_expand_dk()` above, but decoding `s_hat`, `t_hat` and sampling `A_hat` into
+/// caller-provided storage (e.g., a `Workspace`).
+///
+/// Input:  decapsulation key `dk ∈ B^{768·k+96}` <br>
+/// Output: `s_hat`, `t_hat` and `A_hat` (in place), and `H(ek)` and `z`
+#[cfg(feature = "decaps")]
+pub(crate) fn ml_kem_expand_dk_into<const K: usize>(
+    dk: &[u8], s_hat: &mut [Poly; K], t_hat: &mut [Poly; K], a_hat: &mut [[Poly; K]; K],
+) -> Result<([u8; 32], [u8; 32]), &'static str> {
+    check!(dk.len() == 768 * K + 96, "Expand: dk len not 768 * K + 96");
+
+    // 1: dk_PKE ← dk[0 : 384·k]    ▷ then s_hat ← ByteDecode_12(dk_PKE)
+    for (i, chunk) in dk[0..384 * K].chunks(384).enumerate() {
+        s_hat[i] = byte_decode(12, chunk)?;
+    }
+
+    // 2: ek_PKE ← dk[384·k : 768·k + 32]    ▷ then t_hat and A_hat are expanded
+    let _h_ek = ml_kem_expand_ek_into::<K>(&dk[384 * K..768 * K + 32], t_hat, a_hat)?;
+
+    // 3: h ← dk[768·k + 32 : 768·k + 64]    ▷ hash of PKE encryption key (validated on deserialization)
+    let h = dk[768 * K + 32..768 * K + 64].try_into().map_err(|_| "Expand: h malformed")?;
+
+    // 4: z ← dk[768·k + 64 : 768·k + 96]    ▷ implicit rejection value
+    let z = dk[768 * K + 64..768 * K + 96].try_into().map_err(|_| "Expand: z malformed")?;
+
+    Ok((h, z))
+}
+
+
+/// Algorithm 18 `ML-KEM.Decaps_internal(dk, c)` on page 34, operating on an expanded decapsulation
+/// key. Uses the cached `s_hat`, `t_hat`, `A_hat`, `H(ek)` and `z` to produce a shared secret key
+/// from a ciphertext.
+///
+/// Validated input: expanded decapsulation key `s_hat`, `t_hat`, `A_hat`, `h` and `z` <br>
+/// Validated input: ciphertext `c ∈ B^{32(du·k+dv)}` <br>
+/// Output: shared key `K ∈ B^{32}`
+#[cfg(feature = "decaps")]
+#[allow(clippy::similar_names, clippy::too_many_arguments)]
+pub(crate) fn ml_kem_decaps_expanded<
+    const K: usize,
+    const ETA1_64: usize,
+    const ETA2_64: usize,
+    const CT_LEN: usize,
+>(
+    du: u32, dv: u32, s_hat: &[Poly; K], t_hat: &[Poly; K], a_hat: &[[Poly; K]; K], h: &[u8; 32],
+    z: &[u8; 32], ct: &[u8; CT_LEN],
+) -> Result<SharedSecretKey, &'static str> {
+    let mut c_prime = Zeroizing::new([0u8; CT_LEN]);
+    ml_kem_decaps_expanded_with::<K, ETA1_64, ETA2_64, CT_LEN>(
+        du,
+        dv,
+        s_hat,
+        t_hat,
+        a_hat,
+        h,
+        z,
+        ct,
+        &mut c_prime,
+    )
+}
+
+
+/// As `ml_kem_decaps_expanded()` above, but re-encrypting into the caller-provided `c_prime`.
+#[cfg(feature = "decaps")]
+#[allow(clippy::similar_names, clippy::too_many_arguments)]
+fn ml_kem_decaps_expanded_with<
+    const K: usize,
+    const ETA1_64: usize,
+    const ETA2_64: usize,
+    const CT_LEN: usize,
+>(
+    du: u32, dv: u32, s_hat: &[Poly; K], t_hat: &[Poly; K], a_hat: &[[Poly; K]; K], h: &[u8; 32],
+    z: &[u8; 32], ct: &[u8; CT_LEN], c_prime: &mut [u8; CT_LEN],
+) -> Result<SharedSecretKey, &'static str> {
+    // Ciphertext type check
+    check!(ct.len() == 32 * (du as usize * K + dv as usize), "Alg 18: ct len not 32 * ...");
+
+    // Steps 1-4 are cached in the expanded decapsulation key (or workspace)
+
+    // 5: m′ ← K-PKE.Decrypt(dk_PKE,c)
+    let m_prime = Zeroizing::new(k_pke_decrypt_hat::<K>(du, dv, s_hat, ct)?);
+
+    // 6: (K′, r′) ← G(m′ ∥ h)
+    let (k_prime, r_prime) = g(&[&*m_prime, h]);
+    let (mut k_prime, r_prime) = (Zeroizing::new(k_prime), Zeroizing::new(r_prime));
+
+    // 7: K̄ ← J(z ∥ c, 32)
+    let k_bar = Zeroizing::new(j(z, ct));
+
+    // 8: c′ ← K-PKE.Encrypt(ek_PKE , m′ , r′ )    ▷ re-encrypt using the derived randomness r′
+    k_pke_encrypt_hat::<K, ETA1_64, ETA2_64>(du, dv, t_hat, a_hat, &*m_prime, &r_prime, c_prime)?;
+
+    // 9:  if 𝑐 ≠ 𝑐 ′ then
+    // 10:   𝐾 ′ ← 𝐾̄    ▷ if ciphertexts do not match, “implicitly reject”
+    // 11: end if
+    implicit_reject(&mut k_prime, &k_bar, ct, c_prime)?;
+
+    crate::indicator::record::<K>(true);
+
+    // 12: return 𝐾 ′
+    Ok(SharedSecretKey(*k_prime))
+}
+
+
 /// Algorithm 21 `ML-KEM.Decaps(c, dk)` on page 38.
 /// Uses the decapsulation key to produce a shared key from a ciphertext.
 ///
 /// Validated input: ciphertext `c` ∈ `B^{32(du·k+dv)}` <br>
 /// Validated input: decapsulation key `dk` ∈ `B^{768·k+96}` <br>
 /// Output: shared key `K` ∈ `B^{32}`
+#[cfg(feature = "decaps")]
 #[allow(clippy::similar_names)]
 pub(crate) fn ml_kem_decaps<
     const K: usize,
@@ -219,11 +557,12 @@ pub(crate) fn ml_kem_decaps<
     du: u32, dv: u32, dk: &[u8], ct: &[u8; CT_LEN],
 ) -> Result<SharedSecretKey, &'static str> {
     // Ciphertext type check
-    debug_assert_eq!(ct.len(), 32 * (du as usize * K + dv as usize), "Alg 21: ct len not 32 * ...");
+    check!(ct.len() == 32 * (du as usize * K + dv as usize), "Alg 21: ct len not 32 * ...");
     // Decapsulation key type check
-    debug_assert_eq!(dk.len(), 768 * K + 96, "Alg 21: dk len not 768 ...");
+    check!(dk.len() == 768 * K + 96, "Alg 21: dk len not 768 ...");
     // Note: decaps key is either correctly sourced from KeyGen, or validated by try_from_bytes(). As
-    // such, the two above checks are redundant but will be removed in release builds. The are left
+    // such, the two above checks are redundant but will be removed in release builds (unless the
+    // `strict-checks` feature is enabled). The are left
     // here for A) caution, B) give guardrails for future changes
 
     // 1: 𝐾 ′ ← ML-KEM.Decaps_internal(dk, 𝑐)    ▷ run internal decapsulation algorithm
@@ -232,7 +571,148 @@ pub(crate) fn ml_kem_decaps<
 }
 
 
-#[cfg(test)]
+/// Algorithm 21 `ML-KEM.Decaps(c, dk)` on page 38, with the secret arithmetic of decryption
+/// (step 5 of Algorithm 18) blinded by fresh randomness from `rng`, see `blinding.rs`. The shared
+/// key is that of `ml_kem_decaps()` above.
+///
+/// Validated input: ciphertext `c` ∈ `B^{32(du·k+dv)}` <br>
+/// Validated input: decapsulation key `dk` ∈ `B^{768·k+96}` <br>
+/// Output: shared key `K` ∈ `B^{32}`
+#[cfg(all(feature = "blinding", feature = "decaps"))]
+#[allow(clippy::similar_names)]
+pub(crate) fn ml_kem_decaps_blinded<
+    const K: usize,
+    const ETA1_64: usize,
+    const ETA2_64: usize,
+    const CT_LEN: usize,
+>(
+    rng: &mut impl CryptoRngCore, du: u32, dv: u32, dk: &[u8], ct: &[u8; CT_LEN],
+) -> Result<SharedSecretKey, &'static str> {
+    check!(ct.len() == 32 * (du as usize * K + dv as usize), "Alg 21: ct len not 32 * ...");
+    check!(dk.len() == 768 * K + 96, "Alg 21: dk len not 768 ...");
+
+    let mut seed = Zeroizing::new([0u8; 32]);
+    draw_seed(rng, &mut seed, "Alg 21: random number generator failed for blinding")?;
+    let blinding = Blinding::new(&seed);
+
+    // 1-4 of Algorithm 18: extract dk_PKE (decoded as s_hat), ek_PKE, h and z
+    let mut s_hat = Zeroizing::new([Poly::default(); K]);
+    for (s_hat_i, chunk) in s_hat.iter_mut().zip(dk[0..384 * K].chunks(384)) {
+        *s_hat_i = byte_decode(12, chunk)?;
+    }
+    let ek_pke = &dk[384 * K..768 * K + 32];
+    let h = &dk[768 * K + 32..768 * K + 64];
+    let z: &[u8; 32] = dk
+        .get(768 * K + 64..)
+        .and_then(|z| z.try_into().ok())
+        .ok_or("Alg 18: dk len not 768 * K + 96")?;
+
+    // 5: m′ ← K-PKE.Decrypt(dk_PKE,c)    ▷ blinded
+    let m_prime = Zeroizing::new(k_pke_decrypt_blinded::<K>(du, dv, &s_hat, ct, &blinding)?);
+
+    // 6: (K′, r′) ← G(m′ ∥ h)
+    let (k_prime, r_prime) = g(&[&*m_prime, h]);
+    let (mut k_prime, r_prime) = (Zeroizing::new(k_prime), Zeroizing::new(r_prime));
+
+    // 7: K̄ ← J(z ∥ c, 32)
+    let k_bar = Zeroizing::new(j(z, ct));
+
+    // 8: c′ ← K-PKE.Encrypt(ek_PKE , m′ , r′ )    ▷ re-encrypt using the derived randomness r′
+    let mut c_prime = Zeroizing::new([0u8; CT_LEN]);
+    k_pke_encrypt::<K, ETA1_64, ETA2_64>(du, dv, ek_pke, &*m_prime, &r_prime, &mut *c_prime)?;
+
+    // 9-11: implicit rejection, as ml_kem_decaps_internal()
+    implicit_reject(&mut k_prime, &k_bar, ct, &*c_prime)?;
+
+    crate::indicator::record::<K>(true);
+
+    // 12: return 𝐾 ′
+    Ok(SharedSecretKey(*k_prime))
+}
+
+
+/// Algorithm 19 `ML-KEM.KeyGen()` on page 35, with `s_hat`, `e_hat` and the sampled `A_hat` of
+/// Algorithm 13 `K-PKE.KeyGen(d)` written into the caller-provided `Workspace` rather than fresh
+/// temporaries.
+///
+/// Output: Encapsulation key `ek` ∈ `B^{384·k+32}` <br>
+/// Output: Decapsulation key `dk` ∈ `B^{768·k+96}`
+#[cfg(feature = "keygen")]
+pub(crate) fn ml_kem_key_gen_workspace<
+    const K: usize,
+    const ETA1_64: usize,
+    const CT_LEN: usize,
+>(
+    rng: &mut impl CryptoRngCore, ek: &mut [u8], dk: &mut [u8], ws: &mut Workspace<K, CT_LEN>,
+) -> Result<(), &'static str> {
+    check!(ek.len() == 384 * K + 32, "Alg 19: ek len not 384 * K + 32");
+    check!(dk.len() == 768 * K + 96, "Alg 19: dk len not 768 * K + 96");
+
+    // 1-5: d, z ←− B^{32}, returning an error indication if random bit generation failed
+    let mut d = Zeroizing::new([0u8; 32]);
+    draw_seed(rng, &mut d, "Alg 19: Random number generator failed for d")?;
+    let mut z = Zeroizing::new([0u8; 32]);
+    draw_seed(rng, &mut z, "Alg 19: Random number generator failed for z")?;
+
+    // 6: (ek, dk) ← ML-KEM.KeyGen_internal(𝑑, 𝑧)    ▷ with t_hat holding e_hat in Alg 13
+    let p1 = 384 * K;
+    let Workspace { s_hat, t_hat, a_hat, .. } = ws;
+    k_pke_key_gen_into::<K, ETA1_64>(*d, ek, &mut dk[..p1], s_hat, t_hat, a_hat);
+    append_dk(p1, *z, ek, dk);
+
+    crate::indicator::record::<K>(true);
+
+    // 7: return (ek, dk)
+    Ok(())
+}
+
+
+/// Algorithm 20 `ML-KEM.Encaps(ek)` on page 37, with the decoded `t_hat` and sampled `A_hat`
+/// written into the caller-provided `Workspace` rather than fresh temporaries.
+///
+/// Checked input: encapsulation key `ek ∈ B^{384·k+32}` <br>
+/// Output: shared secret key `K ∈ B^{32}` <br>
+/// Output: ciphertext `c ∈ B^{32·(du·k+dv)}` <br>
+#[cfg(feature = "encaps")]
+pub(crate) fn ml_kem_encaps_workspace<
+    const K: usize,
+    const ETA1_64: usize,
+    const ETA2_64: usize,
+    const CT_LEN: usize,
+>(
+    rng: &mut impl CryptoRngCore, du: u32, dv: u32, ek: &[u8], ct: &mut [u8],
+    ws: &mut Workspace<K, CT_LEN>,
+) -> Result<SharedSecretKey, &'static str> {
+    let h_ek = ml_kem_expand_ek_into::<K>(ek, &mut ws.t_hat, &mut ws.a_hat)?;
+    ml_kem_encaps_expanded::<K, ETA1_64, ETA2_64>(rng, du, dv, &ws.t_hat, &ws.a_hat, &h_ek, ct)
+}
+
+
+/// Algorithm 21 `ML-KEM.Decaps(c, dk)` on page 38, with the decoded `s_hat`, `t_hat`, sampled
+/// `A_hat` and re-encrypted `c′` written into the caller-provided `Workspace` rather than fresh
+/// temporaries.
+///
+/// Validated input: ciphertext `c` ∈ `B^{32(du·k+dv)}` <br>
+/// Validated input: decapsulation key `dk` ∈ `B^{768·k+96}` <br>
+/// Output: shared key `K` ∈ `B^{32}`
+#[cfg(feature = "decaps")]
+pub(crate) fn ml_kem_decaps_workspace<
+    const K: usize,
+    const ETA1_64: usize,
+    const ETA2_64: usize,
+    const CT_LEN: usize,
+>(
+    du: u32, dv: u32, dk: &[u8], ct: &[u8; CT_LEN], ws: &mut Workspace<K, CT_LEN>,
+) -> Result<SharedSecretKey, &'static str> {
+    let Workspace { s_hat, t_hat, a_hat, c_prime } = ws;
+    let (h, z) = ml_kem_expand_dk_into::<K>(dk, s_hat, t_hat, a_hat)?;
+    ml_kem_decaps_expanded_with::<K, ETA1_64, ETA2_64, CT_LEN>(
+        du, dv, s_hat, t_hat, a_hat, &h, &z, ct, c_prime,
+    )
+}
+
+
+#[cfg(all(test, feature = "keygen", feature = "encaps", feature = "decaps"))]
 mod tests {
     use rand_core::SeedableRng;
 
@@ -267,4 +747,123 @@ mod tests {
         let res = ml_kem_decaps::<K, ETA1_64, ETA2_64, J_LEN, CT_LEN>(DU, DV, &dk, &ct);
         assert!(res.is_ok());
     }
+
+    // A reused workspace must give the same results as fresh temporaries, including for the
+    // implicit rejection of a modified ciphertext
+    #[test]
+    fn test_workspace_matches() {
+        use crate::ml_kem::{
+            ml_kem_decaps_workspace, ml_kem_encaps_workspace, ml_kem_key_gen_workspace,
+        };
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(4560);
+        let (mut ek, mut dk) = ([0u8; EK_LEN], [0u8; DK_LEN]);
+        ml_kem_key_gen::<K, ETA1_64>(&mut rng, &mut ek, &mut dk).unwrap();
+        let mut ws = crate::types::Workspace::<K, CT_LEN>::new();
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(4560);
+        let (mut ek_ws, mut dk_ws) = ([0u8; EK_LEN], [0u8; DK_LEN]);
+        ml_kem_key_gen_workspace::<K, ETA1_64, CT_LEN>(&mut rng, &mut ek_ws, &mut dk_ws, &mut ws)
+            .unwrap();
+        assert_eq!((ek, dk), (ek_ws, dk_ws));
+        // All fresh-temporary encapsulations run before the workspace ones replay the same rng
+        // output, so that no draw repeats the one before it (as would fail the `rng-health-checks`)
+        let (mut cts1, mut cts2) = ([[0u8; CT_LEN]; 4], [[0u8; CT_LEN]; 4]);
+        let mut rng1 = rand_chacha::ChaCha8Rng::seed_from_u64(4561);
+        let results1: [_; 4] = core::array::from_fn(|i| {
+            ml_kem_encaps::<K, ETA1_64, ETA2_64>(&mut rng1, DU, DV, &ek, &mut cts1[i])
+        });
+        let mut rng2 = rand_chacha::ChaCha8Rng::seed_from_u64(4561);
+        let results2: [_; 4] = core::array::from_fn(|i| {
+            ml_kem_encaps_workspace::<K, ETA1_64, ETA2_64, CT_LEN>(
+                &mut rng2,
+                DU,
+                DV,
+                &ek,
+                &mut cts2[i],
+                &mut ws,
+            )
+        });
+        for (i, ((ssk1, ssk2), (mut ct1, ct2))) in
+            results1.into_iter().zip(results2).zip(cts1.into_iter().zip(cts2)).enumerate()
+        {
+            assert_eq!(ssk1.unwrap(), ssk2.unwrap());
+            assert_eq!(ct1, ct2);
+
+            ct1[0] ^= u8::from(i % 2 == 1); // every other ciphertext is implicitly rejected
+            let ssk1 = ml_kem_decaps::<K, ETA1_64, ETA2_64, J_LEN, CT_LEN>(DU, DV, &dk, &ct1);
+            let ssk2 =
+                ml_kem_decaps_workspace::<K, ETA1_64, ETA2_64, CT_LEN>(DU, DV, &dk, &ct1, &mut ws);
+            assert_eq!(ssk1.unwrap(), ssk2.unwrap());
+        }
+    }
+
+    // Internal invariants are reported as errors (rather than debug panics) with `strict-checks`
+    #[test]
+    #[cfg(feature = "strict-checks")]
+    fn test_strict_checks() {
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(789);
+        let (mut ek, mut dk, mut ct) = ([0u8; EK_LEN], [0u8; DK_LEN], [0u8; CT_LEN]);
+        ml_kem_key_gen::<K, ETA1_64>(&mut rng, &mut ek, &mut dk).unwrap();
+        let res = ml_kem_key_gen::<K, ETA1_64>(&mut rng, &mut ek[1..], &mut dk);
+        assert_eq!(res, Err("Alg 19: ek len not 384 * K + 32"));
+        let res = ml_kem_decaps::<K, ETA1_64, ETA2_64, J_LEN, CT_LEN>(DU, DV, &dk[1..], &ct);
+        assert!(res.is_err());
+        ek[0..2].copy_from_slice(&[0xFF; 2]); // the first coefficient is now 4095 ≥ q
+        let res = ml_kem_encaps::<K, ETA1_64, ETA2_64>(&mut rng, DU, DV, &ek, &mut ct);
+        assert!(res.is_err());
+    }
+
+    // A fault affecting either comparison (simulated by inconsistent inputs) must be detected
+    #[test]
+    #[cfg(feature = "redundant-decaps")]
+    fn test_select_checked() {
+        use crate::helpers::select_checked;
+        use subtle::Choice;
+        let (k_prime, k_bar) = ([1u8; 32], [2u8; 32]);
+        for (c_ne, diff, expected) in [
+            (0, 0, Some(k_prime)),
+            (1, 4, Some(k_bar)),
+            (1, 0, None),
+            (0, 4, None),
+        ] {
+            let mut k = k_prime;
+            let res = select_checked(&mut k, &k_bar, Choice::from(c_ne), diff);
+            assert_eq!(res.ok().map(|()| k), expected);
+            if expected.is_none() {
+                assert_eq!(k, [0u8; 32]);
+            }
+        }
+    }
+
+    // A stuck rng (all-zero, or returning the same buffer each time) must fail closed
+    #[test]
+    #[cfg(feature = "rng-health-checks")]
+    fn test_rng_health_checks() {
+        struct ZeroRng; // as a broken platform rng that silently returns a zeroed buffer
+        impl rand_core::RngCore for ZeroRng {
+            fn next_u32(&mut self) -> u32 { 0 }
+
+            fn next_u64(&mut self) -> u64 { 0 }
+
+            fn fill_bytes(&mut self, out: &mut [u8]) { out.fill(0) }
+
+            fn try_fill_bytes(&mut self, out: &mut [u8]) -> Result<(), rand_core::Error> {
+                out.fill(0);
+                Ok(())
+            }
+        }
+        impl rand_core::CryptoRng for ZeroRng {}
+
+        let (mut ek, mut dk, mut ct) = ([0u8; EK_LEN], [0u8; DK_LEN], [0u8; CT_LEN]);
+        let res = ml_kem_key_gen::<K, ETA1_64>(&mut ZeroRng, &mut ek, &mut dk);
+        assert_eq!(res, Err("Random number generator failed health check"));
+        let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(321);
+        ml_kem_key_gen::<K, ETA1_64>(&mut rng, &mut ek, &mut dk).unwrap();
+        let stuck_rng = rand_chacha::ChaCha8Rng::seed_from_u64(654);
+        let res =
+            ml_kem_encaps::<K, ETA1_64, ETA2_64>(&mut stuck_rng.clone(), DU, DV, &ek, &mut ct);
+        assert!(res.is_ok());
+        let res =
+            ml_kem_encaps::<K, ETA1_64, ETA2_64>(&mut stuck_rng.clone(), DU, DV, &ek, &mut ct);
+        assert_eq!(res, Err("Random number generator failed health check"));
+    }
 }
@@ -1,57 +1,138 @@
-use crate::types::Z;
+use crate::ct_mul::mul;
+use crate::types::{Poly, Z};
 use crate::{Q, ZETA};
 
 
+/// Algorithm 9 `NTT(f)` in place, dispatching to the AVX-512 or AVX2 backend when enabled and available,
+/// or to the Cortex-M4 backend when enabled.
+pub(crate) fn ntt(array_f: &mut Poly) {
+    #[cfg(all(feature = "avx512", target_arch = "x86_64"))]
+    if crate::avx512::ntt(array_f) {
+        return;
+    }
+    #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+    if crate::avx2::ntt(array_f) {
+        return;
+    }
+    #[cfg(feature = "cortex-m4-asm")]
+    crate::cortex_m4::ntt(array_f);
+    #[cfg(not(feature = "cortex-m4-asm"))]
+    ntt_portable(array_f);
+}
+
+
 /// Algorithm 9 `NTT(f)` on page 26.
 /// Computes the NTT representation `f_hat` of the given polynomial `f ∈ R_q`.
 ///
 /// Input: array `f ∈ Z^{256}_q`    ▷ the coefficients of the input polynomial <br>
-/// Output: array `f_hat ∈ Z^{256}_q`    ▷ the coefficients of the NTT of the input polynomial
-#[must_use]
-#[allow(clippy::module_name_repetitions)]
-pub(crate) fn ntt(array_f: &[Z; 256]) -> [Z; 256] {
+/// Output: array `f_hat ∈ Z^{256}_q`    ▷ the coefficients of the NTT of the input polynomial (in place) <br>
+/// The layers are merged in pairs (`len` and `len/2`) so that each group of four coefficients
+/// is loaded once with its three zetas hoisted, and the coefficients are kept as signed values
+/// that grow by less than `q` per layer (so `|f_hat[j]| < 8q`) with a single final reduction.
+#[allow(clippy::module_name_repetitions, clippy::cast_possible_truncation)] // for perf
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)] // coefficients < 8q fit in i16
+#[cfg_attr(feature = "cortex-m4-asm", allow(dead_code))] // the reference in tests
+pub(crate) fn ntt_portable(array_f: &mut Poly) {
     //
-    // 1: f_hat ← f    ▷ will compute NTT in-place on a copy of input array
-    let mut f_hat: [Z; 256] = core::array::from_fn(|i| array_f[i]);
+    // 1: f_hat ← f    ▷ will compute NTT in-place on a (signed) copy of input array
+    let mut f_hat: [i16; 256] = core::array::from_fn(|j| array_f[j].0 as i16);
+
+    // 2: i ← 1    ▷ `i` is calculated per block below, as ζ^{BitRev_7(i)} is ZETA_TABLE[i << 1]
+    // 3: for (len ← 128; len ≥ 2; len ← len/2)    ▷ as (128, 64), (32, 16), (8, 4) then 2
+    ntt_layers::<128>(&mut f_hat);
+    ntt_layers::<32>(&mut f_hat);
+    ntt_layers::<8>(&mut f_hat);
+
+    // The final layer with len = 2
+    for (block, chunk) in f_hat.chunks_exact_mut(4).enumerate() {
+        let zeta = i32::from(ZETA_TABLE[(64 + block) << 1].0);
+        let (lo, hi) = chunk.split_at_mut(2);
+        for (x, y) in lo.iter_mut().zip(hi) {
+            let t = Z::mont_reduce_lazy(mul(i32::from(*y), zeta));
+            let x0 = i32::from(*x);
+            *x = (x0 + t) as i16;
+            *y = (x0 - t) as i16;
+        }
+    }
 
-    // 2: i ← 1
-    let mut i = 1;
+    // 14: return f_hat    ▷ reduced into [0, q), written back in place
+    for (f, x) in array_f.iter_mut().zip(f_hat) {
+        *f = Z(Z::barrett_reduce(i32::from(x)) as u16);
+    }
+}
 
-    // 3: for (len ← 128; len ≥ 2; len ← len/2)
-    for len in [128, 64, 32, 16, 8, 4, 2] {
+
+/// Steps 4-12 of `NTT(f)` for the pair of layers `LEN` and `LEN/2`. The blocks and quarters are
+/// iterated as exact chunks (rather than indexed) so that no bounds checks remain.
+#[allow(clippy::cast_possible_truncation)] // coefficients < 8q fit in i16
+fn ntt_layers<const LEN: usize>(f_hat: &mut [i16; 256]) {
+    //
+    // 4: for (start ← 0; start < 256; start ← start + 2 · len)    ▷ as `block` = start / (2 · len)
+    for (block, chunk) in f_hat.chunks_exact_mut(2 * LEN).enumerate() {
         //
-        // 4: for (start ← 0; start < 256; start ← start + 2 · len)
-        for start in (0..256).step_by(2 * len) {
-            //
-            // 5: zeta ← ζ^{BitRev_7(i)} mod q
-            let zeta = ZETA_TABLE[i << 1];
+        // 5: zeta ← ζ^{BitRev_7(i)} mod q    ▷ for `len` then both `len/2` blocks
+        let i = 128 / LEN + block;
+        let z1 = i32::from(ZETA_TABLE[i << 1].0);
+        let z2 = i32::from(ZETA_TABLE[(2 * i) << 1].0);
+        let z3 = i32::from(ZETA_TABLE[(2 * i + 1) << 1].0);
+
+        // 7: for ( j ← start; j < start + len; j ++)    ▷ four coefficients at a time
+        let (lo, hi) = chunk.split_at_mut(LEN);
+        let (q0, q1) = lo.split_at_mut(LEN / 2);
+        let (q2, q3) = hi.split_at_mut(LEN / 2);
+        for (((f0, f1), f2), f3) in q0.iter_mut().zip(q1).zip(q2).zip(q3) {
+            let (x0, x1, x2, x3) = (i32::from(*f0), i32::from(*f1), i32::from(*f2), i32::from(*f3));
+
+            // 8-10: t ← zeta · f_hat[j + len], then f_hat[j + len] ← f_hat[j] − t and f_hat[j] ← f_hat[j] + t
+            let t = Z::mont_reduce_lazy(mul(x2, z1));
+            let (x0, x2) = (x0 + t, x0 - t);
+            let t = Z::mont_reduce_lazy(mul(x3, z1));
+            let (x1, x3) = (x1 + t, x1 - t);
+
+            // 8-10: again, for the next layer with `len/2`
+            let t = Z::mont_reduce_lazy(mul(x1, z2));
+            *f0 = (x0 + t) as i16;
+            *f1 = (x0 - t) as i16;
+            let t = Z::mont_reduce_lazy(mul(x3, z3));
+            *f2 = (x2 + t) as i16;
+            *f3 = (x2 - t) as i16;
+
+            // 11: end for
+        }
 
-            // 6: i ← i + 1
-            i += 1;
+        // 12: end for
+    }
 
-            // 7: for ( j ← start; j < start + len; j ++)
-            for j in start..(start + len) {
-                //
-                // 8: t ← zeta · f_hat[j + len]    ▷ steps 8-10 done modulo q
-                let t = f_hat[j + len].mul(zeta);
+    // 13: end for
+}
 
-                // 9: f_hat[j + len] ← f_hat [j] − t
-                f_hat[j + len] = f_hat[j].sub(t);
 
-                // 10: f_hat[j] ← f_hat[j] + t
-                f_hat[j] = f_hat[j].add(t);
+/// Algorithm 10 `NTTinv(f)` in place, dispatching to the AVX-512 or AVX2 backend when enabled and available,
+/// or to the Cortex-M4 backend when enabled.
+#[cfg(any(feature = "decaps", feature = "research"))]
+pub(crate) fn ntt_inv(f_hat: &mut Poly) { ntt_inv_with(f_hat, None); }
 
-                // 11: end for
-            }
 
-            // 12: end for
-        }
+/// Computes `NTTinv(f) + addend` in place, with the addition folded into the final scaling of
+/// `NTTinv()` rather than taking a separate pass over the coefficients.
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+pub(crate) fn ntt_inv_add(f_hat: &mut Poly, addend: &Poly) { ntt_inv_with(f_hat, Some(addend)); }
 
-        // 13: end for
-    }
 
-    // 14: return f_hat
-    f_hat
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+fn ntt_inv_with(f_hat: &mut Poly, addend: Option<&Poly>) {
+    #[cfg(all(feature = "avx512", target_arch = "x86_64"))]
+    if crate::avx512::ntt_inv(f_hat, addend) {
+        return;
+    }
+    #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+    if crate::avx2::ntt_inv(f_hat, addend) {
+        return;
+    }
+    #[cfg(feature = "cortex-m4-asm")]
+    crate::cortex_m4::ntt_inv(f_hat, addend);
+    #[cfg(not(feature = "cortex-m4-asm"))]
+    ntt_inv_portable(f_hat, addend);
 }
 
 
@@ -59,78 +140,170 @@ pub(crate) fn ntt(array_f: &[Z; 256]) -> [Z; 256] {
 /// Computes the polynomial `f ∈ R_q` corresponding to the given NTT representation `f_hat ∈ T_q`.
 ///
 /// Input: array `f_hat ∈ Z^{256}`    ▷ the coefficients of input NTT representation <br>
-/// Output: array `f ∈ Z^{256}`    ▷ the coefficients of the inverse-NTT of the input
-#[must_use]
-#[allow(clippy::module_name_repetitions)]
-pub(crate) fn ntt_inv(f_hat: &[Z; 256]) -> [Z; 256] {
+/// Output: array `f ∈ Z^{256}`    ▷ the coefficients of the inverse-NTT of the input (in place) <br>
+/// As with `ntt_portable()`, the layers are merged in pairs with signed coefficients. The sums
+/// double per layer, so they are reduced after each pair of layers, and the final scaling by
+/// 3303 is folded into the last layer, along with the addition of `addend` when provided.
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+#[allow(clippy::module_name_repetitions, clippy::cast_possible_truncation)] // for perf
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)] // coefficients < 8q fit in i16
+#[cfg_attr(feature = "cortex-m4-asm", allow(dead_code))] // the reference in tests
+pub(crate) fn ntt_inv_portable(f_hat: &mut Poly, addend: Option<&Poly>) {
     //
-    // 1: f ← f_hat    ▷ will compute in-place on a copy of input array
-    let mut f: [Z; 256] = core::array::from_fn(|i| f_hat[i]);
+    // 1: f ← f_hat    ▷ will compute in-place on a (signed) copy of input array
+    let mut f: [i16; 256] = core::array::from_fn(|j| f_hat[j].0 as i16);
 
-    // 2: i ← 127
-    let mut i = 127;
+    // 2: i ← 127    ▷ `i` is calculated per block below, as ζ^{BitRev_7(i)} is ZETA_TABLE[i << 1]
+    // 3: for (len ← 2; len ≤ 128; len ← 2 · len)    ▷ as (2, 4), (8, 16), (32, 64) then 128
+    ntt_inv_layers::<2>(&mut f);
+    ntt_inv_layers::<8>(&mut f);
+    ntt_inv_layers::<32>(&mut f);
 
-    // 3: for (len ← 2; len ≤ 128; len ← 2 · len)
-    for len in [2, 4, 8, 16, 32, 64, 128] {
-        //
-        // 4: for (start ← 0; start < 256; start ← start + 2 · len)
-        for start in (0..256).step_by(2 * len) {
-            //
-            // 5: zeta ← ζ^{BitRev_7(i)} mod q
-            let zeta = ZETA_TABLE[i << 1];
+    // The final layer with len = 128, folding in step 14 below via both constants
+    // 14: f ← f · 3303 mod q    ▷ multiply every entry by 3303 ≡ 128^{−1} mod q
+    // Every input to this function is a product from `multiply_ntts()` (or a sum thereof), which
+    // carries a factor of 2^{-16}. Multiplying by 1441 ≡ 3303·2^{32} mod q via `mont_reduce()`
+    // thus scales by 3303 and also reverses that factor in the same reduction.
+    let f_3303 = Z(1441);
+    let zeta_3303 = i32::from(ZETA_TABLE[1 << 1].mont_mul(f_3303).0);
+    let f_3303 = i32::from(f_3303.0);
+    for j in 0..128 {
+        let (t, u) = (i32::from(f[j]), i32::from(f[j + 128]));
+        f_hat[j] = Z::mont_reduce(mul(t + u, f_3303));
+        f_hat[j + 128] = Z::mont_reduce(mul(u - t, zeta_3303));
+        if let Some(addend) = addend {
+            f_hat[j] = f_hat[j].add(addend[j]);
+            f_hat[j + 128] = f_hat[j + 128].add(addend[j + 128]);
+        }
+    }
 
-            // 6: i ← i − 1
-            i -= 1;
+    // 15: return f    ▷ written back in place
+}
 
-            // 7: for ( j ← start; j < start + len; j ++)
-            for j in start..(start + len) {
-                //
-                // 8: t ← f[j]
-                let t = f[j];
 
-                // 9: f[j] ← t + f[j + len]    ▷ steps 9-10 done modulo q
-                f[j] = t.add(f[j + len]);
+/// Steps 4-12 of `NTTinv(f)` for the pair of layers `LEN` and `2·LEN`, iterated as exact chunks
+/// as in `ntt_layers()` above.
+#[cfg(any(feature = "encaps", feature = "decaps", feature = "research"))]
+#[allow(clippy::cast_possible_truncation)] // reduced coefficients fit in i16
+fn ntt_inv_layers<const LEN: usize>(f: &mut [i16; 256]) {
+    //
+    // 4: for (start ← 0; start < 256; start ← start + 2 · len)    ▷ two `len` blocks per `2·len` block
+    for (block, chunk) in f.chunks_exact_mut(4 * LEN).enumerate() {
+        //
+        // 5: zeta ← ζ^{BitRev_7(i)} mod q    ▷ for both `len` blocks then `2·len`
+        let i = 256 / LEN - 1 - 2 * block;
+        let z1 = i32::from(ZETA_TABLE[i << 1].0);
+        let z2 = i32::from(ZETA_TABLE[(i - 1) << 1].0);
+        let z3 = i32::from(ZETA_TABLE[(i / 2) << 1].0);
+
+        // 7: for ( j ← start; j < start + len; j ++)    ▷ four coefficients at a time
+        let (lo, hi) = chunk.split_at_mut(2 * LEN);
+        let (q0, q1) = lo.split_at_mut(LEN);
+        let (q2, q3) = hi.split_at_mut(LEN);
+        for (((f0, f1), f2), f3) in q0.iter_mut().zip(q1).zip(q2).zip(q3) {
+            let (x0, x1, x2, x3) = (i32::from(*f0), i32::from(*f1), i32::from(*f2), i32::from(*f3));
+
+            // 8-10: t ← f[j], then f[j] ← t + f[j + len] and f[j + len] ← zeta · (f[j + len] − t)
+            let (x0, x1) = (x0 + x1, Z::mont_reduce_lazy(mul(x1 - x0, z1)));
+            let (x2, x3) = (x2 + x3, Z::mont_reduce_lazy(mul(x3 - x2, z2)));
+
+            // 8-10: again, for the next layer with `2·len`, then reduce the sums
+            *f0 = Z::barrett_reduce(x0 + x2) as i16;
+            *f2 = Z::mont_reduce_lazy(mul(x2 - x0, z3)) as i16;
+            *f1 = Z::barrett_reduce(x1 + x3) as i16;
+            *f3 = Z::mont_reduce_lazy(mul(x3 - x1, z3)) as i16;
+
+            // 11: end for
+        }
 
-                // 10: f[j + len] ← zeta · ( f[j + len] − t)
-                f[j + len] = zeta.mul(f[j + len].sub(t));
+        // 12: end for
+    }
 
-                // 11: end for
-            }
+    // 13: end for
+}
 
-            // 12: end for
-        }
 
-        // 13: end for
+/// Algorithm 11 `MultiplyNTTs(f_hat, g_hat)`, dispatching to the AVX-512 or AVX2 backend when available.
+#[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+#[must_use]
+pub(crate) fn multiply_ntts(f_hat: &Poly, g_hat: &Poly) -> Poly {
+    #[cfg(feature = "avx512")]
+    if let Some(result) = crate::avx512::multiply_ntts(f_hat, g_hat) {
+        return result;
     }
+    if let Some(result) = crate::avx2::multiply_ntts(f_hat, g_hat) {
+        return result;
+    }
+    multiply_ntts_portable(f_hat, g_hat)
+}
+
+
+/// Algorithm 11 `MultiplyNTTs(f_hat, g_hat)` for a single pair; see `multiply_ntts_sum_portable()`.
+#[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+#[must_use]
+pub(crate) fn multiply_ntts_portable(f_hat: &Poly, g_hat: &Poly) -> Poly {
+    multiply_ntts_sum_portable([f_hat], core::array::from_ref(g_hat))
+}
 
-    // 14: f ← f · 3303 mod q    ▷ multiply every entry by 3303 ≡ 128^{−1} mod q
-    let mut z3303 = Z::default();
-    z3303.set_u16(3303);
-    f.iter_mut().for_each(|item| *item = item.mul(z3303));
 
-    // 15: return f
-    f
+/// Computes `Σ_j MultiplyNTTs(f_hat[j], g_hat[j])` over `K` pairs, as used for the matrix-vector
+/// and dot products, dispatching to the AVX-512 or AVX2 backend when enabled and available, or to
+/// the Cortex-M4 backend when enabled.
+#[must_use]
+pub(crate) fn multiply_ntts_sum<const K: usize>(f_hat: [&Poly; K], g_hat: &[Poly; K]) -> Poly {
+    #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+    if crate::avx2::available() {
+        let mut h_hat = Poly::default();
+        for j in 0..K {
+            let tmp = multiply_ntts(f_hat[j], &g_hat[j]);
+            for (h, t) in h_hat.iter_mut().zip(tmp.iter()) {
+                *h = h.add(*t);
+            }
+        }
+        return h_hat;
+    }
+    #[cfg(feature = "cortex-m4-asm")]
+    let h_hat = crate::cortex_m4::multiply_ntts_sum(f_hat, g_hat);
+    #[cfg(not(feature = "cortex-m4-asm"))]
+    let h_hat = multiply_ntts_sum_portable(f_hat, g_hat);
+    h_hat
 }
 
 
-/// Algorithm 11 `MultiplyNTTs(f_hat, g_hat)` on page 27.
+/// Algorithm 11 `MultiplyNTTs(f_hat, g_hat)` on page 27, summed over `K` pairs.
 /// Computes the product (in the ring `T_q`) of two NTT representations.
 ///
 /// Input: Two arrays `f_hat ∈ Z^{256}_q` and `g_hat ∈ Z^{256}_q`    ▷ the coefficients of two NTT representations <br>
-/// Output: An array `h_hat ∈ Z^{256}_q`    ▷ the coefficients of the product of the inputs
+/// Output: An array `h_hat ∈ Z^{256}_q`    ▷ the coefficients of the product of the inputs <br>
+/// The `K` unreduced products (each below `2q^2`) are accumulated per coefficient and reduced just
+/// once, which requires `K ≤ 4`. The output is scaled by `2^{-16}` (via Montgomery reduction),
+/// which `ntt_inv()` reverses.
 #[must_use]
-pub(crate) fn multiply_ntts(f_hat: &[Z; 256], g_hat: &[Z; 256]) -> [Z; 256] {
-    let mut h_hat: [Z; 256] = [Z::default(); 256];
+#[cfg_attr(feature = "cortex-m4-asm", allow(dead_code))] // the reference in tests
+pub(crate) fn multiply_ntts_sum_portable<const K: usize>(
+    f_hat: [&Poly; K], g_hat: &[Poly; K],
+) -> Poly {
+    debug_assert!(K <= 4, "Alg 11: lazy reduction bound exceeded");
+    let mut h_hat: Poly = Poly::default();
 
     // for (i ← 0; i < 128; i ++)
-    for i in 0..128 {
+    for (i, &zt) in GAMMA_TABLE.iter().enumerate() {
         //
         // 2: (h_hat[2i], h_hat[2i + 1]) ← BaseCaseMultiply(f_hat[2i], f_hat[2i + 1], g_hat[2i], g_hat[2i + 1], ζ^{2BitRev7(i) + 1})
-        let zt = ZETA_TABLE[i ^ 0x80];
-        let (h_hat_2i, h_hat_2ip1) =
-            base_case_multiply(f_hat[2 * i], f_hat[2 * i + 1], g_hat[2 * i], g_hat[2 * i + 1], zt);
-        h_hat[2 * i] = h_hat_2i;
-        h_hat[2 * i + 1] = h_hat_2ip1;
+        let (mut c0, mut c1) = (0i32, 0i32);
+        for j in 0..K {
+            let (d0, d1) = base_case_multiply(
+                f_hat[j][2 * i],
+                f_hat[j][2 * i + 1],
+                g_hat[j][2 * i],
+                g_hat[j][2 * i + 1],
+                zt,
+            );
+            c0 += d0;
+            c1 += d1;
+        }
+        h_hat[2 * i] = Z::mont_reduce(c0);
+        h_hat[2 * i + 1] = Z::mont_reduce(c1);
 
         // 3: end for
     }
@@ -145,9 +318,11 @@ pub(crate) fn multiply_ntts(f_hat: &[Z; 256], g_hat: &[Z; 256]) -> [Z; 256] {
 ///
 /// Input: `a0, a1, b0, b1 ∈ Z_q`    ▷ the coefficients of `a0` + `a1` X and `b0` + `b1` X
 /// Input: `γ ∈ Z_q`    ▷ the modulus is `X^2 − γ`
-/// Output: `c0`, `c1` ∈ `Z_q`    ▷ the coefficients of the product of the two polynomials
+/// Output: `c0`, `c1` ∈ `Z_q`    ▷ the coefficients of the product of the two polynomials <br>
+/// The input `γ` is in Montgomery form, and the outputs are left unreduced in `[0, 2q^2)` for
+/// `Z::mont_reduce()`, which then returns them scaled by `2^{-16}`.
 #[must_use]
-pub(crate) fn base_case_multiply(a0: Z, a1: Z, b0: Z, b1: Z, gamma: Z) -> (Z, Z) {
+pub(crate) fn base_case_multiply(a0: Z, a1: Z, b0: Z, b1: Z, gamma: Z) -> (i32, i32) {
     // 1: c0 ← a0 · b0 + a1 · b1 · γ    ▷ steps 1-2 done modulo q
     let c0 = a0.base_mul(a1, b0, b1, gamma);
 
@@ -161,6 +336,7 @@ pub(crate) fn base_case_multiply(a0: Z, a1: Z, b0: Z, b1: Z, gamma: Z) -> (Z, Z)
 
 // ----------
 // The functionality below calculates the Zeta array at compile-time. Thus, not particularly optimal or CT.
+// The entries are stored in Montgomery form `ζ^{BitRev_8(i)}·2^16 mod q` for `Z::mont_mul()`.
 
 #[must_use]
 #[allow(clippy::cast_possible_truncation)] // const fns cannot use u32::from() etc...
@@ -169,7 +345,7 @@ const fn gen_zeta_table() -> [Z; 256] {
     let mut x = 1u32;
     let mut i = 0u32;
     while i < 256 {
-        result[(i as u8).reverse_bits() as usize] = Z(x as u16); // as u16;
+        result[(i as u8).reverse_bits() as usize] = Z(((x << 16) % (Q as u32)) as u16);
         x = (x * (ZETA as u32)) % (Q as u32);
         i += 1;
     }
@@ -178,20 +354,116 @@ const fn gen_zeta_table() -> [Z; 256] {
 
 pub(crate) static ZETA_TABLE: [Z; 256] = gen_zeta_table();
 
+// The 128 `γ = ζ^{2·BitRev_7(i)+1}` of `MultiplyNTTs()`, also in Montgomery form. As
+// `BitRev_8(128 + i) = 2·BitRev_7(i) + 1`, these are simply the upper half of the zeta table.
+#[must_use]
+const fn gen_gamma_table() -> [Z; 128] {
+    let zetas = gen_zeta_table();
+    let mut result = [Z(0); 128];
+    let mut i = 0;
+    while i < 128 {
+        result[i] = zetas[128 + i];
+        i += 1;
+    }
+    result
+}
+
+pub(crate) static GAMMA_TABLE: [Z; 128] = gen_gamma_table();
+
 
 #[cfg(test)]
 mod tests {
-    use crate::ntt::gen_zeta_table;
+    use crate::ntt::{
+        gen_zeta_table, multiply_ntts_sum_portable, ntt_inv, ntt_inv_add, GAMMA_TABLE, ZETA_TABLE,
+    };
     use crate::traits::SerDes;
     use crate::SharedSecretKey;
 
     #[test]
     fn test_zeta_misc() {
         let res = gen_zeta_table();
-        assert_eq!(res[4].0, 2580);
+        assert_eq!(u32::from(res[4].0), (2580 << 16) % u32::from(crate::Q)); // Montgomery form
 
         let ssk_bytes = [0u8; 32];
         let ssk = SharedSecretKey::try_from_bytes(ssk_bytes);
         assert!(ssk.is_ok());
     }
+
+    #[test]
+    fn test_gamma_table() {
+        // γ_i = ζ^{2·BitRev_7(i)+1} (in Montgomery form), with γ_{2i+1} = −γ_{2i} as ζ^{128} = −1
+        let q = u32::from(crate::Q);
+        for (i, gamma) in GAMMA_TABLE.iter().enumerate() {
+            let exp = 2 * u32::from(u8::try_from(i).unwrap().reverse_bits() >> 1) + 1;
+            let expected = (0..exp).fold(1, |acc, _| acc * u32::from(crate::ZETA) % q);
+            assert_eq!(u32::from(gamma.0), (expected << 16) % q);
+            if i % 2 == 1 {
+                assert_eq!(u32::from(gamma.0), q - u32::from(GAMMA_TABLE[i - 1].0));
+            }
+        }
+    }
+
+    #[test]
+    fn test_ntt_inv_add() {
+        // The folded addition must match a separate pass after `ntt_inv()`
+        use crate::types::{Poly, Z};
+        let f_hat = Poly(core::array::from_fn(|i| Z(u16::try_from(i * 13 % 3329).unwrap())));
+        let addend = Poly(core::array::from_fn(|i| Z(u16::try_from(3328 - i).unwrap())));
+        let (mut expected, mut actual) = (f_hat, f_hat);
+        ntt_inv(&mut expected);
+        for (e, a) in expected.iter_mut().zip(addend.iter()) {
+            *e = e.add(*a);
+        }
+        ntt_inv_add(&mut actual, &addend);
+        assert!(expected.iter().zip(actual.iter()).all(|(e, a)| e.0 == a.0));
+    }
+
+    #[test]
+    fn test_lazy_sum() {
+        // Worst-case (q - 1) inputs for K = 4 must match the eagerly reduced sum
+        let max = crate::types::Poly([crate::types::Z(crate::Q - 1); 256]);
+        let f_hat = [&max; 4];
+        let g_hat = [max; 4];
+        let prod = multiply_ntts_sum_portable([&max], &[max]);
+        let sum = multiply_ntts_sum_portable(f_hat, &g_hat);
+        for n in 0..256 {
+            assert_eq!(sum[n].0, prod[n].add(prod[n]).add(prod[n].add(prod[n])).0);
+        }
+    }
+
+    #[test]
+    fn test_reductions() {
+        let q = i32::from(crate::Q);
+        for a in (1 - (1 << 15))..(1 << 15) {
+            assert_eq!(crate::types::Z::barrett_reduce(a), a.rem_euclid(q));
+            let r = crate::types::Z::mont_reduce_lazy(a * (q - 1));
+            assert!(r.abs() < q);
+            assert_eq!((r << 16).rem_euclid(q), (a * (q - 1)).rem_euclid(q));
+        }
+    }
+
+    #[test]
+    fn test_base_mul() {
+        // The (i32) base multiplications must match a plain 64-bit reference, including the extremes
+        use crate::types::Z;
+        let q = u64::from(crate::Q);
+        let r_inv = 169; // 2^{-16} mod q
+        let values = [0, 1, 2, 1664, 1665, 3327, 3328];
+        for a0 in values {
+            for a1 in values {
+                for b0 in values {
+                    for b1 in values {
+                        let gamma = ZETA_TABLE[a0 as usize % 128];
+                        let c0 = Z::mont_reduce(Z(a0).base_mul(Z(a1), Z(b0), Z(b1), gamma));
+                        let c1 = Z::mont_reduce(Z(a0).base_mul2(Z(a1), Z(b0), Z(b1)));
+                        let (a0, a1, b0, b1) =
+                            (u64::from(a0), u64::from(a1), u64::from(b0), u64::from(b1));
+                        let g = u64::from(gamma.0) * r_inv % q; // γ is in Montgomery form
+                        assert_eq!(u64::from(c0.0), (a0 * b0 + a1 * b1 % q * g) % q * r_inv % q);
+                        assert_eq!(u64::from(c1.0), (a0 * b1 + a1 * b0) % q * r_inv % q);
+                    }
+                }
+            }
+        }
+    }
 }
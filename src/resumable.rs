@@ -0,0 +1,378 @@
+// Time-sliced key generation and decapsulation, for cooperative schedulers (e.g., an RTOS task
+// that must yield to hard-real-time work every so often). Each operation is a state machine that
+// advances by a caller-chosen number of steps per `poll()`, where a step is a single polynomial
+// operation: one sampling of `A_hat` (or a CBD sample with its NTT), one multiplication, one
+// decoding or one inverse NTT with its encoding. All intermediate state lives in the operation
+// itself, which is zeroized on drop. The number of steps is fixed per parameter set, so the
+// schedule does not depend upon any secret.
+//
+// The matrix `A_hat` is sampled one entry at a time and immediately multiplied into a single
+// accumulator, so it is never materialized. The products are summed after reduction rather than
+// before (as `multiply_ntts_sum()` does), which gives the same coefficients in `[0, q)`. The
+// re-encrypted ciphertext in decapsulation is likewise compared chunk by chunk as it is encoded.
+
+#[cfg(feature = "decaps")]
+use crate::byte_fns::byte_decode;
+use crate::byte_fns::byte_encode;
+#[cfg(feature = "keygen")]
+use crate::helpers::h;
+#[cfg(feature = "decaps")]
+use crate::helpers::{compress_1_encode, compress_vector, decompress_vector, dot_t_prod, j};
+#[cfg(all(feature = "decaps", feature = "redundant-decaps"))]
+use crate::helpers::{diff, select_checked};
+use crate::helpers::{g, prf, xof};
+use crate::ntt::{multiply_ntts_sum, ntt};
+#[cfg(feature = "decaps")]
+use crate::ntt::{ntt_inv, ntt_inv_add};
+use crate::sampling::{sample_ntt, sample_poly_cbd};
+use crate::types::Poly;
+#[cfg(feature = "keygen")]
+use crate::types::{DecapsKey, EncapsKey};
+#[cfg(feature = "decaps")]
+use crate::SharedSecretKey;
+use core::task::Poll;
+#[cfg(all(feature = "decaps", not(feature = "redundant-decaps")))]
+use subtle::ConditionallySelectable;
+#[cfg(feature = "decaps")]
+use subtle::{Choice, ConstantTimeEq};
+use zeroize::{Zeroize, ZeroizeOnDrop};
+
+
+/// `acc ← acc + A_hat[i, j] ◦ v_hat`, with `A_hat[i, j]` sampled from `rho` on demand
+fn add_a_hat_product(acc: &mut Poly, rho: &[u8; 32], i: usize, j: usize, v_hat: &Poly) {
+    let a_hat_ij = sample_ntt(xof(rho, j.to_le_bytes()[0], i.to_le_bytes()[0]));
+    let product = multiply_ntts_sum([&a_hat_ij], core::array::from_ref(v_hat));
+    for (a, p) in acc.iter_mut().zip(product.iter()) {
+        *a = a.add(*p);
+    }
+}
+
+
+/// Key generation (Algorithms 16 and 13) advanced a bounded number of steps at a time, specific
+/// to the target security parameter set. Create it with `KeyGenOp::try_new_with_rng()` (or
+/// `KeyGenOp::from_seed()`), then call `poll()` until it returns the key pair.
+#[cfg(feature = "keygen")]
+#[derive(Zeroize, ZeroizeOnDrop)]
+pub struct KeyGenOp<const K: usize, const ETA1_64: usize, const EK_LEN: usize, const DK_LEN: usize>
+{
+    step: usize,
+    d: [u8; 32],
+    rho: [u8; 32],
+    sigma: [u8; 32],
+    s_hat: [Poly; K],
+    e_hat: [Poly; K],
+    t_hat_i: Poly,
+    ek: [u8; EK_LEN],
+    dk: [u8; DK_LEN],
+}
+
+
+#[cfg(feature = "keygen")]
+impl<const K: usize, const ETA1_64: usize, const EK_LEN: usize, const DK_LEN: usize>
+    KeyGenOp<K, ETA1_64, EK_LEN, DK_LEN>
+{
+    /// The total number of steps: `G` (1), `s` and `e` (2·k), `t_hat` (k²) and `H(ek)` (1)
+    pub const STEPS: usize = 2 + 2 * K + K * K;
+
+    pub(crate) fn start(d: [u8; 32], z: [u8; 32]) -> Self {
+        let mut dk = [0u8; DK_LEN];
+        dk[DK_LEN - 32..].copy_from_slice(&z);
+        Self {
+            step: 0,
+            d,
+            rho: [0u8; 32],
+            sigma: [0u8; 32],
+            s_hat: [Poly::default(); K],
+            e_hat: [Poly::default(); K],
+            t_hat_i: Poly::default(),
+            ek: [0u8; EK_LEN],
+            dk,
+        }
+    }
+
+    /// Returns the number of steps remaining before the key pair is ready.
+    #[must_use]
+    pub fn steps_remaining(&self) -> usize { Self::STEPS.saturating_sub(self.step) }
+
+    /// Advances key generation by up to `max_steps` steps, returning the encapsulation and
+    /// decapsulation keys once complete. This function is intended to operate in constant time
+    /// (outside of `rho`, as with `KeyGen::try_keygen()`).
+    /// # Errors
+    /// Returns an error when polled again after completion.
+    pub fn poll(
+        &mut self, max_steps: usize,
+    ) -> Poll<Result<(EncapsKey<EK_LEN>, DecapsKey<DK_LEN>), &'static str>> {
+        if self.step >= Self::STEPS {
+            return Poll::Ready(Err("Operation already complete"));
+        }
+        for _ in 0..max_steps {
+            self.advance();
+            self.step += 1;
+            if self.step == Self::STEPS {
+                let keys = (EncapsKey(self.ek), DecapsKey(self.dk));
+                self.dk.zeroize();
+                return Poll::Ready(Ok(keys));
+            }
+        }
+        Poll::Pending
+    }
+
+    fn advance(&mut self) {
+        let k = K.to_le_bytes()[0];
+        match self.step {
+            // Alg 13, 1: (𝜌, 𝜎) ← G(𝑑 ‖ 𝑘)
+            0 => {
+                (self.rho, self.sigma) = g(&[&self.d, &[k]]);
+                self.d.zeroize();
+            }
+            // Alg 13, 8-11, 16 and 20: s_hat[i] ← NTT(SamplePolyCBD_η1(PRF_η1(σ, i))), encoded into dk
+            s if s <= K => {
+                let i = s - 1;
+                self.s_hat[i] = sample_poly_cbd(&prf::<ETA1_64>(&self.sigma, i.to_le_bytes()[0]));
+                ntt(&mut self.s_hat[i]);
+                byte_encode(12, &self.s_hat[i], &mut self.dk[384 * i..384 * (i + 1)]);
+            }
+            // Alg 13, 12-15 and 17: e_hat[i] ← NTT(SamplePolyCBD_η1(PRF_η1(σ, k + i)))
+            s if s <= 2 * K => {
+                let i = s - 1 - K;
+                self.e_hat[i] =
+                    sample_poly_cbd(&prf::<ETA1_64>(&self.sigma, k + i.to_le_bytes()[0]));
+                ntt(&mut self.e_hat[i]);
+            }
+            // Alg 13, 18-19: t_hat[i] ← Σ_j A_hat[i, j] ◦ s_hat[j] + e_hat[i], encoded into ek
+            s if s <= 2 * K + K * K => {
+                let (i, j) = ((s - 1 - 2 * K) / K, (s - 1 - 2 * K) % K);
+                if j == 0 {
+                    self.t_hat_i = Poly::default();
+                }
+                add_a_hat_product(&mut self.t_hat_i, &self.rho, i, j, &self.s_hat[j]);
+                if j == K - 1 {
+                    // As in `mul_row_vec_add()`, the products carry a factor of 2^{-16}
+                    for (t, e) in self.t_hat_i.iter_mut().zip(self.e_hat[i].iter()) {
+                        *t = t.to_mont().add(*e);
+                    }
+                    byte_encode(12, &self.t_hat_i, &mut self.ek[384 * i..384 * (i + 1)]);
+                }
+            }
+            // Alg 13, 19: ek ← ... ‖ ρ, then Alg 16, 3: dk ← (dk_PKE ‖ ek ‖ H(ek) ‖ 𝑧)
+            _ => {
+                self.ek[384 * K..].copy_from_slice(&self.rho);
+                let h_ek = h(&self.ek);
+                self.dk[384 * K..768 * K + 32].copy_from_slice(&self.ek);
+                self.dk[768 * K + 32..768 * K + 64].copy_from_slice(&h_ek);
+            }
+        }
+    }
+}
+
+
+/// Decapsulation (Algorithms 18, 15 and 14) advanced a bounded number of steps at a time,
+/// specific to the target security parameter set. Create it with `DecapsOp::new()`, then call
+/// `poll()` until it returns the shared secret.
+#[cfg(feature = "decaps")]
+pub struct DecapsOp<
+    'a,
+    const K: usize,
+    const ETA1_64: usize,
+    const ETA2_64: usize,
+    const CT_LEN: usize,
+> {
+    dk: &'a [u8],
+    ct: &'a [u8; CT_LEN],
+    du: u32,
+    dv: u32,
+    step: usize,
+    s_hat: [Poly; K], // then t_hat for the re-encryption
+    u_hat: [Poly; K], // then y_hat for the re-encryption
+    acc: Poly,
+    m_prime: [u8; 32],
+    k_prime: [u8; 32],
+    r_prime: [u8; 32],
+    c_ne: Choice,
+    #[cfg(feature = "redundant-decaps")]
+    c_diff: u8, // the comparison again, along an independent data flow
+}
+
+
+// The borrowed key and ciphertext are left to their owners
+#[cfg(feature = "decaps")]
+impl<const K: usize, const ETA1_64: usize, const ETA2_64: usize, const CT_LEN: usize> Drop
+    for DecapsOp<'_, K, ETA1_64, ETA2_64, CT_LEN>
+{
+    fn drop(&mut self) {
+        self.s_hat.zeroize();
+        self.u_hat.zeroize();
+        self.acc.zeroize();
+        self.m_prime.zeroize();
+        self.k_prime.zeroize();
+        self.r_prime.zeroize();
+        self.c_ne = Choice::from(0);
+        #[cfg(feature = "redundant-decaps")]
+        {
+            self.c_diff = 0;
+        }
+    }
+}
+
+#[cfg(feature = "decaps")]
+impl<const K: usize, const ETA1_64: usize, const ETA2_64: usize, const CT_LEN: usize> ZeroizeOnDrop
+    for DecapsOp<'_, K, ETA1_64, ETA2_64, CT_LEN>
+{
+}
+
+
+#[cfg(feature = "decaps")]
+impl<'a, const K: usize, const ETA1_64: usize, const ETA2_64: usize, const CT_LEN: usize>
+    DecapsOp<'a, K, ETA1_64, ETA2_64, CT_LEN>
+{
+    /// The total number of steps: decryption (2·k + 1) and re-encryption (k² + 3·k + 1)
+    pub const STEPS: usize = K * K + 5 * K + 2;
+
+    pub(crate) fn start(du: u32, dv: u32, dk: &'a [u8], ct: &'a [u8; CT_LEN]) -> Self {
+        debug_assert_eq!(dk.len(), 768 * K + 96, "Alg 18: dk len not 768 * K + 96");
+        Self {
+            dk,
+            ct,
+            du,
+            dv,
+            step: 0,
+            s_hat: [Poly::default(); K],
+            u_hat: [Poly::default(); K],
+            acc: Poly::default(),
+            m_prime: [0u8; 32],
+            k_prime: [0u8; 32],
+            r_prime: [0u8; 32],
+            c_ne: Choice::from(0),
+            #[cfg(feature = "redundant-decaps")]
+            c_diff: 0,
+        }
+    }
+
+    /// Returns the number of steps remaining before the shared secret is ready.
+    #[must_use]
+    pub fn steps_remaining(&self) -> usize { Self::STEPS.saturating_sub(self.step) }
+
+    /// Advances decapsulation by up to `max_steps` steps, returning the shared secret once
+    /// complete. This function is intended to operate in constant time.
+    /// # Errors
+    /// Returns an error if an internal error condition arises, or when polled again after completion.
+    pub fn poll(&mut self, max_steps: usize) -> Poll<Result<SharedSecretKey, &'static str>> {
+        if self.step >= Self::STEPS {
+            return Poll::Ready(Err("Operation already complete"));
+        }
+        for _ in 0..max_steps {
+            if let Err(e) = self.advance() {
+                self.step = Self::STEPS;
+                return Poll::Ready(Err(e));
+            }
+            self.step += 1;
+            if self.step == Self::STEPS {
+                let ssk = SharedSecretKey(self.k_prime);
+                self.k_prime.zeroize();
+                return Poll::Ready(Ok(ssk));
+            }
+        }
+        Poll::Pending
+    }
+
+    #[allow(clippy::many_single_char_names, clippy::similar_names)]
+    fn advance(&mut self) -> Result<(), &'static str> {
+        let (du, dv, k) = (self.du, self.dv, K.to_le_bytes()[0]);
+        let (c1_step, c2_step) = (32 * du as usize, 32 * dv as usize);
+        let rho: &[u8; 32] =
+            self.dk[768 * K..768 * K + 32].try_into().map_err(|_| "Alg 14: rho")?;
+        match self.step {
+            // Alg 15, 5: s_hat ← ByteDecode_12(dk_PKE)
+            s if s < K => self.s_hat[s] = byte_decode(12, &self.dk[384 * s..384 * (s + 1)])?,
+            // Alg 15, 3 and 6: u_hat[i] ← NTT(Decompress_du(ByteDecode_du(c1[i])))
+            s if s < 2 * K => {
+                let i = s - K;
+                self.u_hat[i] = byte_decode(du, &self.ct[c1_step * i..c1_step * (i + 1)])?;
+                decompress_vector(du, &mut self.u_hat[i]);
+                ntt(&mut self.u_hat[i]);
+            }
+            // Alg 15, 4 and 6-7: m′ ← ByteEncode_1(Compress_1(v − NTT−1(s_hat ◦ u_hat))), then
+            // Alg 18, 6: (K′, r′) ← G(m′ ∥ h)
+            s if s == 2 * K => {
+                let mut yy = dot_t_prod(&self.s_hat, &self.u_hat);
+                ntt_inv(&mut yy);
+                let mut w = byte_decode(dv, &self.ct[c1_step * K..c1_step * K + c2_step])?;
+                decompress_vector(dv, &mut w);
+                for (w_i, yy_i) in w.iter_mut().zip(yy.iter()) {
+                    *w_i = w_i.sub(*yy_i);
+                }
+                self.m_prime = compress_1_encode(&w);
+                (self.k_prime, self.r_prime) =
+                    g(&[&self.m_prime, &self.dk[768 * K + 32..768 * K + 64]]);
+                yy.zeroize();
+                w.zeroize();
+            }
+            // Alg 14, 2: t_hat ← ByteDecode_12(ek_PKE), overwriting s_hat
+            s if s <= 3 * K => {
+                let i = s - 1 - 2 * K;
+                let ek_i = &self.dk[384 * (K + i)..384 * (K + i + 1)];
+                self.s_hat[i] = byte_decode(12, ek_i)?;
+            }
+            // Alg 14, 9-12 and 18: y_hat[i] ← NTT(SamplePolyCBD_η1(PRF_η1(r′, i))), overwriting u_hat
+            s if s <= 4 * K => {
+                let i = s - 1 - 3 * K;
+                self.u_hat[i] = sample_poly_cbd(&prf::<ETA1_64>(&self.r_prime, i.to_le_bytes()[0]));
+                ntt(&mut self.u_hat[i]);
+            }
+            // Alg 14, 13-16, 19 and 22: u[i] ← NTT−1(Σ_j A_hat[j, i] ◦ y_hat[j]) + e1[i], compared
+            // against c1[i] once encoded
+            s if s <= 4 * K + K * (K + 1) => {
+                let (i, j) = ((s - 1 - 4 * K) / (K + 1), (s - 1 - 4 * K) % (K + 1));
+                if j == 0 {
+                    self.acc = Poly::default();
+                }
+                if j < K {
+                    add_a_hat_product(&mut self.acc, rho, j, i, &self.u_hat[j]);
+                } else {
+                    let b = k + i.to_le_bytes()[0];
+                    let e1_i = sample_poly_cbd(&prf::<ETA2_64>(&self.r_prime, b));
+                    ntt_inv_add(&mut self.acc, &e1_i);
+                    compress_vector(du, &mut self.acc);
+                    let mut c1_i = [0u8; 32 * 11]; // 32·du for du ≤ 11
+                    byte_encode(du, &self.acc, &mut c1_i[..c1_step]);
+                    self.c_ne |= self.ct[c1_step * i..c1_step * (i + 1)].ct_ne(&c1_i[..c1_step]);
+                    #[cfg(feature = "redundant-decaps")]
+                    {
+                        self.c_diff |=
+                            diff(&self.ct[c1_step * i..c1_step * (i + 1)], &c1_i[..c1_step]);
+                    }
+                }
+            }
+            // Alg 14, 17, 20-21 and 23: v ← NTT−1(t_hat ◦ y_hat) + e2 + µ, compared against c2,
+            // then Alg 18, 7-11: K′ ← J(z ∥ c) if c ≠ c′
+            _ => {
+                let e2 = sample_poly_cbd(&prf::<ETA2_64>(&self.r_prime, 2 * k));
+                let mut mu = byte_decode(1, &self.m_prime)?;
+                decompress_vector(1, &mut mu);
+                for (mu_i, e2_i) in mu.iter_mut().zip(e2.iter()) {
+                    *mu_i = mu_i.add(*e2_i);
+                }
+                self.acc = dot_t_prod(&self.s_hat, &self.u_hat);
+                ntt_inv_add(&mut self.acc, &mu);
+                compress_vector(dv, &mut self.acc);
+                let mut c2 = [0u8; 32 * 5]; // 32·dv for dv ≤ 5
+                byte_encode(dv, &self.acc, &mut c2[..c2_step]);
+                self.c_ne |= self.ct[c1_step * K..].ct_ne(&c2[..c2_step]);
+                #[cfg(feature = "redundant-decaps")]
+                {
+                    self.c_diff |= diff(&self.ct[c1_step * K..], &c2[..c2_step]);
+                }
+                let z: &[u8; 32] = self.dk[768 * K + 64..]
+                    .try_into()
+                    .map_err(|_| "Alg 18: dk len not 768 * K + 96")?;
+                let k_bar = j(z, self.ct);
+                mu.zeroize();
+                #[cfg(not(feature = "redundant-decaps"))]
+                self.k_prime.conditional_assign(&k_bar, self.c_ne);
+                #[cfg(feature = "redundant-decaps")]
+                select_checked(&mut self.k_prime, &k_bar, self.c_ne, self.c_diff)?;
+            }
+        }
+        Ok(())
+    }
+}
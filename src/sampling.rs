@@ -1,4 +1,4 @@
-use crate::types::Z;
+use crate::types::{Poly, Z};
 use crate::Q;
 use sha3::digest::XofReader;
 
@@ -9,10 +9,12 @@ use sha3::digest::XofReader;
 ///
 /// Input: byte stream `B ∈ B^{34}`     ▷ a 32-byte seed along with two indices <br>
 /// Output: array `a_hat ∈ Z^{256}_q`    ▷ the coefficients of the NTT of a polynomial
-pub(crate) fn sample_ntt(mut xof_reader: impl XofReader) -> [Z; 256] {
+pub(crate) fn sample_ntt(mut xof_reader: impl XofReader) -> Poly {
     //
-    let mut array_a_hat = [Z::default(); 256];
-    let mut c = [0u8; 3]; // Space for 3 random (byte) draws
+    let mut array_a_hat = Poly::default();
+    // The XOF is squeezed a full SHAKE128 rate block (168 bytes, i.e., 56 draws) at a time,
+    // which yields the same byte stream as squeezing 3 bytes per draw but with less overhead
+    let mut block = [0u8; 168];
 
     // Not needed as XofReader is passed into function.
     // 1: ctx ← XOF.Init()
@@ -27,36 +29,53 @@ pub(crate) fn sample_ntt(mut xof_reader: impl XofReader) -> [Z; 256] {
     while j < 256 {
         //
         // 5: (ctx, 𝐶) ← XOF.Squeeze(ctx, 3)    ▷ get a fresh 3-byte array 𝐶 from XOF
-        xof_reader.read(&mut c); // Draw 3 bytes
+        xof_reader.read(&mut block);
 
-        // 6: 𝑑1 ← 𝐶[0] + 256 ⋅ (𝐶[1] mod 16)    ▷ 0 ≤ 𝑑1 < 2^{12}
-        let d1 = u16::from(c[0]) + 256 * (u16::from(c[1]) & 0x0F);
-
-        // 7: 𝑑2 ← ⌊𝐶[1]/16⌋ + 16 ⋅ 𝐶[2]    ▷ 0 ≤ 𝑑2 < 2^{12}
-        let d2 = (u16::from(c[1]) >> 4) + 16 * u16::from(c[2]);
-
-        // 8: if d1 < q then
-        if d1 < Q {
-            //
-            // 9: a_hat[j] ← d1         ▷ a_hat ∈ Z256
-            array_a_hat[j].set_u16(d1);
-
-            // 10: j ← j + 1
-            j += 1;
-
-            // 11: end if
+        // The AVX2 backend (when enabled and available) parses the whole block at once
+        #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+        if let Some(next_j) = crate::avx2::sample_ntt_block(&block, &mut array_a_hat, j) {
+            j = next_j;
+            continue;
         }
 
-        // 12: if d2 < q and j < 256 then
-        if (d2 < Q) & (j < 256) {
+        // Draw 3 bytes at a time from the block, until it is exhausted or a_hat is complete
+        for c in block.chunks_exact(3) {
             //
-            // 13: a_hat[j] ← d2
-            array_a_hat[j].set_u16(d2);
-
-            // 14: j ← j + 1
-            j += 1;
-
-            // 15: end if
+            // 6: 𝑑1 ← 𝐶[0] + 256 ⋅ (𝐶[1] mod 16)    ▷ 0 ≤ 𝑑1 < 2^{12}
+            let d1 = u16::from(c[0]) + 256 * (u16::from(c[1]) & 0x0F);
+
+            // 7: 𝑑2 ← ⌊𝐶[1]/16⌋ + 16 ⋅ 𝐶[2]    ▷ 0 ≤ 𝑑2 < 2^{12}
+            let d2 = (u16::from(c[1]) >> 4) + 16 * u16::from(c[2]);
+
+            // 8: if d1 < q then
+            if d1 < Q {
+                //
+                // 9: a_hat[j] ← d1         ▷ a_hat ∈ Z256
+                let Some(a_hat_j) = array_a_hat.get_mut(j) else {
+                    break;
+                };
+                a_hat_j.set_u16(d1);
+
+                // 10: j ← j + 1
+                j += 1;
+
+                // 11: end if
+            }
+
+            // 12: if d2 < q and j < 256 then
+            if d2 < Q {
+                //
+                // 13: a_hat[j] ← d2
+                let Some(a_hat_j) = array_a_hat.get_mut(j) else {
+                    break;
+                };
+                a_hat_j.set_u16(d2);
+
+                // 14: j ← j + 1
+                j += 1;
+
+                // 15: end if
+            }
         }
 
         // 16: end while
@@ -67,6 +86,17 @@ pub(crate) fn sample_ntt(mut xof_reader: impl XofReader) -> [Z; 256] {
 }
 
 
+/// Algorithm 8 `SamplePolyCBD_η(B)`, dispatching to the AVX2 backend when enabled and available.
+#[must_use]
+pub(crate) fn sample_poly_cbd(byte_array_b: &[u8]) -> Poly {
+    #[cfg(all(feature = "avx2", target_arch = "x86_64"))]
+    if let Some(result) = crate::avx2::sample_poly_cbd(byte_array_b) {
+        return result;
+    }
+    sample_poly_cbd_portable(byte_array_b)
+}
+
+
 /// Algorithm 8 `SamplePolyCBD_η(B)` on page 23.
 /// Takes a seed as input and outputs a pseudorandom sample from the distribution `D_𝜂(𝑅_𝑞)`. <br>
 /// This function is an optimized version that avoids the `BytesToBits` function (algorithm 4).
@@ -74,17 +104,17 @@ pub(crate) fn sample_ntt(mut xof_reader: impl XofReader) -> [Z; 256] {
 /// Input: byte array `B ∈ B^{64·η}` <br>
 /// Output: array `f ∈ Z^{256}_q`
 #[must_use]
-pub(crate) fn sample_poly_cbd(byte_array_b: &[u8]) -> [Z; 256] {
-    let eta = u32::try_from(byte_array_b.len()).unwrap() >> 6;
-    debug_assert_eq!(byte_array_b.len(), 64 * eta as usize, "Alg 8: byte array not 64 * eta");
-    let mut array_f: [Z; 256] = [Z::default(); 256];
+pub(crate) fn sample_poly_cbd_portable(byte_array_b: &[u8]) -> Poly {
+    let eta = byte_array_b.len() >> 6;
+    debug_assert_eq!(byte_array_b.len(), 64 * eta, "Alg 8: byte array not 64 * eta");
+    let mut array_f: Poly = Poly::default();
     let mut temp = 0;
     let mut int_index = 0;
     let mut bit_index = 0;
     for byte in byte_array_b {
         temp |= u32::from(*byte) << bit_index;
         bit_index += 8;
-        while bit_index >= 2 * (eta as usize) {
+        while bit_index >= 2 * eta {
             let tmask_x = temp & ((1 << eta) - 1);
             let x = count_ones(tmask_x);
             let tmask_y = (temp >> eta) & ((1 << eta) - 1);
@@ -92,9 +122,11 @@ pub(crate) fn sample_poly_cbd(byte_array_b: &[u8]) -> [Z; 256] {
             let (mut xx, mut yy) = (Z::default(), Z::default());
             xx.set_u16(x);
             yy.set_u16(y);
-            array_f[int_index] = xx.sub(yy);
-            bit_index -= 2 * (eta as usize);
-            temp >>= 2 * (eta as usize);
+            if let Some(f) = array_f.get_mut(int_index) {
+                *f = xx.sub(yy);
+            }
+            bit_index -= 2 * eta;
+            temp >>= 2 * eta;
             int_index += 1;
         }
     }
@@ -0,0 +1,80 @@
+// Passphrase-sealed (encrypted at rest) decapsulation key container (with the `seal` feature).
+// This is not part of FIPS 203, and is injected into each parameter set via the macro.
+//
+// Container format, version 1:
+//   version (1) ‖ param_id (1) ‖ salt (16) ‖ nonce (12) ‖ ChaCha20-Poly1305(dk) ‖ tag (16)
+// The key is Argon2id(passphrase, salt) with m = 19 MiB, t = 2 and p = 1; the parameters are fixed
+// per version so that a crafted container cannot demand excessive memory or time. The header
+// (version through nonce) is authenticated as associated data.
+
+use crate::helpers::{check, ensure};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::AeadInPlace;
+use chacha20poly1305::{ChaCha20Poly1305, KeyInit};
+use rand_core::CryptoRngCore;
+use zeroize::Zeroizing;
+
+
+/// Container format version
+const VERSION: u8 = 1;
+
+/// Bytes added to the decapsulation key by sealing: the header and the authentication tag
+pub(crate) const OVERHEAD: usize = HEADER_LEN + 16;
+
+const HEADER_LEN: usize = 1 + 1 + 16 + 12;
+
+
+// Derives the 32-byte AEAD key from the passphrase and salt per version 1
+fn derive_key(passphrase: &[u8], salt: &[u8]) -> Result<Zeroizing<[u8; 32]>, &'static str> {
+    let params = Params::new(19 * 1024, 2, 1, Some(32)).map_err(|_| "Seal parameters invalid")?;
+    let mut key = Zeroizing::new([0u8; 32]);
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(passphrase, salt, &mut *key)
+        .map_err(|_| "Seal key derivation failed")?;
+    Ok(key)
+}
+
+
+/// Seals `dk` into `out`, which must be `dk.len() + OVERHEAD` bytes.
+pub(crate) fn seal(
+    rng: &mut impl CryptoRngCore, passphrase: &[u8], param_id: u8, dk: &[u8], out: &mut [u8],
+) -> Result<(), &'static str> {
+    check!(out.len() == dk.len() + OVERHEAD, "Sealed length wrong");
+    let (header, body) = out.split_at_mut(HEADER_LEN);
+    header[0] = VERSION;
+    header[1] = param_id;
+    rng.try_fill_bytes(&mut header[2..]).map_err(|_| "Random number generator failed")?;
+    let key = derive_key(passphrase, &header[2..18])?;
+    let (ciphertext, tag) = body.split_at_mut(dk.len());
+    ciphertext.copy_from_slice(dk);
+    let aead_tag = ChaCha20Poly1305::new((&*key).into())
+        .encrypt_in_place_detached((&header[18..]).into(), header, ciphertext)
+        .map_err(|_| "Seal encryption failed")?;
+    tag.copy_from_slice(&aead_tag);
+    Ok(())
+}
+
+
+/// Unseals `sealed` into `dk`, checking the version and parameter set.
+pub(crate) fn unseal(
+    sealed: &[u8], passphrase: &[u8], param_id: u8, dk: &mut [u8],
+) -> Result<(), &'static str> {
+    ensure!(sealed.len() == dk.len() + OVERHEAD, "Sealed decaps key length wrong");
+    let (header, body) = sealed.split_at(HEADER_LEN);
+    ensure!(header[0] == VERSION, "Sealed decaps key version unsupported");
+    ensure!(header[1] == param_id, "Sealed decaps key parameter set wrong");
+    let key = derive_key(passphrase, &header[2..18])?;
+    let (ciphertext, tag) = body.split_at(dk.len());
+    dk.copy_from_slice(ciphertext);
+    let result = ChaCha20Poly1305::new((&*key).into()).decrypt_in_place_detached(
+        (&header[18..]).into(),
+        header,
+        dk,
+        tag.into(),
+    );
+    if result.is_err() {
+        dk.fill(0);
+        return Err("Sealed decaps key passphrase wrong or container corrupted");
+    }
+    Ok(())
+}
@@ -0,0 +1,96 @@
+// Known-answer self-tests per the FIPS 140-3 cryptographic algorithm self-test (CAST) requirements.
+// Each parameter set embeds the digests of its expected keys and ciphertext (rather than the
+// multi-kilobyte values themselves) along with the expected shared secrets, all for the fixed
+// seeds below. The outcome of the first run is retained, so that later calls cost a single load.
+
+use core::sync::atomic::{AtomicU8, Ordering};
+
+
+/// Key generation seed `d` of the known-answer tests
+pub(crate) const D: [u8; 32] = [0x11; 32];
+/// Key generation seed `z` of the known-answer tests
+pub(crate) const Z: [u8; 32] = [0x22; 32];
+/// Encapsulation message `m` of the known-answer tests
+pub(crate) const M: [u8; 32] = [0x33; 32];
+
+
+/// Expected results of the known-answer tests for one parameter set
+pub(crate) struct Kat {
+    /// `H(ek)` of `KeyGen_internal(d, z)`
+    pub(crate) h_ek: [u8; 32],
+    /// `H(dk)` of `KeyGen_internal(d, z)`
+    pub(crate) h_dk: [u8; 32],
+    /// `H(c)` of `Encaps_internal(ek, m)`
+    pub(crate) h_ct: [u8; 32],
+    /// Shared secret of `Encaps_internal(ek, m)`, which `Decaps(dk, c)` must also produce
+    pub(crate) ssk: [u8; 32],
+    /// Shared secret of `Decaps(dk, c′)` (implicit rejection), with the first byte of `c` flipped
+    pub(crate) ssk_rejected: [u8; 32],
+}
+
+
+/// Decodes 64 hex digits at compile time (for readable embedded vectors)
+pub(crate) const fn hex32(hex: &str) -> [u8; 32] {
+    const fn nibble(c: u8) -> u8 {
+        match c {
+            b'0'..=b'9' => c - b'0',
+            b'a'..=b'f' => c - b'a' + 10,
+            _ => panic!("invalid hex digit"),
+        }
+    }
+    let hex = hex.as_bytes();
+    assert!(hex.len() == 64, "expected 64 hex digits");
+    let mut out = [0u8; 32];
+    let mut i = 0;
+    while i < 32 {
+        out[i] = (nibble(hex[2 * i]) << 4) | nibble(hex[2 * i + 1]);
+        i += 1;
+    }
+    out
+}
+
+
+const UNTESTED: u8 = 0;
+const PASSED: u8 = 1;
+const FAILED: u8 = 2;
+
+/// Lazily-initialized outcome of the self-tests of one parameter set. Only atomic loads and
+/// stores are used (so as to suit targets without compare-and-swap); concurrent first calls may
+/// each run the tests, which is harmless as they agree on the outcome.
+pub(crate) struct SelfTest(AtomicU8);
+
+impl SelfTest {
+    pub(crate) const fn new() -> Self { SelfTest(AtomicU8::new(UNTESTED)) }
+
+    /// Runs `tests` on the first call only, then returns the retained outcome
+    pub(crate) fn run(&self, tests: impl FnOnce() -> bool) -> Result<(), &'static str> {
+        let mut state = self.0.load(Ordering::Acquire);
+        if state == UNTESTED {
+            // The tests must not change the service indicator, see `indicator::unrecorded()`
+            state = if crate::indicator::unrecorded(tests) { PASSED } else { FAILED };
+            self.0.store(state, Ordering::Release);
+        }
+        if state == PASSED {
+            Ok(())
+        } else {
+            Err("Self-test failed")
+        }
+    }
+}
+
+
+static SELF_TEST_512: SelfTest = SelfTest::new();
+static SELF_TEST_768: SelfTest = SelfTest::new();
+static SELF_TEST_1024: SelfTest = SelfTest::new();
+
+/// The self-test state of the parameter set with dimension `k`
+pub(crate) fn state(k: usize) -> &'static SelfTest {
+    match k {
+        2 => &SELF_TEST_512,
+        3 => &SELF_TEST_768,
+        _ => &SELF_TEST_1024,
+    }
+}
+
+/// Whether the self-tests of the parameter set with dimension `k` have passed (without running them)
+pub(crate) fn passed(k: usize) -> bool { state(k).0.load(Ordering::Acquire) == PASSED }
@@ -0,0 +1,56 @@
+// Serde support (with the `serde` feature) for the fixed-length byte arrays underlying the keys,
+// ciphertexts and shared secrets. The arrays are (de)serialized as a tuple of `N` bytes, which
+// matches how serde handles (short) native arrays. Validation is left to the per-parameter-set
+// `Deserialize` impls, which run the resulting array through `SerDes::try_from_bytes()`.
+
+use core::fmt;
+use serde::de::{Error, SeqAccess, Visitor};
+use serde::ser::SerializeTuple;
+use serde::{Deserializer, Serializer};
+use zeroize::Zeroizing;
+
+
+/// Serializes `bytes` as a fixed-length tuple of `N` bytes.
+pub(crate) fn serialize_array<S: Serializer, const N: usize>(
+    bytes: &[u8; N], serializer: S,
+) -> Result<S::Ok, S::Error> {
+    let mut tuple = serializer.serialize_tuple(N)?;
+    for byte in bytes {
+        tuple.serialize_element(byte)?;
+    }
+    tuple.end()
+}
+
+
+/// Deserializes a fixed-length tuple (or byte string) of exactly `N` bytes.
+pub(crate) fn deserialize_array<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[u8; N], D::Error> {
+    deserializer.deserialize_tuple(N, ArrayVisitor::<N>)
+}
+
+
+struct ArrayVisitor<const N: usize>;
+
+impl<'de, const N: usize> Visitor<'de> for ArrayVisitor<N> {
+    type Value = [u8; N];
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "an array of {N} bytes")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut out = Zeroizing::new([0u8; N]); // may hold secret bytes on an early return
+        for (i, byte) in out.iter_mut().enumerate() {
+            *byte = seq.next_element()?.ok_or_else(|| A::Error::invalid_length(i, &self))?;
+        }
+        if seq.next_element::<u8>()?.is_some() {
+            return Err(A::Error::invalid_length(N + 1, &self));
+        }
+        Ok(*out)
+    }
+
+    fn visit_bytes<E: Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        bytes.try_into().map_err(|_| E::invalid_length(bytes.len(), &self))
+    }
+}
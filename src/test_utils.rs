@@ -0,0 +1,91 @@
+// Deterministic random number generator for known-answer tests against the public API (with the
+// `test-utils` feature). It replays queued byte strings in order, so that each call to
+// `try_fill_bytes()` (e.g., the d and z seeds of key generation, or the m seed of encapsulation)
+// receives exactly the supplied bytes.
+
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+use rand_core::{CryptoRng, RngCore};
+
+
+/// A queueing (first-in, first-out) random number generator that replays supplied byte strings,
+/// for test purposes only. Each fill request consumes the next queued byte string, which must be
+/// of exactly the requested length; an empty queue or length mismatch panics. Available with the
+/// `test-utils` feature.
+///
+/// **It must never be used outside of tests, as its output is entirely predictable.**
+///
+/// # Examples
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # #[cfg(feature = "ml-kem-512")] {
+/// use fips203::ml_kem_512;
+/// use fips203::test_utils::SeedRng;
+/// use fips203::traits::{KeyGen, SerDes};
+///
+/// // Key generation draws the 32-byte d seed, followed by the 32-byte z seed
+/// let d: [u8; 32] = core::array::from_fn(|i| i as u8);
+/// let z: [u8; 32] = core::array::from_fn(|i| 255 - i as u8);
+/// let mut rng = SeedRng::new();
+/// rng.push(&d);
+/// rng.push(&z);
+/// let (ek1, _dk1) = ml_kem_512::KG::try_keygen_with_rng(&mut rng)?;
+/// assert!(rng.is_empty());
+///
+/// // The same seeds produce the same keys
+/// let (ek2, _dk2) = ml_kem_512::KG::keygen_from_seed(d, z);
+/// assert_eq!(ek1.into_bytes(), ek2.into_bytes());
+/// # }
+/// # Ok(())}
+/// ```
+#[derive(Clone, Debug, Default)]
+pub struct SeedRng {
+    data: VecDeque<Vec<u8>>,
+}
+
+
+impl SeedRng {
+    /// Creates an empty `SeedRng`.
+    #[must_use]
+    pub fn new() -> Self { Self::default() }
+
+    /// Queues `bytes` to be returned by a later fill request.
+    pub fn push(&mut self, bytes: &[u8]) { self.data.push_back(bytes.to_vec()); }
+
+    /// Returns the number of queued byte strings not yet consumed.
+    #[must_use]
+    pub fn len(&self) -> usize { self.data.len() }
+
+    /// Returns `true` when all queued byte strings have been consumed.
+    #[must_use]
+    pub fn is_empty(&self) -> bool { self.data.is_empty() }
+}
+
+
+impl RngCore for SeedRng {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0u8; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0u8; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, out: &mut [u8]) {
+        let bytes = self.data.pop_front().expect("SeedRng has no bytes queued");
+        assert_eq!(bytes.len(), out.len(), "SeedRng queued bytes length differs from request");
+        out.copy_from_slice(&bytes);
+    }
+
+    fn try_fill_bytes(&mut self, out: &mut [u8]) -> Result<(), rand_core::Error> {
+        self.fill_bytes(out);
+        Ok(()) // panic on problems is OK for test purposes
+    }
+}
+
+impl CryptoRng for SeedRng {}
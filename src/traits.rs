@@ -1,7 +1,50 @@
+use crate::helpers::HedgedRng;
+use crate::SSK_LEN;
 use rand_core::{CryptoRng, CryptoRngCore, RngCore};
 
-#[cfg(feature = "default-rng")]
-use rand_core::OsRng;
+#[cfg(all(feature = "default-rng", not(feature = "getrandom")))]
+pub(crate) use rand_core::OsRng as DefaultRng;
+
+
+/// The `EntropySource` trait is a minimal source of randomness for keygen and encaps, decoupled from
+/// `rand_core`, so that bare-metal users with a hardware TRNG can plug it in directly. All `rand_core`
+/// cryptographic random number generators are entropy sources via a blanket implementation.
+pub trait EntropySource {
+    /// Fills `out` entirely with random bytes.
+    /// # Errors
+    /// Returns an error when the source fails to provide randomness.
+    fn fill(&mut self, out: &mut [u8]) -> Result<(), &'static str>;
+}
+
+impl<R: CryptoRngCore> EntropySource for R {
+    fn fill(&mut self, out: &mut [u8]) -> Result<(), &'static str> {
+        self.try_fill_bytes(out).map_err(|_| "Random number generator failed")
+    }
+}
+
+// With the `getrandom` feature, `try_keygen()` and `try_encaps()` pull randomness straight from
+// `getrandom` rather than `rand_core::OsRng`
+#[cfg(feature = "getrandom")]
+pub(crate) struct DefaultRng;
+
+#[cfg(feature = "getrandom")]
+impl EntropySource for DefaultRng {
+    fn fill(&mut self, out: &mut [u8]) -> Result<(), &'static str> {
+        getrandom::getrandom(out).map_err(|_| "Random number generator failed")
+    }
+}
+
+
+/// The `KeccakBackend` trait provides the `Keccak-f[1600]` permutation underlying SHA3-256, SHA3-512,
+/// SHAKE128 and SHAKE256, so that platforms with a SHA-3 hardware accelerator or a validated Keccak
+/// core can use it in place of the software implementation. Register it via `set_keccak_backend()`,
+/// available (along with this trait) with the `custom-keccak` feature.
+#[cfg(feature = "custom-keccak")]
+pub trait KeccakBackend {
+    /// Applies the 24-round `Keccak-f[1600]` permutation in place, with lane `(x, y)` of the state at
+    /// index `x + 5·y` (and bytes within each lane in little-endian order).
+    fn keccak_f1600(state: &mut [u64; 25]);
+}
 
 
 /// The `KeyGen` trait is defined to allow trait objects.
@@ -47,9 +90,9 @@ pub trait KeyGen {
     /// # }
     /// # Ok(())}
     /// ```
-    #[cfg(feature = "default-rng")]
+    #[cfg(any(feature = "default-rng", feature = "getrandom"))]
     fn try_keygen() -> Result<(Self::EncapsKey, Self::DecapsKey), &'static str> {
-        Self::try_keygen_with_rng(&mut OsRng)
+        Self::try_keygen_with_entropy(&mut DefaultRng)
     }
 
 
@@ -90,6 +133,43 @@ pub trait KeyGen {
     ) -> Result<(Self::EncapsKey, Self::DecapsKey), &'static str>;
 
 
+    /// Generates an encapsulation and decapsulation key pair specific to this security parameter set. <br>
+    /// This function utilizes a provided `EntropySource` (e.g., a hardware TRNG) and is intended to operate
+    /// in constant time outside of `rho` which crosses the trust boundary in the clear.
+    /// # Errors
+    /// Returns an error when the entropy source fails or an internal error condition arises.
+    /// # Examples
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # #[cfg(feature = "ml-kem-512")] {
+    /// use fips203::ml_kem_512;  // Could also be ml_kem_768 or ml_kem_1024.
+    /// use fips203::traits::{Decaps, Encaps, EntropySource, KeyGen};
+    ///
+    /// # fn trng_read_u8() -> u8 { use rand_core::RngCore; rand_core::OsRng.next_u32().to_le_bytes()[0] }
+    /// struct Trng();  // Stands in for a hardware TRNG peripheral
+    ///
+    /// impl EntropySource for Trng {
+    ///     fn fill(&mut self, out: &mut [u8]) -> Result<(), &'static str> {
+    ///         out.iter_mut().for_each(|b| *b = trng_read_u8());
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let (ek, dk) = ml_kem_512::KG::try_keygen_with_entropy(&mut Trng())?;
+    /// let (ssk1, ct) = ek.try_encaps_with_entropy(&mut Trng())?;
+    /// let ssk2 = dk.try_decaps(&ct)?;
+    /// assert_eq!(ssk1, ssk2);
+    /// # }
+    /// # Ok(())}
+    /// ```
+    fn try_keygen_with_entropy(
+        src: &mut impl EntropySource,
+    ) -> Result<(Self::EncapsKey, Self::DecapsKey), &'static str> {
+        Self::try_keygen_with_rng(&mut EntropyRng { src })
+    }
+
+
     /// Generates an encapsulation and decapsulation key key pair specific to this security parameter set
     /// based on a provided seed. <br>
     /// This function is intended to operate in constant time outside of `rho` which crosses the trust
@@ -134,11 +214,15 @@ pub trait KeyGen {
     /// Performs validation between an encapsulation key and a decapsulation key (both in byte arrays), perhaps in the
     /// scenario where both have been serialized, stored to disk, and then retrieved. This function is not intended
     /// to operate in constant-time.
+    ///
+    /// **Without both the `encaps` and `decaps` features, this always returns `false`**, even for a valid pair, as
+    /// the check includes an encaps/decaps round trip. A `keygen`-only build therefore cannot validate a pair, and
+    /// should not treat `false` as evidence of a corrupted key.
     /// # Examples
     /// ```rust
     /// # use std::error::Error;
     /// # fn main() -> Result<(), Box<dyn Error>> {
-    /// # #[cfg(feature = "ml-kem-512")] {
+    /// # #[cfg(all(feature = "ml-kem-512", feature = "encaps", feature = "decaps"))] {
     /// use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
     /// use fips203::traits::{Decaps, Encaps, KeyGen, SerDes};
     /// use rand_core::OsRng;
@@ -199,9 +283,9 @@ pub trait Encaps {
     /// # }
     /// # Ok(())}
     /// ```
-    #[cfg(feature = "default-rng")]
+    #[cfg(any(feature = "default-rng", feature = "getrandom"))]
     fn try_encaps(&self) -> Result<(Self::SharedSecretKey, Self::CipherText), &'static str> {
-        self.try_encaps_with_rng(&mut OsRng)
+        self.try_encaps_with_entropy(&mut DefaultRng)
     }
 
 
@@ -242,6 +326,50 @@ pub trait Encaps {
     ) -> Result<(Self::SharedSecretKey, Self::CipherText), &'static str>;
 
 
+    /// Generates a shared secret and ciphertext from an encapsulation key specific to this security parameter set. <br>
+    /// This function utilizes a provided `EntropySource` (e.g., a hardware TRNG) and is intended to operate in
+    /// constant time. See `KeyGen::try_keygen_with_entropy()` for an example.
+    /// # Errors
+    /// Returns an error when the entropy source fails or an internal error condition arises.
+    fn try_encaps_with_entropy(
+        &self, src: &mut impl EntropySource,
+    ) -> Result<(Self::SharedSecretKey, Self::CipherText), &'static str> {
+        self.try_encaps_with_rng(&mut EntropyRng { src })
+    }
+
+
+    /// Generates a shared secret and ciphertext from an encapsulation key specific to this security parameter set. <br>
+    /// This function utilizes a provided random number generator in a **hedged** fashion: rather than trusting
+    /// the rng output directly, the message is derived as `m ← SHAKE256(rng_output ‖ H(ek) ‖ counter)`. This
+    /// offers some protection on platforms with a low quality rng, but is not a substitute for a good one. The
+    /// resulting ciphertext is decapsulated as normal. This function is intended to operate in constant time.
+    /// The provided implementation has no access to the key bytes and so uses an all-zero `H(ek)`; the
+    /// implementations in this crate override it to bind the actual `H(ek)`.
+    /// # Errors
+    /// Returns an error when the random number generator fails or an internal error condition arises.
+    /// # Examples
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # #[cfg(feature = "ml-kem-512")] {
+    /// use fips203::ml_kem_512; // Could also be ml_kem_768 or ml_kem_1024.
+    /// use fips203::traits::{Decaps, Encaps, KeyGen};
+    /// use rand_core::OsRng;
+    ///
+    /// let (ek, dk) = ml_kem_512::KG::try_keygen_with_rng(&mut OsRng)?;
+    /// let (ssk1, ct) = ek.try_encaps_hedged_with_rng(&mut OsRng)?;
+    /// let ssk2 = dk.try_decaps(&ct)?;
+    /// assert_eq!(ssk1, ssk2);
+    /// # }
+    /// # Ok(())}
+    /// ```
+    fn try_encaps_hedged_with_rng(
+        &self, rng: &mut impl CryptoRngCore,
+    ) -> Result<(Self::SharedSecretKey, Self::CipherText), &'static str> {
+        self.try_encaps_with_rng(&mut HedgedRng::new(rng, [0u8; 32]))
+    }
+
+
     /// Generates a shared secret and ciphertext from an encapsulation key specific to this security parameter set. <br>
     /// This function utilizes a provided **seed** (rather than a random number generator) and is intended to operate in constant
     /// time.
@@ -275,7 +403,57 @@ pub trait Encaps {
     /// # Ok(())}
     /// ```
     fn encaps_from_seed(&self, seed: &[u8; 32]) -> (Self::SharedSecretKey, Self::CipherText) {
-        self.try_encaps_with_rng(&mut DummyRng { data: *seed }).expect("rng will not fail")
+        let result =
+            self.try_encaps_with_rng(&mut DummyRng { data: *seed }).expect("rng will not fail");
+        crate::indicator::record_supplied_rng();
+        result
+    }
+}
+
+/// Encapsulation through a reference, so that keys held in an `Arc` or a collection satisfy
+/// `Encaps` bounds without being cloned.
+/// # Examples
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # #[cfg(feature = "ml-kem-512")] {
+/// use fips203::ml_kem_512;
+/// use fips203::traits::{Decaps, Encaps, KeyGen};
+/// use rand_core::OsRng;
+/// use std::sync::Arc;
+///
+/// fn encaps_generic<E: Encaps>(
+///     ek: E,
+/// ) -> Result<(E::SharedSecretKey, E::CipherText), &'static str> {
+///     ek.try_encaps_with_rng(&mut OsRng)
+/// }
+///
+/// let (ek, dk) = ml_kem_512::KG::try_keygen_with_rng(&mut OsRng)?;
+/// let (ek, dk) = (Arc::new(ek), Arc::new(dk));
+/// let (ssk1, ct) = encaps_generic(&*ek)?;
+/// let ssk2 = (&*dk).try_decaps(&ct)?;
+/// assert_eq!(ssk1, ssk2);
+/// # }
+/// # Ok(())}
+/// ```
+impl<T: Encaps + ?Sized> Encaps for &T {
+    type CipherText = T::CipherText;
+    type SharedSecretKey = T::SharedSecretKey;
+
+    fn try_encaps_with_rng(
+        &self, rng: &mut impl CryptoRngCore,
+    ) -> Result<(Self::SharedSecretKey, Self::CipherText), &'static str> {
+        (**self).try_encaps_with_rng(rng)
+    }
+
+    fn try_encaps_hedged_with_rng(
+        &self, rng: &mut impl CryptoRngCore,
+    ) -> Result<(Self::SharedSecretKey, Self::CipherText), &'static str> {
+        (**self).try_encaps_hedged_with_rng(rng)
+    }
+
+    fn encaps_from_seed(&self, seed: &[u8; 32]) -> (Self::SharedSecretKey, Self::CipherText) {
+        (**self).encaps_from_seed(seed)
     }
 }
 
@@ -299,6 +477,31 @@ impl RngCore for DummyRng {
 
 impl CryptoRng for DummyRng {}
 
+// Adapts an `EntropySource` into the rng expected internally
+pub(crate) struct EntropyRng<'a, E: EntropySource> {
+    pub(crate) src: &'a mut E,
+}
+
+impl<E: EntropySource> RngCore for EntropyRng<'_, E> {
+    fn next_u32(&mut self) -> u32 { unimplemented!() }
+
+    fn next_u64(&mut self) -> u64 { unimplemented!() }
+
+    fn fill_bytes(&mut self, _out: &mut [u8]) { unimplemented!() }
+
+    fn try_fill_bytes(&mut self, out: &mut [u8]) -> Result<(), rand_core::Error> {
+        // The (constant) error code is checked as non-zero at compile time
+        const CODE: core::num::NonZeroU32 =
+            match core::num::NonZeroU32::new(rand_core::Error::CUSTOM_START) {
+                Some(code) => code,
+                None => panic!("rand_core::Error::CUSTOM_START is zero"),
+            };
+        self.src.fill(out).map_err(|_| rand_core::Error::from(CODE))
+    }
+}
+
+impl<E: EntropySource> CryptoRng for EntropyRng<'_, E> {}
+
 /// The `Decaps` trait uses the decapsulation key and ciphertext to generate the shared secret.
 pub trait Decaps {
     /// Ciphertext struct
@@ -341,6 +544,125 @@ pub trait Decaps {
     fn try_decaps(&self, ct: &Self::CipherText) -> Result<Self::SharedSecretKey, &'static str>;
 }
 
+/// Decapsulation through a reference, so that keys held in an `Arc` or a collection satisfy
+/// `Decaps` bounds without being cloned. See the `Encaps` implementation for `&T` for an example.
+impl<T: Decaps + ?Sized> Decaps for &T {
+    type CipherText = T::CipherText;
+    type SharedSecretKey = T::SharedSecretKey;
+
+    fn try_decaps(&self, ct: &Self::CipherText) -> Result<Self::SharedSecretKey, &'static str> {
+        (**self).try_decaps(ct)
+    }
+}
+
+
+/// The `KeyStore` trait fronts decapsulation keys held outside of normal RAM, such as in a secure
+/// element or behind a `TrustZone` secure-world call, where each key is known only by an opaque
+/// handle. Wrapping a handle in a parameter set's `StoredDecapsKey` yields a `Decaps`
+/// implementation that delegates to the store, so it can be used wherever a `DecapsKey` is.
+/// The private key material is never seen by this crate; only the ciphertext goes in and only
+/// the shared secret comes out. Stores that need exclusive access to their hardware should use
+/// interior mutability (e.g., a critical section), as decapsulation takes `&self`.
+/// # Examples
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # #[cfg(feature = "ml-kem-512")] {
+/// use fips203::ml_kem_512;
+/// use fips203::traits::{Decaps, Encaps, KeyGen, KeyStore, SerDes};
+/// use rand_core::OsRng;
+///
+/// // A stand-in for a secure element holding a single key in slot 7
+/// struct SecureElement(ml_kem_512::DecapsKey);
+///
+/// impl KeyStore for SecureElement {
+///     type Handle = u8;
+///
+///     fn decaps(&self, slot: &u8, ct: &[u8], ssk: &mut [u8; 32]) -> Result<(), &'static str> {
+///         if *slot != 7 {
+///             return Err("No key in slot");
+///         }
+///         let ct = ml_kem_512::CipherText::try_from(ct)?;
+///         *ssk = self.0.try_decaps(&ct)?.into_bytes();
+///         Ok(())
+///     }
+/// }
+///
+/// let (ek, dk) = ml_kem_512::KG::try_keygen_with_rng(&mut OsRng)?;
+/// let element = SecureElement(dk); // The decapsulation key is now 'in the hardware'
+///
+/// let stored_dk = ml_kem_512::StoredDecapsKey::new(&element, 7);
+/// let (ssk1, ct) = ek.try_encaps_with_rng(&mut OsRng)?;
+/// let ssk2 = stored_dk.try_decaps(&ct)?;
+/// assert_eq!(ssk1, ssk2);
+/// # }
+/// # Ok(())}
+/// ```
+pub trait KeyStore {
+    /// Opaque identifier of a decapsulation key within the store (e.g., a slot number).
+    type Handle;
+
+    /// Decapsulates the serialized ciphertext `ct` with the key identified by `handle`, writing
+    /// the shared secret to `ssk`. The length of `ct` identifies the security parameter set.
+    /// # Errors
+    /// Returns an error when the handle does not identify a key of the matching security
+    /// parameter set, or when the store fails.
+    fn decaps(
+        &self, handle: &Self::Handle, ct: &[u8], ssk: &mut [u8; SSK_LEN],
+    ) -> Result<(), &'static str>;
+}
+
+
+/// The `Kem` trait ties together `KeyGen`, the corresponding `Encaps`/`Decaps` key types, the ciphertext
+/// and the length constants of a security parameter set, so that generic code needs only a single bound.
+/// It is implemented by each parameter set's `KG` struct. As `KeyGen` also names the key types, generic
+/// code refers to them as `<P as Kem>::EncapsKey` (though they rarely need to be named at all).
+/// # Examples
+/// ```rust
+/// # use std::error::Error;
+/// # fn main() -> Result<(), Box<dyn Error>> {
+/// # #[cfg(all(feature = "ml-kem-512", feature = "ml-kem-1024"))] {
+/// use fips203::traits::{Decaps, Encaps, Kem, KeyGen, SerDes};
+/// use fips203::{ml_kem_1024, ml_kem_512};
+/// use rand_core::OsRng;
+///
+/// fn round_trip<P: Kem>() -> Result<bool, &'static str> {
+///     let (ek, dk) = P::try_keygen_with_rng(&mut OsRng)?;
+///     let (ssk1, ct) = ek.try_encaps_with_rng(&mut OsRng)?;
+///     let ct_bytes = ct.into_bytes(); // Serialize and deserialize the ciphertext
+///     let ct = <P as Kem>::CipherText::try_from_bytes(ct_bytes)?;
+///     let ssk2 = dk.try_decaps(&ct)?;
+///     Ok(ssk1 == ssk2)
+/// }
+///
+/// assert!(round_trip::<ml_kem_512::KG>()?);
+/// assert!(round_trip::<ml_kem_1024::KG>()?);
+/// assert_eq!(<ml_kem_512::KG as Kem>::CT_LEN, ml_kem_512::CT_LEN);
+/// # }
+/// # Ok(())}
+/// ```
+pub trait Kem:
+    KeyGen<EncapsKey = <Self as Kem>::EncapsKey, DecapsKey = <Self as Kem>::DecapsKey>
+{
+    /// The (public) encapsulation key, supporting encapsulation and serialization.
+    type EncapsKey: Encaps<SharedSecretKey = Self::SharedSecretKey, CipherText = Self::CipherText>
+        + SerDes<ByteArray = Self::EncapsByteArray>;
+    /// The (private) decapsulation key, supporting decapsulation and serialization.
+    type DecapsKey: Decaps<SharedSecretKey = Self::SharedSecretKey, CipherText = Self::CipherText>
+        + SerDes<ByteArray = Self::DecapsByteArray>;
+    /// The ciphertext transmitted from the remote party to the originator.
+    type CipherText: SerDes;
+    /// The common shared secret
+    type SharedSecretKey: SerDes + PartialEq;
+
+    /// Serialized Encapsulation Key Length (in bytes)
+    const EK_LEN: usize;
+    /// Serialized Decapsulation Key Length (in bytes)
+    const DK_LEN: usize;
+    /// Serialized Ciphertext Length (in bytes)
+    const CT_LEN: usize;
+}
+
 
 /// Serialization and Deserialization of structs
 pub trait SerDes {
@@ -412,4 +734,41 @@ pub trait SerDes {
     fn try_from_bytes(ba: Self::ByteArray) -> Result<Self, &'static str>
     where
         Self: Sized;
+
+
+    /// Borrows a byte array of fixed-size specific to the struct being deserialized; performs validation.
+    /// This avoids passing the (potentially large) byte array by value, which is helpful on constrained
+    /// targets, as the bytes are only copied once validated. The provided implementation clones the
+    /// byte array and defers to `try_from_bytes()`; the types in this crate override it to avoid the copy.
+    /// # Errors
+    /// Returns an error on malformed input.
+    /// # Examples
+    /// ```rust
+    /// # use std::error::Error;
+    /// # fn main() -> Result<(), Box<dyn Error>> {
+    /// # #[cfg(feature = "ml-kem-512")] {
+    /// use rand_core::OsRng;
+    /// use fips203::ml_kem_512;  // Could also be ml_kem_768 or ml_kem_1024.
+    /// use fips203::traits::{KeyGen, SerDes, Decaps, Encaps};
+    ///
+    /// let (ek1, dk1) = ml_kem_512::KG::try_keygen_with_rng(&mut OsRng)?;  // Party 1 generates both encaps and decaps keys
+    /// let ek1_bytes = ek1.into_bytes();  // Party 1 serializes the encaps key
+    ///
+    /// let ek2 = ml_kem_512::EncapsKey::try_from_bytes_ref(&ek1_bytes)?;  // Party 2 deserializes by reference
+    /// let (ssk2, ct2) = ek2.try_encaps_with_rng(&mut OsRng)?;  // Party 2 generates shared secret and ciphertext
+    ///
+    /// let ct1 = ml_kem_512::CipherText::try_from_bytes_ref(ct2.as_bytes())?;  // Party 1 deserializes by reference
+    /// let ssk1 = dk1.try_decaps(&ct1)?;  // Party 1 runs decaps to generate the shared secret
+    ///
+    /// assert_eq!(ssk1, ssk2);  // Each party has the same shared secret
+    /// # }
+    /// # Ok(())}
+    /// ```
+    fn try_from_bytes_ref(ba: &Self::ByteArray) -> Result<Self, &'static str>
+    where
+        Self: Sized,
+        Self::ByteArray: Clone,
+    {
+        Self::try_from_bytes(ba.clone())
+    }
 }
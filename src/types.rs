@@ -1,9 +1,17 @@
+use crate::ct_mul::mul;
+use crate::helpers::h;
+#[cfg(feature = "decaps")]
+use crate::traits::KeyStore;
 use crate::Q;
+use core::fmt;
+use subtle::{Choice, ConstantTimeEq};
 use zeroize::{Zeroize, ZeroizeOnDrop};
 
 
 /// Correctly sized encapsulation key specific to the target security parameter set.
-#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+#[derive(Clone, Eq, Hash, PartialEq, Zeroize, ZeroizeOnDrop)]
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "rkyv", archive(check_bytes))]
 #[repr(align(8))]
 pub struct EncapsKey<const EK_LEN: usize>(pub(crate) [u8; EK_LEN]);
 
@@ -13,19 +21,271 @@ pub struct EncapsKey<const EK_LEN: usize>(pub(crate) [u8; EK_LEN]);
 #[repr(align(8))]
 pub struct DecapsKey<const DK_LEN: usize>(pub(crate) [u8; DK_LEN]);
 
+impl<const DK_LEN: usize> ConstantTimeEq for DecapsKey<DK_LEN> {
+    fn ct_eq(&self, other: &Self) -> Choice { self.0.ct_eq(&other.0) }
+}
+
+// Conservative constant-time support
+impl<const DK_LEN: usize> PartialEq for DecapsKey<DK_LEN> {
+    fn eq(&self, other: &Self) -> bool { bool::from(self.ct_eq(other)) }
+}
+
+impl<const DK_LEN: usize> Eq for DecapsKey<DK_LEN> {}
+
+// With the `secrecy` feature, `Secret<DecapsKey>` supports `Clone` and a redacted `Debug`
+#[cfg(feature = "secrecy")]
+impl<const DK_LEN: usize> secrecy::CloneableSecret for DecapsKey<DK_LEN> {}
+
+#[cfg(feature = "secrecy")]
+impl<const DK_LEN: usize> secrecy::DebugSecret for DecapsKey<DK_LEN> {}
+
 
 /// Correctly sized ciphertext specific to the target security parameter set.
-#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+#[derive(Clone, Eq, Hash, PartialEq, Zeroize, ZeroizeOnDrop)]
+#[cfg_attr(feature = "rkyv", derive(rkyv::Archive, rkyv::Serialize, rkyv::Deserialize))]
+#[cfg_attr(feature = "rkyv", archive(check_bytes))]
 #[repr(align(8))]
 pub struct CipherText<const CT_LEN: usize>(pub(crate) [u8; CT_LEN]);
 
 
-// While Z is simple and correct, the performance is somewhat suboptimal.
-// This will be addressed (particularly in matrix operations etc) over
-// the medium-term - potentially using 256-entry rows.
+/// Validated encapsulation key borrowed from caller-owned bytes (e.g., flash, mmap or a static
+/// buffer), specific to the target security parameter set. The key material is not copied.
+#[derive(Clone, Copy)]
+pub struct EncapsKeyRef<'a, const EK_LEN: usize>(pub(crate) &'a [u8; EK_LEN]);
+
+
+/// Validated decapsulation key borrowed from caller-owned bytes (e.g., flash, mmap or a static
+/// buffer), specific to the target security parameter set. The key material is not copied, and
+/// so zeroization remains the responsibility of the owner of the bytes.
+#[derive(Clone, Copy)]
+pub struct DecapsKeyRef<'a, const DK_LEN: usize>(pub(crate) &'a [u8; DK_LEN]);
+
+
+/// Decapsulation key held by a `KeyStore` (e.g., a secure element) and known here only by its
+/// opaque handle, specific to the target security parameter set. Decapsulation is delegated to
+/// the store, so the private key material never enters this crate.
+#[cfg(feature = "decaps")]
+pub struct StoredDecapsKey<'a, S: KeyStore, const CT_LEN: usize> {
+    pub(crate) store: &'a S,
+    pub(crate) handle: S::Handle,
+}
+
+#[cfg(feature = "decaps")]
+impl<'a, S: KeyStore, const CT_LEN: usize> StoredDecapsKey<'a, S, CT_LEN> {
+    /// Fronts the key identified by `handle` within `store`. The handle is not checked here;
+    /// the store reports an unknown handle upon decapsulation.
+    pub const fn new(store: &'a S, handle: S::Handle) -> Self { Self { store, handle } }
+
+    /// Returns the handle identifying the key within the store.
+    pub const fn handle(&self) -> &S::Handle { &self.handle }
+}
+
+
+/// Encapsulation key with the decoded `t_hat`, sampled `A_hat` and `H(ek)` cached, specific to
+/// the target security parameter set. This supports repeated encapsulation to the same key.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+#[cfg(feature = "encaps")]
+pub struct EncapsKeyExpanded<const K: usize> {
+    pub(crate) t_hat: [Poly; K],
+    pub(crate) a_hat: [[Poly; K]; K],
+    pub(crate) h_ek: [u8; 32],
+}
+
+
+/// Decapsulation key with the decoded `s_hat`, `t_hat`, sampled `A_hat`, `H(ek)` and `z` cached,
+/// specific to the target security parameter set. This supports repeated decapsulation.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+#[cfg(feature = "decaps")]
+pub struct DecapsKeyExpanded<const K: usize> {
+    pub(crate) s_hat: [Poly; K],
+    pub(crate) t_hat: [Poly; K],
+    pub(crate) a_hat: [[Poly; K]; K],
+    pub(crate) h_ek: [u8; 32],
+    pub(crate) z: [u8; 32],
+}
+
+
+/// Caller-owned scratch space for key generation, encapsulation and decapsulation, specific to the
+/// target security parameter set. Reusing one workspace across calls (e.g., per thread in a server
+/// loop) avoids re-initializing the multi-kilobyte decoded keys, `A_hat` matrix and re-encryption
+/// ciphertext on every call. As `Workspace::new()` is a `const fn`, the workspace can also be a
+/// `static` (e.g., in a dedicated linker section), so that its RAM is accounted for by the linker
+/// rather than the stack. It holds secret material between calls and is zeroized on drop.
+#[derive(Clone, Zeroize, ZeroizeOnDrop)]
+#[cfg(any(feature = "keygen", feature = "encaps", feature = "decaps"))]
+pub struct Workspace<const K: usize, const CT_LEN: usize> {
+    pub(crate) s_hat: [Poly; K],
+    pub(crate) t_hat: [Poly; K],
+    pub(crate) a_hat: [[Poly; K]; K],
+    pub(crate) c_prime: [u8; CT_LEN],
+}
+
+#[cfg(any(feature = "keygen", feature = "encaps", feature = "decaps"))]
+impl<const K: usize, const CT_LEN: usize> Workspace<K, CT_LEN> {
+    /// Returns a zeroed workspace. Note that a `static` workspace is never dropped, so it should
+    /// be zeroized (via `Zeroize`) once its secret material is no longer needed.
+    #[must_use]
+    pub const fn new() -> Self {
+        Workspace {
+            s_hat: [Poly::ZERO; K],
+            t_hat: [Poly::ZERO; K],
+            a_hat: [[Poly::ZERO; K]; K],
+            c_prime: [0u8; CT_LEN],
+        }
+    }
+}
+
+#[cfg(any(feature = "keygen", feature = "encaps", feature = "decaps"))]
+impl<const K: usize, const CT_LEN: usize> Default for Workspace<K, CT_LEN> {
+    fn default() -> Self { Self::new() }
+}
+
+
+// Public data prints its length and fingerprint `H(..)`; secret data prints a redacted placeholder
+impl<const EK_LEN: usize> fmt::Debug for EncapsKey<EK_LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncapsKey")
+            .field("len", &EK_LEN)
+            .field("fingerprint", &Hex(&h(&self.0)))
+            .finish()
+    }
+}
+
+impl<const DK_LEN: usize> fmt::Debug for DecapsKey<DK_LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DecapsKey(<redacted>)")
+    }
+}
+
+impl<const EK_LEN: usize> fmt::Debug for EncapsKeyRef<'_, EK_LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncapsKeyRef")
+            .field("len", &EK_LEN)
+            .field("fingerprint", &Hex(&h(self.0)))
+            .finish()
+    }
+}
+
+impl<const DK_LEN: usize> fmt::Debug for DecapsKeyRef<'_, DK_LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DecapsKeyRef(<redacted>)")
+    }
+}
+
+impl<const CT_LEN: usize> fmt::Debug for CipherText<CT_LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CipherText")
+            .field("len", &CT_LEN)
+            .field("fingerprint", &Hex(&h(&self.0)))
+            .finish()
+    }
+}
+
+#[cfg(feature = "encaps")]
+impl<const K: usize> fmt::Debug for EncapsKeyExpanded<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncapsKeyExpanded")
+            .field("fingerprint", &Hex(&self.h_ek))
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "decaps")]
+impl<const K: usize> fmt::Debug for DecapsKeyExpanded<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("DecapsKeyExpanded(<redacted>)")
+    }
+}
+
+#[cfg(any(feature = "keygen", feature = "encaps", feature = "decaps"))]
+impl<const K: usize, const CT_LEN: usize> fmt::Debug for Workspace<K, CT_LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Workspace(<redacted>)")
+    }
+}
+
+// Public data also displays (and formats as lowercase hex) a short fingerprint, i.e., the first 8
+// bytes of `H(..)`, so that logs can identify it; the alternate flag `{:#x}` adds a `0x` prefix
+impl<const EK_LEN: usize> fmt::Display for EncapsKey<EK_LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { fmt::LowerHex::fmt(self, f) }
+}
+
+impl<const EK_LEN: usize> fmt::LowerHex for EncapsKey<EK_LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { short_fingerprint(&self.0, f) }
+}
+
+impl<const CT_LEN: usize> fmt::Display for CipherText<CT_LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { fmt::LowerHex::fmt(self, f) }
+}
+
+impl<const CT_LEN: usize> fmt::LowerHex for CipherText<CT_LEN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result { short_fingerprint(&self.0, f) }
+}
+
+fn short_fingerprint(bytes: &[u8], f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    if f.alternate() {
+        f.write_str("0x")?;
+    }
+    write!(f, "{:?}", Hex(&h(bytes)[0..8]))
+}
+
+// Prints bytes as lowercase hex without allocation
+struct Hex<'a>(&'a [u8]);
+
+impl fmt::Debug for Hex<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for byte in self.0 {
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+
+// Polynomials are stored as 256-entry rows of `Z` aligned to 64 bytes, so a vector `[Poly; K]`
+// (or matrix `[[Poly; K]; K]`) is one contiguous block of K·512 (or K²·512) bytes and every row
+// starts on a cache line. This suits both the SIMD backends (aligned loads) and autovectorization.
+
+/// A polynomial (or its NTT representation) as an aligned 256-entry row of coefficients
+#[derive(Clone, Copy, Zeroize)]
+#[repr(C, align(64))] // the SIMD backends use aligned loads and stores
+pub(crate) struct Poly(pub(crate) [Z; 256]);
+
+impl Poly {
+    pub(crate) const ZERO: Poly = Poly([Z(0); 256]);
+}
+
+impl Default for Poly {
+    fn default() -> Self { Poly::ZERO }
+}
+
+impl core::ops::Deref for Poly {
+    type Target = [Z; 256];
+
+    fn deref(&self) -> &Self::Target { &self.0 }
+}
+
+impl core::ops::DerefMut for Poly {
+    fn deref_mut(&mut self) -> &mut Self::Target { &mut self.0 }
+}
+
+impl<'a> IntoIterator for &'a Poly {
+    type IntoIter = core::slice::Iter<'a, Z>;
+    type Item = &'a Z;
+
+    fn into_iter(self) -> Self::IntoIter { self.0.iter() }
+}
+
+impl<'a> IntoIterator for &'a mut Poly {
+    type IntoIter = core::slice::IterMut<'a, Z>;
+    type Item = &'a mut Z;
+
+    fn into_iter(self) -> Self::IntoIter { self.0.iter_mut() }
+}
 
 /// Stored as u16 for space, but arithmetic as u32 for perf
-#[derive(Clone, Copy, Default)]
+#[derive(Clone, Copy, Default, Zeroize)]
+#[repr(transparent)] // the AVX2 backend loads and stores arrays of Z as u16
 pub(crate) struct Z(pub(crate) u16);
 
 
@@ -58,50 +318,169 @@ impl Z {
         Self(res as u16)
     }
 
+    // Signed Montgomery reduction: returns `a·2^{-16} mod q` for `|a| < q·2^15`. The zetas (and
+    // gammas) are stored in Montgomery form `ζ·2^16 mod q`, so that a single reduction returns the
+    // exact product. Both `t` and the shifted result are computed without branches.
     #[inline(always)]
-    #[allow(clippy::cast_possible_truncation)] // rem as u16; for perf
-    pub(crate) fn mul(self, other: Self) -> Self {
-        const M: u64 = ((1u64 << 36) + Q as u64 - 1) / Q as u64;
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)] // r as u16; for perf
+    pub(crate) fn mont_reduce(a: i32) -> Self {
+        let r = Self::mont_reduce_lazy(a);
+        let r = r + ((r >> 31) & i32::from(Q));
+        debug_assert!(r < i32::from(Q));
+        Self(r as u16)
+    }
+
+    // As above, but without the final correction, so the result `r ≡ a·2^{-16}` is in (-q, q)
+    #[inline(always)]
+    #[allow(clippy::cast_possible_truncation)] // (a as i16); for perf
+    pub(crate) fn mont_reduce_lazy(a: i32) -> i32 {
+        const QINV: u16 = 62209; // q^{-1} mod 2^16
+        debug_assert!(a.unsigned_abs() < u32::from(Q) << 15);
+        let t = i32::from(mul(i32::from(a as i16), i32::from(QINV)) as i16); // a·q^{-1} mod 2^16 (signed)
+        (a - mul(t, i32::from(Q))) >> 16 // exact, as a ≡ t·q mod 2^16; r ∈ (-q, q)
+    }
+
+    // Signed Barrett reduction: returns `a mod q` in [0, q) for `|a| < 2^15`, without branches
+    #[inline(always)]
+    pub(crate) fn barrett_reduce(a: i32) -> i32 {
+        const V: i32 = 20159; // round(2^26/q)
+        debug_assert!(a.unsigned_abs() < 1 << 15);
+        let t = (mul(a, V) + (1 << 25)) >> 26; // ≈ round(a/q)
+        let r = a - mul(t, i32::from(Q)); // r ∈ [-(q-1)/2, (q-1)/2]
+        let r = r + ((r >> 31) & i32::from(Q));
+        debug_assert!((0..i32::from(Q)).contains(&r));
+        r
+    }
+
+    #[inline(always)]
+    pub(crate) fn mont_mul(self, other: Self) -> Self {
         debug_assert!(self.0 < Q);
         debug_assert!(other.0 < Q);
-        let prod = u32::from(self.0) * u32::from(other.0); // * debug=strict, release=wrapping
-        let quot = ((u64::from(prod) * M) >> 36) as u32;
-        let rem = prod - quot * u32::from(Q); // further reduction is not needed
-        debug_assert!(rem < u32::from(Q));
-        Self(rem as u16)
+        Self::mont_reduce(mul(i32::from(self.0), i32::from(other.0)))
     }
 
+    // Returns `self·2^16 mod q`, i.e., reverses the `2^{-16}` factor of a `mont_mul()` product
+    #[cfg(any(feature = "keygen", feature = "research"))]
     #[inline(always)]
-    #[allow(clippy::cast_possible_truncation)] // rem as u16; for perf
-    pub(crate) fn base_mul(self, a1: Self, b0: Self, b1: Self, gamma: Self) -> Self {
+    pub(crate) fn to_mont(self) -> Self {
+        const R2: Z = Z(1353); // 2^32 mod q
+        self.mont_mul(R2)
+    }
+
+    // Unreduced, in [0, 2q^2) so that up to 4 results can be summed before `mont_reduce()`
+    #[inline(always)]
+    pub(crate) fn base_mul(self, a1: Self, b0: Self, b1: Self, gamma: Self) -> i32 {
         // 1: c0 ← a0 · b0 + a1 · b1 · γ    ▷ steps 1-2 done modulo q
-        const M: u128 = ((1u128 << 100) + Q as u128 - 1) / Q as u128;
+        // With γ in Montgomery form, the reduced result is c0·2^{-16} (as the result of `mont_mul()`)
         debug_assert!(self.0 < Q);
-        debug_assert!(a1.0 < Q);
         debug_assert!(b0.0 < Q);
-        debug_assert!(b1.0 < Q);
         debug_assert!(gamma.0 < Q);
-        let prod = u64::from(self.0) * u64::from(b0.0)
-            + u64::from(a1.0) * u64::from(b1.0) * u64::from(gamma.0);
-        let quot = (u128::from(prod) * M) >> 100;
-        let rem = u128::from(prod) - quot * u128::from(Q); // further reduction is not needed
-        debug_assert!(rem < u128::from(Q));
-        Self(rem as u16)
+        let a1_b1_gamma = mul(i32::from(a1.mont_mul(b1).0), i32::from(gamma.0)); // ≡ a1·b1·γ
+        mul(i32::from(self.0), i32::from(b0.0)) + a1_b1_gamma
     }
 
+    // Unreduced, in [0, 2q^2) as above
     #[inline(always)]
-    #[allow(clippy::cast_possible_truncation)] // rem as u16; for perf
-    pub(crate) fn base_mul2(self, a1: Self, b0: Self, b1: Self) -> Self {
+    pub(crate) fn base_mul2(self, a1: Self, b0: Self, b1: Self) -> i32 {
         // 2: c1 ← a0 · b1 + a1 · b0
-        const M: u64 = ((1u64 << 36) + Q as u64 - 1) / Q as u64;
+        // The reduced result is c1·2^{-16}, matching `base_mul()`
         debug_assert!(self.0 < Q);
         debug_assert!(a1.0 < Q);
         debug_assert!(b0.0 < Q);
         debug_assert!(b1.0 < Q);
-        let prod = u32::from(self.0) * u32::from(b1.0) + u32::from(a1.0) * u32::from(b0.0); // * debug=strict, release=wrapping
-        let quot = ((u64::from(prod) * M) >> 36) as u32;
-        let rem = prod - quot * u32::from(Q); // further reduction is not needed
-        debug_assert!(rem < u32::from(Q));
-        Self(rem as u16)
+        mul(i32::from(self.0), i32::from(b1.0)) + mul(i32::from(a1.0), i32::from(b0.0))
+    }
+}
+
+
+// Kani (bounded model checking) proofs that the arithmetic above returns fully reduced and correct
+// results for all inputs in its documented domain, run via `cargo kani` (the `kani` CI job). Once
+// passing, these turn the `debug_assert!()`s (which Kani checks as assertions) into machine-checked
+// guarantees, and correctness over the full domain also confirms the embedded constants (`QINV`,
+// `V` and `R2`).
+#[cfg(kani)]
+mod verification {
+    use super::{Q, Z};
+
+    const R_INV: i64 = 169; // 2^{-16} mod q
+
+    fn any_z() -> Z {
+        let a: u16 = kani::any();
+        kani::assume(a < Q);
+        Z(a)
+    }
+
+    // Returns `a mod q` in [0, q)
+    fn modq(a: i64) -> i64 { a.rem_euclid(i64::from(Q)) }
+
+    #[kani::proof]
+    fn add() {
+        let (a, b) = (any_z(), any_z());
+        let res = a.add(b);
+        assert!(res.0 < Q);
+        assert_eq!(i64::from(res.0), modq(i64::from(a.0) + i64::from(b.0)));
+    }
+
+    #[kani::proof]
+    fn sub() {
+        let (a, b) = (any_z(), any_z());
+        let res = a.sub(b);
+        assert!(res.0 < Q);
+        assert_eq!(i64::from(res.0), modq(i64::from(a.0) - i64::from(b.0)));
+    }
+
+    #[kani::proof]
+    fn mont_reduce() {
+        let a: i32 = kani::any();
+        kani::assume(a.unsigned_abs() < u32::from(Q) << 15);
+        let lazy = Z::mont_reduce_lazy(a);
+        assert!(lazy.unsigned_abs() < u32::from(Q));
+        assert_eq!(modq(i64::from(lazy)), modq(i64::from(a) * R_INV));
+        let res = Z::mont_reduce(a);
+        assert!(res.0 < Q);
+        assert_eq!(i64::from(res.0), modq(i64::from(a) * R_INV));
+    }
+
+    #[kani::proof]
+    fn barrett_reduce() {
+        let a: i32 = kani::any();
+        kani::assume(a.unsigned_abs() < 1 << 15);
+        assert_eq!(i64::from(Z::barrett_reduce(a)), modq(i64::from(a)));
+    }
+
+    #[kani::proof]
+    fn mont_mul() {
+        let (a, b) = (any_z(), any_z());
+        let res = a.mont_mul(b);
+        assert!(res.0 < Q);
+        assert_eq!(i64::from(res.0), modq(i64::from(a.0) * i64::from(b.0) * R_INV));
+    }
+
+    #[kani::proof]
+    fn to_mont() {
+        let a = any_z();
+        let res = a.to_mont();
+        assert!(res.0 < Q);
+        assert_eq!(i64::from(res.0), modq(i64::from(a.0) << 16));
+    }
+
+    #[kani::proof]
+    fn base_mul() {
+        let (a0, a1, b0, b1, gamma) = (any_z(), any_z(), any_z(), any_z(), any_z());
+        let res = a0.base_mul(a1, b0, b1, gamma);
+        assert!((0..2 * i32::from(Q) * i32::from(Q)).contains(&res));
+        let a1_b1 = i64::from(a1.0) * i64::from(b1.0) * R_INV;
+        let expected = i64::from(a0.0) * i64::from(b0.0) + a1_b1 * i64::from(gamma.0);
+        assert_eq!(modq(i64::from(res)), modq(expected));
+        assert!(Z::mont_reduce(res).0 < Q); // the range suits a single reduction
+    }
+
+    #[kani::proof]
+    fn base_mul2() {
+        let (a0, a1, b0, b1) = (any_z(), any_z(), any_z(), any_z());
+        let res = a0.base_mul2(a1, b0, b1);
+        assert!((0..2 * i32::from(Q) * i32::from(Q)).contains(&res));
+        let expected = i64::from(a0.0) * i64::from(b1.0) + i64::from(a1.0) * i64::from(b0.0);
+        assert_eq!(modq(i64::from(res)), modq(expected));
     }
 }
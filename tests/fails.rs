@@ -1,3 +1,6 @@
+// These tests exercise all roles
+#![cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+
 #[cfg(feature = "ml-kem-512")]
 use fips203::ml_kem_512;
 use fips203::traits::{KeyGen, SerDes};
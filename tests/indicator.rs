@@ -0,0 +1,44 @@
+// The service indicator is process-wide, so its transitions are exercised by this single test
+#![cfg(all(feature = "keygen", feature = "encaps", feature = "decaps", feature = "ml-kem-768"))]
+
+use fips203::ml_kem_768;
+use fips203::traits::{Decaps, Encaps, KeyGen};
+use fips203::{service_indicator, ServiceIndicator};
+use rand_chacha::rand_core::SeedableRng;
+
+#[test]
+fn service_indicator_transitions() {
+    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(4858);
+    assert_eq!(service_indicator(), ServiceIndicator::None);
+
+    // Before the self-tests, nothing is approved
+    let (ek, dk) = ml_kem_768::KG::try_keygen_with_rng(&mut rng).unwrap();
+    assert_eq!(service_indicator(), ServiceIndicator::SelfTestNotPassed);
+
+    // The self-tests run the services internally, but leave the indicator unchanged (including
+    // those of the other parameter sets, via `fips203::self_test()`)
+    ml_kem_768::self_test().unwrap();
+    assert_eq!(service_indicator(), ServiceIndicator::SelfTestNotPassed);
+    let (ek2, dk2) = ml_kem_768::KG::try_keygen_with_rng(&mut rng).unwrap();
+    assert_eq!(service_indicator(), ServiceIndicator::Approved);
+    fips203::self_test().unwrap();
+    assert_eq!(service_indicator(), ServiceIndicator::Approved);
+    let (ssk1, ct) = ek.try_encaps_with_rng(&mut rng).unwrap();
+    assert_eq!(service_indicator(), ServiceIndicator::Approved);
+
+    // Supplied (rather than drawn) randomness is not approved, while decaps needs none
+    let (_ssk, _ct) = ek2.encaps_from_seed(&[7u8; 32]);
+    assert_eq!(service_indicator(), ServiceIndicator::UnapprovedRng);
+    assert_eq!(ssk1, dk.try_decaps(&ct).unwrap());
+    assert_eq!(service_indicator(), ServiceIndicator::Approved);
+    let _keys = ml_kem_768::KG::keygen_from_seed([1u8; 32], [2u8; 32]);
+    assert_eq!(service_indicator(), ServiceIndicator::UnapprovedRng);
+
+    // The workspace and expanded-key variants report likewise
+    let mut ws = ml_kem_768::Workspace::new();
+    let (_ssk, ct2) = ek2.try_encaps_in_workspace_with_rng(&mut rng, &mut ws).unwrap();
+    assert_eq!(service_indicator(), ServiceIndicator::Approved);
+    let _keys = ml_kem_768::KG::keygen_from_seed([1u8; 32], [2u8; 32]);
+    let _ssk = dk2.try_precompute().unwrap().try_decaps(&ct2).unwrap();
+    assert_eq!(service_indicator(), ServiceIndicator::Approved);
+}
@@ -1,3 +1,6 @@
+// These tests exercise all roles
+#![cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+
 #[cfg(feature = "ml-kem-1024")]
 use fips203::ml_kem_1024;
 #[cfg(feature = "ml-kem-512")]
@@ -160,3 +163,64 @@ fn test_forever() {
         ));
     }
 }
+
+
+#[test]
+#[cfg(all(feature = "ml-kem-512", feature = "ml-kem-768", feature = "ml-kem-1024"))]
+fn test_decode_encaps_key() {
+    use fips203::{decode_encaps_key, AnyEncapsKey};
+    let (ek512, _dk) = ml_kem_512::KG::keygen_from_seed([1u8; 32], [2u8; 32]);
+    let (ek768, _dk) = ml_kem_768::KG::keygen_from_seed([3u8; 32], [4u8; 32]);
+    let (ek1024, _dk) = ml_kem_1024::KG::keygen_from_seed([5u8; 32], [6u8; 32]);
+
+    // tagged and raw keys are both detected
+    let tagged = ek512.to_tagged_bytes();
+    assert_eq!(decode_encaps_key(&tagged).unwrap(), AnyEncapsKey::MlKem512(ek512.clone()));
+    assert_eq!(decode_encaps_key(ek512.as_bytes()).unwrap(), AnyEncapsKey::MlKem512(ek512));
+    let tagged = ek768.to_tagged_bytes();
+    assert_eq!(decode_encaps_key(&tagged).unwrap(), AnyEncapsKey::MlKem768(ek768.clone()));
+    assert_eq!(decode_encaps_key(ek768.as_bytes()).unwrap(), AnyEncapsKey::MlKem768(ek768));
+    let tagged = ek1024.to_tagged_bytes();
+    let any = decode_encaps_key(&tagged).unwrap();
+    assert_eq!(any.param_id(), ml_kem_1024::PARAM_ID);
+    assert_eq!(any.as_bytes(), ek1024.as_bytes());
+    assert_eq!(decode_encaps_key(ek1024.as_bytes()).unwrap(), AnyEncapsKey::MlKem1024(ek1024));
+
+    // a mismatched tag, unknown length or invalid key is rejected
+    let mut bad = tagged;
+    bad[0] = ml_kem_512::PARAM_ID;
+    assert!(decode_encaps_key(&bad).is_err());
+    assert!(ml_kem_1024::EncapsKey::try_from_tagged_bytes(&bad).is_err());
+    assert!(decode_encaps_key(&tagged[..100]).is_err());
+    assert!(decode_encaps_key(&[0xFFu8; ml_kem_768::EK_LEN]).is_err());
+}
+
+
+#[test]
+#[cfg(all(feature = "ml-kem-512", feature = "ml-kem-768", feature = "ml-kem-1024"))]
+fn test_tagged_decaps_key() {
+    let (_ek, dk512) = ml_kem_512::KG::keygen_from_seed([1u8; 32], [2u8; 32]);
+    let (_ek, dk768) = ml_kem_768::KG::keygen_from_seed([3u8; 32], [4u8; 32]);
+    let (_ek, dk1024) = ml_kem_1024::KG::keygen_from_seed([5u8; 32], [6u8; 32]);
+
+    // each round-trips, and only loads as its own parameter set
+    let tagged = dk512.to_tagged_bytes();
+    assert_eq!(tagged[0], ml_kem_512::PARAM_ID);
+    assert_eq!(ml_kem_512::DecapsKey::try_from_tagged_bytes(&*tagged).unwrap(), dk512);
+    assert!(ml_kem_768::DecapsKey::try_from_tagged_bytes(&*tagged).is_err());
+    assert!(ml_kem_1024::DecapsKey::try_from_tagged_bytes(&*tagged).is_err());
+    let tagged = dk768.to_tagged_bytes();
+    assert_eq!(ml_kem_768::DecapsKey::try_from_tagged_bytes(&*tagged).unwrap(), dk768);
+    assert!(ml_kem_512::DecapsKey::try_from_tagged_bytes(&*tagged).is_err());
+    let tagged = dk1024.to_tagged_bytes();
+    assert_eq!(ml_kem_1024::DecapsKey::try_from_tagged_bytes(&*tagged).unwrap(), dk1024);
+
+    // a mismatched tag, untagged key or corrupted key is rejected
+    let mut bad = tagged.clone();
+    bad[0] = ml_kem_768::PARAM_ID;
+    assert!(ml_kem_1024::DecapsKey::try_from_tagged_bytes(&*bad).is_err());
+    assert!(ml_kem_1024::DecapsKey::try_from_tagged_bytes(&tagged[1..]).is_err());
+    let mut bad = tagged.clone();
+    bad[1 + ml_kem_1024::DK_LEN - 64] ^= 1; // within the embedded H(ek)
+    assert!(ml_kem_1024::DecapsKey::try_from_tagged_bytes(&*bad).is_err());
+}
@@ -0,0 +1,75 @@
+// These tests exercise all roles, and the stack bounds apply to optimized builds only
+#![cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+
+use fips203::traits::{Decaps, Encaps, KeyGen};
+use rand_chacha::rand_core::SeedableRng;
+
+
+// The thread stack, and the portion of it below the measuring frame that is painted
+const THREAD_STACK: usize = 1024 * 1024;
+const PAINT_LEN: usize = 512 * 1024;
+const PAINT: u64 = 0xA5A5_5A5A_A5A5_5A5A;
+
+
+// Returns the (high-water) stack usage of `op`, by painting the stack below the current frame and
+// then finding the lowest overwritten word after `op` returns. The painting starts 1 KiB below a
+// local (clear of the x86_64 red zone), so the result includes the calls into `op`.
+#[inline(never)]
+fn stack_usage<T>(op: impl FnOnce() -> T) -> (T, usize) {
+    let marker = 0u8;
+    let top = (core::ptr::addr_of!(marker) as usize - 1024) & !7;
+    let bottom = top - PAINT_LEN;
+    for addr in (bottom..top).step_by(8) {
+        unsafe { core::ptr::write_volatile(addr as *mut u64, PAINT) };
+    }
+    let result = std::hint::black_box(op)();
+    let mut addr = bottom;
+    while (addr < top) && unsafe { core::ptr::read_volatile(addr as *const u64) } == PAINT {
+        addr += 8;
+    }
+    assert!(addr > bottom, "painted region exhausted");
+    (result, top - addr)
+}
+
+
+// Runs `op` on a fresh thread with ample stack, returning its result and stack usage
+fn measure<T: Send + 'static>(op: impl FnOnce() -> T + Send + 'static) -> (T, usize) {
+    std::thread::Builder::new()
+        .stack_size(THREAD_STACK)
+        .spawn(move || stack_usage(op))
+        .unwrap()
+        .join()
+        .unwrap()
+}
+
+
+macro_rules! stack_test {
+    ($feature:literal, $name:ident, $ml_kem:ident) => {
+        #[test]
+        #[cfg(feature = $feature)]
+        #[cfg_attr(debug_assertions, ignore = "stack bounds apply to optimized builds")]
+        fn $name() {
+            use fips203::$ml_kem::{MAX_STACK_DECAP, MAX_STACK_ENCAP, MAX_STACK_KEYGEN, KG};
+            for seed in 0..4 {
+                let ((ek, dk), keygen) = measure(move || {
+                    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed);
+                    KG::try_keygen_with_rng(&mut rng).unwrap()
+                });
+                let ((ssk1, ct), encap) = measure(move || {
+                    let mut rng = rand_chacha::ChaCha8Rng::seed_from_u64(seed + 100);
+                    ek.try_encaps_with_rng(&mut rng).unwrap()
+                });
+                let (ssk2, decap) = measure(move || dk.try_decaps(&ct).unwrap());
+                assert_eq!(ssk1, ssk2);
+                println!("{}: keygen {keygen}, encap {encap}, decap {decap}", stringify!($ml_kem));
+                assert!(keygen <= MAX_STACK_KEYGEN, "keygen used {keygen} bytes");
+                assert!(encap <= MAX_STACK_ENCAP, "encap used {encap} bytes");
+                assert!(decap <= MAX_STACK_DECAP, "decap used {decap} bytes");
+            }
+        }
+    };
+}
+
+stack_test!("ml-kem-512", test_max_stack_512, ml_kem_512);
+stack_test!("ml-kem-768", test_max_stack_768, ml_kem_768);
+stack_test!("ml-kem-1024", test_max_stack_1024, ml_kem_1024);
@@ -1,6 +1,8 @@
 // This file implements a variety of top-level tests, including: official vectors, random
 // round trips, and (soon) fails.
 
+#![cfg(all(feature = "keygen", feature = "encaps", feature = "decaps"))]
+
 use rand_core::{CryptoRng, RngCore};
 
 mod cctv_vectors;